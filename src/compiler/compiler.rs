@@ -1,13 +1,21 @@
 use eframe::egui_wgpu::wgpu::{Device, Queue, TextureFormat};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
+use super::preprocessor::ShaderLibrary;
+use super::source_map::{SourceBuilder, BOILERPLATE_SECTION};
+use crate::utils::diagnostics::Diagnostic;
 use crate::screens::shader_buffer::ShaderBuffer;
+use crate::utils::channel_texture::{shader_boilerplate, ChannelInput, ChannelKind};
+use crate::utils::gpu_errors::{capture_errors, DeviceMonitor};
+use crate::utils::mesh::Mesh;
 use crate::utils::{
-    catch_panic_mut, format_panic_message, format_shader_error, validate_shader, BufferKind,
-    MultiPassPipelines, ShaderError, DEFAULT_BUFFER_RESOLUTION, DEFAULT_FRAGMENT,
-    DEFAULT_VERTEX, SHADER_BOILERPLATE, STANDARD_VERTEX, TEXTURE_BINDINGS,
+    format_shader_error, has_shader_code, validate_shader, BufferKind, MultiPassPipelines, PassGeometry, ShaderError,
+    DEFAULT_BUFFER_RESOLUTION,
+    DEFAULT_FRAGMENT, DEFAULT_VERTEX, MESH_BINDINGS, STANDARD_MESH_VERTEX, STANDARD_VERTEX,
+    TEXTURE_BINDINGS,
 };
 
 /// Handles shader compilation and pipeline creation
@@ -20,6 +28,18 @@ pub struct ShaderCompiler {
 
     /// Flag to trigger recompilation
     needs_update: Arc<AtomicBool>,
+
+    /// Directories `//#include` directives are resolved against
+    library: ShaderLibrary,
+
+    /// Library files the last gathered sources included
+    included_files: Mutex<Vec<PathBuf>>,
+
+    /// Error and device-loss handlers for the device pipelines are built on
+    device_monitor: Mutex<Option<DeviceMonitor>>,
+
+    /// Set once the device loss has been reported; cleared by a new device
+    device_lost: AtomicBool,
 }
 
 impl ShaderCompiler {
@@ -29,9 +49,29 @@ impl ShaderCompiler {
             pipeline: Arc::new(Mutex::new(None)),
             last_error: Arc::new(Mutex::new(None)),
             needs_update: Arc::new(AtomicBool::new(false)),
+            library: ShaderLibrary::with_default_dirs(),
+            included_files: Mutex::new(Vec::new()),
+            device_monitor: Mutex::new(None),
+            device_lost: AtomicBool::new(false),
         }
     }
 
+    /// Shader library used to resolve includes
+    pub fn library(&self) -> &ShaderLibrary {
+        &self.library
+    }
+
+    /// Library files included by the last compilation, sorted
+    pub fn included_files(&self) -> Vec<PathBuf> {
+        self.included_files.lock().unwrap().clone()
+    }
+
+    /// Change the library directories and recompile against them
+    pub fn set_library_dirs(&mut self, dirs: Vec<PathBuf>) {
+        self.library.set_dirs(dirs);
+        self.trigger_compilation();
+    }
+
     /// Get shared reference to the pipeline
     pub fn pipeline(&self) -> Arc<Mutex<Option<Arc<MultiPassPipelines>>>> {
         self.pipeline.clone()
@@ -43,17 +83,54 @@ impl ShaderCompiler {
         *self.last_error.lock().unwrap() = None;
     }
 
+    /// Watch `device` for uncaptured errors and device loss
+    ///
+    /// Call once per frame before [`Self::compile_if_needed`]. Returns the new
+    /// problems to show. eframe keeps one device for the life of the process,
+    /// so the monitor is installed on the first call; pipelines are dropped
+    /// when the device is lost and only come back by restarting the editor
+    /// (see [`crate::utils::gpu_errors`]).
+    pub fn check_device(&self, device: &Device) -> Vec<ShaderError> {
+        let mut monitor = self.device_monitor.lock().unwrap();
+        let monitor = monitor.get_or_insert_with(|| DeviceMonitor::install(device));
+
+        let mut problems: Vec<ShaderError> = monitor
+            .take_uncaptured()
+            .iter()
+            .map(|report| ShaderError::DeviceError(Box::new(Diagnostic::from_wgpu_message(report))))
+            .collect();
+
+        if let Some(reason) = monitor.lost_reason() {
+            if !self.device_lost.swap(true, Ordering::Relaxed) {
+                *self.pipeline.lock().unwrap() = None;
+                let error = ShaderError::DeviceError(Box::new(Diagnostic::error(format!(
+                    "GPU device lost\n\n{}; restart the renderer to recover, keeping the current shader",
+                    reason
+                ))));
+                *self.last_error.lock().unwrap() = Some(error.clone());
+                problems.push(error);
+            }
+        }
+        problems
+    }
+
+    /// Whether the device pipelines were built on has been lost
+    pub fn device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::Relaxed)
+    }
+
     /// Compile shaders if update is pending
     /// Returns Ok(true) if compiled, Ok(false) if no update needed, Err on failure
     pub fn compile_if_needed(
         &self,
         buffers: &HashMap<BufferKind, ShaderBuffer>,
-        image_paths: &[Option<String>; 4],
+        common: &str,
+        channels: &[ChannelInput; 4],
         device: &Device,
         queue: &Queue,
         format: TextureFormat,
     ) -> Result<bool, CompilationResult> {
-        if !self.needs_update.load(Ordering::Relaxed) {
+        if !self.needs_update.load(Ordering::Relaxed) || self.device_lost.load(Ordering::Relaxed) {
             return Ok(false);
         }
 
@@ -61,7 +138,8 @@ impl ShaderCompiler {
         self.needs_update.store(false, Ordering::Relaxed);
 
         // Gather shader sources
-        let sources = match self.gather_sources(buffers) {
+        let kinds = channels.each_ref().map(|channel| channel.settings.kind);
+        let sources = match self.gather_sources(buffers, common, kinds) {
             Ok(sources) => sources,
             Err(err) => {
                 *self.last_error.lock().unwrap() = Some(err.clone());
@@ -71,109 +149,130 @@ impl ShaderCompiler {
 
         log::debug!("[ShaderCompiler] Compiling multi-pass pipeline with {} buffers", sources.len());
 
-        // Compile pipeline (with panic catching)
-        let result = catch_panic_mut(|| {
-            MultiPassPipelines::new(device, queue, format, DEFAULT_BUFFER_RESOLUTION, &sources, image_paths)
-        });
+        let geometry: HashMap<BufferKind, PassGeometry> =
+            buffers.iter().map(|(kind, buffer)| (*kind, buffer.geometry)).collect();
+        let meshes: HashMap<BufferKind, Arc<Mesh>> = buffers
+            .iter()
+            .filter_map(|(kind, buffer)| buffer.mesh.clone().map(|mesh| (*kind, mesh)))
+            .collect();
+
+        // Passes capture their own errors; the outer scope catches shared resources
+        let result = capture_errors(device, None, || {
+            MultiPassPipelines::new(
+                device,
+                queue,
+                format,
+                DEFAULT_BUFFER_RESOLUTION,
+                &sources,
+                &geometry,
+                &meshes,
+                channels,
+            )
+        })
+        .and_then(|pipeline| pipeline);
 
         match result {
-            Ok(Ok(pipeline)) => {
+            Ok(pipeline) => {
                 // Success
                 *self.pipeline.lock().unwrap() = Some(Arc::new(pipeline));
                 *self.last_error.lock().unwrap() = None;
                 log::info!("[ShaderCompiler] Multi-pass shader compiled successfully");
                 Ok(true)
             }
-            Ok(Err(err)) => {
-                // Shader compilation error
+            Err(err) => {
+                // Shader compilation or GPU error
                 *self.last_error.lock().unwrap() = Some(err.clone());
                 log::error!("[ShaderCompiler] Shader compilation failed: {}", format_shader_error(&err));
                 Err(CompilationResult::CompilationError(err))
             }
-            Err(panic_msg) => {
-                // Caught panic
-                let formatted = format_panic_message(&panic_msg);
-                let error = ShaderError::CompilationError(formatted.clone());
-                *self.last_error.lock().unwrap() = Some(error.clone());
-                log::error!("[ShaderCompiler] Pipeline creation panicked: {}", panic_msg);
-                Err(CompilationResult::Panic(error))
-            }
         }
     }
 
     /// Gather shader sources from buffers and apply boilerplate injection
-    fn gather_sources(
+    ///
+    /// Each pass is assembled as boilerplate, Common code, vertex shader and the
+    /// buffer's fragment, with `//#include` directives expanded in place.
+    /// Diagnostic spans are relocated to the section they occur in
+    /// (e.g. `Common:3:5` or `sdf/box.wgsl:2:9`) rather than the assembled source.
+    pub fn gather_sources(
         &self,
         buffers: &HashMap<BufferKind, ShaderBuffer>,
+        common: &str,
+        channels: [ChannelKind; 4],
     ) -> Result<HashMap<BufferKind, String>, ShaderError> {
         let mut sources = HashMap::with_capacity(5);
+        let common = if has_shader_code(common) { common.trim() } else { "" };
+        let boilerplate = shader_boilerplate(channels);
+        let mut included = BTreeSet::new();
 
-        for buffer_kind in [
-            BufferKind::MainImage,
-            BufferKind::BufferA,
-            BufferKind::BufferB,
-            BufferKind::BufferC,
-            BufferKind::BufferD,
-        ] {
+        for buffer_kind in BufferKind::ALL {
             let (vertex, fragment) = buffers
                 .get(&buffer_kind)
                 .map(|b| b.get_shaders())
                 .unwrap_or(("", ""));
+            let has_mesh = buffers.get(&buffer_kind).is_some_and(|b| b.mesh.is_some());
 
             let fragment_trimmed = fragment.trim();
 
-            // Skip empty fragments (except MainImage which needs default)
-            let has_code = fragment_trimmed.lines().any(|line| {
-                let trimmed_line = line.trim();
-                !trimmed_line.is_empty() && !trimmed_line.starts_with("//")
-            });
-
-            if fragment_trimmed.is_empty() || !has_code {
-                if buffer_kind == BufferKind::MainImage {
-                    // MainImage must exist, use default
-                    sources.insert(
-                        buffer_kind,
-                        format!("{}\n{}\n{}", SHADER_BOILERPLATE, STANDARD_VERTEX, DEFAULT_FRAGMENT),
-                    );
-                }
+            // Skip empty fragments (MainImage falls back to the default below)
+            if !has_shader_code(fragment_trimmed) {
                 continue;
             }
 
             // Auto-inject boilerplate + standard vertex unless user provides custom vertex
             let vertex_trimmed = vertex.trim();
-            let user_vertex =
-                if vertex_trimmed.is_empty() || vertex_trimmed == DEFAULT_VERTEX.trim() {
-                    STANDARD_VERTEX
-                } else {
-                    vertex_trimmed
-                };
+            let custom_vertex = !vertex_trimmed.is_empty() && vertex_trimmed != DEFAULT_VERTEX.trim();
 
             // Build complete shader with conditional texture bindings
             // MainImage gets texture bindings to sample from buffers A-D
             // Buffer A-D do NOT get texture bindings (they're independent)
             let needs_textures = buffer_kind == BufferKind::MainImage;
 
-            let mut complete_shader = String::with_capacity(
-                SHADER_BOILERPLATE.len() + user_vertex.len() + fragment_trimmed.len() + 200,
-            );
-            complete_shader.push_str(SHADER_BOILERPLATE);
-
-            // Add texture bindings ONLY for MainImage
+            let mut builder = SourceBuilder::new();
+            let mut includes = self.library.expansion();
             if needs_textures {
-                complete_shader.push_str(TEXTURE_BINDINGS);
+                builder.push(BOILERPLATE_SECTION, &format!("{}{}", boilerplate, TEXTURE_BINDINGS));
+            } else {
+                builder.push(BOILERPLATE_SECTION, &boilerplate);
             }
-
-            complete_shader.push('\n');
-            complete_shader.push_str(user_vertex);
-            complete_shader.push('\n');
-            complete_shader.push_str(fragment_trimmed);
+            if has_mesh {
+                builder.push(BOILERPLATE_SECTION, MESH_BINDINGS);
+            }
+            let expanded = (|| {
+                if !common.is_empty() {
+                    includes.push(&mut builder, "Common", common)?;
+                }
+                if custom_vertex {
+                    includes.push(&mut builder, &format!("{} vertex", buffer_kind.as_str()), vertex_trimmed)?;
+                } else if has_mesh {
+                    builder.push(BOILERPLATE_SECTION, STANDARD_MESH_VERTEX);
+                } else {
+                    builder.push(BOILERPLATE_SECTION, STANDARD_VERTEX);
+                }
+                includes.push(&mut builder, buffer_kind.as_str(), fragment_trimmed)
+            })();
+            included.extend(includes.included().cloned());
+            *self.included_files.lock().unwrap() = included.iter().cloned().collect();
+            if let Err(e) = expanded {
+                let diagnostic = e.to_diagnostic().in_buffer(buffer_kind);
+                return Err(ShaderError::ValidationError(Box::new(diagnostic)));
+            }
+            let (complete_shader, source_map) = builder.finish();
 
             // Validate the complete shader
-            if let Err(e) = validate_shader(&complete_shader) {
-                return Err(ShaderError::ValidationError(format!(
-                    "[{:?}] {}",
-                    buffer_kind, e
-                )));
+            if let Err(mut e) = validate_shader(&complete_shader) {
+                let diagnostic = e.diagnostic_mut();
+                source_map.relocate(diagnostic);
+                diagnostic.buffer = Some(buffer_kind);
+
+                // Shared code fails in every pass; say which one reported it
+                let section = diagnostic.primary_span().and_then(|span| span.section.clone());
+                if let Some(section) = section.filter(|s| s != buffer_kind.as_str() && s != BOILERPLATE_SECTION) {
+                    diagnostic
+                        .notes
+                        .push(format!("{} is compiled into the {} pass", section, buffer_kind.as_str()));
+                }
+                return Err(e);
             }
 
             sources.insert(buffer_kind, complete_shader);
@@ -183,7 +282,7 @@ impl ShaderCompiler {
         sources.entry(BufferKind::MainImage).or_insert_with(|| {
             format!(
                 "{}\n{}\n{}",
-                SHADER_BOILERPLATE, STANDARD_VERTEX, DEFAULT_FRAGMENT
+                boilerplate, STANDARD_VERTEX, DEFAULT_FRAGMENT
             )
         });
 
@@ -199,9 +298,6 @@ pub enum CompilationResult {
 
     /// Compilation error (during WGPU pipeline creation)
     CompilationError(ShaderError),
-
-    /// Panic during compilation
-    Panic(ShaderError),
 }
 
 impl CompilationResult {
@@ -210,7 +306,63 @@ impl CompilationResult {
         match self {
             CompilationResult::ValidationError(e) => e,
             CompilationResult::CompilationError(e) => e,
-            CompilationResult::Panic(e) => e,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffers(main_image: &str) -> HashMap<BufferKind, ShaderBuffer> {
+        let mut buffers = HashMap::new();
+        buffers.insert(
+            BufferKind::MainImage,
+            ShaderBuffer::new(BufferKind::MainImage, DEFAULT_VERTEX.to_string(), main_image.to_string()),
+        );
+        buffers
+    }
+
+    #[test]
+    fn test_common_injected_before_vertex() {
+        let main_image = "@fragment\nfn fs_main(in: VSOut) -> @location(0) vec4<f32> {\n    return vec4<f32>(tint(), 1.0);\n}";
+        let common = "fn tint() -> vec3<f32> {\n    return vec3<f32>(uniforms.time);\n}";
+
+        let sources = ShaderCompiler::new().gather_sources(&buffers(main_image), common, Default::default()).unwrap();
+        let code = &sources[&BufferKind::MainImage];
+        assert!(code.find("fn tint()").unwrap() < code.find("fn vs_main").unwrap());
+    }
+
+    #[test]
+    fn test_errors_attributed_to_common() {
+        let main_image = "@fragment\nfn fs_main(in: VSOut) -> @location(0) vec4<f32> {\n    return vec4<f32>(tint(), 1.0);\n}";
+        let common = "// helpers\nfn tint() -> vec3<f32> {\n    return vec3<f32>(undefined_name);\n}";
+
+        let err = ShaderCompiler::new()
+            .gather_sources(&buffers(main_image), common, Default::default())
+            .unwrap_err();
+        let diagnostic = err.diagnostic();
+        assert_eq!(diagnostic.buffer, Some(BufferKind::MainImage));
+        let span = diagnostic.primary_span().unwrap();
+        assert_eq!((span.section.as_deref(), span.line), (Some("Common"), 3));
+        let report = format_shader_error(&err);
+        assert!(report.contains("┌─ Common:3:"), "{}", report);
+        assert!(report.contains("= note: Common is compiled into the MainImage pass"), "{}", report);
+    }
+
+    #[test]
+    fn test_mesh_pass_gets_camera_and_mesh_vertex() {
+        // Fragments written against VSOut keep working on a mesh
+        let mut buffers = buffers(DEFAULT_FRAGMENT);
+        buffers.get_mut(&BufferKind::MainImage).unwrap().mesh = Some(Arc::new(Mesh {
+            source: "cube.obj".to_string(),
+            vertices: Vec::new(),
+            indices: Vec::new(),
+        }));
+
+        let sources = ShaderCompiler::new().gather_sources(&buffers, "", Default::default()).unwrap();
+        let code = &sources[&BufferKind::MainImage];
+        assert!(code.contains("var<uniform> camera: Camera;"));
+        assert!(code.contains("fn vs_main(v: MeshVertex) -> MeshOut"));
+    }
+}
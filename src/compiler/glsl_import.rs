@@ -0,0 +1,510 @@
+//! ShaderToy GLSL import
+//!
+//! Translates ShaderToy-style passes — a `mainImage(out vec4, in vec2)`
+//! fragment per buffer plus shared Common code — to WGSL with naga's GLSL
+//! frontend and WGSL backend.
+//!
+//! Each pass is wrapped in a prelude declaring the ShaderToy inputs it uses.
+//! In the translated code those are private globals, set from `uniforms` by a
+//! generated `fs_main` that calls `mainImage`, and `iChannel0..3` sample the
+//! editor's image channels. Translated Common code goes to the Common tab and
+//! is stripped from the passes, which only reference it.
+
+use std::collections::HashSet;
+
+use super::source_map::SourceBuilder;
+use crate::utils::diagnostics::Diagnostic;
+use crate::utils::{BufferKind, ShaderError, SourceKind};
+
+/// Section name for the generated GLSL wrapper code
+const PRELUDE_SECTION: &str = "ShaderToy prelude";
+
+/// ShaderToy inputs: name, GLSL declaration and WGSL assignment in `fs_main`
+const SHADERTOY_INPUTS: &[(&str, &str, &str)] = &[
+    (
+        "iResolution",
+        "vec3 iResolution;",
+        "iResolution = vec3<f32>(uniforms.resolution, 1.0);",
+    ),
+    ("iTime", "float iTime;", "iTime = uniforms.time;"),
+    ("iTimeDelta", "float iTimeDelta;", "iTimeDelta = uniforms.time_delta;"),
+    ("iFrame", "int iFrame;", "iFrame = i32(uniforms.frame);"),
+    ("iMouse", "vec4 iMouse;", "iMouse = uniforms.mouse;"),
+    (
+        "iDate",
+        "vec4 iDate;",
+        "iDate = vec4<f32>(0.0, 0.0, 0.0, uniforms.time);",
+    ),
+    (
+        "iChannelTime",
+        "float iChannelTime[4];",
+        "iChannelTime = array<f32, 4>(uniforms.channel_time.x, uniforms.channel_time.y, uniforms.channel_time.z, uniforms.channel_time.w);",
+    ),
+    // Assigned from the pass's channel textures in `entry_point`
+    ("iChannelResolution", "vec3 iChannelResolution[4];", ""),
+];
+
+/// What a pass's `iChannelN` samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Channel {
+    /// The editor's image slot with the same index
+    #[default]
+    Image,
+    /// The output of Buffer A-D; only MainImage has buffer textures bound
+    Buffer(BufferKind),
+}
+
+impl Channel {
+    /// WGSL texture and sampler names, and their bindings in group 1
+    fn binding(&self, index: usize) -> (String, String, usize) {
+        match self {
+            Channel::Image => (
+                format!("iChannel{}", index),
+                format!("iChannel{}Sampler", index),
+                8 + index * 2,
+            ),
+            Channel::Buffer(kind) => {
+                // Same layout as TEXTURE_BINDINGS
+                let (name, slot) = match kind {
+                    BufferKind::BufferA => ("a", 0),
+                    BufferKind::BufferB => ("b", 1),
+                    BufferKind::BufferC => ("c", 2),
+                    BufferKind::BufferD => ("d", 3),
+                    // MainImage has no texture other passes can read
+                    BufferKind::MainImage => return Channel::Image.binding(index),
+                };
+                (
+                    format!("buffer_{}_texture", name),
+                    format!("buffer_{}_sampler", name),
+                    slot * 2,
+                )
+            }
+        }
+    }
+}
+
+/// One ShaderToy pass to translate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlslPass<'a> {
+    pub kind: BufferKind,
+    pub code: &'a str,
+    pub channels: [Channel; 4],
+}
+
+impl<'a> GlslPass<'a> {
+    /// A pass whose channels all sample the editor's image slots
+    pub fn new(kind: BufferKind, code: &'a str) -> Self {
+        Self {
+            kind,
+            code,
+            channels: [Channel::Image; 4],
+        }
+    }
+}
+
+/// WGSL translated from a ShaderToy shader, ready to load into the editor
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlslImport {
+    /// Translated Common code, if any was given
+    pub common: Option<String>,
+    /// Translated fragment code per buffer
+    pub passes: Vec<(BufferKind, String)>,
+}
+
+/// Translate ShaderToy GLSL passes (and optional Common code) to WGSL
+///
+/// Errors point at the GLSL the user gave, e.g. `BufferA (GLSL):12:5`.
+pub fn import_shadertoy(
+    common: Option<&str>,
+    passes: &[GlslPass],
+) -> Result<GlslImport, ShaderError> {
+    let common = common.filter(|code| !code.trim().is_empty());
+    let common_code = common.unwrap_or("");
+
+    let (common_wgsl, common_names) = match common {
+        Some(code) => {
+            let wgsl = translate(code, "", SourceKind::Common, &[Channel::Image; 4])?;
+            let items = items(&wgsl);
+            let names: HashSet<String> =
+                items.iter().filter_map(|(name, _)| name.clone()).collect();
+            let text = items.into_iter().map(|(_, text)| text).collect::<Vec<_>>();
+            (
+                Some(format!(
+                    "// Translated from ShaderToy GLSL\n{}\n",
+                    text.join("\n\n")
+                )),
+                names,
+            )
+        }
+        None => (None, HashSet::new()),
+    };
+
+    let mut translated = Vec::with_capacity(passes.len());
+    for pass in passes {
+        let wgsl = translate(
+            common_code,
+            pass.code,
+            SourceKind::Buffer(pass.kind),
+            &pass.channels,
+        )?;
+        let body = items(&wgsl)
+            .into_iter()
+            .filter(|(name, _)| {
+                name.as_ref()
+                    .is_none_or(|name| !common_names.contains(name))
+            })
+            .map(|(_, text)| text)
+            .collect::<Vec<_>>();
+
+        let mut fragment = format!(
+            "// Translated from ShaderToy GLSL\n{}\n\n",
+            body.join("\n\n")
+        );
+        fragment.push_str(&entry_point(
+            &format!("{}\n{}", common_code, pass.code),
+            &pass.channels,
+        ));
+        translated.push((pass.kind, fragment));
+    }
+
+    Ok(GlslImport {
+        common: common_wgsl,
+        passes: translated,
+    })
+}
+
+/// Editor source a ShaderToy file belongs to, from names like `Buffer A.glsl`,
+/// `buf_b.frag`, `common.glsl` or `image.glsl`
+pub fn source_for_file_name(file_name: &str) -> Option<SourceKind> {
+    let stem = file_name.split('.').next().unwrap_or(file_name);
+    let name: String = stem
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    match name.as_str() {
+        "common" => Some(SourceKind::Common),
+        "image" | "mainimage" | "main" => Some(SourceKind::Buffer(BufferKind::MainImage)),
+        _ => {
+            let letter = name
+                .strip_prefix("buffer")
+                .or_else(|| name.strip_prefix("buf"))?;
+            match letter {
+                "a" => Some(SourceKind::Buffer(BufferKind::BufferA)),
+                "b" => Some(SourceKind::Buffer(BufferKind::BufferB)),
+                "c" => Some(SourceKind::Buffer(BufferKind::BufferC)),
+                "d" => Some(SourceKind::Buffer(BufferKind::BufferD)),
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Translate Common plus one pass (empty when translating Common alone)
+fn translate(
+    common: &str,
+    pass: &str,
+    source: SourceKind,
+    channels: &[Channel; 4],
+) -> Result<String, ShaderError> {
+    let in_source = |diagnostic: Diagnostic| match source {
+        SourceKind::Buffer(kind) => diagnostic.in_buffer(kind),
+        SourceKind::Common => diagnostic,
+    };
+
+    let mut builder = SourceBuilder::new();
+    builder.push(
+        PRELUDE_SECTION,
+        &prelude(&format!("{}\n{}", common, pass), channels),
+    );
+    if !common.trim().is_empty() {
+        builder.push("Common (GLSL)", &without_version(common));
+    }
+    if let SourceKind::Buffer(kind) = source {
+        builder.push(&format!("{} (GLSL)", kind.as_str()), &without_version(pass));
+    }
+    builder.push(PRELUDE_SECTION, "void main() {}");
+    let (glsl, source_map) = builder.finish();
+
+    let options = naga::front::glsl::Options::from(naga::ShaderStage::Fragment);
+    let mut module = naga::front::glsl::Frontend::default()
+        .parse(&options, &glsl)
+        .map_err(|errors| {
+            let mut diagnostic = Diagnostic::from_glsl_errors(&errors, &glsl);
+            source_map.relocate(&mut diagnostic);
+            ShaderError::ValidationError(Box::new(in_source(diagnostic)))
+        })?;
+
+    if matches!(source, SourceKind::Buffer(_))
+        && !module
+            .functions
+            .iter()
+            .any(|(_, f)| f.name.as_deref() == Some("mainImage"))
+    {
+        let diagnostic = Diagnostic::error(
+            "No mainImage function to translate\n\nShaderToy passes define `void mainImage(out vec4 fragColor, in vec2 fragCoord)`",
+        );
+        return Err(ShaderError::ValidationError(Box::new(in_source(
+            diagnostic,
+        ))));
+    }
+
+    // Only the functions are wanted; `fs_main` is generated separately
+    module.entry_points.clear();
+    let info = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .map_err(|error| {
+        let mut diagnostic = Diagnostic::from_validation_error(&error, &glsl);
+        source_map.relocate(&mut diagnostic);
+        ShaderError::ValidationError(Box::new(in_source(diagnostic)))
+    })?;
+
+    let wgsl =
+        naga::back::wgsl::write_string(&module, &info, naga::back::wgsl::WriterFlags::empty())
+            .map_err(|error| {
+                let diagnostic =
+                    Diagnostic::error(format!("Could not write the translated WGSL\n\n{}", error));
+                ShaderError::CompilationError(Box::new(in_source(diagnostic)))
+            })?;
+
+    // Image channels are declared by the editor's boilerplate
+    let mut wgsl = wgsl;
+    for channel in 0..4 {
+        wgsl = wgsl.replace(
+            &format!("iChannel{}Texture", channel),
+            &format!("iChannel{}", channel),
+        );
+    }
+    Ok(wgsl)
+}
+
+/// Declarations for the ShaderToy inputs `code` mentions
+fn prelude(code: &str, channels: &[Channel; 4]) -> String {
+    let mut prelude = String::from("#version 450\n");
+    for (name, declaration, _) in SHADERTOY_INPUTS {
+        if mentions(code, name) {
+            prelude.push_str(declaration);
+            prelude.push('\n');
+        }
+    }
+
+    let mut declared = HashSet::new();
+    for (index, channel) in channels.iter().enumerate() {
+        let name = format!("iChannel{}", index);
+        if !mentions(code, &name) {
+            continue;
+        }
+        let (texture, sampler, binding) = channel.binding(index);
+        // GLSL names ending in a digit come out of naga suffixed with `_`
+        let texture = match channel {
+            Channel::Image => format!("{}Texture", texture),
+            Channel::Buffer(_) => texture,
+        };
+        if declared.insert(binding) {
+            prelude.push_str(&format!(
+                "layout(set = 1, binding = {}) uniform texture2D {};\n\
+                 layout(set = 1, binding = {}) uniform sampler {};\n",
+                binding,
+                texture,
+                binding + 1,
+                sampler
+            ));
+        }
+        prelude.push_str(&format!(
+            "#define {} sampler2D({}, {})\n",
+            name, texture, sampler
+        ));
+    }
+    prelude
+}
+
+/// Generated `fs_main` setting the ShaderToy inputs and calling `mainImage`
+///
+/// ShaderToy's `fragCoord` has its origin at the bottom left, so `uv.y` is flipped.
+fn entry_point(code: &str, channels: &[Channel; 4]) -> String {
+    let mut out = String::from("@fragment\nfn fs_main(in: VSOut) -> @location(0) vec4<f32> {\n");
+    for (name, _, assignment) in SHADERTOY_INPUTS {
+        if !mentions(code, name) {
+            continue;
+        }
+        if *name == "iChannelResolution" {
+            out.push_str("    iChannelResolution = array<vec3<f32>, 4>(\n");
+            for (index, channel) in channels.iter().enumerate() {
+                let (texture, _, _) = channel.binding(index);
+                out.push_str(&format!(
+                    "        vec3<f32>(vec2<f32>(textureDimensions({})), 1.0),\n",
+                    texture
+                ));
+            }
+            out.push_str("    );\n");
+        } else {
+            out.push_str(&format!("    {}\n", assignment));
+        }
+    }
+    out.push_str(
+        "    var fragColor = vec4<f32>(0.0, 0.0, 0.0, 1.0);\n\
+         \x20   mainImage(&fragColor, vec2<f32>(in.uv.x, 1.0 - in.uv.y) * uniforms.resolution);\n\
+         \x20   return fragColor;\n}\n",
+    );
+    out
+}
+
+/// Top-level items of naga's WGSL output with their names, minus the empty
+/// `main` and the channel bindings the editor declares itself
+fn items(wgsl: &str) -> Vec<(Option<String>, String)> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0i32;
+
+    for line in wgsl.lines() {
+        if current.is_empty() && line.trim().is_empty() {
+            continue;
+        }
+        current.push_str(line.trim_end());
+        current.push('\n');
+        depth += line.matches('{').count() as i32 - line.matches('}').count() as i32;
+
+        let trimmed = line.trim_end();
+        if depth == 0 && (trimmed.ends_with(';') || trimmed.ends_with('}')) {
+            let item = std::mem::take(&mut current);
+            let name = item
+                .lines()
+                .find(|line| !line.starts_with('@'))
+                .and_then(item_name)
+                .map(str::to_string);
+            items.push((name, item.trim_end().to_string()));
+        }
+    }
+
+    let is_dropped = |name: &str| {
+        name == "main"
+            || (0..4).any(|c| {
+                name == format!("iChannel{}", c) || name == format!("iChannel{}Sampler", c)
+            })
+            || (name.starts_with("buffer_")
+                && (name.ends_with("_texture") || name.ends_with("_sampler")))
+    };
+    items.retain(|(name, _)| !name.as_deref().is_some_and(is_dropped));
+    items
+}
+
+/// Name declared by the first line of a WGSL item
+fn item_name(line: &str) -> Option<&str> {
+    let rest = ["fn ", "struct ", "const ", "alias ", "override ", "var "]
+        .iter()
+        .find_map(|keyword| line.strip_prefix(keyword))
+        .or_else(|| {
+            line.strip_prefix("var<")?
+                .split_once("> ")
+                .map(|(_, rest)| rest)
+        })?;
+    let end = rest
+        .find(|c: char| !c.is_alphanumeric() && c != '_')
+        .unwrap_or(rest.len());
+    Some(&rest[..end])
+}
+
+/// Whether `code` uses the identifier `name`
+fn mentions(code: &str, name: &str) -> bool {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    code.match_indices(name).any(|(i, _)| {
+        !code[..i].chars().next_back().is_some_and(is_ident)
+            && !code[i + name.len()..].chars().next().is_some_and(is_ident)
+    })
+}
+
+/// ShaderToy code has no `#version`; drop one so the prelude's comes first
+fn without_version(code: &str) -> String {
+    code.lines()
+        .map(|line| {
+            if line.trim_start().starts_with("#version") {
+                ""
+            } else {
+                line
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{validate_shader, SHADER_BOILERPLATE, STANDARD_VERTEX};
+
+    const COMMON: &str = "float hash12(vec2 p) {\n    return fract(sin(dot(p, vec2(12.9898, 78.233))) * 43758.5453);\n}";
+
+    const IMAGE: &str = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {\n    vec2 uv = fragCoord / iResolution.xy;\n    vec3 col = 0.5 + 0.5 * cos(iTime + iChannelTime[0] + uv.xyx + vec3(0, 2, 4));\n    col *= texture(iChannel0, uv).rgb * hash12(uv);\n    fragColor = vec4(mod(col, 1.0), 1.0);\n}";
+
+    #[test]
+    fn test_import_compiles() {
+        let import = import_shadertoy(Some(COMMON), &[GlslPass::new(BufferKind::MainImage, IMAGE)]).unwrap();
+        let common = import.common.unwrap();
+        let (_, image) = &import.passes[0];
+        assert!(common.contains("fn hash12_("), "{}", common);
+        assert!(!image.contains("fn hash12_("), "{}", image);
+        assert!(
+            image.contains("textureSample(iChannel0, iChannel0Sampler"),
+            "{}",
+            image
+        );
+        assert!(!image.contains("iMouse"), "{}", image);
+
+        // The translation validates like any other buffer
+        let shader = format!(
+            "{}\n{}\n{}\n{}",
+            SHADER_BOILERPLATE, common, STANDARD_VERTEX, image
+        );
+        validate_shader(&shader)
+            .unwrap_or_else(|e| panic!("{}\n{}", crate::utils::format_shader_error(&e), shader));
+
+        // Mouse and frame timing read the pipeline's uniforms
+        let interactive = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {\n    float d = length(fragCoord - iMouse.xy) * iTimeDelta;\n    fragColor = vec4(d, float(iFrame % 2), 0.0, 1.0);\n}";
+        let import = import_shadertoy(None, &[GlslPass::new(BufferKind::MainImage, interactive)]).unwrap();
+        let (_, image) = &import.passes[0];
+        assert!(image.contains("iMouse = uniforms.mouse;"), "{}", image);
+        assert!(image.contains("iFrame = i32(uniforms.frame);"), "{}", image);
+        assert!(image.contains("iTimeDelta = uniforms.time_delta;"), "{}", image);
+        let shader = format!("{}\n{}\n{}", SHADER_BOILERPLATE, STANDARD_VERTEX, image);
+        validate_shader(&shader)
+            .unwrap_or_else(|e| panic!("{}\n{}", crate::utils::format_shader_error(&e), shader));
+    }
+
+    #[test]
+    fn test_errors_point_at_glsl() {
+        let image = "void mainImage(out vec4 fragColor, in vec2 fragCoord) {\n    fragColor = vec4(undefined_name);\n}";
+        let err = import_shadertoy(None, &[GlslPass::new(BufferKind::BufferA, image)]).unwrap_err();
+        let diagnostic = err.diagnostic();
+        assert_eq!(diagnostic.buffer, Some(BufferKind::BufferA));
+        let span = diagnostic.primary_span().unwrap();
+        assert_eq!((span.section.as_deref(), span.line), (Some("BufferA (GLSL)"), 2));
+
+        let err = import_shadertoy(None, &[GlslPass::new(BufferKind::MainImage, COMMON)]).unwrap_err();
+        assert!(err.diagnostic().message.contains("mainImage"));
+    }
+
+    #[test]
+    fn test_source_for_file_name() {
+        assert_eq!(
+            source_for_file_name("Common.glsl"),
+            Some(SourceKind::Common)
+        );
+        assert_eq!(
+            source_for_file_name("Buffer A.glsl"),
+            Some(SourceKind::Buffer(BufferKind::BufferA))
+        );
+        assert_eq!(
+            source_for_file_name("buf_d.frag"),
+            Some(SourceKind::Buffer(BufferKind::BufferD))
+        );
+        assert_eq!(
+            source_for_file_name("image.glsl"),
+            Some(SourceKind::Buffer(BufferKind::MainImage))
+        );
+        assert_eq!(source_for_file_name("plasma.glsl"), None);
+    }
+}
@@ -1,3 +1,9 @@
 pub mod compiler;
+pub mod glsl_import;
+pub mod preprocessor;
+pub mod shadertoy;
+pub mod source_map;
+pub mod standalone;
+pub mod web_export;
 
 pub use compiler::ShaderCompiler;
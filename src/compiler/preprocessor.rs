@@ -0,0 +1,412 @@
+//! `//#include` directives resolved against a local shader library
+//!
+//! A line of the form `//#include "sdf/box.wgsl"` is replaced by the contents
+//! of that file. Paths are looked up next to the including library file first,
+//! then in each library directory in order. Since the directive is a comment,
+//! sources that use it still parse in tools that know nothing about includes.
+//!
+//! Every file is included at most once per pass (an implicit include guard),
+//! so two helpers may both include `common/math.wgsl` without redefining it.
+//! Including a file that is still being expanded is reported as a cycle.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::source_map::SourceBuilder;
+use crate::utils::diagnostics::{Diagnostic, Span};
+
+/// Environment variable with extra library directories (`PATH`-style list)
+pub const LIBRARY_PATH_ENV: &str = "WEBSHARD_LIBRARY_PATH";
+
+/// Parse an `//#include "path"` directive line
+pub fn include_directive(line: &str) -> Option<&str> {
+    let rest = line.trim().strip_prefix("//#include")?.trim();
+    let path = rest.strip_prefix('"')?.strip_suffix('"')?;
+    (!path.is_empty()).then_some(path)
+}
+
+/// Why an include could not be expanded
+#[derive(Debug, Clone, PartialEq)]
+pub enum IncludeErrorKind {
+    NotFound { path: String, searched: Vec<PathBuf> },
+    Unreadable { path: PathBuf, message: String },
+    Cycle(Vec<String>),
+}
+
+/// Include failure, located at the directive that triggered it
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncludeError {
+    /// Section (buffer, Common or library file) containing the directive
+    pub section: String,
+    /// 1-based line of the directive within that section
+    pub line: usize,
+    /// The directive line itself
+    pub line_text: String,
+    pub kind: IncludeErrorKind,
+}
+
+impl IncludeError {
+    /// Diagnostic pointing at the include path in the directive
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let (column, len) = match self.line_text.find('"') {
+            Some(quote) => (quote + 1, self.line_text.trim_end().len() - quote),
+            None => (1, self.line_text.len()),
+        };
+        let label = match &self.kind {
+            IncludeErrorKind::NotFound { .. } => "not found in the shader library",
+            IncludeErrorKind::Unreadable { .. } => "cannot be read",
+            IncludeErrorKind::Cycle(_) => "includes a file that is still being included",
+        };
+        Diagnostic::error(self.kind.to_string()).with_label(
+            Span {
+                section: Some(self.section.clone()),
+                line: self.line,
+                column,
+                len,
+                line_text: self.line_text.clone(),
+            },
+            label,
+        )
+    }
+}
+
+impl fmt::Display for IncludeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IncludeErrorKind::NotFound { path, searched } => {
+                write!(f, "cannot find include \"{}\"", path)?;
+                if searched.is_empty() {
+                    write!(f, " (no library directories configured)")
+                } else {
+                    let dirs: Vec<String> = searched.iter().map(|d| d.display().to_string()).collect();
+                    write!(f, " (searched: {})", dirs.join(", "))
+                }
+            }
+            IncludeErrorKind::Unreadable { path, message } => {
+                write!(f, "cannot read include {}: {}", path.display(), message)
+            }
+            IncludeErrorKind::Cycle(chain) => write!(f, "include cycle: {}", chain.join(" → ")),
+        }
+    }
+}
+
+impl fmt::Display for IncludeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.section, self.line, self.kind)
+    }
+}
+
+impl std::error::Error for IncludeError {}
+
+/// Ordered list of directories `//#include` paths are resolved against
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShaderLibrary {
+    dirs: Vec<PathBuf>,
+}
+
+impl ShaderLibrary {
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        Self { dirs }
+    }
+
+    /// Library from `WEBSHARD_LIBRARY_PATH` plus the per-user library folder
+    pub fn with_default_dirs() -> Self {
+        Self::new(Self::default_dirs())
+    }
+
+    /// Directories used when the user has not configured any
+    pub fn default_dirs() -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = std::env::var_os(LIBRARY_PATH_ENV)
+            .map(|paths| std::env::split_paths(&paths).collect())
+            .unwrap_or_default();
+        if let Some(data_dir) = dirs::data_dir() {
+            dirs.push(data_dir.join("webshard_editor").join("library"));
+        }
+        dirs
+    }
+
+    pub fn dirs(&self) -> &[PathBuf] {
+        &self.dirs
+    }
+
+    pub fn set_dirs(&mut self, dirs: Vec<PathBuf>) {
+        self.dirs = dirs;
+    }
+
+    /// Start expanding one pass; include guards are shared by everything pushed through it
+    pub fn expansion(&self) -> Expansion<'_> {
+        Expansion {
+            library: self,
+            included: HashSet::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    /// Replace every include in `source` with the file contents, for export
+    pub fn inline(&self, name: &str, source: &str) -> Result<String, IncludeError> {
+        self.expansion().inline(name, source)
+    }
+
+    /// Inline a pass's vertex and fragment code for export, as [`Self::inline`]
+    ///
+    /// Common is compiled into every pass ahead of its own code, so files
+    /// Common already includes are left out, and a file both the vertex and
+    /// fragment code include is inlined once.
+    pub fn inline_pass(
+        &self,
+        common: &str,
+        vertex: (&str, &str),
+        fragment: (&str, &str),
+    ) -> Result<(String, String), IncludeError> {
+        let mut includes = self.expansion();
+        includes.inline("Common", common)?;
+        Ok((includes.inline(vertex.0, vertex.1)?, includes.inline(fragment.0, fragment.1)?))
+    }
+
+    fn resolve(&self, path: &str, from_dir: Option<&Path>) -> Result<PathBuf, IncludeErrorKind> {
+        let candidate = Path::new(path);
+        if candidate.is_absolute() {
+            return if candidate.is_file() {
+                Ok(candidate.to_path_buf())
+            } else {
+                Err(IncludeErrorKind::NotFound { path: path.to_string(), searched: Vec::new() })
+            };
+        }
+
+        let searched: Vec<PathBuf> = from_dir
+            .into_iter()
+            .map(Path::to_path_buf)
+            .chain(self.dirs.iter().cloned())
+            .collect();
+        searched
+            .iter()
+            .map(|dir| dir.join(candidate))
+            .find(|full| full.is_file())
+            .ok_or(IncludeErrorKind::NotFound { path: path.to_string(), searched })
+    }
+
+    /// Name shown in error locations: the path relative to its library directory
+    fn display_name(&self, canonical: &Path, written: &str) -> String {
+        self.dirs
+            .iter()
+            .filter_map(|dir| dir.canonicalize().ok())
+            .find_map(|dir| canonical.strip_prefix(dir).ok().map(Path::to_path_buf))
+            .map(|relative| {
+                relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .unwrap_or_else(|| written.to_string())
+    }
+}
+
+/// An `//#include` line: where it is and the path it names
+struct Directive<'s> {
+    section: &'s str,
+    line: usize,
+    text: &'s str,
+    path: &'s str,
+}
+
+/// Include state for one assembled pass
+pub struct Expansion<'a> {
+    library: &'a ShaderLibrary,
+    /// Canonical paths already included in this pass (include guards)
+    included: HashSet<PathBuf>,
+    /// Files currently being expanded, for cycle detection
+    stack: Vec<(PathBuf, String)>,
+}
+
+impl Expansion<'_> {
+    /// Files included so far, canonicalized
+    pub fn included(&self) -> impl Iterator<Item = &PathBuf> {
+        self.included.iter()
+    }
+
+    /// `source` with its includes replaced by the file contents, skipping files already included
+    pub fn inline(&mut self, name: &str, source: &str) -> Result<String, IncludeError> {
+        let mut builder = SourceBuilder::new();
+        self.push(&mut builder, name, source)?;
+        let (mut text, _) = builder.finish();
+        text.pop();
+        Ok(text)
+    }
+
+    /// Push a user section, expanding its includes into their own sections
+    pub fn push(&mut self, builder: &mut SourceBuilder, name: &str, source: &str) -> Result<(), IncludeError> {
+        self.expand(builder, name, source, None)
+    }
+
+    fn expand(
+        &mut self,
+        builder: &mut SourceBuilder,
+        name: &str,
+        source: &str,
+        dir: Option<&Path>,
+    ) -> Result<(), IncludeError> {
+        if !source.lines().any(|line| include_directive(line).is_some()) {
+            builder.push(name, source);
+            return Ok(());
+        }
+
+        let mut chunk = String::new();
+        let mut chunk_start = 1;
+        for (index, line) in source.lines().enumerate() {
+            let Some(path) = include_directive(line) else {
+                chunk.push_str(line);
+                chunk.push('\n');
+                continue;
+            };
+
+            if !chunk.is_empty() {
+                chunk.pop();
+                builder.push_from(name, &chunk, chunk_start);
+                chunk.clear();
+            }
+            chunk_start = index + 2;
+
+            let directive = Directive { section: name, line: index + 1, text: line, path };
+            self.include(builder, &directive, dir)?;
+        }
+        if !chunk.is_empty() {
+            chunk.pop();
+            builder.push_from(name, &chunk, chunk_start);
+        }
+        Ok(())
+    }
+
+    fn include(&mut self, builder: &mut SourceBuilder, directive: &Directive, dir: Option<&Path>) -> Result<(), IncludeError> {
+        let path = directive.path;
+        let located = |kind| IncludeError {
+            section: directive.section.to_string(),
+            line: directive.line,
+            line_text: directive.text.to_string(),
+            kind,
+        };
+
+        let resolved = self.library.resolve(path, dir).map_err(located)?;
+        let canonical = resolved.canonicalize().unwrap_or_else(|_| resolved.clone());
+        let name = self.library.display_name(&canonical, path);
+
+        if let Some(start) = self.stack.iter().position(|(p, _)| *p == canonical) {
+            let mut chain: Vec<String> = self.stack[start..].iter().map(|(_, n)| n.clone()).collect();
+            chain.push(name);
+            return Err(located(IncludeErrorKind::Cycle(chain)));
+        }
+        if !self.included.insert(canonical.clone()) {
+            return Ok(());
+        }
+
+        let text = std::fs::read_to_string(&resolved).map_err(|e| {
+            located(IncludeErrorKind::Unreadable {
+                path: resolved.clone(),
+                message: e.to_string(),
+            })
+        })?;
+
+        self.stack.push((canonical, name.clone()));
+        let result = self.expand(builder, &name, &text, resolved.parent());
+        self.stack.pop();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::source_map::SourceBuilder;
+
+    /// Fresh library directory populated with `files`
+    fn library(test: &str, files: &[(&str, &str)]) -> ShaderLibrary {
+        let dir = std::env::temp_dir().join(format!("webshard_library_{}_{}", test, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (path, text) in files {
+            let full = dir.join(path);
+            std::fs::create_dir_all(full.parent().unwrap()).unwrap();
+            std::fs::write(full, text).unwrap();
+        }
+        ShaderLibrary::new(vec![dir])
+    }
+
+    #[test]
+    fn test_include_directive() {
+        assert_eq!(include_directive("  //#include \"sdf/box.wgsl\""), Some("sdf/box.wgsl"));
+        assert_eq!(include_directive("// #include \"sdf/box.wgsl\""), None);
+        assert_eq!(include_directive("//#include sdf/box.wgsl"), None);
+    }
+
+    #[test]
+    fn test_inline_with_include_guards() {
+        let lib = library(
+            "guards",
+            &[
+                ("math.wgsl", "const PI: f32 = 3.14159;"),
+                ("sdf/box.wgsl", "//#include \"../math.wgsl\"\nfn sd_box() -> f32 { return PI; }"),
+                ("sdf/circle.wgsl", "//#include \"math.wgsl\"\nfn sd_circle() -> f32 { return PI; }"),
+            ],
+        );
+
+        let code = "//#include \"sdf/box.wgsl\"\n//#include \"sdf/circle.wgsl\"\nfn main_fn() {}";
+        let inlined = lib.inline("MainImage", code).unwrap();
+        assert_eq!(inlined.matches("const PI").count(), 1);
+        assert!(inlined.find("fn sd_box").unwrap() < inlined.find("fn sd_circle").unwrap());
+        assert!(inlined.ends_with("fn main_fn() {}"));
+        assert!(!inlined.contains("#include"));
+    }
+
+    #[test]
+    fn test_inline_pass_shares_includes_with_common() {
+        let lib = library("shared", &[("math.wgsl", "const PI: f32 = 3.14159;\nfn twice(x: f32) -> f32 { return x * 2.0; }")]);
+        let common = "//#include \"math.wgsl\"\nfn tau() -> f32 { return twice(PI); }";
+        let fragment = "//#include \"math.wgsl\"\n@fragment\nfn fs_main(in: VSOut) -> @location(0) vec4<f32> {\n    return vec4<f32>(twice(tau()), 0.0, 0.0, 1.0);\n}";
+
+        let (vertex, fragment) = lib.inline_pass(common, ("MainImage vertex", ""), ("MainImage", fragment)).unwrap();
+        let common = lib.inline("Common", common).unwrap();
+        assert!(vertex.is_empty());
+        assert!(!fragment.contains("const PI") && !fragment.contains("#include"));
+
+        // The exported shard compiles without redefinitions
+        let json = serde_json::json!({ "version": "2.0", "encoding": "plain", "common": common, "fragment": fragment });
+        let shard = crate::utils::ShaderJson::from_json(&json.to_string()).unwrap();
+        let main_image = &shard.to_shader_map()[&crate::utils::BufferKind::MainImage];
+        assert_eq!(main_image.matches("const PI").count(), 1);
+        crate::utils::validate_shader(main_image).unwrap();
+    }
+
+    #[test]
+    fn test_locations_point_into_included_file() {
+        let lib = library("locations", &[("noise/hash.wgsl", "// hash\nfn hash() -> f32 {\n    return 1.0;\n}")]);
+
+        let mut builder = SourceBuilder::new();
+        lib.expansion()
+            .push(&mut builder, "MainImage", "let a = 1;\n//#include \"noise/hash.wgsl\"\nlet b = 2;")
+            .unwrap();
+        let (source, map) = builder.finish();
+
+        let hash_line = source.lines().position(|l| l.starts_with("fn hash")).unwrap() + 1;
+        assert_eq!(map.locate(hash_line), Some(("noise/hash.wgsl", 2)));
+        assert_eq!(map.locate(hash_line + 3), Some(("MainImage", 3)));
+    }
+
+    #[test]
+    fn test_missing_include_and_cycle() {
+        let lib = library(
+            "errors",
+            &[("a.wgsl", "//#include \"b.wgsl\""), ("b.wgsl", "\n//#include \"a.wgsl\"")],
+        );
+
+        let missing = lib.inline("Common", "\n\n//#include \"nope.wgsl\"").unwrap_err();
+        assert_eq!((missing.section.as_str(), missing.line), ("Common", 3));
+        assert!(matches!(missing.kind, IncludeErrorKind::NotFound { .. }));
+
+        let cycle = lib.inline("MainImage", "//#include \"a.wgsl\"").unwrap_err();
+        assert_eq!((cycle.section.as_str(), cycle.line), ("b.wgsl", 2));
+        assert_eq!(
+            cycle.kind,
+            IncludeErrorKind::Cycle(vec!["a.wgsl".to_string(), "b.wgsl".to_string(), "a.wgsl".to_string()])
+        );
+    }
+}
@@ -0,0 +1,396 @@
+//! Import of ShaderToy API exports
+//!
+//! Reads the JSON the ShaderToy API returns for a shader, wires each pass's
+//! inputs to our buffers and image channels, translates the GLSL with
+//! [`glsl_import`] and returns the result as a shard.
+
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path};
+
+use super::glsl_import::{self, Channel, GlslPass};
+use crate::utils::channel_texture::{ChannelFilter, ChannelSettings, ChannelWrap};
+use crate::utils::diagnostics::Diagnostic;
+use crate::utils::shader_json;
+use crate::utils::{BufferKind, ShaderError, ShaderJson, SourceKind};
+
+/// A ShaderToy export translated to our format
+#[derive(Debug, Clone)]
+pub struct ShaderToyImport {
+    pub shader: ShaderJson,
+    /// Title from the export's info block
+    pub name: Option<String>,
+    /// Passes and inputs that could not be mapped, for the user to fix by hand
+    pub unmapped: Vec<String>,
+}
+
+/// ShaderToy API export (`{"Shader": {"info": ..., "renderpass": [...]}}`)
+#[derive(Debug, Deserialize)]
+struct ShaderToyShader {
+    #[serde(default)]
+    info: ShaderToyInfo,
+    renderpass: Vec<ShaderToyPass>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ShaderToyInfo {
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShaderToyPass {
+    #[serde(default)]
+    inputs: Vec<ShaderToyInput>,
+    #[serde(default)]
+    outputs: Vec<ShaderToyOutput>,
+    #[serde(default)]
+    code: String,
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type", default)]
+    pass_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShaderToyInput {
+    #[serde(default)]
+    id: serde_json::Value,
+    #[serde(default)]
+    src: String,
+    /// Older exports use `filepath`/`type` instead of `src`/`ctype`
+    #[serde(default)]
+    filepath: String,
+    #[serde(default)]
+    ctype: String,
+    #[serde(rename = "type", default)]
+    legacy_type: String,
+    #[serde(default)]
+    channel: usize,
+    #[serde(default)]
+    sampler: Option<ShaderToySampler>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ShaderToySampler {
+    #[serde(default)]
+    filter: String,
+    #[serde(default)]
+    wrap: String,
+    /// `"true"` or `"false"`, as a string
+    #[serde(default)]
+    vflip: String,
+}
+
+impl ShaderToySampler {
+    /// Channel settings for the sampler; mipmaps are sampled as linear
+    fn settings(&self) -> ChannelSettings {
+        ChannelSettings {
+            wrap: if self.wrap == "repeat" { ChannelWrap::Repeat } else { ChannelWrap::Clamp },
+            filter: if self.filter == "nearest" { ChannelFilter::Nearest } else { ChannelFilter::Linear },
+            flip_y: self.vflip == "true",
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ShaderToyOutput {
+    #[serde(default)]
+    id: serde_json::Value,
+}
+
+/// Output ids ShaderToy uses for Buffer A-D (current and numeric legacy ids)
+const SHADERTOY_BUFFER_IDS: [(&str, &str, BufferKind); 4] = [
+    ("4dXGR8", "257", BufferKind::BufferA),
+    ("XsXGR8", "258", BufferKind::BufferB),
+    ("4sXGR8", "259", BufferKind::BufferC),
+    ("XdfGR8", "260", BufferKind::BufferD),
+];
+
+/// The shader object of a ShaderToy export, which may be wrapped in
+/// `{"Shader": ...}` or in a list of shaders
+fn shadertoy_shader(value: &serde_json::Value) -> Option<&serde_json::Value> {
+    let value = value.as_array().and_then(|list| list.first()).unwrap_or(value);
+    let shader = value.get("Shader").unwrap_or(value);
+    shader.get("renderpass").is_some().then_some(shader)
+}
+
+fn shadertoy_id(id: &serde_json::Value) -> String {
+    match id {
+        serde_json::Value::String(id) => id.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Whether `json_str` is a ShaderToy API export rather than a shard
+pub fn is_export(json_str: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(json_str)
+        .ok()
+        .is_some_and(|value| shadertoy_shader(&value).is_some())
+}
+
+/// Import a ShaderToy API export
+///
+/// GLSL passes are translated to WGSL, and the Image pass's buffer inputs
+/// are wired to the buffer textures. Texture inputs are read from
+/// `media_dir` by the file name of their ShaderToy path; nothing is
+/// downloaded. Inputs our model has no equivalent for (cubemaps, video,
+/// keyboard, webcam, audio, buffers read outside MainImage) are reported
+/// in [`ShaderToyImport::unmapped`].
+pub fn import_export(json_str: &str, media_dir: Option<&Path>) -> Result<ShaderToyImport, ShaderError> {
+    let not_shadertoy = |message: String| ShaderError::validation(message);
+    let value: serde_json::Value = serde_json::from_str(json_str)
+        .map_err(|e| not_shadertoy(format!("Invalid JSON: {}", e)))?;
+    let shader = shadertoy_shader(&value)
+        .ok_or_else(|| not_shadertoy("Not a ShaderToy export (no renderpass list)".to_string()))?;
+    let shader: ShaderToyShader = serde_json::from_value(shader.clone())
+        .map_err(|e| not_shadertoy(format!("Unexpected ShaderToy export layout: {}", e)))?;
+
+    // Buffer passes by output id, so inputs can name the buffer they read
+    let mut outputs: HashMap<String, BufferKind> = SHADERTOY_BUFFER_IDS
+        .iter()
+        .flat_map(|(id, legacy, kind)| [(id.to_string(), *kind), (legacy.to_string(), *kind)])
+        .collect();
+    for pass in &shader.renderpass {
+        if let Some(SourceKind::Buffer(kind)) = glsl_import::source_for_file_name(&pass.name) {
+            for output in &pass.outputs {
+                outputs.insert(shadertoy_id(&output.id), kind);
+            }
+        }
+    }
+
+    let mut unmapped = Vec::new();
+    let mut common = None;
+    let mut passes = Vec::new();
+    let mut images: [Option<(String, Vec<u8>)>; 4] = Default::default();
+    let mut settings = [ChannelSettings::default(); 4];
+
+    for pass in &shader.renderpass {
+        let kind = match (pass.pass_type.as_str(), glsl_import::source_for_file_name(&pass.name)) {
+            ("common", _) => {
+                common = Some(pass.code.as_str());
+                continue;
+            }
+            ("image", _) => BufferKind::MainImage,
+            ("buffer", Some(SourceKind::Buffer(kind))) => kind,
+            (pass_type, _) => {
+                unmapped.push(format!("{} pass ({}) is not supported", pass.name, pass_type));
+                continue;
+            }
+        };
+
+        let mut channels = [Channel::Image; 4];
+        for input in &pass.inputs {
+            let n = input.channel;
+            let ctype = if input.ctype.is_empty() { &input.legacy_type } else { &input.ctype };
+            let at = format!("{} iChannel{}", kind.as_str(), n);
+            if n >= 4 {
+                unmapped.push(format!("{}: there are only 4 channels", at));
+                continue;
+            }
+
+            match ctype.as_str() {
+                "buffer" => match outputs.get(&shadertoy_id(&input.id)) {
+                    Some(source) if kind == BufferKind::MainImage => channels[n] = Channel::Buffer(*source),
+                    Some(source) => unmapped.push(format!(
+                        "{}: reads {}, but only MainImage can sample buffers",
+                        at,
+                        source.as_str()
+                    )),
+                    None => unmapped.push(format!("{}: unknown buffer {}", at, shadertoy_id(&input.id))),
+                },
+                "texture" => {
+                    let src = if input.src.is_empty() { &input.filepath } else { &input.src };
+                    match load_shadertoy_media(media_dir, src) {
+                        Some(bytes) => match &images[n] {
+                            Some((other, _)) if other != src => unmapped.push(format!(
+                                "{}: {} conflicts with {} on the shared iChannel{} slot",
+                                at, src, other, n
+                            )),
+                            _ => images[n] = Some((src.clone(), bytes)),
+                        },
+                        None => unmapped.push(format!(
+                            "{}: texture {} not found next to the export; load it into iChannel{}",
+                            at, src, n
+                        )),
+                    }
+                    if let Some(sampler) = &input.sampler {
+                        settings[n] = sampler.settings();
+                    }
+                }
+                other => unmapped.push(format!("{}: {} inputs are not supported", at, other)),
+            }
+        }
+
+        passes.push(GlslPass {
+            kind,
+            code: pass.code.as_str(),
+            channels,
+        });
+    }
+
+    if !passes.iter().any(|pass| pass.kind == BufferKind::MainImage) {
+        return Err(ShaderError::ValidationError(Box::new(Diagnostic::error(
+            "ShaderToy export has no Image pass",
+        ))));
+    }
+
+    let import = glsl_import::import_shadertoy(common, &passes)?;
+    let pass_code = |kind: BufferKind| {
+        import
+            .passes
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .map(|(_, code)| code.clone())
+    };
+    let [ichannel0, ichannel1, ichannel2, ichannel3] = images.map(|image| {
+        image.map(|(_, bytes)| base64::Engine::encode(&base64::engine::general_purpose::STANDARD, bytes))
+    });
+
+    let channels: BTreeMap<String, ChannelSettings> = shader_json::CHANNEL_KEYS
+        .iter()
+        .zip(settings)
+        .filter(|(_, settings)| !settings.is_default())
+        .map(|(key, settings)| (key.to_string(), settings))
+        .collect();
+
+    for note in &unmapped {
+        log::warn!("ShaderToy import: {}", note);
+    }
+
+    Ok(ShaderToyImport {
+        shader: ShaderJson {
+            version: shader_json::default_version(),
+            name: shader.info.name.clone(),
+            description: None,
+            exported_at: None,
+            encoding: shader_json::default_encoding(),
+            vertex: None,
+            fragment: pass_code(BufferKind::MainImage).unwrap_or_default(),
+            common: import.common.clone(),
+            buffer_a: pass_code(BufferKind::BufferA),
+            buffer_b: pass_code(BufferKind::BufferB),
+            buffer_c: pass_code(BufferKind::BufferC),
+            buffer_d: pass_code(BufferKind::BufferD),
+            buffer_a_vertex: None,
+            buffer_b_vertex: None,
+            buffer_c_vertex: None,
+            buffer_d_vertex: None,
+            geometry: None,
+            ichannel0,
+            ichannel1,
+            ichannel2,
+            ichannel3,
+            channels: (!channels.is_empty()).then_some(channels),
+            gamma: None,
+            contrast: None,
+            saturation: None,
+        },
+        name: shader.info.name,
+        unmapped,
+    })
+}
+
+/// Bytes of a ShaderToy media file (`/media/a/<hash>.png`) saved locally,
+/// either by file name or with its directory structure
+///
+/// `src` comes from the export, so paths that could leave `media_dir`
+/// (`..`, drive prefixes) are ignored.
+fn load_shadertoy_media(media_dir: Option<&Path>, src: &str) -> Option<Vec<u8>> {
+    let dir = media_dir?;
+    let relative = Path::new(src.trim_start_matches('/'));
+    if !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+        log::warn!("ShaderToy import: ignoring media path {}", src);
+        return None;
+    }
+    let file_name = relative.file_name()?;
+    [dir.join(file_name), dir.join(relative)]
+        .iter()
+        .find_map(|path| std::fs::read(path).ok())
+}
+
+impl ShaderJson {
+    /// Whether `json_str` is a ShaderToy API export rather than a shard, see [`is_export`]
+    pub fn is_shadertoy(json_str: &str) -> bool {
+        is_export(json_str)
+    }
+
+    /// Import a ShaderToy API export, see [`import_export`]
+    pub fn from_shadertoy(json_str: &str, media_dir: Option<&Path>) -> Result<ShaderToyImport, ShaderError> {
+        import_export(json_str, media_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shadertoy_import() {
+        let media_dir = std::env::temp_dir().join(format!("webshard_shadertoy_{}", std::process::id()));
+        std::fs::create_dir_all(&media_dir).unwrap();
+        std::fs::write(media_dir.join("noise.png"), b"png bytes").unwrap();
+
+        let export = serde_json::json!({
+            "Shader": {
+                "ver": "0.1",
+                "info": { "name": "Feedback" },
+                "renderpass": [
+                    {
+                        "type": "common", "name": "Common", "inputs": [], "outputs": [],
+                        "code": "float wave(float t) { return 0.5 + 0.5 * sin(t); }"
+                    },
+                    {
+                        "type": "buffer", "name": "Buffer A",
+                        "inputs": [{ "id": "4dXGR8", "ctype": "buffer", "channel": 0 },
+                                   { "id": 33, "ctype": "keyboard", "channel": 1 }],
+                        "outputs": [{ "id": "4dXGR8", "channel": 0 }],
+                        "code": "void mainImage(out vec4 fragColor, in vec2 fragCoord) {\n    fragColor = vec4(wave(iTime));\n}"
+                    },
+                    {
+                        "type": "image", "name": "Image",
+                        "inputs": [{ "id": "4dXGR8", "ctype": "buffer", "channel": 0 },
+                                   { "id": 16, "ctype": "texture", "channel": 1, "src": "/media/a/noise.png",
+                                     "sampler": { "filter": "nearest", "wrap": "repeat", "vflip": "true" } },
+                                   { "id": 17, "ctype": "texture", "channel": 2, "src": "/media/a/missing.jpg" }],
+                        "outputs": [{ "id": "4dfGRr", "channel": 0 }],
+                        "code": "void mainImage(out vec4 fragColor, in vec2 fragCoord) {\n    vec2 uv = fragCoord / iResolution.xy;\n    fragColor = texture(iChannel0, uv) * texture(iChannel1, uv);\n}"
+                    }
+                ]
+            }
+        })
+        .to_string();
+
+        assert!(is_export(&export));
+        let import = ShaderJson::from_shadertoy(&export, Some(&media_dir)).unwrap();
+
+        // Media paths can't leave the media directory
+        let outside = media_dir.with_extension("secret");
+        std::fs::write(&outside, b"secret").unwrap();
+        let escape = format!("/../{}", outside.file_name().unwrap().to_string_lossy());
+        assert!(std::fs::read(media_dir.join(&escape[1..])).is_ok());
+        assert!(load_shadertoy_media(Some(&media_dir), &escape).is_none());
+        std::fs::remove_file(&outside).ok();
+        std::fs::remove_dir_all(&media_dir).ok();
+
+        assert_eq!(import.name.as_deref(), Some("Feedback"));
+        let shader = import.shader;
+        assert!(shader.common.as_deref().unwrap().contains("fn wave("));
+        assert!(shader.buffer_a.as_deref().unwrap().contains("fn fs_main"));
+        assert!(shader.fragment.contains("textureSample(buffer_a_texture, buffer_a_sampler"), "{}", shader.fragment);
+        assert!(shader.fragment.contains("textureSample(iChannel1, iChannel1Sampler"), "{}", shader.fragment);
+        assert_eq!(shader.decode_embedded_images()[1].as_deref(), Some(&b"png bytes"[..]));
+        let settings = shader.channel_settings(1);
+        assert_eq!((settings.wrap, settings.filter, settings.flip_y), (ChannelWrap::Repeat, ChannelFilter::Nearest, true));
+        assert!(shader.channel_settings(2).is_default());
+        let main_image = &shader.to_shader_map()[&BufferKind::MainImage];
+        crate::utils::validate_shader(main_image).unwrap_or_else(|e| panic!("{}", crate::utils::format_shader_error(&e)));
+
+        let unmapped = import.unmapped.join("\n");
+        assert!(unmapped.contains("BufferA iChannel0: reads BufferA"), "{}", unmapped);
+        assert!(unmapped.contains("BufferA iChannel1: keyboard inputs are not supported"), "{}", unmapped);
+        assert!(unmapped.contains("MainImage iChannel2: texture /media/a/missing.jpg not found"), "{}", unmapped);
+        assert!(!is_export(r#"{ "fragment": "code" }"#));
+    }
+}
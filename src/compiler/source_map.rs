@@ -0,0 +1,152 @@
+//! Line attribution for assembled shaders
+//!
+//! `gather_sources` concatenates boilerplate, Common code, the vertex shader
+//! and the buffer's fragment into one module. naga reports errors against that
+//! module, so its line numbers mean nothing to the user. The source map records
+//! where every section starts and relocates diagnostic spans to
+//! `Section:line:column`.
+//!
+//! A section may be pushed in several chunks (e.g. the parts of a buffer
+//! around an `//#include` directive), each remembering the line of the
+//! original text it starts at.
+
+use crate::utils::diagnostics::Diagnostic;
+
+/// Name used for injected code the user never sees
+pub const BOILERPLATE_SECTION: &str = "boilerplate";
+
+#[derive(Debug, Clone, PartialEq)]
+struct Section {
+    name: String,
+    /// 1-based line of the section's first line in the assembled source
+    first_line: usize,
+    line_count: usize,
+    /// 1-based line in the section's own text that `first_line` corresponds to
+    source_line: usize,
+}
+
+/// Builds an assembled shader while recording the line range of each section
+#[derive(Debug, Default)]
+pub struct SourceBuilder {
+    source: String,
+    sections: Vec<Section>,
+}
+
+impl SourceBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a section, always starting it on a fresh line
+    pub fn push(&mut self, name: &str, text: &str) {
+        self.push_from(name, text, 1);
+    }
+
+    /// Append a chunk of a section that starts at `source_line` of its text
+    pub fn push_from(&mut self, name: &str, text: &str, source_line: usize) {
+        if !self.source.is_empty() && !self.source.ends_with('\n') {
+            self.source.push('\n');
+        }
+        let first_line = self.source.matches('\n').count() + 1;
+        let line_count = text.lines().count().max(1);
+
+        self.source.push_str(text);
+        self.source.push('\n');
+        self.sections.push(Section {
+            name: name.to_string(),
+            first_line,
+            line_count,
+            source_line,
+        });
+    }
+
+    pub fn finish(self) -> (String, SourceMap) {
+        (
+            self.source,
+            SourceMap {
+                sections: self.sections,
+            },
+        )
+    }
+}
+
+/// Maps lines of an assembled shader back to the section they came from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    sections: Vec<Section>,
+}
+
+impl SourceMap {
+    /// Section name and 1-based line within it for a line of the assembled source
+    pub fn locate(&self, line: usize) -> Option<(&str, usize)> {
+        self.sections
+            .iter()
+            .find(|s| line >= s.first_line && line < s.first_line + s.line_count)
+            .map(|s| (s.name.as_str(), line - s.first_line + s.source_line))
+    }
+
+    /// Move every span of a diagnostic from assembled lines to section lines
+    pub fn relocate(&self, diagnostic: &mut Diagnostic) {
+        for span in diagnostic.spans_mut().filter(|span| span.section.is_none()) {
+            if let Some((name, line)) = self.locate(span.line) {
+                span.section = Some(name.to_string());
+                span.line = line;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> (String, SourceMap) {
+        let mut builder = SourceBuilder::new();
+        builder.push(
+            BOILERPLATE_SECTION,
+            "struct A { x: f32 }\nvar<private> a: A;",
+        );
+        builder.push("Common", "fn helper() -> f32 {\n    return 1.0;\n}");
+        builder.push(
+            "MainImage",
+            "fn main_fn() -> f32 {\n    return helper() + oops;\n}",
+        );
+        builder.finish()
+    }
+
+    #[test]
+    fn test_locate_sections() {
+        let (source, map) = sample();
+        assert_eq!(source.lines().nth(2), Some("fn helper() -> f32 {"));
+        assert_eq!(map.locate(1), Some((BOILERPLATE_SECTION, 1)));
+        assert_eq!(map.locate(4), Some(("Common", 2)));
+        assert_eq!(map.locate(7), Some(("MainImage", 2)));
+        assert_eq!(map.locate(40), None);
+    }
+
+    #[test]
+    fn test_locate_split_section() {
+        let mut builder = SourceBuilder::new();
+        builder.push_from("MainImage", "let a = 1;", 1);
+        builder.push("lib.wgsl", "fn lib() {}");
+        builder.push_from("MainImage", "let b = 2;\nlet c = 3;", 3);
+        let (_, map) = builder.finish();
+
+        assert_eq!(map.locate(2), Some(("lib.wgsl", 1)));
+        assert_eq!(map.locate(4), Some(("MainImage", 4)));
+    }
+
+    #[test]
+    fn test_relocate_naga_error() {
+        let (source, map) = sample();
+        let error = naga::front::wgsl::parse_str(&source).unwrap_err();
+        let mut diagnostic = Diagnostic::from_parse_error(&error, &source);
+        assert_eq!(diagnostic.primary_span().unwrap().line, 7);
+
+        map.relocate(&mut diagnostic);
+        let span = diagnostic.primary_span().unwrap();
+        assert_eq!(span.section.as_deref(), Some("MainImage"));
+        assert_eq!(span.line, 2);
+        assert_eq!(span.line_text, "    return helper() + oops;");
+    }
+}
@@ -0,0 +1,365 @@
+//! Standalone export of the assembled passes
+//!
+//! Writes one self-contained shader per pass — exactly what `gather_sources`
+//! hands the pipeline, with boilerplate, bindings, vertex shader and includes
+//! inlined — plus a `manifest.json` giving the pass order, render targets and
+//! what to bind where. GLSL output goes through naga's GLSL backend as one
+//! vertex and one fragment shader per pass.
+
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::utils::diagnostics::Diagnostic;
+use crate::utils::{BufferKind, ShaderError, DEFAULT_BUFFER_RESOLUTION};
+
+/// Order the pipeline renders passes in: buffers first, then MainImage
+pub const RENDER_ORDER: [BufferKind; 5] = [
+    BufferKind::BufferA,
+    BufferKind::BufferB,
+    BufferKind::BufferC,
+    BufferKind::BufferD,
+    BufferKind::MainImage,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StandaloneFormat {
+    Wgsl,
+    Glsl,
+}
+
+impl StandaloneFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StandaloneFormat::Wgsl => "wgsl",
+            StandaloneFormat::Glsl => "glsl",
+        }
+    }
+}
+
+/// A file of a standalone export, named relative to the export folder
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportFile {
+    pub name: String,
+    pub contents: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Manifest {
+    generator: &'static str,
+    format: &'static str,
+    /// Size of the offscreen buffer textures
+    resolution: [u32; 2],
+    uniforms: UniformsLayout,
+    /// In render order
+    passes: Vec<PassManifest>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct UniformsLayout {
+    size: u32,
+    fields: Vec<UniformField>,
+}
+
+#[derive(Debug, Serialize)]
+struct UniformField {
+    name: String,
+    offset: u32,
+    #[serde(rename = "type")]
+    ty: String,
+}
+
+#[derive(Debug, Serialize)]
+struct PassManifest {
+    name: &'static str,
+    files: Vec<String>,
+    vertex_entry: &'static str,
+    fragment_entry: &'static str,
+    /// `screen` for MainImage, otherwise the texture other passes sample
+    target: String,
+    bindings: Vec<BindingManifest>,
+}
+
+#[derive(Debug, Serialize)]
+struct BindingManifest {
+    group: u32,
+    binding: u32,
+    name: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    resource: String,
+    /// Name of the combined sampler or uniform block in the GLSL output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    glsl_name: Option<String>,
+}
+
+/// Export assembled pass sources as standalone shaders plus a manifest
+pub fn export(
+    sources: &HashMap<BufferKind, String>,
+    format: StandaloneFormat,
+) -> Result<Vec<ExportFile>, ShaderError> {
+    let mut files = Vec::new();
+    let mut passes = Vec::new();
+    let mut uniforms = UniformsLayout::default();
+
+    for kind in RENDER_ORDER {
+        let Some(source) = sources.get(&kind) else {
+            continue;
+        };
+        let failed = |what: &str, error: String| {
+            let diagnostic = Diagnostic::error(format!("{} failed\n\n{}", what, error)).in_buffer(kind);
+            ShaderError::CompilationError(Box::new(diagnostic))
+        };
+
+        let module = naga::front::wgsl::parse_str(source).map_err(|e| failed("Parsing the pass", e.message().to_string()))?;
+        let info = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&module)
+        .map_err(|e| failed("Validating the pass", e.as_inner().to_string()))?;
+
+        let mut glsl_names = HashMap::new();
+        let pass_files = match format {
+            StandaloneFormat::Wgsl => {
+                let name = format!("{}.wgsl", kind.file_stem());
+                files.push(ExportFile {
+                    name: name.clone(),
+                    contents: source.clone(),
+                });
+                vec![name]
+            }
+            StandaloneFormat::Glsl => {
+                let mut names = Vec::new();
+                for (stage, entry, extension) in [
+                    (naga::ShaderStage::Vertex, "vs_main", "vert"),
+                    (naga::ShaderStage::Fragment, "fs_main", "frag"),
+                ] {
+                    let (contents, reflection) = write_glsl(&module, &info, stage, entry)
+                        .map_err(|e| failed(&format!("GLSL export of {}", entry), e.to_string()))?;
+                    for (glsl_name, mapping) in reflection.texture_mapping {
+                        glsl_names.insert(mapping.texture, glsl_name.clone());
+                        if let Some(sampler) = mapping.sampler {
+                            glsl_names.insert(sampler, glsl_name);
+                        }
+                    }
+                    glsl_names.extend(reflection.uniforms);
+
+                    let name = format!("{}.{}", kind.file_stem(), extension);
+                    files.push(ExportFile {
+                        name: name.clone(),
+                        contents,
+                    });
+                    names.push(name);
+                }
+                names
+            }
+        };
+
+        if uniforms.fields.is_empty() {
+            uniforms = uniforms_layout(&module);
+        }
+        passes.push(PassManifest {
+            name: kind.as_str(),
+            files: pass_files,
+            vertex_entry: "vs_main",
+            fragment_entry: "fs_main",
+            target: match kind {
+                BufferKind::MainImage => "screen".to_string(),
+                other => format!("{}_texture", other.file_stem()),
+            },
+            bindings: bindings(&module, &glsl_names),
+        });
+    }
+
+    let manifest = Manifest {
+        generator: concat!("webshard_editor ", env!("CARGO_PKG_VERSION")),
+        format: format.as_str(),
+        resolution: DEFAULT_BUFFER_RESOLUTION,
+        uniforms,
+        passes,
+    };
+    files.push(ExportFile {
+        name: "manifest.json".to_string(),
+        contents: serde_json::to_string_pretty(&manifest).unwrap_or_default(),
+    });
+    Ok(files)
+}
+
+fn write_glsl(
+    module: &naga::Module,
+    info: &naga::valid::ModuleInfo,
+    shader_stage: naga::ShaderStage,
+    entry_point: &str,
+) -> Result<(String, naga::back::glsl::ReflectionInfo), naga::back::glsl::Error> {
+    let options = naga::back::glsl::Options {
+        version: naga::back::glsl::Version::Desktop(450),
+        ..Default::default()
+    };
+    let pipeline_options = naga::back::glsl::PipelineOptions {
+        shader_stage,
+        entry_point: entry_point.to_string(),
+        multiview: None,
+    };
+
+    let mut out = String::new();
+    let reflection = naga::back::glsl::Writer::new(
+        &mut out,
+        module,
+        info,
+        &options,
+        &pipeline_options,
+        naga::proc::BoundsCheckPolicies::default(),
+    )?
+    .write()?;
+    Ok((out, reflection))
+}
+
+/// Resource bindings declared by a pass
+fn bindings(
+    module: &naga::Module,
+    glsl_names: &HashMap<naga::Handle<naga::GlobalVariable>, String>,
+) -> Vec<BindingManifest> {
+    let mut bindings: Vec<BindingManifest> = module
+        .global_variables
+        .iter()
+        .filter_map(|(handle, var)| {
+            let binding = var.binding.as_ref()?;
+            let name = var.name.clone().unwrap_or_default();
+            let kind = match module.types[var.ty].inner {
+                naga::TypeInner::Image { dim: naga::ImageDimension::Cube, .. } => "texture_cube",
+                naga::TypeInner::Image { dim: naga::ImageDimension::D3, .. } => "texture_3d",
+                naga::TypeInner::Image { .. } => "texture_2d",
+                naga::TypeInner::Sampler { .. } => "sampler",
+                _ if var.space == naga::AddressSpace::Uniform => "uniform_buffer",
+                _ => "other",
+            };
+            Some(BindingManifest {
+                group: binding.group,
+                binding: binding.binding,
+                resource: resource(&name),
+                name,
+                kind,
+                glsl_name: glsl_names.get(&handle).cloned(),
+            })
+        })
+        .collect();
+    bindings.sort_by_key(|b| (b.group, b.binding));
+    bindings
+}
+
+/// What the editor binds to a resource, by its boilerplate name
+fn resource(name: &str) -> String {
+    if name == "uniforms" {
+        return "Uniforms buffer, see `uniforms`".to_string();
+    }
+    if let Some(channel) = name.strip_prefix("iChannel") {
+        return match channel.strip_suffix("Sampler") {
+            Some(index) => format!("Sampler for image channel {}", index),
+            None => format!("Image channel {} (user texture)", channel),
+        };
+    }
+    if let Some(buffer) = name.strip_prefix("buffer_") {
+        let letter = buffer.chars().next().unwrap_or('?').to_ascii_uppercase();
+        if buffer.ends_with("_texture") {
+            return format!("Buffer{} output from this frame", letter);
+        }
+        if buffer.ends_with("_sampler") {
+            return format!("Sampler for Buffer{}", letter);
+        }
+    }
+    String::new()
+}
+
+/// Byte layout of the `Uniforms` struct, for filling the uniform buffer
+fn uniforms_layout(module: &naga::Module) -> UniformsLayout {
+    let Some(naga::TypeInner::Struct { members, span }) = module
+        .types
+        .iter()
+        .find(|(_, ty)| ty.name.as_deref() == Some("Uniforms"))
+        .map(|(_, ty)| &ty.inner)
+    else {
+        return UniformsLayout::default();
+    };
+
+    UniformsLayout {
+        size: *span,
+        fields: members
+            .iter()
+            .map(|member| UniformField {
+                name: member.name.clone().unwrap_or_default(),
+                offset: member.offset,
+                ty: type_name(&module.types[member.ty].inner),
+            })
+            .collect(),
+    }
+}
+
+fn type_name(inner: &naga::TypeInner) -> String {
+    let scalar_name = |scalar: &naga::Scalar| match scalar.kind {
+        naga::ScalarKind::Float => "f32",
+        naga::ScalarKind::Sint => "i32",
+        naga::ScalarKind::Uint => "u32",
+        naga::ScalarKind::Bool => "bool",
+        _ => "abstract",
+    };
+    match inner {
+        naga::TypeInner::Scalar(scalar) => scalar_name(scalar).to_string(),
+        naga::TypeInner::Vector { size, scalar } => {
+            format!("vec{}<{}>", *size as u8, scalar_name(scalar))
+        }
+        other => format!("{:?}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::ShaderCompiler;
+    use crate::screens::shader_buffer::ShaderBuffer;
+    use crate::utils::{DEFAULT_FRAGMENT, DEFAULT_VERTEX};
+
+    fn sources() -> HashMap<BufferKind, String> {
+        let mut buffers = HashMap::new();
+        for kind in [BufferKind::MainImage, BufferKind::BufferA] {
+            buffers.insert(
+                kind,
+                ShaderBuffer::new(kind, DEFAULT_VERTEX.to_string(), DEFAULT_FRAGMENT.to_string()),
+            );
+        }
+        ShaderCompiler::new().gather_sources(&buffers, "", Default::default()).unwrap()
+    }
+
+    #[test]
+    fn test_wgsl_export_with_manifest() {
+        let sources = sources();
+        let files = export(&sources, StandaloneFormat::Wgsl).unwrap();
+        let names: Vec<&str> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["buffer_a.wgsl", "main_image.wgsl", "manifest.json"]);
+        assert_eq!(files[1].contents, sources[&BufferKind::MainImage]);
+
+        let manifest: serde_json::Value = serde_json::from_str(&files[2].contents).unwrap();
+        assert_eq!(manifest["passes"][0]["name"], "BufferA");
+        assert_eq!(manifest["passes"][0]["target"], "buffer_a_texture");
+        assert_eq!(manifest["passes"][1]["target"], "screen");
+
+        let bindings = manifest["passes"][1]["bindings"].as_array().unwrap();
+        let buffer_a = bindings.iter().find(|b| b["name"] == "buffer_a_texture").unwrap();
+        assert_eq!((buffer_a["group"].as_u64(), buffer_a["binding"].as_u64()), (Some(1), Some(0)));
+
+        let fields = manifest["uniforms"]["fields"].as_array().unwrap();
+        let resolution = fields.iter().find(|f| f["name"] == "resolution").unwrap();
+        assert_eq!((resolution["offset"].as_u64(), resolution["type"].as_str()), (Some(16), Some("vec2<f32>")));
+    }
+
+    #[test]
+    fn test_glsl_export() {
+        let files = export(&sources(), StandaloneFormat::Glsl).unwrap();
+        let fragment = files.iter().find(|f| f.name == "main_image.frag").unwrap();
+        assert!(fragment.contents.starts_with("#version 450"), "{}", fragment.contents);
+        assert!(fragment.contents.contains("void main()"));
+        assert!(files.iter().any(|f| f.name == "buffer_a.vert"));
+
+        let manifest = &files.last().unwrap().contents;
+        assert!(manifest.contains("\"glsl_name\""), "{}", manifest);
+    }
+}
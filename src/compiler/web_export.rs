@@ -0,0 +1,276 @@
+//! Standalone WebGPU HTML export
+//!
+//! Writes a shard as a single HTML page that plays it in the browser: the
+//! assembled pass sources, the iChannel images as data URLs and the
+//! `ShaderUniforms` layout are embedded as JSON next to a small WebGPU player
+//! (`assets/web_player.html`) that mirrors `MultiPassPipelines`. The output
+//! depends only on the shard, so it can be snapshot-tested.
+
+use serde::Serialize;
+use std::mem::offset_of;
+
+use crate::compiler::standalone::RENDER_ORDER;
+use crate::utils::channel_texture::{ChannelFormat, ChannelKind};
+use crate::utils::pipeline::ShaderUniforms;
+use crate::utils::{validate_shader, BufferKind, ShaderError, ShaderJson, DEFAULT_BUFFER_RESOLUTION};
+
+const PLAYER_TEMPLATE: &str = include_str!("../assets/web_player.html");
+
+#[derive(Debug, Serialize)]
+struct WebShard {
+    buffer_resolution: [u32; 2],
+    gamma: f32,
+    contrast: f32,
+    saturation: f32,
+    uniforms: UniformsLayout,
+    /// In render order
+    passes: Vec<WebPass>,
+    /// iChannel0-3 as data URLs
+    channels: [Option<String>; 4],
+    /// WebGPU texture format of each channel
+    channel_formats: [&'static str; 4],
+    /// Sampler and orientation of each channel
+    channel_samplers: [WebSampler; 4],
+}
+
+#[derive(Debug, Serialize)]
+struct WebSampler {
+    address_mode: &'static str,
+    filter: &'static str,
+    flip_y: bool,
+}
+
+/// Size and field offsets of `ShaderUniforms`, in bytes and in f32s
+#[derive(Debug, Serialize)]
+struct UniformsLayout {
+    size: usize,
+    offsets: UniformOffsets,
+}
+
+#[derive(Debug, Serialize)]
+struct UniformOffsets {
+    time: usize,
+    audio_bass: usize,
+    audio_mid: usize,
+    audio_high: usize,
+    resolution: usize,
+    gamma: usize,
+    contrast: usize,
+    saturation: usize,
+    mouse: usize,
+    time_delta: usize,
+    frame: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct WebPass {
+    name: &'static str,
+    /// `screen` for MainImage, otherwise the offscreen texture's name
+    target: String,
+    code: String,
+    vertex_count: u32,
+    instance_count: u32,
+    /// WebGPU primitive topology, e.g. `triangle-list`
+    topology: &'static str,
+    depth: bool,
+}
+
+/// Render a shard as a self-contained WebGPU HTML page
+pub fn export_html(shader: &ShaderJson, title: &str) -> Result<String, ShaderError> {
+    // The player only creates 2D textures from the embedded images
+    if let Some((index, settings)) = (0..4)
+        .map(|i| (i, shader.channel_settings(i)))
+        .find(|(_, settings)| settings.kind != ChannelKind::Texture2d)
+    {
+        return Err(ShaderError::validation(format!(
+            "iChannel{} is a {} channel; the web export only supports 2D channels",
+            index,
+            settings.kind.as_str()
+        )));
+    }
+
+    let sources = shader.to_shader_map();
+    let mut passes = Vec::new();
+    for kind in RENDER_ORDER {
+        let Some(code) = sources.get(&kind) else {
+            continue;
+        };
+        validate_shader(code).map_err(|err| err.in_buffer(kind))?;
+        let geometry = shader.geometry_for(kind);
+        passes.push(WebPass {
+            name: kind.as_str(),
+            target: match kind {
+                BufferKind::MainImage => "screen".to_string(),
+                other => format!("{}_texture", other.file_stem()),
+            },
+            code: code.clone(),
+            vertex_count: geometry.vertex_count,
+            instance_count: geometry.instance_count,
+            topology: geometry.topology.web_name(),
+            depth: geometry.depth,
+        });
+    }
+
+    // Procedural channels are generated here, so the page needs no generators of its own
+    let mut images = shader.decode_embedded_images();
+    for (i, image) in images.iter_mut().enumerate() {
+        if let Some(procedural) = shader.channel_settings(i).procedural {
+            *image = Some(procedural.encode_png().map_err(ShaderError::validation)?);
+        }
+    }
+    let mut channel_formats = ["rgba8unorm-srgb"; 4];
+    for (i, image) in images.iter().enumerate() {
+        let hdr = image.as_deref().is_some_and(|bytes| {
+            matches!(image::guess_format(bytes), Ok(image::ImageFormat::OpenExr | image::ImageFormat::Hdr))
+        });
+        let settings = shader.channel_settings(i);
+        let format = match settings.procedural {
+            Some(procedural) => procedural.resolve_format(settings.format, ChannelKind::Texture2d),
+            None => settings.format.resolve(ChannelKind::Texture2d, hdr),
+        };
+        // Browsers only decode 8-bit images into textures
+        channel_formats[i] = match format {
+            ChannelFormat::Linear => "rgba8unorm",
+            format if format.is_float() => {
+                return Err(ShaderError::validation(format!(
+                    "iChannel{} is a {} channel; the web export only supports sRGB and Linear channels",
+                    i,
+                    format.as_str()
+                )));
+            }
+            _ => "rgba8unorm-srgb",
+        };
+    }
+
+    let channel_samplers = std::array::from_fn(|i| {
+        let settings = shader.channel_settings(i);
+        WebSampler {
+            address_mode: settings.wrap.web_name(),
+            filter: settings.filter.web_name(),
+            flip_y: settings.flip_y,
+        }
+    });
+
+    let channels = images.map(|image| {
+        let bytes = image?;
+        let mime = image::guess_format(&bytes)
+            .map(|format| format.to_mime_type())
+            .unwrap_or("image/png");
+        let data = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &bytes);
+        Some(format!("data:{};base64,{}", mime, data))
+    });
+
+    let f32s = std::mem::size_of::<f32>();
+    let shard = WebShard {
+        buffer_resolution: DEFAULT_BUFFER_RESOLUTION,
+        gamma: shader.gamma.unwrap_or(1.0),
+        contrast: shader.contrast.unwrap_or(1.0),
+        saturation: shader.saturation.unwrap_or(1.0),
+        uniforms: UniformsLayout {
+            size: std::mem::size_of::<ShaderUniforms>(),
+            offsets: UniformOffsets {
+                time: offset_of!(ShaderUniforms, time) / f32s,
+                audio_bass: offset_of!(ShaderUniforms, audio_bass) / f32s,
+                audio_mid: offset_of!(ShaderUniforms, audio_mid) / f32s,
+                audio_high: offset_of!(ShaderUniforms, audio_high) / f32s,
+                resolution: offset_of!(ShaderUniforms, resolution) / f32s,
+                gamma: offset_of!(ShaderUniforms, gamma) / f32s,
+                contrast: offset_of!(ShaderUniforms, contrast) / f32s,
+                saturation: offset_of!(ShaderUniforms, saturation) / f32s,
+                mouse: offset_of!(ShaderUniforms, mouse) / f32s,
+                time_delta: offset_of!(ShaderUniforms, time_delta) / f32s,
+                frame: offset_of!(ShaderUniforms, frame) / f32s,
+            },
+        },
+        passes,
+        channels,
+        channel_formats,
+        channel_samplers,
+    };
+
+    // `<` only occurs inside JSON strings, so escaping it keeps shader code
+    // such as `a</b` from closing the script element early
+    let json = serde_json::to_string_pretty(&shard)
+        .unwrap_or_default()
+        .replace('<', "\\u003c");
+
+    Ok(PLAYER_TEMPLATE
+        .replace("{{TITLE}}", &escape_html(title))
+        .replace("{{SHARD}}", &json))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::procedural_texture::{Pattern, ProceduralTexture};
+
+    fn shard_json(html: &str) -> serde_json::Value {
+        let start = html.find("const SHARD = ").unwrap() + "const SHARD = ".len();
+        let end = start + html[start..].find("};\n").unwrap() + 1;
+        serde_json::from_str(&html[start..end]).unwrap()
+    }
+
+    #[test]
+    fn test_export_is_deterministic_and_complete() {
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(2, 2, image::Rgba([255, 0, 0, 255]))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let mut shader = ShaderJson::from_json(include_str!("../assets/shards/default.json")).unwrap();
+        shader.ichannel1 = Some(base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &png));
+
+        let html = export_html(&shader, "Default <demo>").unwrap();
+        assert_eq!(html, export_html(&shader, "Default <demo>").unwrap());
+        assert!(html.contains("<title>Default &lt;demo&gt;</title>"));
+        assert_eq!(html.matches("</script>").count(), 1);
+
+        let shard = shard_json(&html);
+        let names: Vec<&str> = shard["passes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["BufferA", "BufferB", "BufferC", "BufferD", "MainImage"]);
+        assert_eq!(shard["passes"][4]["target"], "screen");
+        assert_eq!(shard["passes"][4]["vertex_count"], 6);
+        assert_eq!(shard["passes"][4]["topology"], "triangle-list");
+        assert!(shard["passes"][4]["code"].as_str().unwrap().contains("buffer_a_texture"));
+
+        assert!(shard["channels"][0].is_null());
+        assert!(shard["channels"][1].as_str().unwrap().starts_with("data:image/png;base64,"));
+        assert_eq!(shard["channel_formats"][1], "rgba8unorm-srgb");
+        assert_eq!(shard["channel_samplers"][1]["address_mode"], "clamp-to-edge");
+        assert_eq!(shard["channel_samplers"][1]["flip_y"], false);
+
+        let mut noise = shader.clone();
+        let settings = noise.channels.get_or_insert_with(Default::default).entry("ichannel2".to_string()).or_default();
+        settings.procedural = Some(ProceduralTexture {
+            pattern: Pattern::BlueNoise,
+            size: [16, 16],
+            ..Default::default()
+        });
+        let shard = shard_json(&export_html(&noise, "Noise").unwrap());
+        assert!(shard["channels"][2].as_str().unwrap().starts_with("data:image/png;base64,"));
+        assert_eq!(shard["channel_formats"][2], "rgba8unorm");
+
+        assert_eq!(shard["uniforms"]["size"], std::mem::size_of::<ShaderUniforms>());
+        assert_eq!(shard["uniforms"]["offsets"]["resolution"], 4);
+        assert_eq!(shard["uniforms"]["offsets"]["mouse"], 16);
+        assert_eq!(shard["uniforms"]["offsets"]["frame"], 21);
+    }
+
+    #[test]
+    fn test_invalid_pass_is_reported() {
+        let shader = ShaderJson::from_json(r#"{"fragment": "@fragment fn fs_main() -> @location(0) vec4<f32> { return oops; }"}"#).unwrap();
+        let err = export_html(&shader, "broken").unwrap_err();
+        assert_eq!(err.diagnostic().buffer, Some(BufferKind::MainImage));
+    }
+}
@@ -35,6 +35,18 @@ fn main() {
 
     log::info!("Application starting...");
 
+    // RGBA32F iChannels need a filterable float texture; request it where the adapter has it
+    let default_setup = eframe::egui_wgpu::WgpuSetupCreateNew::default();
+    let default_device_descriptor = default_setup.device_descriptor.clone();
+    let wgpu_setup = eframe::egui_wgpu::WgpuSetup::CreateNew(eframe::egui_wgpu::WgpuSetupCreateNew {
+        device_descriptor: std::sync::Arc::new(move |adapter| {
+            let mut descriptor = default_device_descriptor(adapter);
+            descriptor.required_features |= adapter.features() & eframe::wgpu::Features::FLOAT32_FILTERABLE;
+            descriptor
+        }),
+        ..default_setup
+    });
+
     let mut native_options = NativeOptions {
         renderer: eframe::Renderer::Wgpu,
         vsync: false,
@@ -44,6 +56,7 @@ fn main() {
                 log::error!("WGPU surface error: {}", err);
                 eframe::egui_wgpu::SurfaceErrorAction::SkipFrame
             }),
+            wgpu_setup,
             ..Default::default()
         },
         ..Default::default()
@@ -1,22 +1,59 @@
 use eframe::egui;
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
+use crate::compiler::preprocessor::IncludeError;
+use crate::compiler::glsl_import;
+use crate::compiler::standalone::{self, StandaloneFormat};
+use crate::compiler::web_export;
 use crate::compiler::ShaderCompiler;
 use crate::screens::shader_buffer::ShaderBuffer;
-use crate::ui_components::{settings_menu, shader_properties};
+use crate::ui_components::shader_editor::{self, EditorAction};
+use crate::ui_components::{channels_panel, settings_menu, shader_properties};
+use crate::utils::camera::OrbitCamera;
+use crate::utils::channel_animation;
+use crate::utils::channel_texture::{self, ChannelInput};
+use crate::utils::diagnostics::{Diagnostic, InlineMarker};
+use crate::utils::file_watcher::{FileWatcher, WatchTarget};
+use crate::utils::gpu_errors;
+use crate::utils::mesh;
+use crate::utils::pipeline::PreviewMouse;
+use crate::utils::shader_json::EditorState;
+use crate::utils::shard_bundle::{self, BundleAsset, ShardBundle};
+use crate::utils::video_capture::{self, LiveCapture};
+use crate::utils::wgsl_symbols::{self, Reference, SymbolIndex, SymbolKind};
 use crate::utils::{
     catch_panic_mut, format_panic_message, format_shader_error, BufferKind,
-    MultiPassCallback, NotificationManager, ShaderJson,
-    DEFAULT_FONT_SIZE, DEFAULT_VERTEX, STANDARD_VERTEX,
+    MultiPassCallback, NotificationManager, ShaderError, ShaderJson, SourceKind,
+    DEFAULT_COMMON, DEFAULT_FONT_SIZE, DEFAULT_VERTEX,
 };
 
+/// Editor id of the Common tab
+const COMMON_EDITOR_ID: &str = "common_frag";
+
+/// Pending rename started with F2
+struct RenameRequest {
+    source: SourceKind,
+    at: usize,
+    old_name: String,
+    new_name: String,
+    kind: SymbolKind,
+    module_scope: bool,
+    all_sources: bool,
+}
+
 pub struct TopApp {
     // Unified buffer system - single HashMap instead of 5 separate fields
     buffers: HashMap<BufferKind, ShaderBuffer>,
     current_buffer: BufferKind,
 
+    // Shared code injected into every pass, shown as the sixth tab
+    common_code: String,
+    editing_common: bool,
+
     saved_shaders: Option<HashMap<BufferKind, (String, String)>>,
+    saved_common: Option<String>,
 
     // Shader compiler module
     compiler: ShaderCompiler,
@@ -25,11 +62,29 @@ pub struct TopApp {
     // UI state
     editor_font_size: f32,
     show_settings: bool,
+    library_dirs: Vec<PathBuf>,
+    inline_includes_on_export: bool,
+    // Reject shards with unknown fields or broken base64 instead of loading what we can
+    strict_shard_import: bool,
+    // Thumbnail of the last imported bundle, written back when exporting one
+    bundle_thumbnail: Option<Vec<u8>>,
+    // Pass whose draw settings the Shader Properties window shows
+    geometry_pass: BufferKind,
+    // Camera of mesh passes, orbited by dragging the preview
+    camera: OrbitCamera,
+    // Primary button state over the preview, for iMouse
+    preview_mouse: PreviewMouse,
     show_error_window: bool,
     error_message: String,
+    // Diagnostics of the last failed compilation, underlined in the editor
+    diagnostics: Vec<Diagnostic>,
     show_preset_menu: bool,
     show_presets_window: bool,
 
+    // Symbol navigation: find references results and pending rename
+    references: Option<(String, Vec<Reference>)>,
+    rename_request: Option<RenameRequest>,
+
     // Audio - FFT energy values
     bass_energy: Arc<Mutex<f32>>,
     mid_energy: Arc<Mutex<f32>>,
@@ -39,8 +94,15 @@ pub struct TopApp {
     debug_mid: f32,
     debug_high: f32,
     audio_file_path: Option<String>,
-    image_file_paths: [Option<String>; 4], // Support up to 4 image channels (iChannel0-3)
-    selected_image_channel: usize, // Which channel to load next image into (0-3)
+    channels: [ChannelInput; 4], // Files and texture types of iChannel0-3
+    selected_image_channel: usize, // Channel shown in the Channels panel (0-3)
+    capture_picker: channels_panel::CapturePicker, // Cameras listed in the Channels panel
+    show_channels_panel: bool,
+    channel_previews: channels_panel::ChannelPreviews, // Thumbnails of the Channels panel
+
+    // Hot reload of files loaded from disk
+    file_watcher: FileWatcher,
+    linked_shard: Option<PathBuf>, // Shard JSON re-imported whenever it is saved
 
     // Rendering adjustments
     gamma: Arc<Mutex<f32>>,
@@ -71,7 +133,7 @@ impl TopApp {
             BufferKind::MainImage,
             ShaderBuffer::new(
                 BufferKind::MainImage,
-                default_shader.vertex.clone().unwrap_or_else(|| DEFAULT_VERTEX.to_string()),
+                default_shader.vertex_for(BufferKind::MainImage).unwrap_or(DEFAULT_VERTEX).to_string(),
                 default_shader.fragment.clone(),
             ),
         );
@@ -116,21 +178,40 @@ impl TopApp {
             ),
         );
 
+        let compiler = ShaderCompiler::new();
+        let library_dirs = compiler.library().dirs().to_vec();
+
         let mut app = Self {
             buffers,
             current_buffer: BufferKind::MainImage,
+
+            common_code: DEFAULT_COMMON.to_string(),
+            editing_common: false,
+
             saved_shaders: None,
+            saved_common: None,
 
-            compiler: ShaderCompiler::new(),
+            compiler,
             target_format: None,
 
             editor_font_size: DEFAULT_FONT_SIZE,
             show_settings: false,
+            library_dirs,
+            inline_includes_on_export: true,
+            strict_shard_import: false,
+            bundle_thumbnail: None,
+            geometry_pass: BufferKind::MainImage,
+            camera: OrbitCamera::default(),
+            preview_mouse: PreviewMouse::default(),
             show_error_window: false,
             error_message: String::new(),
+            diagnostics: Vec::new(),
             show_preset_menu: false,
             show_presets_window: false,
 
+            references: None,
+            rename_request: None,
+
             bass_energy: Arc::new(Mutex::new(0.0)),
             mid_energy: Arc::new(Mutex::new(0.0)),
             high_energy: Arc::new(Mutex::new(0.0)),
@@ -139,8 +220,14 @@ impl TopApp {
             debug_mid: 0.0,
             debug_high: 0.0,
             audio_file_path: None,
-            image_file_paths: [None, None, None, None],
+            channels: Default::default(),
             selected_image_channel: 0,
+            capture_picker: Default::default(),
+            show_channels_panel: false,
+            channel_previews: Default::default(),
+
+            file_watcher: FileWatcher::new(),
+            linked_shard: None,
 
             gamma: Arc::new(Mutex::new(1.0)),  // Default: no gamma correction (matches player)
             contrast: Arc::new(Mutex::new(1.0)),  // Default: normal contrast
@@ -154,15 +241,25 @@ impl TopApp {
         // Load default shader into MainImage on startup
         app.load_preset_shader("default");
 
+        // Restore the session saved before a lost GPU device restarted the editor
+        if let Some(session) = std::env::var_os(gpu_errors::RECOVERY_ENV) {
+            let session = std::path::PathBuf::from(session);
+            log::info!("Restoring session {:?} after a GPU device loss", session);
+            app.import_shard_file(&session);
+            let _ = std::fs::remove_file(&session);
+        }
+
         // Compile initial shader
         if let Some(render_state) = cc.wgpu_render_state.as_ref() {
             let format = render_state.target_format;
             app.target_format = Some(format);
 
             // Use compiler module for initial compilation
+            app.compiler.check_device(&render_state.device);
             let _ = app.compiler.compile_if_needed(
                 &app.buffers,
-                &app.image_file_paths,
+                &app.common_code,
+                &app.channels,
                 &render_state.device,
                 &render_state.queue,
                 format,
@@ -187,28 +284,48 @@ impl eframe::App for TopApp {
 
         // Handle shader compilation if needed (using compiler module)
         if let Some(render_state) = frame.wgpu_render_state() {
+            // GPU errors raised outside pipeline creation, and device loss
+            for err in self.compiler.check_device(&render_state.device) {
+                self.notification_mgr.error(err.diagnostic().summary());
+                if self.compiler.device_lost() {
+                    self.error_message = format_shader_error(&err);
+                    self.diagnostics = vec![err.diagnostic().clone()];
+                    self.show_error_window = true;
+                }
+            }
+            if self.compiler.device_lost() {
+                self.render_device_lost_window(ctx);
+            }
+
             match self.compiler.compile_if_needed(
                 &self.buffers,
-                &self.image_file_paths,
+                &self.common_code,
+                &self.channels,
                 &render_state.device,
                 &render_state.queue,
                 render_state.target_format,
             ) {
                 Ok(true) => {
                     // Success: pipeline compiled
+                    self.diagnostics.clear();
                     self.notification_mgr.dismiss_all();
                     self.notification_mgr.success("Multi-pass shader compiled successfully!");
                 }
                 Err(err) => {
                     // Compilation error
-                    let formatted = format_shader_error(err.error());
-                    self.error_message = formatted;
+                    let diagnostic = err.error().diagnostic();
+                    self.notification_mgr.dismiss_errors();
+                    self.notification_mgr.error(diagnostic.summary());
+                    self.error_message = format_shader_error(err.error());
+                    self.diagnostics = vec![diagnostic.clone()];
                     self.show_error_window = true;
                 }
                 Ok(false) => {
                     // No compilation needed
                 }
             }
+
+            self.reload_changed_files(&render_state.queue);
         }
 
         // Main layout: SidePanel (left) + CentralPanel (right)
@@ -237,7 +354,14 @@ impl eframe::App for TopApp {
             &self.gamma,
             &self.contrast,
             &self.saturation,
+            &mut self.library_dirs,
+            &mut self.inline_includes_on_export,
+            &mut self.strict_shard_import,
         );
+        if self.library_dirs != self.compiler.library().dirs() {
+            log::info!("Shader library directories changed: {:?}", self.library_dirs);
+            self.compiler.set_library_dirs(self.library_dirs.clone());
+        }
         // Font size changes propagated automatically - no need to update individual tabs
         if (self.editor_font_size - old_font_size).abs() > 0.01 {
             log::debug!("Font size changed to {}", self.editor_font_size);
@@ -248,14 +372,21 @@ impl eframe::App for TopApp {
             self.render_presets_window(ctx);
         }
 
+        // Symbol navigation windows
+        if self.references.is_some() {
+            self.render_references_window(ctx);
+        }
+        if self.rename_request.is_some() {
+            self.render_rename_window(ctx);
+        }
+
         // Shader Properties window
         if self.show_preset_menu {
             let action = shader_properties::render(
                 ctx,
                 &mut self.show_preset_menu,
                 &self.audio_file_path,
-                &self.image_file_paths,
-                &mut self.selected_image_channel,
+                &self.linked_shard,
                 &mut self.debug_audio,
                 &mut self.debug_bass,
                 &mut self.debug_mid,
@@ -263,25 +394,116 @@ impl eframe::App for TopApp {
                 &self.bass_energy,
                 &self.mid_energy,
                 &self.high_energy,
+                &mut self.geometry_pass,
+                &mut self.buffers,
             );
 
             match action {
                 shader_properties::ShaderPropertiesAction::LoadAudioFile(path) => {
                     self.load_audio_file(path);
                 }
-                shader_properties::ShaderPropertiesAction::LoadImageFile(channel, path) => {
-                    self.load_image_file(channel, path);
-                }
                 shader_properties::ShaderPropertiesAction::ExportShard => {
                     self.export_shard();
                 }
                 shader_properties::ShaderPropertiesAction::ImportShard => {
                     self.import_shard();
                 }
+                shader_properties::ShaderPropertiesAction::LinkShard => {
+                    self.link_shard();
+                }
+                shader_properties::ShaderPropertiesAction::UnlinkShard => {
+                    if let Some(path) = self.linked_shard.take() {
+                        log::info!("Unlinked shard {:?}", path);
+                    }
+                }
+                shader_properties::ShaderPropertiesAction::ImportGlsl => {
+                    self.import_glsl();
+                }
+                shader_properties::ShaderPropertiesAction::ExportStandalone(format) => {
+                    self.export_standalone(format);
+                }
+                shader_properties::ShaderPropertiesAction::ExportWeb => {
+                    self.export_web();
+                }
+                shader_properties::ShaderPropertiesAction::ConvertShard => {
+                    self.convert_shard();
+                }
+                shader_properties::ShaderPropertiesAction::GeometryChanged => {
+                    self.compiler.trigger_compilation();
+                }
+                shader_properties::ShaderPropertiesAction::LoadMesh(kind, path) => {
+                    self.load_mesh_file(kind, path);
+                }
+                shader_properties::ShaderPropertiesAction::ClearMesh(kind) => {
+                    if let Some(buffer) = self.buffers.get_mut(&kind) {
+                        buffer.mesh = None;
+                        self.compiler.trigger_compilation();
+                    }
+                }
                 shader_properties::ShaderPropertiesAction::None => {}
             }
         }
 
+        // Channels panel; dragging files over the window opens it to drop them on a slot
+        if ctx.input(|i| !i.raw.hovered_files.is_empty() || !i.raw.dropped_files.is_empty()) {
+            self.show_channels_panel = true;
+        }
+        if self.show_channels_panel {
+            if let Some(render_state) = frame.wgpu_render_state() {
+                let pipeline = self.compiler.pipeline().lock().unwrap().clone();
+                self.channel_previews.sync(render_state, pipeline.as_ref());
+            }
+            let action = channels_panel::render(
+                ctx,
+                &mut self.show_channels_panel,
+                &mut self.channels,
+                &mut self.selected_image_channel,
+                &mut self.capture_picker,
+                &self.channel_previews,
+            );
+
+            match action {
+                channels_panel::ChannelsPanelAction::LoadImageFile(channel, path) => {
+                    self.load_image_file(channel, path);
+                }
+                channels_panel::ChannelsPanelAction::LoadCubeFaces(channel, paths) => {
+                    self.load_cube_faces(channel, paths);
+                }
+                channels_panel::ChannelsPanelAction::ClearChannel(channel) => {
+                    self.stop_capture(channel);
+                    self.channels[channel].path = None;
+                    self.channels[channel].settings.procedural = None;
+                    self.compiler.trigger_compilation();
+                    log::info!("Cleared iChannel{}", channel);
+                }
+                channels_panel::ChannelsPanelAction::RefreshCaptureDevices => {
+                    self.capture_picker.devices = video_capture::list_devices();
+                    if self.capture_picker.devices.is_empty() {
+                        self.notification_mgr.warning("No cameras found");
+                    }
+                }
+                channels_panel::ChannelsPanelAction::StartCapture(channel, path, size) => {
+                    self.start_capture(channel, path, size);
+                }
+                channels_panel::ChannelsPanelAction::StopCapture(channel) => {
+                    self.stop_capture(channel);
+                    self.channels[channel].path = None;
+                    self.compiler.trigger_compilation();
+                }
+                channels_panel::ChannelsPanelAction::ChannelSettingsChanged => {
+                    // A generated texture replaces the channel's file or camera
+                    for channel in 0..4 {
+                        if self.channels[channel].settings.procedural.is_some() && self.channels[channel].path.is_some() {
+                            self.stop_capture(channel);
+                            self.channels[channel].path = None;
+                        }
+                    }
+                    self.compiler.trigger_compilation();
+                }
+                channels_panel::ChannelsPanelAction::None => {}
+            }
+        }
+
         // Toast notifications
         if self.notification_mgr.has_notifications() {
             egui::Window::new("")
@@ -328,10 +550,44 @@ impl eframe::App for TopApp {
                     ui.add_space(5.0);
 
                     ui.horizontal(|ui| {
-                        ui.add_space(ui.available_width() - 70.0);
-                        if ui.button("  Close  ").clicked() {
-                            self.show_error_window = false;
+                        // Jump to the primary location if it is in an editable source
+                        let target = self.diagnostics.first().and_then(|d| {
+                            let span = d.primary_span()?;
+                            let source = SourceKind::ALL
+                                .into_iter()
+                                .find(|kind| Some(kind.as_str()) == span.section.as_deref())?;
+                            let range = span.range_in(self.source_code(source)?)?;
+                            Some((span.location(), source, range))
+                        });
+                        if let Some((location, source, range)) = target {
+                            if ui.button(format!("→ Go to {}", location)).clicked() {
+                                self.go_to_location(ctx, source, range);
+                                self.show_error_window = false;
+                            }
+                        }
+                        if let Some((_, _, message)) = self.quick_fix() {
+                            if ui
+                                .button("🔧 Apply fix")
+                                .on_hover_text(format!("{} (Ctrl+.)", message))
+                                .clicked()
+                            {
+                                self.apply_quick_fix();
+                            }
+                        }
+                        if let Some(diagnostic) = self.diagnostics.first() {
+                            if ui
+                                .button("Copy as JSON")
+                                .on_hover_text("Copy the structured diagnostic for tooling or bug reports")
+                                .clicked()
+                            {
+                                ctx.copy_text(diagnostic.to_json());
+                            }
                         }
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.button("  Close  ").clicked() {
+                                self.show_error_window = false;
+                            }
+                        });
                     });
                 });
         }
@@ -354,24 +610,30 @@ impl TopApp {
                     egui::Color32::from_rgb(35, 35, 40);
 
                 let tab_h = 36.0;
-                let total_tabs = 5.0;
+                let total_tabs = SourceKind::ALL.len() as f32;
                 let tab_width = (ui.available_width() - (total_tabs - 1.0) * 4.0) / total_tabs;
 
                 // Render tabs for all buffers
-                for (i, kind) in [BufferKind::MainImage, BufferKind::BufferA, BufferKind::BufferB, BufferKind::BufferC, BufferKind::BufferD].iter().enumerate() {
+                for (i, kind) in SourceKind::ALL.iter().enumerate() {
                     if i > 0 {
                         ui.add_space(4.0);
                     }
 
-                    let is_selected = self.current_buffer == *kind;
+                    let is_selected = self.current_source() == *kind;
                     let button = egui::Button::new(
                         egui::RichText::new(kind.as_str()).size(12.0),
                     )
                     .selected(is_selected)
                     .min_size(egui::vec2(tab_width, tab_h));
 
-                    if ui.add(button).clicked() {
-                        self.switch_buffer(*kind);
+                    let response = ui.add(button);
+                    let response = if *kind == SourceKind::Common {
+                        response.on_hover_text("Shared helpers injected into every pass")
+                    } else {
+                        response
+                    };
+                    if response.clicked() {
+                        self.switch_source(*kind);
                     }
                 }
             });
@@ -446,7 +708,7 @@ impl TopApp {
                         );
 
                         if properties_response
-                            .on_hover_text("Shader Properties (Audio & Images)")
+                            .on_hover_text("Shader Properties (Audio, Geometry & Export)")
                             .clicked()
                         {
                             self.show_preset_menu = !self.show_preset_menu;
@@ -519,8 +781,383 @@ impl TopApp {
     }
 
     fn render_code_editor(&mut self, ui: &mut egui::Ui) {
-        if let Some(buffer) = self.buffers.get_mut(&self.current_buffer) {
-            buffer.render(ui, true, self.editor_font_size);
+        let source = self.current_source();
+        let markers: Vec<InlineMarker> = match self.source_code(source) {
+            Some(code) => self
+                .diagnostics
+                .iter()
+                .flat_map(|d| d.inline_markers(source.as_str(), code))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let action = if self.editing_common {
+            shader_editor::render_shader_editor(
+                ui,
+                &mut self.common_code,
+                COMMON_EDITOR_ID,
+                self.editor_font_size,
+                "",
+                &markers,
+            )
+        } else {
+            self.buffers
+                .get_mut(&self.current_buffer)
+                .and_then(|buffer| buffer.render(ui, true, self.editor_font_size, &self.common_code, &markers))
+        };
+
+        if let Some(action) = action {
+            self.handle_editor_action(ui.ctx(), action);
+        }
+    }
+
+    /// Source shown in the editor
+    fn current_source(&self) -> SourceKind {
+        if self.editing_common {
+            SourceKind::Common
+        } else {
+            SourceKind::Buffer(self.current_buffer)
+        }
+    }
+
+    /// Fragment code of every buffer plus Common, in tab order
+    fn editor_sources(&self) -> Vec<(SourceKind, &str)> {
+        SourceKind::ALL
+            .iter()
+            .filter_map(|kind| self.source_code(*kind).map(|code| (*kind, code)))
+            .collect()
+    }
+
+    fn source_code(&self, kind: SourceKind) -> Option<&str> {
+        match kind {
+            SourceKind::Common => Some(&self.common_code),
+            SourceKind::Buffer(buffer) => self.buffers.get(&buffer).map(|b| b.fragment_code.as_str()),
+        }
+    }
+
+    fn set_source_code(&mut self, kind: SourceKind, code: String) {
+        match kind {
+            SourceKind::Common => self.common_code = code,
+            SourceKind::Buffer(buffer) => {
+                if let Some(buffer) = self.buffers.get_mut(&buffer) {
+                    buffer.set_fragment(code);
+                }
+            }
+        }
+    }
+
+    /// Module-scope declaration of `name` in Common, for symbols used in a buffer
+    fn common_declaration(&self, name: &str) -> Option<wgsl_symbols::Symbol> {
+        SymbolIndex::build(&self.common_code)
+            .symbols()
+            .iter()
+            .find(|s| s.name == name && s.is_module_scope())
+            .cloned()
+    }
+
+    fn handle_editor_action(&mut self, ctx: &egui::Context, action: EditorAction) {
+        let origin = self.current_source();
+        let Some(code) = self.source_code(origin) else {
+            return;
+        };
+
+        match action {
+            EditorAction::GoToDefinition(at) => {
+                let name = SymbolIndex::build(code).name_at(code, at).map(str::to_string);
+                match name.and_then(|name| self.common_declaration(&name)) {
+                    Some(symbol) if origin != SourceKind::Common => {
+                        self.go_to_location(ctx, SourceKind::Common, symbol.decl);
+                    }
+                    _ => self.notification_mgr.info("No definition found (builtin or injected symbol)"),
+                }
+            }
+            EditorAction::FindReferences(at) => {
+                let sources = self.editor_sources();
+                match wgsl_symbols::find_references(&sources, origin, at) {
+                    Some((name, refs)) => {
+                        log::info!("Found {} references to '{}'", refs.len(), name);
+                        self.references = Some((name, refs));
+                    }
+                    None => self.notification_mgr.info("No symbol under the cursor"),
+                }
+            }
+            EditorAction::Rename(at) => {
+                let index = SymbolIndex::build(code);
+                // Symbols used in a buffer but declared in Common are renamed from Common
+                let target = match index.symbol_at(code, at) {
+                    Some(symbol) => Some((origin, at, symbol.clone())),
+                    None => index
+                        .name_at(code, at)
+                        .and_then(|name| self.common_declaration(name))
+                        .map(|symbol| (SourceKind::Common, symbol.decl.start, symbol)),
+                };
+
+                match target {
+                    Some((source, at, symbol)) => {
+                        self.rename_request = Some(RenameRequest {
+                            source,
+                            at,
+                            old_name: symbol.name.clone(),
+                            new_name: symbol.name.clone(),
+                            kind: symbol.kind,
+                            module_scope: symbol.is_module_scope(),
+                            all_sources: symbol.is_module_scope(),
+                        });
+                    }
+                    None => self
+                        .notification_mgr
+                        .info("Nothing to rename here (only symbols declared in the editor can be renamed)"),
+                }
+            }
+        }
+    }
+
+    /// Source and fixed code for the current error's quick fix, if it is
+    /// machine-applicable to code in the editor
+    fn quick_fix(&self) -> Option<(SourceKind, String, String)> {
+        let fix = self.diagnostics.first()?.fix.as_ref()?;
+        let section = fix.span.as_ref()?.section.as_deref()?;
+        let source = SourceKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == section)?;
+        let fixed = fix.apply(self.source_code(source)?)?;
+        Some((source, fixed, fix.message.clone()))
+    }
+
+    fn apply_quick_fix(&mut self) {
+        let Some((source, fixed, message)) = self.quick_fix() else {
+            self.notification_mgr.info("No quick fix available");
+            return;
+        };
+        log::info!("Applying quick fix to {}: {}", source.as_str(), message);
+        self.set_source_code(source, fixed);
+        self.diagnostics.clear();
+        self.show_error_window = false;
+        self.apply_shader();
+        self.notification_mgr.success(format!("Applied fix: {}", message));
+    }
+
+    /// Switch to a buffer or Common and select a byte range in its editor
+    fn go_to_location(&mut self, ctx: &egui::Context, source: SourceKind, range: std::ops::Range<usize>) {
+        self.switch_source(source);
+        let editor_id = match source {
+            SourceKind::Common => COMMON_EDITOR_ID.to_string(),
+            SourceKind::Buffer(kind) => match self.buffers.get(&kind) {
+                Some(buffer) => buffer.editor_id(true),
+                None => return,
+            },
+        };
+        let Some(code) = self.source_code(source) else {
+            return;
+        };
+        // References are found before later edits, so the range may no longer fit the text
+        match (code.get(..range.start), code.get(range.clone())) {
+            (Some(before), Some(target)) => {
+                let start = before.chars().count();
+                let end = start + target.chars().count();
+                shader_editor::reveal(ctx, &editor_id, start..end);
+            }
+            _ => log::warn!("Reference at bytes {:?} is out of date; search again", range),
+        }
+    }
+
+    /// Offer to restart the renderer once the GPU device is lost
+    fn render_device_lost_window(&mut self, ctx: &egui::Context) {
+        let mut restart = false;
+        egui::Window::new("⚠ GPU Device Lost")
+            .id(egui::Id::new("device_lost_window"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.set_max_width(360.0);
+                ui.label("The GPU device was lost, so the preview can't render until the renderer is restarted.");
+                ui.label(
+                    egui::RichText::new("The editor reopens with the current shaders, channels and settings; cameras, the audio file and playback time are not kept.")
+                        .size(11.0)
+                        .color(egui::Color32::from_rgb(140, 140, 150)),
+                );
+                ui.add_space(4.0);
+                restart = ui.button("Restart Renderer").clicked();
+            });
+        if restart {
+            self.restart_renderer(ctx);
+        }
+    }
+
+    /// Save the session and reopen the editor in a new process with a new GPU device
+    fn restart_renderer(&mut self, ctx: &egui::Context) {
+        let Some(shader) = self.shard(false) else {
+            return;
+        };
+        let saved = dirs::cache_dir()
+            .map(|dir| dir.join("webshard_editor"))
+            .ok_or_else(|| "No cache directory to save the session in".to_string())
+            .and_then(|dir| {
+                std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+                let path = dir.join("recovery.json");
+                let json = shader.to_json().map_err(|e| e.to_string())?;
+                std::fs::write(&path, json).map_err(|e| e.to_string())?;
+                Ok(path)
+            });
+        match saved.and_then(|path| gpu_errors::relaunch_with_session(&path)) {
+            Ok(()) => {
+                log::info!("Restarting the editor after a GPU device loss");
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+            Err(e) => {
+                self.notification_mgr.error(format!("Could not restart the renderer: {}", e));
+                log::error!("Failed to restart after device loss: {}", e);
+            }
+        }
+    }
+
+    fn render_references_window(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        let mut target = None;
+
+        if let Some((name, refs)) = &self.references {
+            egui::Window::new(format!("🔎 References to '{}'", name))
+                .id(egui::Id::new("references_window"))
+                .collapsible(false)
+                .resizable(true)
+                .default_size([480.0, 300.0])
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.label(
+                        egui::RichText::new(format!("{} references", refs.len()))
+                            .size(12.0)
+                            .weak(),
+                    );
+                    ui.separator();
+
+                    egui::ScrollArea::vertical()
+                        .id_salt("references_scroll")
+                        .auto_shrink([false, true])
+                        .show(ui, |ui| {
+                            for kind in SourceKind::ALL {
+                                let in_buffer: Vec<_> =
+                                    refs.iter().filter(|r| r.source == kind).collect();
+                                if in_buffer.is_empty() {
+                                    continue;
+                                }
+
+                                ui.label(egui::RichText::new(kind.as_str()).size(13.0).strong());
+                                for reference in in_buffer {
+                                    let text = format!("{:>4}  {}", reference.line, reference.line_text);
+                                    if ui
+                                        .selectable_label(
+                                            false,
+                                            egui::RichText::new(text).monospace().size(12.0),
+                                        )
+                                        .clicked()
+                                    {
+                                        target = Some((reference.source, reference.range.clone()));
+                                    }
+                                }
+                                ui.add_space(6.0);
+                            }
+                        });
+                });
+        }
+
+        if let Some((source, range)) = target {
+            self.go_to_location(ctx, source, range);
+        }
+        if !open {
+            self.references = None;
+        }
+    }
+
+    fn render_rename_window(&mut self, ctx: &egui::Context) {
+        let mut confirm = false;
+        let mut cancel = false;
+
+        if let Some(request) = &mut self.rename_request {
+            egui::Window::new(format!("✏ Rename {} '{}'", request.kind.as_str(), request.old_name))
+                .id(egui::Id::new("rename_window"))
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.spacing_mut().item_spacing = egui::vec2(8.0, 8.0);
+
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut request.new_name)
+                            .id(egui::Id::new("rename_input"))
+                            .font(egui::TextStyle::Monospace)
+                            .desired_width(280.0),
+                    );
+                    if !response.has_focus() && !response.lost_focus() {
+                        response.request_focus();
+                    }
+                    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                        confirm = true;
+                    }
+
+                    if request.module_scope {
+                        ui.checkbox(&mut request.all_sources, "Rename in all buffers and Common");
+                    }
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Rename").clicked() {
+                            confirm = true;
+                        }
+                        if ui.button("Cancel").clicked() || ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                            cancel = true;
+                        }
+                    });
+                });
+        }
+
+        if confirm {
+            self.apply_rename();
+        } else if cancel {
+            self.rename_request = None;
+        }
+    }
+
+    fn apply_rename(&mut self) {
+        let Some(request) = self.rename_request.take() else {
+            return;
+        };
+        if request.new_name == request.old_name {
+            return;
+        }
+
+        let sources = self.editor_sources();
+        let result = wgsl_symbols::rename(
+            &sources,
+            request.source,
+            request.at,
+            request.new_name.trim(),
+            request.all_sources,
+        );
+
+        match result {
+            Ok(changed) => {
+                let buffers = changed
+                    .iter()
+                    .map(|(kind, _)| kind.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                for (kind, code) in changed {
+                    self.set_source_code(kind, code);
+                }
+                self.references = None;
+                log::info!("Renamed '{}' to '{}' in {}", request.old_name, request.new_name, buffers);
+                self.notification_mgr.success(format!(
+                    "Renamed '{}' to '{}' in {}",
+                    request.old_name,
+                    request.new_name.trim(),
+                    buffers
+                ));
+            }
+            Err(e) => {
+                // Keep the dialog open so the name can be corrected
+                self.notification_mgr.error(format!("Rename failed: {}", e));
+                self.rename_request = Some(request);
+            }
         }
     }
 
@@ -590,7 +1227,40 @@ impl TopApp {
 
     fn render_shader_preview(&mut self, ui: &mut egui::Ui) {
         let size = ui.available_size();
-        let (rect, _response) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let has_mesh = self.buffers.values().any(|buffer| buffer.mesh.is_some());
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click_and_drag());
+
+        // ShaderToy's iMouse: the position is tracked while the primary button is held
+        let down = response.is_pointer_button_down_on();
+        self.preview_mouse.clicked = down && !self.preview_mouse.down;
+        self.preview_mouse.down = down;
+        if let Some(pos) = response.interact_pointer_pos().filter(|_| down) {
+            let position = [
+                ((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0),
+                ((rect.bottom() - pos.y) / rect.height()).clamp(0.0, 1.0),
+            ];
+            self.preview_mouse.position = position;
+            if self.preview_mouse.clicked {
+                self.preview_mouse.click = position;
+            }
+        }
+
+        // Orbit controls: drag to rotate, scroll to zoom, double-click to reframe
+        if has_mesh {
+            if response.dragged_by(egui::PointerButton::Primary) {
+                let delta = response.drag_delta();
+                self.camera.orbit([delta.x, delta.y]);
+            }
+            if response.hovered() {
+                let scroll = ui.input(|i| i.smooth_scroll_delta.y);
+                if scroll != 0.0 {
+                    self.camera.zoom(scroll);
+                }
+            }
+            if response.double_clicked() {
+                self.frame_meshes();
+            }
+        }
 
         if let Some(pipeline_arc) = self.compiler.pipeline().lock().unwrap().as_ref() {
             if self.debug_audio {
@@ -607,6 +1277,9 @@ impl TopApp {
                 gamma: self.gamma.clone(),
                 contrast: self.contrast.clone(),
                 saturation: self.saturation.clone(),
+                camera: self.camera.uniforms(rect.aspect_ratio()),
+                mouse: self.preview_mouse,
+                channels: self.channels.each_ref().map(|channel| channel.settings),
             };
 
             ui.painter()
@@ -678,7 +1351,7 @@ impl TopApp {
                     self.load_audio_file_dialog();
                 }
 
-                // Icon 2: Load image (middle)
+                // Icon 2: Channels panel (middle)
                 let image_pos = egui::pos2(
                     overlay_rect.left(),
                     overlay_rect.top() + icon_size + spacing,
@@ -695,10 +1368,10 @@ impl TopApp {
                     .frame(true),
                 );
                 if image_response
-                    .on_hover_text("Load image texture")
+                    .on_hover_text("Channels (drop image files onto a slot)")
                     .clicked()
                 {
-                    self.load_image_texture();
+                    self.show_channels_panel = !self.show_channels_panel;
                 }
 
                 // Icon 3: Audio toggle (bottom)
@@ -735,17 +1408,6 @@ impl TopApp {
         });
     }
 
-    fn load_image_texture(&mut self) {
-        if let Some(path) = rfd::FileDialog::new()
-            .add_filter("Image Files", &["png", "jpg", "jpeg", "bmp", "gif", "webp"])
-            .pick_file()
-        {
-            let path_str = path.to_string_lossy().to_string();
-            // Use the currently selected channel
-            self.load_image_file(self.selected_image_channel, path_str);
-        }
-    }
-
     fn load_image_file(&mut self, channel: usize, path: String) {
         if channel > 3 {
             self.notification_mgr.error(format!("Invalid channel: {}", channel));
@@ -755,7 +1417,9 @@ impl TopApp {
         log::info!("Loading image texture to iChannel{}: {}", channel, path);
 
         // Store the image file path for the selected channel
-        self.image_file_paths[channel] = Some(path.clone());
+        self.stop_capture(channel);
+        self.channels[channel].path = Some(path.clone());
+        self.channels[channel].settings.procedural = None;
 
         // Trigger shader recompilation to load the new image texture
         self.compiler.trigger_compilation();
@@ -772,6 +1436,106 @@ impl TopApp {
         log::info!("Image texture available as iChannel{} in all shaders", channel);
     }
 
+    /// Combine six face images into a strip in the cache and load it as a cubemap
+    fn load_cube_faces(&mut self, channel: usize, paths: Vec<String>) {
+        let strip = match channel_texture::cube_strip_from_faces(&paths) {
+            Ok(strip) => strip,
+            Err(e) => {
+                log::error!("{}", e);
+                self.notification_mgr.error(e);
+                return;
+            }
+        };
+        let Some(cache_dir) = dirs::cache_dir() else {
+            self.notification_mgr.error("No cache directory to store the cubemap in");
+            return;
+        };
+        let dir = cache_dir.join("webshard_editor").join("cubemaps");
+        // HDR faces stay HDR in an EXR strip
+        let extension = if channel_texture::is_hdr(&strip) { "exr" } else { "png" };
+        let path = dir.join(format!("ichannel{}_faces.{}", channel, extension));
+        if let Err(e) = std::fs::create_dir_all(&dir).map_err(|e| e.to_string()).and_then(|_| strip.save(&path).map_err(|e| e.to_string())) {
+            self.notification_mgr.error(format!("Failed to write cubemap {}: {}", path.display(), e));
+            return;
+        }
+        self.load_image_file(channel, path.to_string_lossy().to_string());
+    }
+
+    /// Feed a 2D channel from a camera, replacing its image or previous camera
+    fn start_capture(&mut self, channel: usize, path: String, size: Option<[u32; 2]>) {
+        self.stop_capture(channel);
+        match LiveCapture::start(&path, size) {
+            Ok(capture) => {
+                let [width, height] = capture.size;
+                self.notification_mgr.success(format!(
+                    "Camera on iChannel{}: {}x{} {}",
+                    channel,
+                    width,
+                    height,
+                    capture.pixel_format.as_str()
+                ));
+                let input = &mut self.channels[channel];
+                input.path = Some(path);
+                input.settings.procedural = None;
+                input.live = Some(Arc::new(capture));
+                self.compiler.trigger_compilation();
+            }
+            Err(e) => {
+                log::error!("{}", e);
+                self.notification_mgr.error(e);
+            }
+        }
+    }
+
+    /// Release a channel's camera, if it has one
+    fn stop_capture(&mut self, channel: usize) {
+        if let Some(capture) = self.channels[channel].live.take() {
+            capture.stop();
+            log::info!("Stopped camera {} on iChannel{}", capture.path, channel);
+        }
+    }
+
+    fn load_mesh_file(&mut self, kind: BufferKind, path: String) {
+        match mesh::load_mesh(&path) {
+            Ok(mesh) => {
+                let triangles = mesh.triangle_count();
+                if let Some(buffer) = self.buffers.get_mut(&kind) {
+                    buffer.mesh = Some(Arc::new(mesh));
+                }
+                self.frame_meshes();
+                self.compiler.trigger_compilation();
+                self.notification_mgr.success(format!(
+                    "Mesh loaded into {}: {} triangles",
+                    kind.as_str(),
+                    triangles
+                ));
+            }
+            Err(e) => {
+                log::error!("{}", e);
+                self.notification_mgr.error(e);
+            }
+        }
+    }
+
+    /// Point the orbit camera at the combined bounds of every pass's mesh
+    fn frame_meshes(&mut self) {
+        let bounds = self
+            .buffers
+            .values()
+            .filter_map(|buffer| buffer.mesh.as_ref())
+            .map(|mesh| mesh.bounds())
+            .reduce(|(min_a, max_a), (min_b, max_b)| {
+                (
+                    std::array::from_fn(|i| min_a[i].min(min_b[i])),
+                    std::array::from_fn(|i| max_a[i].max(max_b[i])),
+                )
+            });
+        if let Some(bounds) = bounds {
+            self.camera = OrbitCamera::default();
+            self.camera.frame(bounds);
+        }
+    }
+
     fn load_audio_file_dialog(&mut self) {
         if let Some(path) = rfd::FileDialog::new()
             .add_filter("Audio Files", &["mp3", "wav", "ogg", "flac"])
@@ -822,6 +1586,9 @@ impl TopApp {
             if i.modifiers.command && i.key_pressed(egui::Key::I) {
                 self.import_shard();
             }
+            if i.modifiers.command && i.key_pressed(egui::Key::Period) {
+                self.apply_quick_fix();
+            }
         });
     }
 
@@ -832,6 +1599,12 @@ impl TopApp {
     }
 
     fn reset_shader(&mut self) {
+        if self.editing_common {
+            log::info!("Resetting Common code");
+            self.common_code = DEFAULT_COMMON.to_string();
+            return;
+        }
+
         log::info!("Resetting {} to default shader", self.current_buffer.as_str());
 
         // Load default preset JSON
@@ -868,6 +1641,7 @@ impl TopApp {
             saved.insert(*kind, (v.to_string(), f.to_string()));
         }
         self.saved_shaders = Some(saved);
+        self.saved_common = Some(self.common_code.clone());
         self.notification_mgr.success("✓ Shader state saved!");
         log::info!("Shader state saved (Ctrl+R to restore)");
     }
@@ -880,6 +1654,9 @@ impl TopApp {
                     buffer.set_fragment(fragment.clone());
                 }
             }
+            if let Some(common) = &self.saved_common {
+                self.common_code = common.clone();
+            }
             self.notification_mgr.success("↶ Shader state restored!");
             log::info!("Shader state restored from save point");
         } else {
@@ -925,14 +1702,20 @@ impl TopApp {
         }
     }
 
-    fn switch_buffer(&mut self, new_buffer: BufferKind) {
-        if new_buffer == self.current_buffer {
+    fn switch_source(&mut self, new_source: SourceKind) {
+        if new_source == self.current_source() {
             return;
         }
 
-        self.current_buffer = new_buffer;
-        log::info!("Switched to buffer: {:?}", new_buffer);
-        self.notification_mgr.info(format!("Switched to {}", new_buffer.as_str()));
+        match new_source {
+            SourceKind::Common => self.editing_common = true,
+            SourceKind::Buffer(kind) => {
+                self.editing_common = false;
+                self.current_buffer = kind;
+            }
+        }
+        log::info!("Switched to buffer: {:?}", new_source);
+        self.notification_mgr.info(format!("Switched to {}", new_source.as_str()));
     }
 
     fn load_preset_shader(&mut self, name: &str) {
@@ -966,45 +1749,37 @@ impl TopApp {
 
     /// Load a shader from ShaderJson into all buffers
     fn load_shader_from_json(&mut self, shader_json: ShaderJson) {
-        // Load MainImage fragment
-        if let Some(buffer) = self.buffers.get_mut(&BufferKind::MainImage) {
-            buffer.set_fragment(shader_json.fragment.clone());
-            if let Some(vertex) = &shader_json.vertex {
-                buffer.set_vertex(vertex.clone());
-            } else {
-                buffer.set_vertex(DEFAULT_VERTEX.to_string());
-            }
-        }
-
-        // Load Buffer A
-        if let Some(buffer_a) = &shader_json.buffer_a {
-            if let Some(buffer) = self.buffers.get_mut(&BufferKind::BufferA) {
-                buffer.set_fragment(buffer_a.clone());
-                buffer.set_vertex(DEFAULT_VERTEX.to_string());
+        // Shards without a Common section (older exports) start with the placeholder
+        self.common_code = shader_json
+            .common
+            .clone()
+            .unwrap_or_else(|| DEFAULT_COMMON.to_string());
+
+        // Load MainImage and Buffer A-D, each with its own vertex shader or the default
+        for (kind, code) in [
+            (BufferKind::MainImage, Some(&shader_json.fragment)),
+            (BufferKind::BufferA, shader_json.buffer_a.as_ref()),
+            (BufferKind::BufferB, shader_json.buffer_b.as_ref()),
+            (BufferKind::BufferC, shader_json.buffer_c.as_ref()),
+            (BufferKind::BufferD, shader_json.buffer_d.as_ref()),
+        ] {
+            if let (Some(code), Some(buffer)) = (code, self.buffers.get_mut(&kind)) {
+                buffer.set_fragment(code.clone());
+                buffer.set_vertex(shader_json.vertex_for(kind).unwrap_or(DEFAULT_VERTEX).to_string());
+                buffer.geometry = shader_json.geometry_for(kind);
             }
         }
 
-        // Load Buffer B
-        if let Some(buffer_b) = &shader_json.buffer_b {
-            if let Some(buffer) = self.buffers.get_mut(&BufferKind::BufferB) {
-                buffer.set_fragment(buffer_b.clone());
-                buffer.set_vertex(DEFAULT_VERTEX.to_string());
+        // Channel types apply even to channels the shard has no image for
+        for i in 0..4 {
+            if self.channels[i].live.is_some() {
+                self.stop_capture(i);
+                self.channels[i].path = None;
             }
-        }
-
-        // Load Buffer C
-        if let Some(buffer_c) = &shader_json.buffer_c {
-            if let Some(buffer) = self.buffers.get_mut(&BufferKind::BufferC) {
-                buffer.set_fragment(buffer_c.clone());
-                buffer.set_vertex(DEFAULT_VERTEX.to_string());
-            }
-        }
-
-        // Load Buffer D
-        if let Some(buffer_d) = &shader_json.buffer_d {
-            if let Some(buffer) = self.buffers.get_mut(&BufferKind::BufferD) {
-                buffer.set_fragment(buffer_d.clone());
-                buffer.set_vertex(DEFAULT_VERTEX.to_string());
+            self.channels[i].settings = shader_json.channel_settings(i);
+            // Generated channels have no file
+            if self.channels[i].settings.procedural.is_some() {
+                self.channels[i].path = None;
             }
         }
 
@@ -1023,9 +1798,14 @@ impl TopApp {
                         let temp_dir = cache_dir.join("webshard_editor").join("embedded_textures");
                         let _ = std::fs::create_dir_all(&temp_dir);
                         
-                        let temp_path = temp_dir.join(format!("ichannel{}.png", i));
+                        // Images are opened by extension, so name the file after its format
+                        let extension = image::guess_format(&image_bytes)
+                            .ok()
+                            .and_then(|format| format.extensions_str().first().copied())
+                            .unwrap_or("png");
+                        let temp_path = temp_dir.join(format!("ichannel{}.{}", i, extension));
                         if std::fs::write(&temp_path, &image_bytes).is_ok() {
-                            self.image_file_paths[i] = Some(temp_path.to_string_lossy().to_string());
+                            self.channels[i].path = Some(temp_path.to_string_lossy().to_string());
                             log::info!("Loaded embedded texture for iChannel{}", i);
                         }
                     }
@@ -1055,10 +1835,96 @@ impl TopApp {
         self.apply_shader();
     }
 
+    /// The current editor state as a shard (images re-encoded as PNG), or
+    /// `None` after reporting why it failed
+    fn shard(&mut self, inline_includes: bool) -> Option<ShaderJson> {
+        let common = match self.export_source("Common", &self.common_code, inline_includes) {
+            Ok(common) => common,
+            Err(e) => {
+                self.include_export_failed(e);
+                return None;
+            }
+        };
+
+        let mut buffers = HashMap::new();
+        for kind in BufferKind::ALL {
+            let Some(buffer) = self.buffers.get(&kind) else {
+                continue;
+            };
+            let (vertex, fragment) = buffer.get_shaders();
+            let vertex_name = format!("{} vertex", kind.as_str());
+            let sources = if inline_includes {
+                self.compiler
+                    .library()
+                    .inline_pass(&self.common_code, (&vertex_name, vertex), (kind.as_str(), fragment))
+            } else {
+                Ok((vertex.to_string(), fragment.to_string()))
+            };
+            match sources {
+                Ok((vertex, fragment)) => {
+                    buffers.insert(kind, ShaderBuffer::new(kind, vertex, fragment));
+                }
+                Err(e) => {
+                    self.include_export_failed(e);
+                    return None;
+                }
+            }
+        }
+
+        // Re-encode images as RGBA8 PNG, or RGBA32F EXR for float channels;
+        // animations keep their GIF/APNG (sequences become a GIF)
+        let mut images: [Option<Vec<u8>>; 4] = Default::default();
+        for (i, channel) in self.channels.iter().enumerate() {
+            let Some(path) = &channel.path else {
+                continue;
+            };
+            if channel.live.is_some() {
+                log::warn!("iChannel{} is a camera; shards don't embed live input", i);
+                continue;
+            }
+            let embedded = match channel_animation::embeddable_animation(path) {
+                Ok(Some(animation)) => Ok(animation),
+                Ok(None) => channel_texture::embeddable_image(path, &channel.settings),
+                Err(e) => Err(e),
+            };
+            match embedded {
+                Ok(image_bytes) => {
+                    log::info!("Embedded image {} ({} bytes)", i, image_bytes.len());
+                    images[i] = Some(image_bytes);
+                }
+                Err(e) => {
+                    log::warn!("Failed to embed image {}: {}", i, e);
+                }
+            }
+        }
+
+        Some(ShaderJson::from_editor_state(&EditorState {
+            buffers: &buffers,
+            common: &common,
+            images: &images,
+            channels: &self.channels.each_ref().map(|channel| channel.settings),
+            gamma: *self.gamma.lock().unwrap(),
+            contrast: *self.contrast.lock().unwrap(),
+            saturation: *self.saturation.lock().unwrap(),
+        }))
+    }
+
+    /// Shader source as exported, with includes inlined if requested
+    fn export_source(&self, name: &str, code: &str, inline_includes: bool) -> Result<String, IncludeError> {
+        if inline_includes {
+            self.compiler.library().inline(name, code)
+        } else {
+            Ok(code.to_string())
+        }
+    }
+
+    fn include_export_failed(&mut self, error: IncludeError) {
+        self.notification_mgr.error(format!("Export failed: {}", error));
+        log::error!("Failed to inline includes for export: {}", error);
+    }
+
     fn export_shard(&mut self) {
         use std::io::Write;
-        use serde_json::json;
-        use image::ImageEncoder;  // Required for write_image method
 
         // Default to cache/TempRS/shaders/ folder (where player looks for shaders)
         let cache_shader_dir = dirs::cache_dir()
@@ -1073,6 +1939,7 @@ impl TopApp {
 
         let mut dialog = rfd::FileDialog::new()
             .add_filter("JSON Shader", &["json"])
+            .add_filter("Shard Bundle", &["zip"])
             .set_file_name("shader.json");
 
         if let Some(dir) = cache_shader_dir {
@@ -1084,94 +1951,17 @@ impl TopApp {
             None => return,
         };
 
-        // Build JSON object with base64-encoded shaders
-        let mut shader_json = json!({
-            "version": "1.0",
-            "exported_at": chrono::Local::now().to_rfc3339(),
-            "encoding": "base64",
-        });
-
-        // Get MainImage fragment (required)
-        if let Some(buffer) = self.buffers.get(&BufferKind::MainImage) {
-            let (_, fragment) = buffer.get_shaders();
-            shader_json["fragment"] = json!(ShaderJson::encode_to_base64(fragment));
-        } else {
-            self.notification_mgr.error("MainImage is required for export");
+        let Some(mut shader) = self.shard(self.inline_includes_on_export) else {
             return;
-        }
-
-        // Add vertex shader if customized (optional)
-        if let Some(buffer) = self.buffers.get(&BufferKind::MainImage) {
-            let (vertex, _) = buffer.get_shaders();
-            let default_vertex = STANDARD_VERTEX.trim();
-            if vertex.trim() != default_vertex {
-                shader_json["vertex"] = json!(ShaderJson::encode_to_base64(vertex));
-            }
-        }
-
-        // Add buffer shaders if they have content (optional)
-        for (kind, json_key) in [
-            (BufferKind::BufferA, "buffer_a"),
-            (BufferKind::BufferB, "buffer_b"),
-            (BufferKind::BufferC, "buffer_c"),
-            (BufferKind::BufferD, "buffer_d"),
-        ] {
-            if let Some(buffer) = self.buffers.get(&kind) {
-                let (_, fragment) = buffer.get_shaders();
-                let trimmed = fragment.trim();
-
-                // Skip empty or comment-only buffers
-                if !(trimmed.is_empty() || trimmed.starts_with("//") && trimmed.lines().count() == 1) {
-                    shader_json[json_key] = json!(ShaderJson::encode_to_base64(fragment));
-                }
-            }
-        }
+        };
+        shader.exported_at = Some(chrono::Local::now().to_rfc3339());
 
-        // Add embedded images if loaded (re-encode as PNG to ensure consistent color space)
-        for (i, path_opt) in self.image_file_paths.iter().enumerate() {
-            if let Some(path) = path_opt {
-                // Decode image, re-encode as PNG with proper RGBA8 color type
-                match image::open(path) {
-                    Ok(img) => {
-                        let rgba = img.to_rgba8();
-                        let (width, height) = rgba.dimensions();
-                        
-                        // Re-encode as PNG with explicit RGBA8 color type (ensures sRGB)
-                        let mut png_bytes = Vec::new();
-                        let encoder = image::codecs::png::PngEncoder::new(&mut png_bytes);
-                        match encoder.write_image(&rgba, width, height, image::ExtendedColorType::Rgba8) {
-                            Ok(_) => {
-                                let encoded = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &png_bytes);
-                                let channel_key = format!("ichannel{}", i);
-                                shader_json[channel_key] = json!(encoded);
-                                log::info!("Embedded image {} ({}x{}, {} bytes PNG, RGBA8)", i, width, height, png_bytes.len());
-                            }
-                            Err(e) => {
-                                log::warn!("Failed to encode image {}: {}", i, e);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        log::warn!("Failed to load image {} from {:?}: {}", i, path, e);
-                    }
-                }
-            }
+        if file_path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip")) {
+            return self.export_bundle(shader, &file_path);
         }
 
-        // Add gamma correction value
-        let gamma_value = *self.gamma.lock().unwrap();
-        shader_json["gamma"] = json!(gamma_value);
-        
-        // Add contrast value
-        let contrast_value = *self.contrast.lock().unwrap();
-        shader_json["contrast"] = json!(contrast_value);
-        
-        // Add saturation value
-        let saturation_value = *self.saturation.lock().unwrap();
-        shader_json["saturation"] = json!(saturation_value);
-
         // Serialize to pretty JSON
-        let json_content = match serde_json::to_string_pretty(&shader_json) {
+        let json_content = match shader.to_json() {
             Ok(content) => content,
             Err(e) => {
                 self.notification_mgr.error(format!("JSON serialization failed: {}", e));
@@ -1183,7 +1973,9 @@ impl TopApp {
         // Debug: Log exported content structure
         log::debug!("=== EXPORT DEBUG ===");
         log::debug!("Export length: {} bytes", json_content.len());
-        log::debug!("Exported keys: {:?}", shader_json.as_object().map(|o| o.keys().collect::<Vec<_>>()));
+        log::debug!("Exported buffers: A={} B={} C={} D={}, common={}",
+            shader.buffer_a.is_some(), shader.buffer_b.is_some(), shader.buffer_c.is_some(),
+            shader.buffer_d.is_some(), shader.common.is_some());
         log::debug!("===================");
 
         match std::fs::File::create(&file_path) {
@@ -1203,6 +1995,129 @@ impl TopApp {
         }
     }
 
+    /// Write a shard as a zip bundle with the loaded audio file and thumbnail
+    fn export_bundle(&mut self, shader: ShaderJson, file_path: &std::path::Path) {
+        let mut bundle = ShardBundle::new(shader);
+        bundle.thumbnail = self.bundle_thumbnail.clone();
+        if let Some(path) = &self.audio_file_path {
+            match std::fs::read(path) {
+                Ok(bytes) => {
+                    let file_name = std::path::Path::new(path)
+                        .file_name()
+                        .map(|n| n.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    bundle.audio = Some(BundleAsset { file_name, bytes });
+                }
+                Err(e) => {
+                    self.notification_mgr.warning(format!("Audio not bundled: {}", e));
+                    log::warn!("Failed to read audio file {}: {}", path, e);
+                }
+            }
+        }
+
+        let result = bundle.to_zip().and_then(|zip| {
+            std::fs::write(file_path, zip).map_err(|e| e.to_string())
+        });
+        match result {
+            Ok(()) => {
+                self.notification_mgr.success("✓ Shader exported as bundle!");
+                log::info!("Shader bundle exported to: {:?}", file_path);
+            }
+            Err(e) => {
+                self.notification_mgr.error(format!("Export failed: {}", e));
+                log::error!("Failed to export bundle {:?}: {}", file_path, e);
+            }
+        }
+    }
+
+    /// Load a zip bundle, extracting its audio to the cache to play it
+    fn import_bundle(&mut self, bytes: &[u8], file_path: &std::path::Path) {
+        let bundle = match ShardBundle::from_zip(bytes) {
+            Ok(bundle) => bundle,
+            Err(e) => {
+                self.notification_mgr.error(format!("Failed to read bundle: {}", e));
+                log::error!("Failed to read shard bundle {:?}: {}", file_path, e);
+                return;
+            }
+        };
+
+        self.load_shader_from_json(bundle.shader);
+        self.bundle_thumbnail = bundle.thumbnail;
+        if let Some(audio) = bundle.audio {
+            let audio_path = dirs::cache_dir().map(|dir| dir.join("webshard_editor").join("bundle_audio"));
+            let written = audio_path.and_then(|dir| {
+                std::fs::create_dir_all(&dir).ok()?;
+                // `from_zip` only lets plain file names through
+                let path = dir.join(std::path::Path::new(&audio.file_name).file_name()?);
+                std::fs::write(&path, &audio.bytes).ok()?;
+                Some(path)
+            });
+            match written {
+                Some(path) => self.load_audio_file(path.to_string_lossy().into_owned()),
+                None => self.notification_mgr.warning("Could not extract the bundled audio"),
+            }
+        }
+        self.notification_mgr.success("✓ Shader bundle imported successfully!");
+        log::info!("Shader bundle imported from: {:?}", file_path);
+    }
+
+    /// Convert a JSON shard to a bundle or back, writing it next to the original
+    fn convert_shard(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("Shard", &["json", "zip"])
+            .pick_file()
+        else {
+            return;
+        };
+
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.notification_mgr.error(format!("Failed to read file: {}", e));
+                log::error!("Failed to read file {:?}: {}", path, e);
+                return;
+            }
+        };
+
+        let converted = if shard_bundle::is_bundle(&bytes) {
+            shard_bundle::bundle_to_json(&bytes).map(|(json, dropped)| {
+                if !dropped.is_empty() {
+                    self.notification_mgr
+                        .warning(format!("JSON can't hold {}; left out", dropped.join(", ")));
+                }
+                (path.with_extension("json"), json.into_bytes())
+            })
+        } else {
+            String::from_utf8(bytes)
+                .map_err(|_| "File is neither a bundle nor JSON text".to_string())
+                .and_then(|json| shard_bundle::json_to_bundle(&json))
+                .map(|zip| (path.with_extension("zip"), zip))
+        };
+
+        // Never replace a shard that is already next to the original
+        match converted.and_then(|(out, contents)| {
+            let mut file = std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&out)
+                .map_err(|e| match e.kind() {
+                    std::io::ErrorKind::AlreadyExists => format!("{} already exists", out.display()),
+                    _ => e.to_string(),
+                })?;
+            std::io::Write::write_all(&mut file, &contents).map_err(|e| e.to_string())?;
+            Ok(out)
+        }) {
+            Ok(out) => {
+                self.notification_mgr.success(format!("✓ Converted to {}", out.display()));
+                log::info!("Converted {:?} to {:?}", path, out);
+            }
+            Err(e) => {
+                self.notification_mgr.error(format!("Conversion failed: {}", e));
+                log::error!("Failed to convert {:?}: {}", path, e);
+            }
+        }
+    }
+
     fn import_shard(&mut self) {
         // Default to cache/TempRS/shaders/ folder (same as export)
         let cache_shader_dir = dirs::cache_dir()
@@ -1211,6 +2126,7 @@ impl TopApp {
 
         let mut dialog = rfd::FileDialog::new()
             .add_filter("JSON Shader", &["json"])
+            .add_filter("Shard Bundle", &["zip"])
             .set_file_name("shader.json");
 
         if let Some(dir) = cache_shader_dir {
@@ -1221,10 +2137,14 @@ impl TopApp {
             Some(path) => path,
             None => return,
         };
+        self.import_shard_file(&file_path);
+    }
 
+    /// Import a shard JSON or bundle
+    fn import_shard_file(&mut self, file_path: &std::path::Path) {
         // Read the file
-        let json_content = match std::fs::read_to_string(&file_path) {
-            Ok(content) => content,
+        let bytes = match std::fs::read(file_path) {
+            Ok(bytes) => bytes,
             Err(e) => {
                 self.notification_mgr.error(format!("Failed to read file: {}", e));
                 log::error!("Failed to read file {:?}: {}", file_path, e);
@@ -1232,8 +2152,31 @@ impl TopApp {
             }
         };
 
+        if shard_bundle::is_bundle(&bytes) {
+            self.import_bundle(&bytes, file_path);
+            return;
+        }
+        let json_content = match String::from_utf8(bytes) {
+            Ok(content) => content,
+            Err(_) => {
+                self.notification_mgr.error("Failed to read file: not a bundle or UTF-8 JSON");
+                log::error!("Shard file {:?} is neither a bundle nor UTF-8", file_path);
+                return;
+            }
+        };
+
+        if ShaderJson::is_shadertoy(&json_content) {
+            self.import_shadertoy_export(&json_content, file_path.parent());
+            return;
+        }
+
         // Parse and load the JSON shader
-        match ShaderJson::from_json(&json_content) {
+        let parsed = if self.strict_shard_import {
+            ShaderJson::from_json_strict(&json_content)
+        } else {
+            ShaderJson::from_json(&json_content)
+        };
+        match parsed {
             Ok(shader_json) => {
                 self.load_shader_from_json(shader_json);
                 self.notification_mgr.success("✓ Shader imported successfully!");
@@ -1245,4 +2188,247 @@ impl TopApp {
             }
         }
     }
+
+    /// Pick a shard JSON to import now and again whenever it is saved
+    fn link_shard(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("JSON Shader", &["json"])
+            .pick_file()
+        else {
+            return;
+        };
+        self.import_shard_file(&path);
+        self.notification_mgr.info(format!(
+            "Linked {}; saving it reloads the shader",
+            path.file_name().and_then(|n| n.to_str()).unwrap_or("shard")
+        ));
+        log::info!("Linked shard {:?}", path);
+        self.linked_shard = Some(path);
+    }
+
+    /// Watch loaded files and reload the ones changed on disk
+    ///
+    /// Still images are rewritten into their textures; anything that changes
+    /// the pipeline (image size or format, animations, includes) recompiles.
+    fn reload_changed_files(&mut self, queue: &eframe::wgpu::Queue) {
+        for (i, channel) in self.channels.iter().enumerate() {
+            let path = channel.path.as_ref().filter(|_| channel.live.is_none());
+            self.file_watcher.watch(WatchTarget::Channel(i), path.map(PathBuf::from));
+        }
+        self.file_watcher.watch(WatchTarget::Audio, self.audio_file_path.as_ref().map(PathBuf::from));
+        self.file_watcher.watch(WatchTarget::Include, self.compiler.included_files());
+        self.file_watcher.watch(WatchTarget::LinkedShard, self.linked_shard.clone());
+
+        let file_name = |path: &std::path::Path| {
+            path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()
+        };
+        for target in self.file_watcher.poll() {
+            match target {
+                WatchTarget::Channel(i) => {
+                    let Some(path) = self.channels[i].path.clone() else {
+                        continue;
+                    };
+                    let pipeline = self.compiler.pipeline().lock().unwrap().clone();
+                    let reloaded = match pipeline {
+                        Some(pipeline) => pipeline.reload_channel(queue, i, &self.channels[i]),
+                        None => Ok(false),
+                    };
+                    match reloaded {
+                        Ok(in_place) => {
+                            if !in_place {
+                                self.compiler.trigger_compilation();
+                            }
+                            self.notification_mgr
+                                .info(format!("Reloaded iChannel{}: {}", i, file_name(path.as_ref())));
+                        }
+                        Err(e) => {
+                            log::error!("Failed to reload iChannel{}: {}", i, e);
+                            self.notification_mgr.error(format!("Failed to reload iChannel{}: {}", i, e));
+                        }
+                    }
+                }
+                WatchTarget::Audio => {
+                    if let Some(path) = self.audio_file_path.clone() {
+                        log::info!("Audio file changed on disk: {}", path);
+                        self.load_audio_file(path);
+                    }
+                }
+                WatchTarget::Include => {
+                    log::info!("Included shader files changed on disk, recompiling");
+                    self.notification_mgr.info("Included file changed, recompiling");
+                    self.compiler.trigger_compilation();
+                }
+                WatchTarget::LinkedShard => {
+                    if let Some(path) = self.linked_shard.clone() {
+                        log::info!("Linked shard changed on disk: {:?}", path);
+                        self.import_shard_file(&path);
+                        // Embedded images were just rewritten by the import itself
+                        self.file_watcher.mark_current();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Import ShaderToy GLSL files, one per pass, translated to WGSL
+    ///
+    /// Files are matched to passes by name (`image`, `buffer_a`..`buffer_d`,
+    /// `common`); a single file with another name goes to MainImage.
+    fn import_glsl(&mut self) {
+        let Some(paths) = rfd::FileDialog::new()
+            .add_filter("GLSL", &["glsl", "frag", "fs", "txt"])
+            .pick_files()
+        else {
+            return;
+        };
+
+        let single = paths.len() == 1;
+        let mut common = None;
+        let mut passes = Vec::new();
+        for path in &paths {
+            let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            let source = match glsl_import::source_for_file_name(&file_name) {
+                Some(source) => source,
+                None if single => SourceKind::Buffer(BufferKind::MainImage),
+                None => {
+                    self.notification_mgr.error(format!(
+                        "Can't tell which pass {} is; name it image, buffer_a..buffer_d or common",
+                        file_name
+                    ));
+                    return;
+                }
+            };
+            let code = match std::fs::read_to_string(path) {
+                Ok(code) => code,
+                Err(e) => {
+                    self.notification_mgr.error(format!("Failed to read {}: {}", file_name, e));
+                    log::error!("Failed to read GLSL file {:?}: {}", path, e);
+                    return;
+                }
+            };
+            match source {
+                SourceKind::Common => common = Some(code),
+                SourceKind::Buffer(kind) => passes.push((kind, code)),
+            }
+        }
+
+        let passes: Vec<glsl_import::GlslPass> = passes
+            .iter()
+            .map(|(kind, code)| glsl_import::GlslPass::new(*kind, code))
+            .collect();
+        match glsl_import::import_shadertoy(common.as_deref(), &passes) {
+            Ok(import) => {
+                if let Some(common) = import.common {
+                    self.set_source_code(SourceKind::Common, common);
+                }
+                for (kind, code) in import.passes {
+                    self.set_source_code(SourceKind::Buffer(kind), code);
+                }
+                if let Some(pass) = passes.first() {
+                    self.switch_source(SourceKind::Buffer(pass.kind));
+                }
+                self.apply_shader();
+                self.notification_mgr.success(format!("✓ Imported {} GLSL file(s)", paths.len()));
+                log::info!("Imported ShaderToy GLSL from {:?}", paths);
+            }
+            Err(err) => self.report_failure("GLSL import", err),
+        }
+    }
+
+    /// Load a ShaderToy API export, reporting inputs that could not be mapped
+    fn import_shadertoy_export(&mut self, json: &str, media_dir: Option<&std::path::Path>) {
+        match ShaderJson::from_shadertoy(json, media_dir) {
+            Ok(import) => {
+                self.load_shader_from_json(import.shader);
+                self.notification_mgr.success(format!(
+                    "✓ Imported ShaderToy shader '{}'",
+                    import.name.as_deref().unwrap_or("untitled")
+                ));
+                match import.unmapped.as_slice() {
+                    [] => {}
+                    [only] => self.notification_mgr.warning(format!("Not imported: {}", only)),
+                    [first, rest @ ..] => self.notification_mgr.warning(format!(
+                        "Not imported: {} (and {} more, see log)",
+                        first,
+                        rest.len()
+                    )),
+                }
+            }
+            Err(err) => self.report_failure("ShaderToy import", err),
+        }
+    }
+
+    /// Export every pass as a self-contained shader plus `manifest.json`
+    fn export_standalone(&mut self, format: StandaloneFormat) {
+        let what = match format {
+            StandaloneFormat::Wgsl => "WGSL export",
+            StandaloneFormat::Glsl => "GLSL export",
+        };
+        let files = match self
+            .compiler
+            .gather_sources(&self.buffers, &self.common_code, self.channels.each_ref().map(|c| c.settings.kind))
+            .and_then(|sources| standalone::export(&sources, format))
+        {
+            Ok(files) => files,
+            Err(err) => return self.report_failure(what, err),
+        };
+
+        let Some(dir) = rfd::FileDialog::new().pick_folder() else {
+            return;
+        };
+        for file in &files {
+            let path = dir.join(&file.name);
+            if let Err(e) = std::fs::write(&path, &file.contents) {
+                self.notification_mgr.error(format!("Failed to write {}: {}", file.name, e));
+                log::error!("Failed to write {:?}: {}", path, e);
+                return;
+            }
+        }
+        self.notification_mgr
+            .success(format!("✓ Exported {} files to {}", files.len(), dir.display()));
+        log::info!("{} written to {:?}", what, dir);
+    }
+
+    /// Export the shard as a single HTML page that plays it with WebGPU
+    fn export_web(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("HTML", &["html"])
+            .set_file_name("shader.html")
+            .save_file()
+        else {
+            return;
+        };
+
+        // The browser has no shader library, so includes are always inlined
+        let Some(shader) = self.shard(true) else {
+            return;
+        };
+        let title = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "shader".to_string());
+        let html = match web_export::export_html(&shader, &title) {
+            Ok(html) => html,
+            Err(err) => return self.report_failure("HTML export", err),
+        };
+
+        match std::fs::write(&path, html) {
+            Ok(()) => {
+                self.notification_mgr.success("✓ Shader exported to HTML!");
+                log::info!("Shader exported to: {:?}", path);
+            }
+            Err(e) => {
+                self.notification_mgr.error(format!("Export failed: {}", e));
+                log::error!("Failed to write {:?}: {}", path, e);
+            }
+        }
+    }
+
+    fn report_failure(&mut self, what: &str, err: ShaderError) {
+        self.notification_mgr
+            .error(format!("{} failed: {}", what, err.diagnostic().summary()));
+        self.error_message = format_shader_error(&err);
+        self.diagnostics = vec![err.diagnostic().clone()];
+        self.show_error_window = true;
+    }
 }
@@ -3,8 +3,11 @@
 //! Replaces the previous separate MainImageTab, BufferATab, etc. with a single
 //! generic structure that can represent any shader buffer (MainImage or Buffer A-D).
 
-use crate::ui_components::shader_editor;
-use crate::utils::BufferKind;
+use crate::ui_components::shader_editor::{self, EditorAction};
+use crate::utils::diagnostics::InlineMarker;
+use crate::utils::mesh::Mesh;
+use crate::utils::{BufferKind, PassGeometry};
+use std::sync::Arc;
 use eframe::egui;
 
 /// A shader buffer containing vertex and fragment shader code
@@ -18,6 +21,10 @@ pub struct ShaderBuffer {
     pub fragment_code: String,
     /// Vertex shader source code
     pub vertex_code: String,
+    /// What the pass draws (the full-screen triangle by default)
+    pub geometry: PassGeometry,
+    /// Mesh drawn instead of `geometry`'s vertices (not saved in shards)
+    pub mesh: Option<Arc<Mesh>>,
 }
 
 impl ShaderBuffer {
@@ -27,6 +34,8 @@ impl ShaderBuffer {
             kind,
             fragment_code: fragment,
             vertex_code: vertex,
+            geometry: PassGeometry::default(),
+            mesh: None,
         }
     }
 
@@ -36,17 +45,29 @@ impl ShaderBuffer {
     /// * `ui` - The egui UI context
     /// * `is_fragment_tab` - Whether to show fragment (true) or vertex (false) code
     /// * `font_size` - Font size for the editor
-    pub fn render(&mut self, ui: &mut egui::Ui, is_fragment_tab: bool, font_size: f32) {
+    /// * `common` - Shared Common code injected ahead of this buffer
+    pub fn render(
+        &mut self,
+        ui: &mut egui::Ui,
+        is_fragment_tab: bool,
+        font_size: f32,
+        common: &str,
+        markers: &[InlineMarker],
+    ) -> Option<EditorAction> {
+        let editor_id = self.editor_id(is_fragment_tab);
         let code = if is_fragment_tab {
             &mut self.fragment_code
         } else {
             &mut self.vertex_code
         };
 
-        let shader_type = if is_fragment_tab { "frag" } else { "vert" };
-        let editor_id = format!("{}_{}", self.kind.as_str().to_lowercase(), shader_type);
+        shader_editor::render_shader_editor(ui, code, &editor_id, font_size, common, markers)
+    }
 
-        shader_editor::render_shader_editor(ui, code, &editor_id, font_size);
+    /// Id of the editor widget showing this buffer's fragment or vertex code
+    pub fn editor_id(&self, is_fragment_tab: bool) -> String {
+        let shader_type = if is_fragment_tab { "frag" } else { "vert" };
+        format!("{}_{}", self.kind.as_str().to_lowercase(), shader_type)
     }
 
     /// Get both vertex and fragment shader code
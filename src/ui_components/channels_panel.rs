@@ -0,0 +1,593 @@
+use eframe::egui;
+use eframe::wgpu::{FilterMode, TextureFormat, TextureViewDescriptor, TextureViewDimension};
+use std::sync::{Arc, Weak};
+
+use crate::utils::channel_animation::PlaybackMode;
+use crate::utils::channel_texture::{self, ChannelFilter, ChannelFormat, ChannelInput, ChannelKind, ChannelWrap};
+use crate::utils::multi_buffer_pipeline::MultiPassPipelines;
+use crate::utils::procedural_texture::{Pattern, ProceduralTexture, Ramp, MAX_OCTAVES, MAX_PROCEDURAL_SIZE, MAX_SCALE};
+use crate::utils::video_capture::CaptureDeviceInfo;
+use crate::utils::BufferKind;
+
+/// Image files a channel can load
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "bmp", "gif", "apng", "webp", "tga", "tif", "tiff", "hdr", "exr"];
+
+/// Edge length of a slot's thumbnail
+const THUMBNAIL_SIZE: f32 = 84.0;
+
+/// Actions that can be triggered from the Channels panel
+pub enum ChannelsPanelAction {
+    LoadImageFile(usize, String), // (channel_index, file_path)
+    /// Six cube face images for a channel, to be combined into one cubemap
+    LoadCubeFaces(usize, Vec<String>),
+    /// Empty a channel, stopping its camera if it has one
+    ClearChannel(usize),
+    /// Rescan for cameras
+    RefreshCaptureDevices,
+    /// Feed a channel from a camera at a frame size, or the device's largest
+    StartCapture(usize, String, Option<[u32; 2]>),
+    StopCapture(usize),
+    /// A channel's type, format, sampler or generated texture changed; the pipelines need rebuilding
+    ChannelSettingsChanged,
+    None,
+}
+
+/// Parameters of a channel's generated texture that its pattern uses
+fn procedural_settings(ui: &mut egui::Ui, procedural: &mut ProceduralTexture) {
+    egui::Grid::new("procedural_grid").num_columns(2).spacing([12.0, 6.0]).show(ui, |ui| {
+        ui.label(egui::RichText::new("Size").size(12.0));
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut procedural.size[0]).range(1..=MAX_PROCEDURAL_SIZE));
+            ui.label("×");
+            ui.add(egui::DragValue::new(&mut procedural.size[1]).range(1..=MAX_PROCEDURAL_SIZE));
+        });
+        ui.end_row();
+
+        if procedural.pattern.is_noise() {
+            ui.label(egui::RichText::new("Seed").size(12.0));
+            ui.add(egui::DragValue::new(&mut procedural.seed));
+            ui.end_row();
+        }
+
+        if procedural.pattern.is_fractal() || procedural.pattern == Pattern::Checker {
+            ui.label(egui::RichText::new("Scale").size(12.0));
+            ui.add(egui::DragValue::new(&mut procedural.scale).range(1..=MAX_SCALE))
+                .on_hover_text("Noise cells or checker squares across the texture");
+            ui.end_row();
+        }
+
+        if procedural.pattern.is_fractal() {
+            ui.label(egui::RichText::new("Octaves").size(12.0));
+            ui.add(egui::Slider::new(&mut procedural.octaves, 1..=MAX_OCTAVES));
+            ui.end_row();
+        }
+
+        if procedural.pattern == Pattern::Gradient {
+            ui.label(egui::RichText::new("Ramp").size(12.0));
+            egui::ComboBox::from_id_salt("procedural_ramp")
+                .width(110.0)
+                .selected_text(procedural.ramp.as_str())
+                .show_ui(ui, |ui| {
+                    for ramp in Ramp::ALL {
+                        ui.selectable_value(&mut procedural.ramp, ramp, ramp.as_str());
+                    }
+                });
+            ui.end_row();
+        }
+
+        if procedural.pattern == Pattern::Checker || (procedural.pattern == Pattern::Gradient && procedural.ramp == Ramp::Colors) {
+            ui.label(egui::RichText::new("Colours").size(12.0));
+            ui.horizontal(|ui| {
+                for color in &mut procedural.colors {
+                    let mut color32 = egui::Color32::from_rgba_unmultiplied(color[0], color[1], color[2], color[3]);
+                    if ui.color_edit_button_srgba(&mut color32).changed() {
+                        *color = color32.to_srgba_unmultiplied();
+                    }
+                }
+            });
+            ui.end_row();
+        }
+    });
+    let hint = match procedural.pattern {
+        Pattern::WhiteNoise => "Independent random values in R, G, B and A",
+        Pattern::BlueNoise => "Random values without clumps, for dithering and sampling offsets",
+        Pattern::ValueNoise | Pattern::PerlinNoise => "Tiling fractal noise, a separate noise in each of R, G, B and A",
+        Pattern::Checker => "Light squares are tinted red across and green down, to show orientation",
+        Pattern::Gradient => "Horizontal ramp, left to right",
+    };
+    ui.label(egui::RichText::new(hint).size(10.0).color(egui::Color32::from_rgb(140, 140, 150)));
+}
+
+/// Cameras found by the last scan and the device and size picked among them
+#[derive(Debug, Default)]
+pub struct CapturePicker {
+    pub devices: Vec<CaptureDeviceInfo>,
+    pub device: usize,
+    pub size: usize,
+}
+
+/// A texture of the pipeline, as the panel shows it
+pub struct Thumbnail {
+    /// `None` for volumes, which egui can't draw
+    pub id: Option<egui::TextureId>,
+    pub size: [u32; 3],
+    pub format: TextureFormat,
+    pub bytes: u64,
+}
+
+/// Channel and buffer textures of the current pipeline, registered with egui
+#[derive(Default)]
+pub struct ChannelPreviews {
+    /// Pipeline the thumbnails belong to; holding it keeps its address unique
+    pipeline: Option<Weak<MultiPassPipelines>>,
+    pub channels: [Option<Thumbnail>; 4],
+    pub buffers: [Option<Thumbnail>; 4],
+}
+
+impl ChannelPreviews {
+    /// Register the textures of `pipeline` if it was rebuilt since the last call
+    pub fn sync(&mut self, render_state: &egui_wgpu::RenderState, pipeline: Option<&Arc<MultiPassPipelines>>) {
+        let current = self.pipeline.as_ref().map(Weak::as_ptr);
+        if current == pipeline.map(Arc::as_ptr) {
+            return;
+        }
+
+        let mut renderer = render_state.renderer.write();
+        for id in self.channels.iter().chain(&self.buffers).flatten().filter_map(|thumbnail| thumbnail.id) {
+            renderer.free_texture(&id);
+        }
+        self.pipeline = pipeline.map(Arc::downgrade);
+        self.channels = Default::default();
+        self.buffers = Default::default();
+        let Some(pipeline) = pipeline else {
+            return;
+        };
+
+        let mut register = |texture: &eframe::wgpu::Texture| {
+            let size = texture.size();
+            // Cubemaps show their +X face
+            let id = (texture.dimension() == eframe::wgpu::TextureDimension::D2).then(|| {
+                let view = texture.create_view(&TextureViewDescriptor {
+                    dimension: Some(TextureViewDimension::D2),
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                });
+                renderer.register_native_texture(&render_state.device, &view, FilterMode::Linear)
+            });
+            Thumbnail {
+                id,
+                size: [size.width, size.height, size.depth_or_array_layers],
+                format: texture.format(),
+                bytes: channel_texture::texture_bytes(size, texture.format()),
+            }
+        };
+        self.channels = std::array::from_fn(|i| pipeline.channel_texture(i).map(&mut register));
+        self.buffers = [&pipeline.buffer_a, &pipeline.buffer_b, &pipeline.buffer_c, &pipeline.buffer_d]
+            .map(|buffer| buffer.as_ref().map(|buffer| register(&buffer.target_texture)));
+    }
+}
+
+/// Human-readable byte count
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// Dimensions, format and memory of a thumbnail
+fn thumbnail_details(thumbnail: &Thumbnail, kind: Option<ChannelKind>) -> String {
+    let [width, height, depth] = thumbnail.size;
+    let size = match kind {
+        Some(ChannelKind::Cube) => format!("{}x{} x6", width, height),
+        Some(ChannelKind::Volume) => format!("{}x{}x{}", width, height, depth),
+        _ => format!("{}x{}", width, height),
+    };
+    format!("{}\n{:?}\n{}", size, thumbnail.format, format_bytes(thumbnail.bytes))
+}
+
+/// Draw a thumbnail, or a placeholder text, filling `rect`
+fn paint_thumbnail(ui: &egui::Ui, rect: egui::Rect, thumbnail: Option<&Thumbnail>, empty: &str) {
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4.0, egui::Color32::from_rgb(13, 17, 23));
+    match thumbnail {
+        Some(Thumbnail { id: Some(id), .. }) => {
+            let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+            painter.image(*id, rect.shrink(2.0), uv, egui::Color32::WHITE);
+        }
+        Some(Thumbnail { id: None, .. }) => {
+            painter.text(rect.center(), egui::Align2::CENTER_CENTER, "3D", egui::FontId::proportional(18.0), egui::Color32::from_rgb(140, 140, 150));
+        }
+        None => {
+            painter.text(rect.center(), egui::Align2::CENTER_CENTER, empty, egui::FontId::proportional(11.0), egui::Color32::from_rgb(100, 100, 110));
+        }
+    }
+}
+
+/// Render the Channels panel
+pub fn render(
+    ctx: &egui::Context,
+    show_window: &mut bool,
+    channels: &mut [ChannelInput; 4],
+    selected_channel: &mut usize,
+    capture_picker: &mut CapturePicker,
+    previews: &ChannelPreviews,
+) -> ChannelsPanelAction {
+    let mut action = ChannelsPanelAction::None;
+
+    // Files dragged in from the OS drop onto the slot under the pointer, or the selected one
+    let (hovered_files, dropped_files, pointer) = ctx.input(|i| {
+        let dropped: Vec<String> = i
+            .raw
+            .dropped_files
+            .iter()
+            .filter_map(|file| file.path.as_ref())
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        (!i.raw.hovered_files.is_empty(), dropped, i.pointer.latest_pos())
+    });
+    let mut drop_target = *selected_channel;
+
+    egui::Window::new("🖼 Channels")
+        .id(egui::Id::new("channels_window"))
+        .default_pos([820.0, 80.0])
+        .resizable(false)
+        .collapsible(false)
+        .open(show_window)
+        .show(ctx, |ui| {
+            ui.set_min_width(420.0);
+            ui.spacing_mut().item_spacing = egui::vec2(8.0, 8.0);
+
+            // Channel slots
+            ui.push_id("channel_slots", |ui| {
+            egui::Frame::group(ui.style())
+                .fill(egui::Color32::from_rgb(25, 25, 30))
+                .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(60, 60, 70)))
+                .corner_radius(6.0)
+                .inner_margin(12.0)
+                .show(ui, |ui| {
+                    ui.label(egui::RichText::new("iChannels").size(16.0).strong());
+                    ui.add_space(4.0);
+                    ui.horizontal_top(|ui| {
+                        for (i, channel) in channels.iter().enumerate() {
+                            ui.vertical(|ui| {
+                                ui.set_width(THUMBNAIL_SIZE);
+                                ui.label(egui::RichText::new(format!("iChannel{}", i)).monospace().size(11.0));
+                                let (rect, response) = ui.allocate_exact_size(egui::vec2(THUMBNAIL_SIZE, THUMBNAIL_SIZE), egui::Sense::click());
+                                let filled = channel.path.is_some() || channel.settings.procedural.is_some();
+                                let thumbnail = previews.channels[i].as_ref().filter(|_| filled);
+                                paint_thumbnail(ui, rect, thumbnail, "Drop image");
+
+                                let drop_hovered = hovered_files && pointer.is_some_and(|pos| rect.contains(pos));
+                                if pointer.is_some_and(|pos| rect.contains(pos)) {
+                                    drop_target = i;
+                                }
+                                let stroke = if drop_hovered {
+                                    egui::Stroke::new(2.0, egui::Color32::from_rgb(120, 220, 120))
+                                } else if *selected_channel == i {
+                                    egui::Stroke::new(2.0, egui::Color32::from_rgb(100, 150, 255))
+                                } else {
+                                    egui::Stroke::new(1.0, egui::Color32::from_rgb(60, 60, 70))
+                                };
+                                ui.painter().rect_stroke(rect, 4.0, stroke, egui::StrokeKind::Inside);
+
+                                let response = match (&channel.settings.procedural, &channel.path) {
+                                    (Some(procedural), _) => response.on_hover_text(procedural.label()),
+                                    (None, Some(path)) => response.on_hover_text(path),
+                                    (None, None) => response.on_hover_text("Click to select, or drop an image file here"),
+                                };
+                                if response.clicked() {
+                                    *selected_channel = i;
+                                }
+
+                                let details = match thumbnail {
+                                    Some(thumbnail) => thumbnail_details(thumbnail, Some(channel.settings.kind)),
+                                    None if filled => "Not loaded".to_string(),
+                                    None => "Empty".to_string(),
+                                };
+                                ui.label(egui::RichText::new(details).size(10.0).color(egui::Color32::from_rgb(140, 140, 150)));
+                            });
+                        }
+                    });
+
+                    let total: u64 = channels
+                        .iter()
+                        .zip(&previews.channels)
+                        .filter(|(channel, _)| channel.path.is_some() || channel.settings.procedural.is_some())
+                        .filter_map(|(_, thumbnail)| thumbnail.as_ref())
+                        .map(|thumbnail| thumbnail.bytes)
+                        .sum();
+                    ui.label(
+                        egui::RichText::new(format!("Channel textures: {}", format_bytes(total)))
+                            .size(11.0)
+                            .color(egui::Color32::from_rgb(140, 140, 150)),
+                    );
+                });
+            });
+
+            ui.add_space(12.0);
+
+            // Buffer outputs
+            ui.push_id("buffer_outputs", |ui| {
+            egui::Frame::group(ui.style())
+                .fill(egui::Color32::from_rgb(25, 25, 30))
+                .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(60, 60, 70)))
+                .corner_radius(6.0)
+                .inner_margin(12.0)
+                .show(ui, |ui| {
+                    ui.label(egui::RichText::new("Buffer Outputs").size(16.0).strong());
+                    ui.add_space(4.0);
+                    ui.horizontal_top(|ui| {
+                        let buffers = [BufferKind::BufferA, BufferKind::BufferB, BufferKind::BufferC, BufferKind::BufferD];
+                        for (kind, thumbnail) in buffers.iter().zip(&previews.buffers) {
+                            ui.vertical(|ui| {
+                                ui.set_width(THUMBNAIL_SIZE);
+                                ui.label(egui::RichText::new(kind.as_str()).monospace().size(11.0));
+                                let (rect, _) = ui.allocate_exact_size(egui::vec2(THUMBNAIL_SIZE, THUMBNAIL_SIZE), egui::Sense::hover());
+                                paint_thumbnail(ui, rect, thumbnail.as_ref(), "Unused");
+                                ui.painter().rect_stroke(rect, 4.0, egui::Stroke::new(1.0, egui::Color32::from_rgb(60, 60, 70)), egui::StrokeKind::Inside);
+                                if let Some(thumbnail) = thumbnail {
+                                    ui.label(egui::RichText::new(thumbnail_details(thumbnail, None)).size(10.0).color(egui::Color32::from_rgb(140, 140, 150)));
+                                }
+                            });
+                        }
+                    });
+                    ui.label(
+                        egui::RichText::new("Read in MainImage as buffer_a_texture..buffer_d_texture")
+                            .size(10.0)
+                            .color(egui::Color32::from_rgb(140, 140, 150)),
+                    );
+                });
+            });
+
+            ui.add_space(12.0);
+
+            // Settings of the selected channel
+            ui.push_id("channel_settings", |ui| {
+            egui::Frame::group(ui.style())
+                .fill(egui::Color32::from_rgb(25, 25, 30))
+                .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(60, 60, 70)))
+                .corner_radius(6.0)
+                .inner_margin(12.0)
+                .show(ui, |ui| {
+                    ui.label(egui::RichText::new(format!("iChannel{} Settings", selected_channel)).size(16.0).strong());
+                    ui.add_space(4.0);
+
+                    let channel = &mut channels[*selected_channel];
+                    let before = channel.settings;
+                    egui::Grid::new("channel_settings_grid").num_columns(2).spacing([12.0, 6.0]).show(ui, |ui| {
+                        ui.label(egui::RichText::new("Type").size(12.0));
+                        egui::ComboBox::from_id_salt("channel_kind")
+                            .width(110.0)
+                            .selected_text(channel.settings.kind.as_str())
+                            .show_ui(ui, |ui| {
+                                for kind in ChannelKind::ALL {
+                                    ui.selectable_value(&mut channel.settings.kind, kind, kind.as_str());
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label(egui::RichText::new("Source").size(12.0));
+                        let source = channel.settings.procedural.map_or("File", |procedural| procedural.pattern.as_str());
+                        ui.add_enabled_ui(channel.settings.kind == ChannelKind::Texture2d, |ui| {
+                            egui::ComboBox::from_id_salt("channel_source")
+                                .width(110.0)
+                                .selected_text(source)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut channel.settings.procedural, None, "File");
+                                    for pattern in Pattern::ALL {
+                                        let procedural = ProceduralTexture {
+                                            pattern,
+                                            ..channel.settings.procedural.unwrap_or_default()
+                                        };
+                                        ui.selectable_value(&mut channel.settings.procedural, Some(procedural), pattern.as_str());
+                                    }
+                                });
+                        });
+                        ui.end_row();
+
+                        ui.label(egui::RichText::new("Format").size(12.0));
+                        egui::ComboBox::from_id_salt("channel_format")
+                            .width(110.0)
+                            .selected_text(channel.settings.format.as_str())
+                            .show_ui(ui, |ui| {
+                                for format in ChannelFormat::ALL {
+                                    ui.selectable_value(&mut channel.settings.format, format, format.as_str());
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label(egui::RichText::new("Wrap").size(12.0));
+                        egui::ComboBox::from_id_salt("channel_wrap")
+                            .width(110.0)
+                            .selected_text(channel.settings.wrap.as_str())
+                            .show_ui(ui, |ui| {
+                                for wrap in ChannelWrap::ALL {
+                                    ui.selectable_value(&mut channel.settings.wrap, wrap, wrap.as_str());
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label(egui::RichText::new("Filter").size(12.0));
+                        egui::ComboBox::from_id_salt("channel_filter")
+                            .width(110.0)
+                            .selected_text(channel.settings.filter.as_str())
+                            .show_ui(ui, |ui| {
+                                for filter in ChannelFilter::ALL {
+                                    ui.selectable_value(&mut channel.settings.filter, filter, filter.as_str());
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label(egui::RichText::new("Flip Y").size(12.0));
+                        ui.add_enabled(
+                            channel.settings.kind == ChannelKind::Texture2d,
+                            egui::Checkbox::without_text(&mut channel.settings.flip_y),
+                        );
+                        ui.end_row();
+                    });
+                    if let Some(procedural) = &mut channel.settings.procedural {
+                        procedural_settings(ui, procedural);
+                    }
+                    // Playback and rate are read by the preview every frame, so they need no rebuild
+                    let mut rebuild = before;
+                    rebuild.playback = channel.settings.playback;
+                    rebuild.rate = channel.settings.rate;
+                    if channel.settings != rebuild {
+                        action = ChannelsPanelAction::ChannelSettingsChanged;
+                    }
+
+                    let hint = match channel.settings.kind {
+                        ChannelKind::Texture2d => "Declared as texture_2d<f32>",
+                        ChannelKind::Cube => "texture_cube<f32>: a 6:1 or 1:6 face strip (+X -X +Y -Y +Z -Z), a 4:3 cross, a 2:1 panorama or six face images",
+                        ChannelKind::Volume => "texture_3d<f32>: an image of square slices (strip or N³ grid) or raw 8-bit voxels named like volume_64x64x64.raw",
+                    };
+                    ui.label(egui::RichText::new(hint).size(10.0).color(egui::Color32::from_rgb(140, 140, 150)));
+                    let format_hint = match channel.settings.format {
+                        ChannelFormat::Auto => "RGBA16F for .hdr/.exr, Linear for volumes and noise, sRGB otherwise",
+                        ChannelFormat::Srgb => "8-bit colour, decoded from sRGB when sampled",
+                        ChannelFormat::Linear => "8-bit data sampled as stored (normal maps, noise)",
+                        ChannelFormat::Rgba16Float | ChannelFormat::Rgba32Float => "Float values as stored in the file, no sRGB decoding",
+                    };
+                    ui.label(egui::RichText::new(format_hint).size(10.0).color(egui::Color32::from_rgb(140, 140, 150)));
+
+                    if channel.settings.kind == ChannelKind::Texture2d && channel.settings.procedural.is_none() {
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("Playback:").strong().size(12.0));
+                            egui::ComboBox::from_id_salt("channel_playback")
+                                .width(90.0)
+                                .selected_text(channel.settings.playback.as_str())
+                                .show_ui(ui, |ui| {
+                                    for mode in PlaybackMode::ALL {
+                                        ui.selectable_value(&mut channel.settings.playback, mode, mode.as_str());
+                                    }
+                                });
+                            ui.label(egui::RichText::new("Rate:").strong().size(12.0));
+                            ui.add(egui::DragValue::new(&mut channel.settings.rate).speed(0.05).range(-8.0..=8.0).suffix("x"));
+                        });
+                        ui.label(
+                            egui::RichText::new("For animated GIF/APNG files and image sequences; read as uniforms.channel_time")
+                                .size(10.0)
+                                .color(egui::Color32::from_rgb(140, 140, 150)),
+                        );
+                    }
+
+                    ui.add_space(4.0);
+                    let kind = channel.settings.kind;
+                    ui.horizontal(|ui| {
+                        let width = (ui.available_width() - ui.spacing().item_spacing.x) / 2.0;
+                        if ui.add_sized([width, 30.0], egui::Button::new(
+                            egui::RichText::new("Load File...").size(13.0)
+                        )).clicked() {
+                            let mut dialog = rfd::FileDialog::new().add_filter("Images", IMAGE_EXTENSIONS);
+                            if kind == ChannelKind::Volume {
+                                dialog = dialog.add_filter("Raw Volumes", &["raw", "vol", "bin"]);
+                            }
+                            if let Some(path) = dialog.pick_file() {
+                                action = ChannelsPanelAction::LoadImageFile(*selected_channel, path.to_string_lossy().to_string());
+                            }
+                        }
+                        if ui.add_enabled_ui(channel.path.is_some() || channel.settings.procedural.is_some(), |ui| {
+                            ui.add_sized([width, 30.0], egui::Button::new(egui::RichText::new("Clear").size(13.0)))
+                        }).inner.clicked() {
+                            action = ChannelsPanelAction::ClearChannel(*selected_channel);
+                        }
+                    });
+                    if kind == ChannelKind::Texture2d
+                        && ui.add_sized([ui.available_width(), 24.0], egui::Button::new("Load Image Sequence...")).clicked()
+                    {
+                        if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                            action = ChannelsPanelAction::LoadImageFile(*selected_channel, dir.to_string_lossy().to_string());
+                        }
+                    }
+                    if kind == ChannelKind::Cube
+                        && ui.add_sized([ui.available_width(), 24.0], egui::Button::new("Load 6 Cube Faces...")).clicked()
+                    {
+                        if let Some(paths) = rfd::FileDialog::new().add_filter("Images", IMAGE_EXTENSIONS).pick_files() {
+                            let paths = paths.iter().map(|path| path.to_string_lossy().to_string()).collect();
+                            action = ChannelsPanelAction::LoadCubeFaces(*selected_channel, paths);
+                        }
+                    }
+
+                    if kind == ChannelKind::Texture2d {
+                        ui.add_space(4.0);
+                        ui.separator();
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new("Camera:").strong().size(12.0));
+                            let selected = capture_picker
+                                .devices
+                                .get(capture_picker.device)
+                                .map_or("None found", |device| device.name.as_str());
+                            let before = capture_picker.device;
+                            egui::ComboBox::from_id_salt("channel_camera")
+                                .width(220.0)
+                                .selected_text(selected)
+                                .show_ui(ui, |ui| {
+                                    for (i, device) in capture_picker.devices.iter().enumerate() {
+                                        ui.selectable_value(&mut capture_picker.device, i, &device.name);
+                                    }
+                                });
+                            if capture_picker.device != before {
+                                capture_picker.size = usize::MAX;
+                            }
+                            if ui.button("⟳").on_hover_text("Scan for cameras").clicked() {
+                                action = ChannelsPanelAction::RefreshCaptureDevices;
+                            }
+                        });
+                        if let Some(device) = capture_picker.devices.get(capture_picker.device) {
+                            // Default to the largest size
+                            if capture_picker.size >= device.sizes.len() {
+                                capture_picker.size = device.sizes.len().saturating_sub(1);
+                            }
+                            let size = device.sizes.get(capture_picker.size).copied();
+                            ui.horizontal(|ui| {
+                                ui.label(egui::RichText::new("Size:").strong().size(12.0));
+                                let size_text = |[width, height]: [u32; 2]| format!("{}x{}", width, height);
+                                egui::ComboBox::from_id_salt("channel_camera_size")
+                                    .width(110.0)
+                                    .selected_text(size.map_or("Device default".to_string(), size_text))
+                                    .show_ui(ui, |ui| {
+                                        for (i, size) in device.sizes.iter().enumerate() {
+                                            ui.selectable_value(&mut capture_picker.size, i, size_text(*size));
+                                        }
+                                    });
+                            });
+                            if ui.add_sized([ui.available_width(), 24.0], egui::Button::new(
+                                format!("Start Camera on iChannel{}", selected_channel)
+                            )).clicked() {
+                                action = ChannelsPanelAction::StartCapture(*selected_channel, device.path.clone(), size);
+                            }
+                        }
+                        if let Some(capture) = &channels[*selected_channel].live {
+                            ui.horizontal(|ui| {
+                                let [width, height] = capture.size;
+                                let status = match capture.error() {
+                                    Some(e) => format!("Camera stopped: {}", e),
+                                    None => format!("Live: {} {}x{}", capture.path, width, height),
+                                };
+                                ui.label(egui::RichText::new(status).size(11.0).color(egui::Color32::from_rgb(120, 220, 120)));
+                                if ui.button("Stop").clicked() {
+                                    action = ChannelsPanelAction::StopCapture(*selected_channel);
+                                }
+                            });
+                        }
+                    }
+                });
+            });
+        });
+
+    // Six images dropped on a cubemap channel become its faces
+    match dropped_files.len() {
+        0 => {}
+        6 if channels[drop_target].settings.kind == ChannelKind::Cube => {
+            action = ChannelsPanelAction::LoadCubeFaces(drop_target, dropped_files);
+        }
+        _ => {
+            *selected_channel = drop_target;
+            action = ChannelsPanelAction::LoadImageFile(drop_target, dropped_files[0].clone());
+        }
+    }
+
+    action
+}
@@ -1,3 +1,4 @@
+pub mod channels_panel;
 pub mod settings_menu;
 pub mod shader_properties;
 pub mod shader_editor;
@@ -1,6 +1,8 @@
 use eframe::egui;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
+#[allow(clippy::too_many_arguments)]
 pub fn settings_overlay(
     ctx: &egui::Context,
     show_settings: &mut bool,
@@ -8,6 +10,9 @@ pub fn settings_overlay(
     gamma: &Arc<Mutex<f32>>,
     contrast: &Arc<Mutex<f32>>,
     saturation: &Arc<Mutex<f32>>,
+    library_dirs: &mut Vec<PathBuf>,
+    inline_includes: &mut bool,
+    strict_shard_import: &mut bool,
 ) {
     if !*show_settings {
         return;
@@ -195,6 +200,56 @@ pub fn settings_overlay(
 
             ui.add_space(10.0);
 
+            // Shader Library Section
+            ui.push_id("library_section", |ui| {
+                ui.group(|ui| {
+                    ui.set_min_width(320.0);
+                    ui.heading("📚 Shader Library");
+                    ui.add_space(4.0);
+                    ui.label(
+                        egui::RichText::new("Folders searched by //#include \"path.wgsl\", in order")
+                            .size(10.0)
+                            .weak(),
+                    );
+                    ui.add_space(6.0);
+
+                    let mut remove = None;
+                    for (i, dir) in library_dirs.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui.small_button("✖").on_hover_text("Remove folder").clicked() {
+                                remove = Some(i);
+                            }
+                            let text = egui::RichText::new(dir.display().to_string()).monospace().size(11.0);
+                            if dir.is_dir() {
+                                ui.label(text);
+                            } else {
+                                ui.label(text.weak()).on_hover_text("Folder does not exist");
+                            }
+                        });
+                    }
+                    if let Some(i) = remove {
+                        library_dirs.remove(i);
+                    }
+
+                    ui.add_space(4.0);
+                    if ui.button(egui::RichText::new("➕ Add Folder...").size(12.0)).clicked() {
+                        if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                            if !library_dirs.contains(&dir) {
+                                library_dirs.push(dir);
+                            }
+                        }
+                    }
+
+                    ui.add_space(8.0);
+                    ui.checkbox(inline_includes, "Inline includes on export")
+                        .on_hover_text("Replace //#include lines with the file contents so players without the library can load the shard");
+                    ui.checkbox(strict_shard_import, "Strict shard import")
+                        .on_hover_text("Reject shards with unknown fields or invalid base64 instead of skipping what can't be read");
+                });
+            });
+
+            ui.add_space(10.0);
+
             // Close button
             ui.vertical_centered(|ui| {
                 if ui.button(egui::RichText::new("Close").size(15.0)).clicked() {
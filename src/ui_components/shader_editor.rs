@@ -4,47 +4,491 @@
 //! eliminating the need for separate editor implementations per buffer.
 
 use eframe::egui;
+use eframe::egui::text::{CCursor, CCursorRange};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::sync::Arc;
 
+use crate::utils::completion::{self, CompletionItem};
+use crate::utils::diagnostics::{InlineMarker, Severity};
+use crate::utils::hover::{self, AnalyzedSource, HoverInfo, SignatureHelp};
+use crate::utils::text::byte_index_from_char_index;
+use crate::utils::wgsl_symbols::SymbolIndex;
 #[cfg(feature = "code_editor")]
 use crate::utils::wgsl_syntax;
 
+/// Completion popup state, kept in egui temp memory per editor
+#[derive(Clone, Default)]
+struct CompletionPopup {
+    visible: bool,
+    selected: usize,
+}
+
+/// Requests that need all buffers and are handled by the caller
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditorAction {
+    /// The symbol at this byte offset is not declared in this editor (F12)
+    GoToDefinition(usize),
+    /// Find references of the symbol at this byte offset (Shift+F12)
+    FindReferences(usize),
+    /// Rename the symbol at this byte offset (F2)
+    Rename(usize),
+}
+
+/// Select a char range in an editor and scroll to it on its next frame
+///
+/// Used to navigate to a location in a buffer that is not currently shown.
+pub fn reveal(ctx: &egui::Context, editor_id: &str, chars: Range<usize>) {
+    ctx.data_mut(|d| d.insert_temp(egui::Id::new(editor_id).with("reveal"), chars));
+}
+
 /// Renders a WGSL shader editor with consistent styling and features
 ///
+/// Go-to-definition (F12 / Ctrl+click) is handled here; find references and
+/// rename are returned as an [`EditorAction`] since they span all buffers.
+///
 /// # Arguments
 /// * `ui` - The egui UI context
 /// * `code` - Mutable reference to the shader code string
 /// * `editor_id` - Unique identifier for this editor instance
 /// * `font_size` - Font size for the editor
+/// * `prelude` - Code compiled ahead of this source (the Common tab), used for hover types
+/// * `markers` - Diagnostics of the last compilation to underline in this source
 pub fn render_shader_editor(
     ui: &mut egui::Ui,
     code: &mut String,
     editor_id: &str,
     font_size: f32,
-) {
+    prelude: &str,
+    markers: &[InlineMarker],
+) -> Option<EditorAction> {
     ui.set_min_height(ui.available_height());
 
+    let popup_id = egui::Id::new(editor_id).with("completion_popup");
+    let mut popup: CompletionPopup = ui.data(|d| d.get_temp(popup_id)).unwrap_or_default();
+
+    // Navigation keys must be consumed before the text edit sees them
+    let mut accept = false;
+    let mut force_open = false;
+    ui.input_mut(|i| {
+        if popup.visible {
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) {
+                popup.selected += 1;
+            }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) {
+                popup.selected = popup.selected.saturating_sub(1);
+            }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::Enter)
+                || i.consume_key(egui::Modifiers::NONE, egui::Key::Tab)
+            {
+                accept = true;
+            }
+            if i.consume_key(egui::Modifiers::NONE, egui::Key::Escape) {
+                popup.visible = false;
+            }
+        }
+        if i.consume_key(egui::Modifiers::COMMAND, egui::Key::Space) {
+            force_open = true;
+        }
+    });
+
     #[cfg(feature = "code_editor")]
-    {
-        egui_code_editor::CodeEditor::default()
-            .id_source(editor_id)
-            .with_fontsize(font_size)
-            .with_theme(egui_code_editor::ColorTheme::GITHUB_DARK)
-            .with_syntax(wgsl_syntax::wgsl())
-            .with_numlines(true)
-            .vscroll(true)
-            .auto_shrink(false)
-            .show(ui, code);
-    }
+    let output = egui_code_editor::CodeEditor::default()
+        .id_source(editor_id)
+        .with_fontsize(font_size)
+        .with_theme(egui_code_editor::ColorTheme::GITHUB_DARK)
+        .with_syntax(wgsl_syntax::wgsl())
+        .with_numlines(true)
+        .vscroll(true)
+        .auto_shrink(false)
+        .show(ui, code);
 
     #[cfg(not(feature = "code_editor"))]
-    {
-        ui.add(
-            egui::TextEdit::multiline(code)
-                .id(egui::Id::new(editor_id))
-                .font(egui::TextStyle::Monospace)
-                .code_editor()
-                .desired_width(f32::INFINITY)
-                .desired_rows(30),
-        );
+    let output = egui::TextEdit::multiline(code)
+        .id(egui::Id::new(editor_id))
+        .font(egui::FontId::monospace(font_size))
+        .code_editor()
+        .desired_width(f32::INFINITY)
+        .desired_rows(30)
+        .show(ui);
+
+    let caret = output
+        .cursor_range
+        .filter(|range| range.is_empty())
+        .map(|range| range.primary.index);
+
+    let items = match caret {
+        Some(caret) if output.response.has_focus() => {
+            if output.response.changed() || force_open {
+                popup.visible = true;
+            }
+            if output.response.clicked() {
+                popup.visible = false;
+            }
+            if popup.visible {
+                completion::completions(code, caret)
+            } else {
+                Vec::new()
+            }
+        }
+        _ => {
+            popup.visible = false;
+            Vec::new()
+        }
+    };
+
+    if items.is_empty() {
+        popup.visible = false;
+        popup.selected = 0;
+    } else {
+        popup.selected = popup.selected.min(items.len() - 1);
+    }
+
+    let mut chosen = if accept && popup.visible {
+        Some(popup.selected)
+    } else {
+        None
+    };
+
+    if popup.visible && chosen.is_none() {
+        if let Some(caret) = caret {
+            let caret_rect = output.galley.pos_from_cursor(CCursor::new(caret));
+            let anchor = output.galley_pos + caret_rect.left_bottom().to_vec2();
+            chosen = show_completion_popup(ui.ctx(), popup_id, anchor, &items, &mut popup.selected);
+        }
+    }
+
+    if let (Some(index), Some(caret)) = (chosen, caret) {
+        let new_caret = completion::accept_completion(code, caret, &items[index]);
+        let mut state = output.state.clone();
+        state
+            .cursor
+            .set_char_range(Some(CCursorRange::one(CCursor::new(new_caret))));
+        state.store(ui.ctx(), output.response.id);
+        output.response.request_focus();
+        popup.visible = false;
+        popup.selected = 0;
+    }
+
+    // Signature help while the caret is inside a call
+    if let Some(caret) = caret.filter(|_| output.response.has_focus() && !popup.visible) {
+        if let Some(help) = hover::signature_help(code, caret) {
+            let caret_rect = output.galley.pos_from_cursor(CCursor::new(caret));
+            let anchor = output.galley_pos + caret_rect.left_top().to_vec2();
+            show_signature_help(
+                ui.ctx(),
+                egui::Id::new(editor_id).with("signature_help"),
+                anchor,
+                &help,
+            );
+        }
+    }
+
+    let hovered_char = output.response.hover_pos().and_then(|pos| {
+        let local = pos - output.galley_pos;
+        output
+            .galley
+            .rect
+            .contains(local.to_pos2())
+            .then(|| output.galley.cursor_from_pos(local).index)
+    });
+
+    // Diagnostics: squiggly underline, message on hover
+    let marker_chars: Vec<(Range<usize>, &InlineMarker)> = markers
+        .iter()
+        .filter(|m| m.range.end <= code.len() && code.is_char_boundary(m.range.start) && code.is_char_boundary(m.range.end))
+        .map(|m| {
+            let start = code[..m.range.start].chars().count();
+            (start..start + code[m.range.clone()].chars().count(), m)
+        })
+        .collect();
+    for (chars, marker) in &marker_chars {
+        paint_marker(ui, &output, chars.clone(), marker.severity);
+    }
+    let hovered_marker = hovered_char.and_then(|c| {
+        marker_chars
+            .iter()
+            .find(|(chars, _)| chars.start <= c && c < chars.end)
+            .map(|(_, marker)| *marker)
+    });
+
+    // Hover documentation for the identifier under the pointer
+    if let Some(marker) = hovered_marker.filter(|_| !popup.visible) {
+        output.response.clone().on_hover_ui_at_pointer(|ui| {
+            ui.set_max_width(420.0);
+            ui.label(
+                egui::RichText::new(&marker.message)
+                    .monospace()
+                    .size(12.0)
+                    .color(marker_color(marker.severity)),
+            );
+        });
+    } else if !popup.visible {
+        if let Some(char_index) = hovered_char {
+            let analysis = analyzed_source(ui.ctx(), editor_id, prelude, code);
+            if let Some(info) = hover::hover_info(code, char_index, &analysis) {
+                output
+                    .response
+                    .clone()
+                    .on_hover_ui_at_pointer(|ui| show_hover_info(ui, &info));
+            }
+        }
+    }
+
+    // Symbol navigation
+    let mut action = None;
+    let (f12, shift, f2, command) = ui.input(|i| {
+        (
+            i.key_pressed(egui::Key::F12),
+            i.modifiers.shift,
+            i.key_pressed(egui::Key::F2),
+            i.modifiers.command,
+        )
+    });
+    let ctrl_click = output.response.clicked() && command;
+    let target = if ctrl_click {
+        hovered_char
+    } else if output.response.has_focus() {
+        caret
+    } else {
+        None
+    };
+
+    if let Some(target) = target.map(|c| byte_index_from_char_index(code, c)) {
+        if ctrl_click || (f12 && !shift) {
+            let index = SymbolIndex::build(code);
+            match index.symbol_at(code, target) {
+                Some(symbol) => {
+                    let start = code[..symbol.decl.start].chars().count();
+                    let end = start + symbol.name.chars().count();
+                    select_chars(ui, &output, start..end);
+                }
+                None => action = Some(EditorAction::GoToDefinition(target)),
+            }
+        } else if f12 && shift {
+            action = Some(EditorAction::FindReferences(target));
+        } else if f2 {
+            action = Some(EditorAction::Rename(target));
+        }
+    }
+
+    let reveal_id = egui::Id::new(editor_id).with("reveal");
+    if let Some(chars) = ui.data_mut(|d| d.remove_temp::<Range<usize>>(reveal_id)) {
+        select_chars(ui, &output, chars);
+    }
+
+    ui.data_mut(|d| d.insert_temp(popup_id, popup));
+
+    action
+}
+
+/// Select a char range, focus the editor and scroll the selection into view
+fn select_chars(ui: &egui::Ui, output: &egui::text_edit::TextEditOutput, chars: Range<usize>) {
+    let mut state = output.state.clone();
+    state.cursor.set_char_range(Some(CCursorRange::two(
+        CCursor::new(chars.start),
+        CCursor::new(chars.end),
+    )));
+    state.store(ui.ctx(), output.response.id);
+    output.response.request_focus();
+
+    let rect = output
+        .galley
+        .pos_from_cursor(CCursor::new(chars.start))
+        .translate(output.galley_pos.to_vec2());
+    ui.scroll_to_rect(rect, Some(egui::Align::Center));
+}
+
+/// Naga analysis of the current code, rebuilt only when the code changes
+fn analyzed_source(
+    ctx: &egui::Context,
+    editor_id: &str,
+    prelude: &str,
+    code: &str,
+) -> Arc<AnalyzedSource> {
+    let cache_id = egui::Id::new(editor_id).with("hover_analysis");
+    let mut hasher = DefaultHasher::new();
+    prelude.hash(&mut hasher);
+    code.hash(&mut hasher);
+    let hash = hasher.finish();
+
+    let cached: Option<(u64, Arc<AnalyzedSource>)> = ctx.data(|d| d.get_temp(cache_id));
+    match cached {
+        Some((cached_hash, analysis)) if cached_hash == hash => analysis,
+        _ => {
+            let analysis = Arc::new(AnalyzedSource::new(prelude, code));
+            ctx.data_mut(|d| d.insert_temp(cache_id, (hash, analysis.clone())));
+            analysis
+        }
+    }
+}
+
+fn marker_color(severity: Severity) -> egui::Color32 {
+    match severity {
+        Severity::Error => egui::Color32::from_rgb(255, 100, 100),
+        Severity::Warning => egui::Color32::from_rgb(255, 200, 90),
+        Severity::Note => egui::Color32::from_rgb(130, 180, 255),
+    }
+}
+
+/// Zigzag underline below a char range, one segment per row
+fn paint_marker(ui: &egui::Ui, output: &egui::text_edit::TextEditOutput, chars: Range<usize>, severity: Severity) {
+    let stroke = egui::Stroke::new(1.0, marker_color(severity));
+    let start = output.galley.pos_from_cursor(CCursor::new(chars.start));
+    let end = output.galley.pos_from_cursor(CCursor::new(chars.end));
+
+    // Spans crossing lines only underline their first row
+    let right = if (start.top() - end.top()).abs() < 1.0 {
+        end.left()
+    } else {
+        output.galley.rect.right()
+    };
+
+    let left = output.galley_pos.x + start.left();
+    let right = (output.galley_pos.x + right).max(left + 4.0);
+    let y = output.galley_pos.y + start.bottom();
+    let mut points = Vec::new();
+    let mut x = left;
+    let mut up = false;
+    while x <= right {
+        points.push(egui::pos2(x, if up { y - 1.5 } else { y + 0.5 }));
+        x += 2.0;
+        up = !up;
+    }
+    ui.painter().add(egui::Shape::line(points, stroke));
+}
+
+fn show_hover_info(ui: &mut egui::Ui, info: &HoverInfo) {
+    ui.set_max_width(420.0);
+    ui.label(
+        egui::RichText::new(&info.signature)
+            .monospace()
+            .size(12.0)
+            .color(egui::Color32::from_rgb(150, 200, 255)),
+    );
+    if !info.doc.is_empty() {
+        ui.label(egui::RichText::new(&info.doc).size(11.0).weak());
     }
 }
+
+/// Draw the signature bubble above the caret with the active parameter highlighted
+fn show_signature_help(
+    ctx: &egui::Context,
+    id: egui::Id,
+    anchor: egui::Pos2,
+    help: &SignatureHelp,
+) {
+    let base = egui::TextFormat {
+        font_id: egui::FontId::monospace(12.0),
+        color: egui::Color32::from_rgb(180, 180, 190),
+        ..Default::default()
+    };
+    let active = egui::TextFormat {
+        color: egui::Color32::from_rgb(255, 210, 120),
+        underline: egui::Stroke::new(1.0, egui::Color32::from_rgb(255, 210, 120)),
+        ..base.clone()
+    };
+
+    let mut job = egui::text::LayoutJob::default();
+    job.append(&format!("{}(", help.name), 0.0, base.clone());
+    for (i, param) in help.params.iter().enumerate() {
+        if i > 0 {
+            job.append(", ", 0.0, base.clone());
+        }
+        let format = if i == help.active {
+            active.clone()
+        } else {
+            base.clone()
+        };
+        job.append(param, 0.0, format);
+    }
+    job.append(")", 0.0, base.clone());
+    if !help.ret.is_empty() {
+        job.append(&format!(" {}", help.ret), 0.0, base);
+    }
+
+    egui::Area::new(id)
+        .order(egui::Order::Foreground)
+        .pivot(egui::Align2::LEFT_BOTTOM)
+        .fixed_pos(anchor - egui::vec2(0.0, 2.0))
+        .interactable(false)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.set_max_width(480.0);
+                ui.label(job);
+                if !help.doc.is_empty() {
+                    ui.label(egui::RichText::new(&help.doc).size(11.0).weak());
+                }
+            });
+        });
+}
+
+/// Draw the completion list with a signature/doc preview of the selected entry
+///
+/// Returns the index of a clicked entry.
+fn show_completion_popup(
+    ctx: &egui::Context,
+    popup_id: egui::Id,
+    anchor: egui::Pos2,
+    items: &[CompletionItem],
+    selected: &mut usize,
+) -> Option<usize> {
+    let mut clicked = None;
+
+    egui::Area::new(popup_id)
+        .order(egui::Order::Foreground)
+        .fixed_pos(anchor + egui::vec2(0.0, 2.0))
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.set_max_width(420.0);
+                ui.spacing_mut().item_spacing = egui::vec2(4.0, 2.0);
+
+                for (i, item) in items.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            egui::RichText::new(item.kind.tag())
+                                .monospace()
+                                .size(10.0)
+                                .color(egui::Color32::from_rgb(140, 140, 150)),
+                        );
+                        let response = ui.selectable_label(
+                            i == *selected,
+                            egui::RichText::new(&item.label).monospace().size(12.0),
+                        );
+                        if response.hovered() {
+                            *selected = i;
+                        }
+                        if response.clicked() {
+                            clicked = Some(i);
+                        }
+                    });
+                }
+
+                if let Some(item) = items.get(*selected) {
+                    if !item.detail.is_empty() || !item.doc.is_empty() {
+                        ui.separator();
+                    }
+                    if !item.detail.is_empty() {
+                        ui.label(
+                            egui::RichText::new(&item.detail)
+                                .monospace()
+                                .size(11.0)
+                                .color(egui::Color32::from_rgb(150, 200, 255)),
+                        );
+                    }
+                    if !item.doc.is_empty() {
+                        ui.label(egui::RichText::new(&item.doc).size(11.0).weak());
+                    }
+                }
+
+                ui.label(
+                    egui::RichText::new("↑↓ select · Enter/Tab accept · Esc close")
+                        .size(9.0)
+                        .color(egui::Color32::from_rgb(100, 100, 110)),
+                );
+            });
+        });
+
+    clicked
+}
@@ -1,12 +1,29 @@
 use eframe::egui;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
+use crate::compiler::standalone::StandaloneFormat;
+use crate::screens::shader_buffer::ShaderBuffer;
+use crate::utils::pass_geometry::{Topology, MAX_INSTANCE_COUNT, MAX_VERTEX_COUNT};
+use crate::utils::{BufferKind, PassGeometry};
+
 /// Actions that can be triggered from the Shader Properties window
 pub enum ShaderPropertiesAction {
     LoadAudioFile(String),
-    LoadImageFile(usize, String), // (channel_index, file_path)
     ExportShard,
     ImportShard,
+    /// Import a shard JSON and re-import it whenever it is saved
+    LinkShard,
+    UnlinkShard,
+    ImportGlsl,
+    ExportStandalone(StandaloneFormat),
+    ExportWeb,
+    ConvertShard,
+    /// Draw settings of a pass were edited; the pipelines need rebuilding
+    GeometryChanged,
+    LoadMesh(BufferKind, String),
+    ClearMesh(BufferKind),
     None,
 }
 
@@ -16,8 +33,7 @@ pub fn render(
     ctx: &egui::Context,
     show_window: &mut bool,
     audio_file_path: &Option<String>,
-    image_file_paths: &[Option<String>; 4],
-    selected_channel: &mut usize,
+    linked_shard: &Option<PathBuf>,
     debug_audio: &mut bool,
     debug_bass: &mut f32,
     debug_mid: &mut f32,
@@ -25,6 +41,8 @@ pub fn render(
     bass_energy: &Arc<Mutex<f32>>,
     mid_energy: &Arc<Mutex<f32>>,
     high_energy: &Arc<Mutex<f32>>,
+    geometry_pass: &mut BufferKind,
+    buffers: &mut HashMap<BufferKind, ShaderBuffer>,
 ) -> ShaderPropertiesAction {
     let mut action = ShaderPropertiesAction::None;
     let mut close_requested = false;
@@ -141,63 +159,117 @@ pub fn render(
 
             ui.add_space(12.0);
 
-            // Image Section with styled frame
-            ui.push_id("image_section", |ui| {
+            // Geometry Section with styled frame
+            ui.push_id("geometry_section", |ui| {
             egui::Frame::group(ui.style())
                 .fill(egui::Color32::from_rgb(25, 25, 30))
                 .stroke(egui::Stroke::new(1.0, egui::Color32::from_rgb(60, 60, 70)))
                 .corner_radius(6.0)
                 .inner_margin(12.0)
                 .show(ui, |ui| {
-                    ui.label(egui::RichText::new("Image Textures").size(16.0).strong());
+                    ui.label(egui::RichText::new("Geometry").size(16.0).strong());
                     ui.add_space(8.0);
 
-                    // Channel selector and status in compact layout
                     ui.horizontal(|ui| {
-                        ui.label(egui::RichText::new("Target:").strong().size(12.0));
-                        egui::ComboBox::from_id_salt("image_channel_selector")
-                            .width(90.0)
-                            .selected_text(format!("iChannel{}", selected_channel))
+                        ui.label(egui::RichText::new("Pass:").strong().size(12.0));
+                        egui::ComboBox::from_id_salt("geometry_pass_selector")
+                            .width(110.0)
+                            .selected_text(geometry_pass.as_str())
                             .show_ui(ui, |ui| {
-                                ui.selectable_value(selected_channel, 0, "iChannel0");
-                                ui.selectable_value(selected_channel, 1, "iChannel1");
-                                ui.selectable_value(selected_channel, 2, "iChannel2");
-                                ui.selectable_value(selected_channel, 3, "iChannel3");
+                                for kind in BufferKind::ALL {
+                                    ui.selectable_value(geometry_pass, kind, kind.as_str());
+                                }
                             });
-                        
-                        ui.add_space(8.0);
-                        ui.separator();
-                        ui.add_space(8.0);
-                        
-                        // Show compact status: "0:✓ 1:- 2:✓ 3:-"
-                        ui.label(egui::RichText::new("Status:").strong().size(12.0));
-                        ui.add_space(4.0);
-                        for (i, image_path) in image_file_paths.iter().enumerate() {
-                            let status = if image_path.is_some() { "✓" } else { "—" };
-                            ui.label(
-                                egui::RichText::new(format!("{}:{}", i, status))
-                                    .monospace()
-                                    .size(11.0)
-                                    .color(if image_path.is_some() {
-                                        egui::Color32::from_rgb(120, 220, 120)
-                                    } else {
-                                        egui::Color32::from_rgb(100, 100, 110)
-                                    })
-                            );
+                    });
+
+                    let Some(buffer) = buffers.get_mut(geometry_pass) else {
+                        return;
+                    };
+                    let before = buffer.geometry;
+                    let has_mesh = buffer.mesh.is_some();
+
+                    ui.add_space(4.0);
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new("Mesh:").strong().size(12.0));
+                        match &buffer.mesh {
+                            Some(mesh) => {
+                                let name = std::path::Path::new(&mesh.source)
+                                    .file_name()
+                                    .map(|n| n.to_string_lossy().to_string())
+                                    .unwrap_or_else(|| mesh.source.clone());
+                                ui.label(egui::RichText::new(format!("{} ({} triangles)", name, mesh.triangle_count())).size(12.0))
+                                    .on_hover_text(&mesh.source);
+                            }
+                            None => {
+                                ui.label(egui::RichText::new("None").size(12.0).color(egui::Color32::from_rgb(140, 140, 150)));
+                            }
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        if ui.button("Load Mesh...").clicked() {
+                            if let Some(path) = rfd::FileDialog::new()
+                                .add_filter("Meshes", &["obj", "gltf", "glb"])
+                                .pick_file()
+                            {
+                                action = ShaderPropertiesAction::LoadMesh(*geometry_pass, path.to_string_lossy().to_string());
+                            }
+                        }
+                        if ui.add_enabled(has_mesh, egui::Button::new("Clear")).clicked() {
+                            action = ShaderPropertiesAction::ClearMesh(*geometry_pass);
                         }
                     });
 
-                    ui.add_space(8.0);
+                    let geometry = &mut buffer.geometry;
+                    ui.add_space(4.0);
+                    egui::Grid::new("geometry_grid").num_columns(2).spacing([12.0, 6.0]).show(ui, |ui| {
+                        ui.label(egui::RichText::new("Vertices").size(12.0));
+                        ui.add_enabled(
+                            !has_mesh,
+                            egui::DragValue::new(&mut geometry.vertex_count).range(1..=MAX_VERTEX_COUNT),
+                        );
+                        ui.end_row();
 
-                    if ui.add_sized([ui.available_width(), 30.0], egui::Button::new(
-                        egui::RichText::new(format!("Load to iChannel{}...", selected_channel)).size(13.0)
-                    )).clicked() {
-                        if let Some(path) = rfd::FileDialog::new()
-                            .add_filter("Images", &["png", "jpg", "jpeg", "bmp", "gif", "webp"])
-                            .pick_file()
-                        {
-                            action = ShaderPropertiesAction::LoadImageFile(*selected_channel, path.to_string_lossy().to_string());
+                        ui.label(egui::RichText::new("Instances").size(12.0));
+                        ui.add(egui::DragValue::new(&mut geometry.instance_count).range(1..=MAX_INSTANCE_COUNT));
+                        ui.end_row();
+
+                        ui.label(egui::RichText::new("Topology").size(12.0));
+                        ui.add_enabled_ui(!has_mesh, |ui| {
+                            egui::ComboBox::from_id_salt("geometry_topology")
+                                .width(130.0)
+                                .selected_text(geometry.topology.as_str())
+                                .show_ui(ui, |ui| {
+                                    for topology in Topology::ALL {
+                                        ui.selectable_value(&mut geometry.topology, topology, topology.as_str());
+                                    }
+                                });
+                        });
+                        ui.end_row();
+
+                        ui.label(egui::RichText::new("Depth test").size(12.0));
+                        ui.add_enabled(!has_mesh, egui::Checkbox::without_text(&mut geometry.depth));
+                        ui.end_row();
+                    });
+
+                    ui.add_space(4.0);
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(!geometry.is_default(), egui::Button::new("Full-screen triangle")).clicked() {
+                            *geometry = PassGeometry::default();
                         }
+                    });
+                    let hint = if has_mesh {
+                        "Draws the mesh's triangles with depth testing for each instance_index; drag the preview to orbit, scroll to zoom"
+                    } else {
+                        "Draws vertex_index 0..Vertices for each instance_index; custom geometry needs a custom vertex shader"
+                    };
+                    ui.label(
+                        egui::RichText::new(hint)
+                            .size(10.0)
+                            .color(egui::Color32::from_rgb(140, 140, 150))
+                    );
+
+                    if *geometry != before {
+                        action = ShaderPropertiesAction::GeometryChanged;
                     }
                 });
             });
@@ -221,7 +293,56 @@ pub fn render(
 
                     ui.add_space(4.0);
                     ui.label(
-                        egui::RichText::new("Load shader from JSON file (Ctrl+I)")
+                        egui::RichText::new("Load shader from JSON file or .zip bundle (Ctrl+I)")
+                            .size(10.0)
+                            .color(egui::Color32::from_rgb(140, 140, 150))
+                    );
+
+                    ui.add_space(8.0);
+
+                    match linked_shard {
+                        Some(path) => {
+                            ui.horizontal(|ui| {
+                                let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+                                ui.label(
+                                    egui::RichText::new(format!("Linked: {}", name))
+                                        .monospace()
+                                        .size(11.0)
+                                        .color(egui::Color32::from_rgb(120, 220, 120))
+                                )
+                                .on_hover_text(path.display().to_string());
+                                if ui.button("Unlink").clicked() {
+                                    action = ShaderPropertiesAction::UnlinkShard;
+                                }
+                            });
+                        }
+                        None => {
+                            if ui.add_sized([ui.available_width(), 32.0], egui::Button::new(
+                                egui::RichText::new("Link Shard File...").size(13.0)
+                            )).clicked() {
+                                action = ShaderPropertiesAction::LinkShard;
+                            }
+                        }
+                    }
+
+                    ui.add_space(4.0);
+                    ui.label(
+                        egui::RichText::new("Re-import a shard JSON whenever it is saved, to edit it in another editor")
+                            .size(10.0)
+                            .color(egui::Color32::from_rgb(140, 140, 150))
+                    );
+
+                    ui.add_space(8.0);
+
+                    if ui.add_sized([ui.available_width(), 32.0], egui::Button::new(
+                        egui::RichText::new("Import ShaderToy GLSL...").size(13.0)
+                    )).clicked() {
+                        action = ShaderPropertiesAction::ImportGlsl;
+                    }
+
+                    ui.add_space(4.0);
+                    ui.label(
+                        egui::RichText::new("Translate GLSL passes to WGSL (name files image, buffer_a..d, common)")
                             .size(10.0)
                             .color(egui::Color32::from_rgb(140, 140, 150))
                     );
@@ -236,7 +357,60 @@ pub fn render(
 
                     ui.add_space(4.0);
                     ui.label(
-                        egui::RichText::new("Save all buffers to JSON file (Ctrl+E)")
+                        egui::RichText::new("Save all buffers to JSON, or to a .zip bundle with audio (Ctrl+E)")
+                            .size(10.0)
+                            .color(egui::Color32::from_rgb(140, 140, 150))
+                    );
+
+                    ui.add_space(8.0);
+
+                    if ui.add_sized([ui.available_width(), 32.0], egui::Button::new(
+                        egui::RichText::new("Convert JSON ⇄ Bundle...").size(13.0)
+                    )).clicked() {
+                        action = ShaderPropertiesAction::ConvertShard;
+                    }
+
+                    ui.add_space(4.0);
+                    ui.label(
+                        egui::RichText::new("Write a .json shard as a .zip bundle or back, next to the original")
+                            .size(10.0)
+                            .color(egui::Color32::from_rgb(140, 140, 150))
+                    );
+
+                    ui.add_space(8.0);
+
+                    ui.horizontal(|ui| {
+                        let width = (ui.available_width() - ui.spacing().item_spacing.x) / 2.0;
+                        if ui.add_sized([width, 32.0], egui::Button::new(
+                            egui::RichText::new("Export WGSL...").size(13.0)
+                        )).clicked() {
+                            action = ShaderPropertiesAction::ExportStandalone(StandaloneFormat::Wgsl);
+                        }
+                        if ui.add_sized([width, 32.0], egui::Button::new(
+                            egui::RichText::new("Export GLSL...").size(13.0)
+                        )).clicked() {
+                            action = ShaderPropertiesAction::ExportStandalone(StandaloneFormat::Glsl);
+                        }
+                    });
+
+                    ui.add_space(4.0);
+                    ui.label(
+                        egui::RichText::new("Write one standalone shader per pass plus manifest.json to a folder")
+                            .size(10.0)
+                            .color(egui::Color32::from_rgb(140, 140, 150))
+                    );
+
+                    ui.add_space(8.0);
+
+                    if ui.add_sized([ui.available_width(), 32.0], egui::Button::new(
+                        egui::RichText::new("Export WebGPU HTML...").size(13.0)
+                    )).clicked() {
+                        action = ShaderPropertiesAction::ExportWeb;
+                    }
+
+                    ui.add_space(4.0);
+                    ui.label(
+                        egui::RichText::new("Single HTML file that plays the shader in the browser")
                             .size(10.0)
                             .color(egui::Color32::from_rgb(140, 140, 150))
                     );
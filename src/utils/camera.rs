@@ -0,0 +1,225 @@
+//! Orbit camera for mesh passes
+//!
+//! Matrices are column-major `[[f32; 4]; 4]` (one array per column), the
+//! layout WGSL expects for `mat4x4<f32>`, with a right-handed view space and
+//! wgpu's 0..1 clip depth.
+
+use bytemuck::{Pod, Zeroable};
+
+pub type Mat4 = [[f32; 4]; 4];
+
+pub const IDENTITY: Mat4 = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// `a * b`
+pub fn mul(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut out = [[0.0; 4]; 4];
+    for (column, out_column) in out.iter_mut().enumerate() {
+        for (row, value) in out_column.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[k][row] * b[column][k]).sum();
+        }
+    }
+    out
+}
+
+/// Transform a point (w = 1)
+pub fn transform_point(m: &Mat4, p: [f32; 3]) -> [f32; 3] {
+    std::array::from_fn(|row| m[0][row] * p[0] + m[1][row] * p[1] + m[2][row] * p[2] + m[3][row])
+}
+
+/// Transform a direction (w = 0)
+pub fn transform_vector(m: &Mat4, v: [f32; 3]) -> [f32; 3] {
+    std::array::from_fn(|row| m[0][row] * v[0] + m[1][row] * v[1] + m[2][row] * v[2])
+}
+
+/// Translation, rotation (unit quaternion `[x, y, z, w]`) and scale, as glTF nodes store them
+pub fn from_trs(translation: [f32; 3], rotation: [f32; 4], scale: [f32; 3]) -> Mat4 {
+    let [x, y, z, w] = rotation;
+    let rotation = [
+        [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y + z * w), 2.0 * (x * z - y * w)],
+        [2.0 * (x * y - z * w), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z + x * w)],
+        [2.0 * (x * z + y * w), 2.0 * (y * z - x * w), 1.0 - 2.0 * (x * x + y * y)],
+    ];
+    let column = |i: usize| {
+        let [a, b, c] = rotation[i];
+        [a * scale[i], b * scale[i], c * scale[i], 0.0]
+    };
+    [column(0), column(1), column(2), [translation[0], translation[1], translation[2], 1.0]]
+}
+
+pub fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = dot(v, v).sqrt();
+    if length > 0.0 {
+        v.map(|c| c / length)
+    } else {
+        v
+    }
+}
+
+pub fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+/// Right-handed view matrix looking from `eye` at `target`
+pub fn look_at(eye: [f32; 3], target: [f32; 3], up: [f32; 3]) -> Mat4 {
+    let forward = normalize(sub(target, eye));
+    let right = normalize(cross(forward, up));
+    let up = cross(right, forward);
+    [
+        [right[0], up[0], -forward[0], 0.0],
+        [right[1], up[1], -forward[1], 0.0],
+        [right[2], up[2], -forward[2], 0.0],
+        [-dot(right, eye), -dot(up, eye), dot(forward, eye), 1.0],
+    ]
+}
+
+/// Right-handed perspective projection with 0..1 depth
+pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Mat4 {
+    let f = 1.0 / (fov_y * 0.5).tan();
+    let range = far / (near - far);
+    [
+        [f / aspect, 0.0, 0.0, 0.0],
+        [0.0, f, 0.0, 0.0],
+        [0.0, 0.0, range, -1.0],
+        [0.0, 0.0, range * near, 0.0],
+    ]
+}
+
+/// `camera` uniform of mesh passes, `@group(0) @binding(1)`
+///
+/// Layout must match `Camera` in `MESH_BINDINGS`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct CameraUniforms {
+    pub view: Mat4,
+    pub projection: Mat4,
+    pub view_projection: Mat4,
+    /// Camera position in world space (w = 1)
+    pub eye: [f32; 4],
+}
+
+impl Default for CameraUniforms {
+    fn default() -> Self {
+        OrbitCamera::default().uniforms(1.0)
+    }
+}
+
+/// Camera circling a target point, driven by dragging and scrolling the preview
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitCamera {
+    pub target: [f32; 3],
+    pub distance: f32,
+    /// Rotation around the Y axis, in radians
+    pub yaw: f32,
+    /// Elevation above the XZ plane, in radians
+    pub pitch: f32,
+    /// Vertical field of view, in radians
+    pub fov_y: f32,
+}
+
+impl Default for OrbitCamera {
+    fn default() -> Self {
+        Self {
+            target: [0.0; 3],
+            distance: 3.0,
+            yaw: 0.6,
+            pitch: 0.35,
+            fov_y: 45f32.to_radians(),
+        }
+    }
+}
+
+impl OrbitCamera {
+    const PITCH_LIMIT: f32 = 1.5;
+
+    /// Aim at the centre of `bounds` (min, max) from far enough to see all of it
+    pub fn frame(&mut self, bounds: ([f32; 3], [f32; 3])) {
+        let (min, max) = bounds;
+        self.target = std::array::from_fn(|i| (min[i] + max[i]) * 0.5);
+        let radius = (dot(sub(max, min), sub(max, min))).sqrt() * 0.5;
+        self.distance = (radius / (self.fov_y * 0.5).sin() * 1.1).max(1e-3);
+    }
+
+    /// Rotate by a drag of `delta` points
+    pub fn orbit(&mut self, delta: [f32; 2]) {
+        self.yaw -= delta[0] * 0.01;
+        self.pitch = (self.pitch + delta[1] * 0.01).clamp(-Self::PITCH_LIMIT, Self::PITCH_LIMIT);
+    }
+
+    /// Move closer (positive `scroll`) or further away
+    pub fn zoom(&mut self, scroll: f32) {
+        self.distance = (self.distance * (-scroll * 0.002).exp()).max(1e-3);
+    }
+
+    pub fn eye(&self) -> [f32; 3] {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        [
+            self.target[0] + self.distance * cos_pitch * sin_yaw,
+            self.target[1] + self.distance * sin_pitch,
+            self.target[2] + self.distance * cos_pitch * cos_yaw,
+        ]
+    }
+
+    pub fn uniforms(&self, aspect: f32) -> CameraUniforms {
+        let eye = self.eye();
+        let view = look_at(eye, self.target, [0.0, 1.0, 0.0]);
+        let projection = perspective(self.fov_y, aspect.max(1e-3), self.distance * 0.01, self.distance * 100.0);
+        CameraUniforms {
+            view,
+            projection,
+            view_projection: mul(&projection, &view),
+            eye: [eye[0], eye[1], eye[2], 1.0],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(m: &Mat4, p: [f32; 3]) -> [f32; 3] {
+        let w = m[0][3] * p[0] + m[1][3] * p[1] + m[2][3] * p[2] + m[3][3];
+        transform_point(m, p).map(|c| c / w)
+    }
+
+    #[test]
+    fn test_orbit_camera_projects_target_to_centre() {
+        let mut camera = OrbitCamera::default();
+        camera.frame(([-1.0, 0.0, -1.0], [3.0, 2.0, 1.0]));
+        camera.orbit([40.0, -25.0]);
+        let uniforms = camera.uniforms(16.0 / 9.0);
+
+        let centre = clip(&uniforms.view_projection, camera.target);
+        assert!(centre[0].abs() < 1e-4 && centre[1].abs() < 1e-4, "{:?}", centre);
+        assert!((0.0..1.0).contains(&centre[2]));
+
+        // Nearer points get smaller depth
+        let toward_eye = std::array::from_fn(|i| camera.target[i] * 0.9 + camera.eye()[i] * 0.1);
+        assert!(clip(&uniforms.view_projection, toward_eye)[2] < centre[2]);
+    }
+
+    #[test]
+    fn test_trs() {
+        // 90° about Y turns +X into -Z
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        let m = from_trs([1.0, 2.0, 3.0], [0.0, half, 0.0, half], [2.0, 2.0, 2.0]);
+        let p = transform_point(&m, [1.0, 0.0, 0.0]);
+        for (a, b) in p.iter().zip([1.0, 2.0, 1.0]) {
+            assert!((a - b).abs() < 1e-5, "{:?}", p);
+        }
+        assert_eq!(mul(&IDENTITY, &m), m);
+    }
+}
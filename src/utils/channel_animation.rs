@@ -0,0 +1,366 @@
+//! Animated iChannel sources: animated GIF/APNG files and image sequences
+//!
+//! An image sequence is a directory of images played in file-name order at
+//! [`SEQUENCE_FPS`]. Frames are decoded once when the pipeline is built; before
+//! each render the frame for the current time is written into the channel's
+//! 2D texture, only when it changed.
+//!
+//! A channel's time is the editor clock scaled by its playback rate and
+//! mapped through its [`PlaybackMode`]. Shaders read it as
+//! `uniforms.channel_time[N]` (`iChannelTime[N]` in imported ShaderToy code);
+//! it stays 0 for still images.
+
+use eframe::wgpu::{Device, Queue, Texture, TextureView};
+use image::{AnimationDecoder, RgbaImage};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::utils::channel_texture::{self, ChannelData, ChannelFormat, ChannelKind, ChannelSettings};
+
+/// Frame rate of image sequences
+pub const SEQUENCE_FPS: f32 = 30.0;
+
+/// Delay used for frames that don't set one, as browsers do
+const DEFAULT_FRAME_DELAY: f32 = 0.1;
+
+/// What an animated channel does once it reaches its last frame
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum PlaybackMode {
+    /// Start again from the first frame
+    #[default]
+    #[serde(rename = "loop")]
+    Loop,
+    /// Play backwards to the first frame, then forwards again
+    #[serde(rename = "ping_pong")]
+    PingPong,
+    /// Stay on the last frame
+    #[serde(rename = "hold")]
+    Hold,
+}
+
+impl PlaybackMode {
+    pub const ALL: [PlaybackMode; 3] = [PlaybackMode::Loop, PlaybackMode::PingPong, PlaybackMode::Hold];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlaybackMode::Loop => "Loop",
+            PlaybackMode::PingPong => "Ping-Pong",
+            PlaybackMode::Hold => "Hold",
+        }
+    }
+}
+
+/// Decoded frames and their timing
+#[derive(Debug, Clone, PartialEq)]
+pub struct Animation {
+    pub frames: Vec<RgbaImage>,
+    /// Start time of each frame in seconds, followed by the total duration
+    starts: Vec<f32>,
+}
+
+impl Animation {
+    /// Frames with their display time in seconds; all frames must be the same size
+    pub fn new(frames: Vec<(RgbaImage, f32)>) -> Result<Self, String> {
+        let Some((first, _)) = frames.first() else {
+            return Err("An animation needs at least one frame".to_string());
+        };
+        let size = first.dimensions();
+        if let Some((index, (frame, _))) = frames.iter().enumerate().find(|(_, (frame, _))| frame.dimensions() != size) {
+            return Err(format!(
+                "Frame {} is {}x{}, expected {}x{} like the first frame",
+                index,
+                frame.width(),
+                frame.height(),
+                size.0,
+                size.1
+            ));
+        }
+
+        let mut starts = Vec::with_capacity(frames.len() + 1);
+        let mut time = 0.0;
+        starts.push(time);
+        let frames = frames
+            .into_iter()
+            .map(|(frame, delay)| {
+                time += if delay > 0.01 { delay } else { DEFAULT_FRAME_DELAY };
+                starts.push(time);
+                frame
+            })
+            .collect();
+        Ok(Self { frames, starts })
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.starts.last().copied().unwrap_or(0.0)
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        let (width, height) = self.frames[0].dimensions();
+        [width, height]
+    }
+
+    /// Channel time at editor time `time`, in `0..=duration`
+    ///
+    /// A negative rate plays the animation backwards.
+    pub fn channel_time(&self, time: f32, mode: PlaybackMode, rate: f32) -> f32 {
+        let duration = self.duration();
+        let time = time * rate;
+        if duration <= 0.0 {
+            return 0.0;
+        }
+        match mode {
+            PlaybackMode::Loop => time.rem_euclid(duration),
+            PlaybackMode::PingPong => {
+                let phase = time.rem_euclid(2.0 * duration);
+                if phase > duration {
+                    2.0 * duration - phase
+                } else {
+                    phase
+                }
+            }
+            PlaybackMode::Hold => time.clamp(0.0, duration),
+        }
+    }
+
+    /// Index of the frame shown at `channel_time`
+    pub fn frame_at(&self, channel_time: f32) -> usize {
+        let shown = self.starts.partition_point(|start| *start <= channel_time);
+        shown.saturating_sub(1).min(self.frames.len() - 1)
+    }
+}
+
+/// Whether `path` names an image sequence rather than a file
+pub fn is_sequence(path: &str) -> bool {
+    Path::new(path).is_dir()
+}
+
+/// Decode `path` as an animation, or `None` for a still image
+pub fn load_animation(path: &str) -> Result<Option<Animation>, String> {
+    if is_sequence(path) {
+        return load_sequence(path).map(Some);
+    }
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    if !matches!(extension.as_str(), "gif" | "png" | "apng") {
+        return Ok(None);
+    }
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    decode_animation(&bytes).map_err(|e| format!("Failed to decode animation {}: {}", path, e))
+}
+
+/// Decode animated GIF or APNG bytes, or `None` for a still image
+pub fn decode_animation(bytes: &[u8]) -> Result<Option<Animation>, String> {
+    let frames = match image::guess_format(bytes) {
+        Ok(image::ImageFormat::Gif) => image::codecs::gif::GifDecoder::new(Cursor::new(bytes))
+            .and_then(|decoder| decoder.into_frames().collect_frames()),
+        Ok(image::ImageFormat::Png) => {
+            let decoder = image::codecs::png::PngDecoder::new(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+            if !decoder.is_apng().map_err(|e| e.to_string())? {
+                return Ok(None);
+            }
+            decoder.apng().and_then(|decoder| decoder.into_frames().collect_frames())
+        }
+        _ => return Ok(None),
+    }
+    .map_err(|e| e.to_string())?;
+    if frames.len() < 2 {
+        return Ok(None);
+    }
+
+    let frames = frames
+        .into_iter()
+        .map(|frame| {
+            let (numerator, denominator) = frame.delay().numer_denom_ms();
+            let delay = numerator as f32 / denominator.max(1) as f32 / 1000.0;
+            (frame.into_buffer(), delay)
+        })
+        .collect();
+    Animation::new(frames).map(Some)
+}
+
+/// Image files of a sequence directory, in play order
+pub fn sequence_files(path: &str) -> Result<Vec<std::path::PathBuf>, String> {
+    let entries = std::fs::read_dir(path).map_err(|e| format!("Failed to read image sequence {}: {}", path, e))?;
+    let mut files: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|file| file.is_file() && image::ImageFormat::from_path(file).is_ok())
+        .collect();
+    files.sort();
+    if files.is_empty() {
+        return Err(format!("Image sequence {} contains no images", path));
+    }
+    Ok(files)
+}
+
+fn load_sequence(path: &str) -> Result<Animation, String> {
+    let frames = sequence_files(path)?
+        .iter()
+        .map(|file| {
+            image::open(file)
+                .map(|image| (image.to_rgba8(), 1.0 / SEQUENCE_FPS))
+                .map_err(|e| format!("Failed to load sequence frame {}: {}", file.display(), e))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Animation::new(frames).map_err(|e| format!("Image sequence {}: {}", path, e))
+}
+
+/// An animation bound to a channel texture
+pub struct AnimatedChannel {
+    animation: Animation,
+    texture: Texture,
+    format: ChannelFormat,
+    /// Frame currently in `texture`
+    shown: AtomicUsize,
+}
+
+impl AnimatedChannel {
+    /// Load `path` as an animated 2D channel showing its first frame, or
+    /// `None` for a still image
+    pub fn load(
+        device: &Device,
+        queue: &Queue,
+        path: &str,
+        settings: &ChannelSettings,
+    ) -> Result<Option<(Self, TextureView)>, String> {
+        let Some(animation) = load_animation(path)? else {
+            return Ok(None);
+        };
+        if settings.kind != ChannelKind::Texture2d {
+            return Err(format!("{} is animated; animations can only be bound to 2D channels", path));
+        }
+
+        let [width, height] = animation.size();
+        log::info!(
+            "Animation loaded from {}: {} frames of {}x{}, {:.2}s",
+            path,
+            animation.frames.len(),
+            width,
+            height,
+            animation.duration()
+        );
+        let (texture, view, format) = channel_texture::upload(
+            device,
+            queue,
+            &ChannelData::from_frame(&animation.frames[0], settings),
+            settings.format.resolve(ChannelKind::Texture2d, false),
+            &format!("channel_animation_{}", path),
+        );
+        let channel = Self {
+            animation,
+            texture,
+            format,
+            shown: AtomicUsize::new(0),
+        };
+        Ok(Some((channel, view)))
+    }
+
+    /// Show the frame for editor time `time`, returning the channel time
+    pub fn update(&self, queue: &Queue, time: f32, settings: &ChannelSettings) -> f32 {
+        let channel_time = self.animation.channel_time(time, settings.playback, settings.rate);
+        let frame = self.animation.frame_at(channel_time);
+        if self.shown.swap(frame, Ordering::Relaxed) != frame {
+            channel_texture::write_texels(queue, &self.texture, &ChannelData::from_frame(&self.animation.frames[frame], settings), self.format);
+        }
+        channel_time
+    }
+
+    /// Texture the frames are uploaded into
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+}
+
+/// An animated channel's file for embedding in shards, or `None` for stills
+///
+/// GIF and APNG files are embedded as they are; image sequences are encoded
+/// as an animated GIF, which limits each frame to 256 colours.
+pub fn embeddable_animation(path: &str) -> Result<Option<Vec<u8>>, String> {
+    if !is_sequence(path) {
+        return Ok(match load_animation(path)? {
+            Some(_) => Some(std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path, e))?),
+            None => None,
+        });
+    }
+
+    let animation = load_sequence(path)?;
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = image::codecs::gif::GifEncoder::new(&mut bytes);
+        encoder
+            .set_repeat(image::codecs::gif::Repeat::Infinite)
+            .map_err(|e| e.to_string())?;
+        let delay = image::Delay::from_numer_denom_ms(1000, SEQUENCE_FPS as u32);
+        encoder
+            .encode_frames(
+                animation
+                    .frames
+                    .into_iter()
+                    .map(|frame| image::Frame::from_parts(frame, 0, 0, delay)),
+            )
+            .map_err(|e| format!("Failed to encode image sequence {} as GIF: {}", path, e))?;
+    }
+    Ok(Some(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(value: u8) -> RgbaImage {
+        RgbaImage::from_pixel(2, 2, image::Rgba([value, value, value, 255]))
+    }
+
+    #[test]
+    fn test_playback_modes() {
+        let animation = Animation::new(vec![(solid(0), 0.5), (solid(1), 0.5), (solid(2), 1.0)]).unwrap();
+        assert_eq!(animation.duration(), 2.0);
+        assert_eq!(animation.frame_at(0.0), 0);
+        assert_eq!(animation.frame_at(0.75), 1);
+        assert_eq!(animation.frame_at(2.0), 2);
+
+        assert_eq!(animation.channel_time(2.5, PlaybackMode::Loop, 1.0), 0.5);
+        assert_eq!(animation.channel_time(2.5, PlaybackMode::PingPong, 1.0), 1.5);
+        assert_eq!(animation.channel_time(4.5, PlaybackMode::PingPong, 1.0), 0.5);
+        assert_eq!(animation.channel_time(9.0, PlaybackMode::Hold, 1.0), 2.0);
+        assert_eq!(animation.channel_time(1.0, PlaybackMode::Loop, 2.0), 0.0);
+        assert_eq!(animation.channel_time(0.5, PlaybackMode::Loop, -1.0), 1.5);
+
+        assert!(Animation::new(vec![(solid(0), 0.1), (RgbaImage::new(3, 3), 0.1)]).is_err());
+        // Zero delays fall back to 100 ms
+        assert_eq!(Animation::new(vec![(solid(0), 0.0)]).unwrap().duration(), 0.1);
+    }
+
+    #[test]
+    fn test_gif_and_sequence_round_trip() {
+        let dir = std::env::temp_dir().join(format!("webshard_channel_animation_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (i, value) in [10u8, 200, 90].iter().enumerate() {
+            solid(*value).save(dir.join(format!("frame_{:03}.png", i))).unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), "not a frame").unwrap();
+        let sequence = dir.to_str().unwrap();
+
+        let animation = load_animation(sequence).unwrap().unwrap();
+        assert_eq!(animation.frames.len(), 3);
+        assert_eq!(animation.frames[1].get_pixel(0, 0).0, [200, 200, 200, 255]);
+        assert!((animation.duration() - 3.0 / SEQUENCE_FPS).abs() < 1e-6);
+
+        // Sequences embed as a GIF that decodes to the same frames
+        let gif = embeddable_animation(sequence).unwrap().unwrap();
+        let decoded = decode_animation(&gif).unwrap().unwrap();
+        assert_eq!(decoded.frames.len(), 3);
+        assert_eq!(decoded.frames[2].get_pixel(1, 1).0, [90, 90, 90, 255]);
+
+        // Still images aren't animations
+        let still = dir.join("frame_000.png");
+        assert!(load_animation(still.to_str().unwrap()).unwrap().is_none());
+        assert!(embeddable_animation(still.to_str().unwrap()).unwrap().is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
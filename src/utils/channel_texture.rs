@@ -0,0 +1,1122 @@
+//! iChannel texture types: 2D images, cubemaps and 3D volumes
+//!
+//! Each channel has a [`ChannelKind`] that decides both how its file is
+//! loaded and how `iChannelN` is declared in the injected boilerplate, so the
+//! validator checks `textureSample` calls against the real texture type.
+//!
+//! - **Cubemaps** load from one image: a horizontal (6:1) or vertical (1:6)
+//!   strip of faces in `+X -X +Y -Y +Z -Z` order, a horizontal cross (4:3),
+//!   or an equirectangular panorama (2:1, centre facing -Z) converted on load.
+//!   Six separate face images are combined into a strip by [`cube_strip_from_faces`].
+//! - **Volumes** load from an image of square slices, either a horizontal strip
+//!   (`depth` slices side by side) or a grid (e.g. 64³ as 8×8 tiles of 64×64
+//!   in a 512×512 image, row by row), or from raw 8-bit voxels (`.raw`, `.vol`,
+//!   `.bin`) whose size comes from a `_WxHxD` suffix in the file name or is
+//!   taken to be a cube.
+//!
+//! Each channel also has a [`ChannelFormat`]. By default HDR images (`.hdr`,
+//! `.exr`) are uploaded as `Rgba16Float`, volumes as linear `Rgba8Unorm` and
+//! everything else as `Rgba8UnormSrgb`. Float formats keep the values stored
+//! in the file, including 16-bit PNG precision, without sRGB decoding.
+//!
+//! Channels are sampled with their own [`ChannelWrap`] and [`ChannelFilter`]
+//! (clamped and linear by default), and 2D channels can be flipped vertically
+//! on upload for shaders written against OpenGL's bottom-up rows.
+//!
+//! Instead of a file, a 2D channel can show a [`ProceduralTexture`], which
+//! shards store as its parameters.
+
+use eframe::wgpu::{
+    AddressMode, Device, Extent3d, Features, FilterMode, Queue, SamplerDescriptor, TexelCopyBufferLayout, Texture,
+    TextureDescriptor, TextureDimension, TextureFormat, TextureUsages, TextureView, TextureViewDescriptor,
+    TextureViewDimension,
+};
+use image::{DynamicImage, ImageBuffer, Pixel, Rgba, RgbaImage};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::utils::channel_animation::PlaybackMode;
+use crate::utils::procedural_texture::ProceduralTexture;
+use crate::utils::shader_constants::SHADER_BOILERPLATE;
+use crate::utils::video_capture::LiveCapture;
+
+/// Texture type bound to an iChannel
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum ChannelKind {
+    #[default]
+    #[serde(rename = "2d")]
+    Texture2d,
+    #[serde(rename = "cube")]
+    Cube,
+    #[serde(rename = "volume")]
+    Volume,
+}
+
+impl ChannelKind {
+    pub const ALL: [ChannelKind; 3] = [ChannelKind::Texture2d, ChannelKind::Cube, ChannelKind::Volume];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelKind::Texture2d => "2D",
+            ChannelKind::Cube => "Cubemap",
+            ChannelKind::Volume => "3D Volume",
+        }
+    }
+
+    /// WGSL type of `iChannelN`
+    pub fn wgsl_type(&self) -> &'static str {
+        match self {
+            ChannelKind::Texture2d => "texture_2d<f32>",
+            ChannelKind::Cube => "texture_cube<f32>",
+            ChannelKind::Volume => "texture_3d<f32>",
+        }
+    }
+
+    pub fn view_dimension(&self) -> TextureViewDimension {
+        match self {
+            ChannelKind::Texture2d => TextureViewDimension::D2,
+            ChannelKind::Cube => TextureViewDimension::Cube,
+            ChannelKind::Volume => TextureViewDimension::D3,
+        }
+    }
+}
+
+/// How a channel is sampled outside 0..1
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum ChannelWrap {
+    #[default]
+    #[serde(rename = "clamp")]
+    Clamp,
+    #[serde(rename = "repeat")]
+    Repeat,
+    #[serde(rename = "mirror")]
+    Mirror,
+}
+
+impl ChannelWrap {
+    pub const ALL: [ChannelWrap; 3] = [ChannelWrap::Clamp, ChannelWrap::Repeat, ChannelWrap::Mirror];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelWrap::Clamp => "Clamp",
+            ChannelWrap::Repeat => "Repeat",
+            ChannelWrap::Mirror => "Mirror",
+        }
+    }
+
+    pub fn address_mode(&self) -> AddressMode {
+        match self {
+            ChannelWrap::Clamp => AddressMode::ClampToEdge,
+            ChannelWrap::Repeat => AddressMode::Repeat,
+            ChannelWrap::Mirror => AddressMode::MirrorRepeat,
+        }
+    }
+
+    /// WebGPU `GPUAddressMode` name, for the web export
+    pub fn web_name(&self) -> &'static str {
+        match self {
+            ChannelWrap::Clamp => "clamp-to-edge",
+            ChannelWrap::Repeat => "repeat",
+            ChannelWrap::Mirror => "mirror-repeat",
+        }
+    }
+}
+
+/// How a channel's texels are filtered when sampled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum ChannelFilter {
+    #[default]
+    #[serde(rename = "linear")]
+    Linear,
+    #[serde(rename = "nearest")]
+    Nearest,
+}
+
+impl ChannelFilter {
+    pub const ALL: [ChannelFilter; 2] = [ChannelFilter::Linear, ChannelFilter::Nearest];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelFilter::Linear => "Linear",
+            ChannelFilter::Nearest => "Nearest",
+        }
+    }
+
+    pub fn filter_mode(&self) -> FilterMode {
+        match self {
+            ChannelFilter::Linear => FilterMode::Linear,
+            ChannelFilter::Nearest => FilterMode::Nearest,
+        }
+    }
+
+    /// WebGPU `GPUFilterMode` name, for the web export
+    pub fn web_name(&self) -> &'static str {
+        match self {
+            ChannelFilter::Linear => "linear",
+            ChannelFilter::Nearest => "nearest",
+        }
+    }
+}
+
+/// Texel format a channel is uploaded as
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum ChannelFormat {
+    /// `Rgba16Float` for HDR images, `Linear` for volumes, `Srgb` otherwise
+    #[default]
+    #[serde(rename = "auto")]
+    Auto,
+    /// 8-bit colour, decoded from sRGB when sampled
+    #[serde(rename = "srgb")]
+    Srgb,
+    /// 8-bit data sampled as stored, e.g. normal maps and noise
+    #[serde(rename = "linear")]
+    Linear,
+    #[serde(rename = "rgba16f")]
+    Rgba16Float,
+    #[serde(rename = "rgba32f")]
+    Rgba32Float,
+}
+
+impl ChannelFormat {
+    pub const ALL: [ChannelFormat; 5] = [
+        ChannelFormat::Auto,
+        ChannelFormat::Srgb,
+        ChannelFormat::Linear,
+        ChannelFormat::Rgba16Float,
+        ChannelFormat::Rgba32Float,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChannelFormat::Auto => "Auto",
+            ChannelFormat::Srgb => "sRGB",
+            ChannelFormat::Linear => "Linear",
+            ChannelFormat::Rgba16Float => "RGBA16F",
+            ChannelFormat::Rgba32Float => "RGBA32F",
+        }
+    }
+
+    /// The format `Auto` stands for, given the channel's kind and whether its
+    /// file holds floating-point (HDR) pixels
+    pub fn resolve(self, kind: ChannelKind, hdr: bool) -> ChannelFormat {
+        match self {
+            ChannelFormat::Auto if hdr => ChannelFormat::Rgba16Float,
+            ChannelFormat::Auto if kind == ChannelKind::Volume => ChannelFormat::Linear,
+            ChannelFormat::Auto => ChannelFormat::Srgb,
+            format => format,
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, ChannelFormat::Rgba16Float | ChannelFormat::Rgba32Float)
+    }
+
+    /// wgpu format of a resolved channel format
+    pub fn texture_format(&self) -> TextureFormat {
+        match self {
+            ChannelFormat::Auto | ChannelFormat::Srgb => TextureFormat::Rgba8UnormSrgb,
+            ChannelFormat::Linear => TextureFormat::Rgba8Unorm,
+            ChannelFormat::Rgba16Float => TextureFormat::Rgba16Float,
+            ChannelFormat::Rgba32Float => TextureFormat::Rgba32Float,
+        }
+    }
+}
+
+/// Whether a decoded image holds floating-point pixels (Radiance HDR, OpenEXR)
+pub fn is_hdr(image: &DynamicImage) -> bool {
+    matches!(image, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_))
+}
+
+/// How one iChannel is loaded and declared
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChannelSettings {
+    /// Texture type of `iChannelN`
+    pub kind: ChannelKind,
+    /// Texel format (since 2.4)
+    #[serde(skip_serializing_if = "is_auto")]
+    pub format: ChannelFormat,
+    /// What animated channels do after their last frame (since 2.5)
+    #[serde(skip_serializing_if = "is_loop")]
+    pub playback: PlaybackMode,
+    /// Playback speed of animated channels relative to the editor clock (since 2.5)
+    #[serde(skip_serializing_if = "is_normal_rate")]
+    pub rate: f32,
+    /// Addressing outside 0..1 (since 2.6)
+    #[serde(skip_serializing_if = "is_clamp")]
+    pub wrap: ChannelWrap,
+    /// Texel filtering (since 2.6)
+    #[serde(skip_serializing_if = "is_linear")]
+    pub filter: ChannelFilter,
+    /// Upload 2D images bottom row first, as OpenGL does (since 2.6)
+    #[serde(skip_serializing_if = "is_false")]
+    pub flip_y: bool,
+    /// Generated texture shown instead of a file (since 2.7)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub procedural: Option<ProceduralTexture>,
+}
+
+impl Default for ChannelSettings {
+    fn default() -> Self {
+        Self {
+            kind: ChannelKind::default(),
+            format: ChannelFormat::default(),
+            playback: PlaybackMode::default(),
+            rate: 1.0,
+            wrap: ChannelWrap::default(),
+            filter: ChannelFilter::default(),
+            flip_y: false,
+            procedural: None,
+        }
+    }
+}
+
+fn is_auto(format: &ChannelFormat) -> bool {
+    *format == ChannelFormat::Auto
+}
+
+fn is_loop(playback: &PlaybackMode) -> bool {
+    *playback == PlaybackMode::Loop
+}
+
+fn is_normal_rate(rate: &f32) -> bool {
+    *rate == 1.0
+}
+
+fn is_clamp(wrap: &ChannelWrap) -> bool {
+    *wrap == ChannelWrap::Clamp
+}
+
+fn is_linear(filter: &ChannelFilter) -> bool {
+    *filter == ChannelFilter::Linear
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl ChannelSettings {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Sampler for the channel's wrap and filter settings
+    pub fn sampler_descriptor(&self) -> SamplerDescriptor<'static> {
+        let address_mode = self.wrap.address_mode();
+        let filter = self.filter.filter_mode();
+        SamplerDescriptor {
+            label: Some("channel_sampler"),
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        }
+    }
+
+    /// Whether uploads are flipped vertically: only 2D channels flip
+    pub fn flips(&self) -> bool {
+        self.flip_y && self.kind == ChannelKind::Texture2d
+    }
+}
+
+/// A channel's file and settings, as the editor holds them
+#[derive(Debug, Clone, Default)]
+pub struct ChannelInput {
+    pub path: Option<String>,
+    pub settings: ChannelSettings,
+    /// Camera feeding the channel; `path` is then the device path
+    pub live: Option<Arc<LiveCapture>>,
+}
+
+/// [`SHADER_BOILERPLATE`] with each `iChannelN` declared as its channel's type
+pub fn shader_boilerplate(kinds: [ChannelKind; 4]) -> Cow<'static, str> {
+    if kinds.iter().all(|kind| *kind == ChannelKind::Texture2d) {
+        return Cow::Borrowed(SHADER_BOILERPLATE);
+    }
+    let mut boilerplate = SHADER_BOILERPLATE.to_string();
+    for (i, kind) in kinds.iter().enumerate() {
+        boilerplate = boilerplate.replace(
+            &format!("var iChannel{}: texture_2d<f32>;", i),
+            &format!("var iChannel{}: {};", i, kind.wgsl_type()),
+        );
+    }
+    Cow::Owned(boilerplate)
+}
+
+/// Component type of decoded texels: `u8` for 8-bit formats, `f32` for float ones
+pub trait Texel: image::Primitive + bytemuck::Pod {
+    type Pixel: Pixel<Subpixel = Self>;
+
+    /// Value in 0..1 for unorm texels, as stored for float ones
+    fn as_float(self) -> f32;
+    fn from_float(value: f32) -> Self;
+    /// `image` converted to `Self` components
+    fn from_image(image: &DynamicImage) -> RgbaBuffer<Self>;
+}
+
+impl Texel for u8 {
+    type Pixel = Rgba<u8>;
+
+    fn as_float(self) -> f32 {
+        self as f32 / 255.0
+    }
+
+    fn from_float(value: f32) -> Self {
+        (value.clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+
+    fn from_image(image: &DynamicImage) -> RgbaImage {
+        image.to_rgba8()
+    }
+}
+
+impl Texel for f32 {
+    type Pixel = Rgba<f32>;
+
+    fn as_float(self) -> f32 {
+        self
+    }
+
+    fn from_float(value: f32) -> Self {
+        value
+    }
+
+    fn from_image(image: &DynamicImage) -> RgbaBuffer<f32> {
+        image.to_rgba32f()
+    }
+}
+
+/// RGBA image with `T` components
+pub type RgbaBuffer<T> = ImageBuffer<<T as Texel>::Pixel, Vec<T>>;
+
+/// Texture data ready for upload
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChannelData<T = u8> {
+    pub kind: ChannelKind,
+    /// Width, height and layers (6 for cubemaps) or depth (volumes)
+    pub size: [u32; 3],
+    /// RGBA texels, layer by layer
+    pub texels: Vec<T>,
+}
+
+impl ChannelData {
+    /// A single 2D frame, as animations and cameras upload, flipped if `settings` say so
+    pub fn from_frame(frame: &RgbaImage, settings: &ChannelSettings) -> Self {
+        let mut data = Self {
+            kind: ChannelKind::Texture2d,
+            size: [frame.width(), frame.height(), 1],
+            texels: frame.as_raw().clone(),
+        };
+        if settings.flips() {
+            data.flip_y();
+        }
+        data
+    }
+}
+
+impl<T: Texel> ChannelData<T> {
+    /// Mirror each layer top to bottom
+    pub fn flip_y(&mut self) {
+        let row = self.size[0] as usize * 4;
+        let rows = self.size[1] as usize;
+        for layer in self.texels.chunks_exact_mut(row * rows) {
+            for y in 0..rows / 2 {
+                let (top, bottom) = layer.split_at_mut((rows - 1 - y) * row);
+                top[y * row..(y + 1) * row].swap_with_slice(&mut bottom[..row]);
+            }
+        }
+    }
+}
+
+/// `image` laid out for the channel in `T` texels, flipped if the settings say so
+fn image_data<T: Texel>(image: &DynamicImage, settings: &ChannelSettings) -> Result<ChannelData<T>, String> {
+    let mut data = layout_image(&T::from_image(image), settings.kind)?;
+    if settings.flips() {
+        data.flip_y();
+    }
+    Ok(data)
+}
+
+/// Load a channel's file and upload it as a texture of the channel's kind and format
+pub fn load_channel_texture(
+    device: &Device,
+    queue: &Queue,
+    path: &str,
+    settings: &ChannelSettings,
+) -> Result<(Texture, TextureView, [u32; 3]), String> {
+    let label = format!("channel_texture_{}", path);
+    let (texture, view, format) = match load_channel_image(path, settings.kind)? {
+        ChannelSource::Raw(data) => upload(device, queue, &data, settings.format.resolve(data.kind, false), &label),
+        ChannelSource::Image(image) => {
+            let format = settings.format.resolve(settings.kind, is_hdr(&image));
+            if format.is_float() {
+                upload(device, queue, &image_data::<f32>(&image, settings)?, format, &label)
+            } else {
+                upload(device, queue, &image_data::<u8>(&image, settings)?, format, &label)
+            }
+        }
+    };
+    let size = texture_size(&texture);
+    log::info!(
+        "{} loaded from {}: {}x{}x{} {}",
+        settings.kind.as_str(),
+        path,
+        size[0],
+        size[1],
+        size[2],
+        format.as_str()
+    );
+    Ok((texture, view, size))
+}
+
+/// Generate a procedural texture and upload it with the channel's format and flip
+pub fn load_procedural_texture(
+    device: &Device,
+    queue: &Queue,
+    procedural: &ProceduralTexture,
+    settings: &ChannelSettings,
+) -> Result<(Texture, TextureView, [u32; 3]), String> {
+    if settings.kind != ChannelKind::Texture2d {
+        return Err(format!("{} is 2D; it can't be bound to a {} channel", procedural.label(), settings.kind.as_str()));
+    }
+    let label = format!("channel_texture_{}", procedural.label());
+    let image = DynamicImage::ImageRgba8(procedural.generate());
+    let format = procedural.resolve_format(settings.format, settings.kind);
+    let (texture, view, format) = if format.is_float() {
+        upload(device, queue, &image_data::<f32>(&image, settings)?, format, &label)
+    } else {
+        upload(device, queue, &image_data::<u8>(&image, settings)?, format, &label)
+    };
+    let size = texture_size(&texture);
+    log::info!("{} generated: {}x{} {}", procedural.label(), size[0], size[1], format.as_str());
+    Ok((texture, view, size))
+}
+
+/// Reload a channel's file into the texture [`load_channel_texture`] made for it
+///
+/// Returns `Ok(false)`, leaving the texture alone, if the file no longer has
+/// the texture's size or format; the channel then needs a new texture.
+pub fn reload_channel_texture(queue: &Queue, texture: &Texture, path: &str, settings: &ChannelSettings) -> Result<bool, String> {
+    let written = match load_channel_image(path, settings.kind)? {
+        ChannelSource::Raw(data) => write_if_fits(queue, texture, &data, settings.format.resolve(data.kind, false)),
+        ChannelSource::Image(image) => {
+            let format = settings.format.resolve(settings.kind, is_hdr(&image));
+            if format.is_float() {
+                write_if_fits(queue, texture, &image_data::<f32>(&image, settings)?, format)
+            } else {
+                write_if_fits(queue, texture, &image_data::<u8>(&image, settings)?, format)
+            }
+        }
+    };
+    if written {
+        log::info!("{} reloaded from {}", settings.kind.as_str(), path);
+    }
+    Ok(written)
+}
+
+/// [`write_texels`] if `data` has the texture's size and format
+fn write_if_fits<T: Texel>(queue: &Queue, texture: &Texture, data: &ChannelData<T>, format: ChannelFormat) -> bool {
+    // RGBA32F channels may have been created as RGBA16F
+    let format = match (format, texture.format()) {
+        (ChannelFormat::Rgba32Float, TextureFormat::Rgba16Float) => ChannelFormat::Rgba16Float,
+        _ => format,
+    };
+    let fits = texture_size(texture) == data.size && format.texture_format() == texture.format();
+    if fits {
+        write_texels(queue, texture, data, format);
+    }
+    fits
+}
+
+/// Width, height and layers or depth of a channel texture
+fn texture_size(texture: &Texture) -> [u32; 3] {
+    let extent = texture.size();
+    [extent.width, extent.height, extent.depth_or_array_layers]
+}
+
+/// A 1×1 black texture of `kind`, bound to channels without a file
+pub fn placeholder_view(device: &Device, queue: &Queue, kind: ChannelKind) -> (Texture, TextureView) {
+    let layers = if kind == ChannelKind::Cube { 6 } else { 1 };
+    let data = ChannelData {
+        kind,
+        size: [1, 1, layers],
+        texels: vec![0u8; 4 * layers as usize],
+    };
+    let (texture, view, _) = upload(device, queue, &data, ChannelFormat::Auto.resolve(kind, false), "channel_placeholder");
+    (texture, view)
+}
+
+/// Texels encoded as `format`, the resolved channel format
+fn texel_bytes<T: Texel>(texels: &[T], format: ChannelFormat) -> Vec<u8> {
+    match format {
+        ChannelFormat::Rgba16Float => texels
+            .iter()
+            .flat_map(|t| half::f16::from_f32(t.as_float()).to_le_bytes())
+            .collect(),
+        ChannelFormat::Rgba32Float => texels.iter().flat_map(|t| t.as_float().to_le_bytes()).collect(),
+        _ => texels.iter().map(|t| u8::from_float(t.as_float())).collect(),
+    }
+}
+
+/// Upload as `format`, returning the format actually used
+///
+/// Without `FLOAT32_FILTERABLE` a `Rgba32Float` texture can't be bound with
+/// the channels' filtering sampler, so it falls back to `Rgba16Float`.
+pub fn upload<T: Texel>(
+    device: &Device,
+    queue: &Queue,
+    data: &ChannelData<T>,
+    format: ChannelFormat,
+    label: &str,
+) -> (Texture, TextureView, ChannelFormat) {
+    let format = if format == ChannelFormat::Rgba32Float && !device.features().contains(Features::FLOAT32_FILTERABLE) {
+        log::warn!("{}: RGBA32F textures aren't filterable on this device, using RGBA16F", label);
+        ChannelFormat::Rgba16Float
+    } else {
+        format
+    };
+    let dimension = match data.kind {
+        ChannelKind::Volume => TextureDimension::D3,
+        _ => TextureDimension::D2,
+    };
+    let texture = device.create_texture(&TextureDescriptor {
+        label: Some(label),
+        size: extent(data),
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension,
+        format: format.texture_format(),
+        usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    write_texels(queue, &texture, data, format);
+    let view = texture.create_view(&TextureViewDescriptor {
+        dimension: Some(data.kind.view_dimension()),
+        ..Default::default()
+    });
+    (texture, view, format)
+}
+
+/// Replace the contents of a texture made by [`upload`] with `format` as returned by it
+pub fn write_texels<T: Texel>(queue: &Queue, texture: &Texture, data: &ChannelData<T>, format: ChannelFormat) {
+    let bytes: Vec<u8>;
+    // 8-bit texels upload as they are
+    let texels: &[u8] = match std::mem::size_of::<T>() {
+        1 if !format.is_float() => bytemuck::cast_slice(&data.texels),
+        _ => {
+            bytes = texel_bytes(&data.texels, format);
+            &bytes
+        }
+    };
+    let texel_size = match format {
+        ChannelFormat::Rgba16Float => 8,
+        ChannelFormat::Rgba32Float => 16,
+        _ => 4,
+    };
+    queue.write_texture(
+        texture.as_image_copy(),
+        texels,
+        TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(texel_size * data.size[0]),
+            rows_per_image: Some(data.size[1]),
+        },
+        extent(data),
+    );
+}
+
+/// GPU memory of a texture of `size` in `format`, in bytes
+pub fn texture_bytes(size: Extent3d, format: TextureFormat) -> u64 {
+    let texel = format.block_copy_size(None).unwrap_or(4) as u64;
+    size.width as u64 * size.height as u64 * size.depth_or_array_layers as u64 * texel
+}
+
+fn extent<T>(data: &ChannelData<T>) -> Extent3d {
+    Extent3d {
+        width: data.size[0],
+        height: data.size[1],
+        depth_or_array_layers: data.size[2],
+    }
+}
+
+/// A channel's file, decoded but not yet laid out
+pub enum ChannelSource {
+    /// Raw 8-bit volume voxels
+    Raw(ChannelData),
+    Image(DynamicImage),
+}
+
+/// Read a channel's file for a channel of `kind`
+pub fn load_channel_image(path: &str, kind: ChannelKind) -> Result<ChannelSource, String> {
+    if kind == ChannelKind::Volume && is_raw_volume(path) {
+        let bytes = std::fs::read(path).map_err(|e| format!("Failed to read volume {}: {}", path, e))?;
+        let name = Path::new(path).file_name().and_then(|n| n.to_str()).unwrap_or("");
+        return volume_from_raw(&bytes, name).map(ChannelSource::Raw);
+    }
+    image::open(path)
+        .map(ChannelSource::Image)
+        .map_err(|e| format!("Failed to load image {}: {}", path, e))
+}
+
+/// Split an image into the layers of a channel of `kind`
+pub fn layout_image<T: Texel>(image: &RgbaBuffer<T>, kind: ChannelKind) -> Result<ChannelData<T>, String> {
+    match kind {
+        ChannelKind::Texture2d => {
+            let (width, height) = image.dimensions();
+            Ok(ChannelData {
+                kind,
+                size: [width, height, 1],
+                texels: image.as_raw().clone(),
+            })
+        }
+        ChannelKind::Cube => cube_from_image(image),
+        ChannelKind::Volume => volume_from_image(image),
+    }
+}
+
+/// Whether a volume file holds raw voxels rather than an image of slices
+pub fn is_raw_volume(path: &str) -> bool {
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    matches!(extension.as_str(), "raw" | "vol" | "bin")
+}
+
+/// Cube face order of texture layers
+const FACES: [&str; 6] = ["+X", "-X", "+Y", "-Y", "+Z", "-Z"];
+
+/// Horizontal cross: (column, row) of each face in a 4×3 grid
+const CROSS: [(u32, u32); 6] = [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)];
+
+/// Split or project one image into six cube faces
+pub fn cube_from_image<T: Texel>(image: &RgbaBuffer<T>) -> Result<ChannelData<T>, String> {
+    let (width, height) = image.dimensions();
+    let (size, tiles): (u32, Vec<(u32, u32)>) = if width == 6 * height {
+        (height, (0..6).map(|face| (face * height, 0)).collect())
+    } else if height == 6 * width {
+        (width, (0..6).map(|face| (0, face * width)).collect())
+    } else if 3 * width == 4 * height && width % 4 == 0 {
+        let size = width / 4;
+        (size, CROSS.iter().map(|(column, row)| (column * size, row * size)).collect())
+    } else if width == 2 * height {
+        return Ok(cube_from_equirect(image));
+    } else {
+        return Err(format!(
+            "A {}x{} image is not a cubemap: expected a 6:1 or 1:6 face strip, a 4:3 cross or a 2:1 panorama",
+            width, height
+        ));
+    };
+
+    let mut texels = Vec::with_capacity((size * size * 6 * 4) as usize);
+    for (x0, y0) in tiles {
+        for y in 0..size {
+            for x in 0..size {
+                texels.extend_from_slice(image.get_pixel(x0 + x, y0 + y).channels());
+            }
+        }
+    }
+    Ok(ChannelData {
+        kind: ChannelKind::Cube,
+        size: [size, size, 6],
+        texels,
+    })
+}
+
+/// World direction through texel centre (`x`, `y`) of cube `face`
+fn face_direction(face: usize, x: u32, y: u32, size: u32) -> [f32; 3] {
+    let u = 2.0 * (x as f32 + 0.5) / size as f32 - 1.0;
+    let v = 2.0 * (y as f32 + 0.5) / size as f32 - 1.0;
+    match face {
+        0 => [1.0, -v, -u],
+        1 => [-1.0, -v, u],
+        2 => [u, 1.0, v],
+        3 => [u, -1.0, -v],
+        4 => [u, -v, 1.0],
+        _ => [-u, -v, -1.0],
+    }
+}
+
+/// Resample an equirectangular panorama onto cube faces of half its height
+fn cube_from_equirect<T: Texel>(image: &RgbaBuffer<T>) -> ChannelData<T> {
+    let (width, height) = image.dimensions();
+    let size = (height / 2).max(1);
+    let mut texels = Vec::with_capacity((size * size * 6 * 4) as usize);
+    for face in 0..6 {
+        for y in 0..size {
+            for x in 0..size {
+                let [dx, dy, dz] = crate::utils::camera::normalize(face_direction(face, x, y, size));
+                let u = 0.5 + dx.atan2(-dz) / std::f32::consts::TAU;
+                let v = dy.clamp(-1.0, 1.0).acos() / std::f32::consts::PI;
+                texels.extend_from_slice(&sample_bilinear(image, u * width as f32, v * height as f32));
+            }
+        }
+    }
+    ChannelData {
+        kind: ChannelKind::Cube,
+        size: [size, size, 6],
+        texels,
+    }
+}
+
+/// Bilinear sample at pixel coordinates, wrapping horizontally and clamping vertically
+fn sample_bilinear<T: Texel>(image: &RgbaBuffer<T>, x: f32, y: f32) -> [T; 4] {
+    let (width, height) = image.dimensions();
+    let x = x - 0.5;
+    let y = (y - 0.5).clamp(0.0, (height - 1) as f32);
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let column = |x: f32| (x as i64).rem_euclid(width as i64) as u32;
+    let row = |y: f32| (y as u32).min(height - 1);
+    let corners = [
+        image.get_pixel(column(x0), row(y0)).channels(),
+        image.get_pixel(column(x0 + 1.0), row(y0)).channels(),
+        image.get_pixel(column(x0), row(y0 + 1.0)).channels(),
+        image.get_pixel(column(x0 + 1.0), row(y0 + 1.0)).channels(),
+    ];
+    std::array::from_fn(|c| {
+        let top = corners[0][c].as_float() * (1.0 - fx) + corners[1][c].as_float() * fx;
+        let bottom = corners[2][c].as_float() * (1.0 - fx) + corners[3][c].as_float() * fx;
+        T::from_float(top * (1.0 - fy) + bottom * fy)
+    })
+}
+
+/// Combine six face images into a horizontal strip
+///
+/// Faces are recognised by name (`px`/`posx`/`right`, `nx`/`negx`/`left`,
+/// `py`/`posy`/`top`/`up`, `ny`/`negy`/`bottom`/`down`, `pz`/`posz`/`front`,
+/// `nz`/`negz`/`back`) and must be square and the same size. The strip is
+/// RGBA32F if any face is an HDR image, RGBA8 otherwise.
+pub fn cube_strip_from_faces(paths: &[String]) -> Result<DynamicImage, String> {
+    const NAMES: [&[&str]; 6] = [
+        &["px", "posx", "right", "+x"],
+        &["nx", "negx", "left", "-x"],
+        &["py", "posy", "top", "up", "+y"],
+        &["ny", "negy", "bottom", "down", "-y"],
+        &["pz", "posz", "front", "+z"],
+        &["nz", "negz", "back", "-z"],
+    ];
+    if paths.len() != 6 {
+        return Err(format!("A cubemap needs 6 face images, got {}", paths.len()));
+    }
+
+    let mut ordered: [Option<&String>; 6] = [None; 6];
+    for path in paths {
+        let stem = Path::new(path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let tokens: Vec<&str> = stem.split(|c: char| !c.is_ascii_alphanumeric() && c != '+' && c != '-').collect();
+        let face = NAMES
+            .iter()
+            .position(|names| names.iter().any(|name| tokens.contains(name) || stem.ends_with(name)))
+            .ok_or_else(|| format!("Can't tell which cube face {} is", path))?;
+        if ordered[face].replace(path).is_some() {
+            return Err(format!("More than one image for cube face {}", FACES[face]));
+        }
+    }
+
+    let mut faces = Vec::with_capacity(6);
+    for (face, path) in ordered.iter().enumerate() {
+        let path = path.ok_or_else(|| format!("No image for cube face {}", FACES[face]))?;
+        let image = image::open(path).map_err(|e| format!("Failed to load cube face {}: {}", path, e))?;
+        let (width, height) = (image.width(), image.height());
+        if width != height {
+            return Err(format!("Cube face {} is {}x{}, faces must be square", path, width, height));
+        }
+        if let Some((_, first)) = faces.first() {
+            let size = DynamicImage::width(first);
+            if width != size {
+                return Err(format!("Cube face {} is {}x{}, expected {}x{}", path, width, height, size, size));
+            }
+        }
+        faces.push((path, image));
+    }
+
+    if faces.iter().any(|(_, image)| is_hdr(image)) {
+        Ok(DynamicImage::ImageRgba32F(join_strip(faces.iter().map(|(_, image)| image.to_rgba32f()))))
+    } else {
+        Ok(DynamicImage::ImageRgba8(join_strip(faces.iter().map(|(_, image)| image.to_rgba8()))))
+    }
+}
+
+/// Square images side by side
+fn join_strip<T: Texel>(faces: impl ExactSizeIterator<Item = RgbaBuffer<T>>) -> RgbaBuffer<T> {
+    let count = faces.len() as u32;
+    let mut strip: Option<RgbaBuffer<T>> = None;
+    for (i, face) in faces.enumerate() {
+        let size = face.width();
+        let strip = strip.get_or_insert_with(|| ImageBuffer::new(size * count, size));
+        image::imageops::replace(strip, &face, (i as u32 * size) as i64, 0);
+    }
+    strip.unwrap_or_default()
+}
+
+/// Stack the square slices of a strip or grid image into a volume
+pub fn volume_from_image<T: Texel>(image: &RgbaBuffer<T>) -> Result<ChannelData<T>, String> {
+    let (width, height) = image.dimensions();
+    let grid = (width == height)
+        .then(|| (width as f64 * height as f64).cbrt().round() as u32)
+        .filter(|size| *size > 0 && size * size * size == width * height && width % size == 0);
+
+    let (size, tiles) = match grid {
+        Some(size) => {
+            let per_row = width / size;
+            (size, (0..size).map(|slice| ((slice % per_row) * size, (slice / per_row) * size)).collect::<Vec<_>>())
+        }
+        None if height > 0 && width % height == 0 => (height, (0..width / height).map(|slice| (slice * height, 0)).collect()),
+        None => {
+            return Err(format!(
+                "A {}x{} image is not a volume: expected a strip of square slices or an N^3 grid of N×N tiles",
+                width, height
+            ))
+        }
+    };
+
+    let mut texels = Vec::with_capacity((size * size * tiles.len() as u32 * 4) as usize);
+    for (x0, y0) in &tiles {
+        for y in 0..size {
+            for x in 0..size {
+                texels.extend_from_slice(image.get_pixel(x0 + x, y0 + y).channels());
+            }
+        }
+    }
+    Ok(ChannelData {
+        kind: ChannelKind::Volume,
+        size: [size, size, tiles.len() as u32],
+        texels,
+    })
+}
+
+/// Raw 8-bit voxels, x fastest; each becomes `(v, v, v, 255)`
+pub fn volume_from_raw(bytes: &[u8], file_name: &str) -> Result<ChannelData, String> {
+    let size = match raw_size_from_name(file_name) {
+        Some(size) => size,
+        None => {
+            let side = (bytes.len() as f64).cbrt().round() as u32;
+            [side, side, side]
+        }
+    };
+    let voxels = size.iter().map(|s| *s as usize).product::<usize>();
+    if voxels == 0 || voxels != bytes.len() {
+        return Err(format!(
+            "{} is {} bytes, which is not {}x{}x{} 8-bit voxels; name it like `volume_64x64x32.raw`",
+            file_name,
+            bytes.len(),
+            size[0],
+            size[1],
+            size[2]
+        ));
+    }
+    Ok(ChannelData {
+        kind: ChannelKind::Volume,
+        size,
+        texels: bytes.iter().flat_map(|v| [*v, *v, *v, 255]).collect(),
+    })
+}
+
+/// `WxHxD` at the end of a file stem, e.g. `cloud_128x128x64.raw`
+fn raw_size_from_name(file_name: &str) -> Option<[u32; 3]> {
+    let stem = Path::new(file_name).file_stem()?.to_str()?;
+    let suffix = stem.rsplit(['_', '-', '.', ' ']).next()?;
+    let dims: Vec<u32> = suffix.split('x').map(|d| d.parse().ok()).collect::<Option<_>>()?;
+    <[u32; 3]>::try_from(dims).ok()
+}
+
+/// Volume as a horizontal strip of slices, for embedding raw volumes in shards as PNG
+///
+/// Only volumes with square slices can be read back from a strip.
+pub fn volume_to_strip(data: &ChannelData) -> Result<RgbaImage, String> {
+    let [width, height, depth] = data.size;
+    if width != height {
+        return Err(format!("A {}x{}x{} volume has non-square slices and can't be stored as a strip", width, height, depth));
+    }
+    let mut strip = RgbaImage::new(width * depth, height);
+    let slice_bytes = (width * height * 4) as usize;
+    for (z, slice) in data.texels.chunks_exact(slice_bytes).enumerate() {
+        for (i, texel) in slice.chunks_exact(4).enumerate() {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            strip.put_pixel(z as u32 * width + x, y, image::Rgba([texel[0], texel[1], texel[2], texel[3]]));
+        }
+    }
+    Ok(strip)
+}
+
+/// A channel's file re-encoded for embedding in shards
+///
+/// Channels with a float format become RGBA32F OpenEXR, so HDR and 16-bit
+/// precision survives; the rest become RGBA8 PNG. Raw volumes become a strip
+/// of slices, which loads back as the same volume.
+pub fn embeddable_image(path: &str, settings: &ChannelSettings) -> Result<Vec<u8>, String> {
+    let (image, hdr) = match load_channel_image(path, settings.kind)? {
+        ChannelSource::Raw(data) => (DynamicImage::ImageRgba8(volume_to_strip(&data)?), false),
+        ChannelSource::Image(image) => {
+            let hdr = is_hdr(&image);
+            (image, hdr)
+        }
+    };
+    let (image, format) = if settings.format.resolve(settings.kind, hdr).is_float() {
+        (DynamicImage::ImageRgba32F(image.to_rgba32f()), image::ImageFormat::OpenExr)
+    } else {
+        (DynamicImage::ImageRgba8(image.to_rgba8()), image::ImageFormat::Png)
+    };
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut bytes), format)
+        .map_err(|e| format!("Failed to encode {} as {:?}: {}", path, format, e))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::shader_constants::STANDARD_VERTEX;
+
+    /// Image whose pixels encode their own position
+    fn coordinates(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, y| image::Rgba([x as u8, y as u8, 0, 255]))
+    }
+
+    #[test]
+    fn test_boilerplate_declares_channel_types() {
+        let boilerplate = shader_boilerplate([ChannelKind::Texture2d, ChannelKind::Cube, ChannelKind::Texture2d, ChannelKind::Volume]);
+        assert!(boilerplate.contains("var iChannel0: texture_2d<f32>;"));
+        assert!(boilerplate.contains("var iChannel1: texture_cube<f32>;"));
+        assert!(boilerplate.contains("var iChannel3: texture_3d<f32>;"));
+        assert_eq!(boilerplate.lines().count(), SHADER_BOILERPLATE.lines().count());
+
+        let source = format!(
+            "{}\n{}\n@fragment\nfn fs_main(in: VSOut) -> @location(0) vec4<f32> {{\n    return textureSample(iChannel1, iChannel1Sampler, vec3<f32>(in.uv, 1.0));\n}}",
+            boilerplate, STANDARD_VERTEX
+        );
+        crate::utils::validate_shader(&source).unwrap();
+        // The same call is a type error against a 2D channel
+        assert!(crate::utils::validate_shader(&source.replace(&*boilerplate, SHADER_BOILERPLATE)).is_err());
+    }
+
+    #[test]
+    fn test_cube_layouts() {
+        let strip = cube_from_image(&coordinates(24, 4)).unwrap();
+        assert_eq!(strip.size, [4, 4, 6]);
+        // First texel of face -Y (layer 3) comes from x = 12
+        assert_eq!(strip.texels[3 * 64], 12);
+
+        let cross = cube_from_image(&coordinates(16, 12)).unwrap();
+        // Layer 2 (+Y) is the top of the cross's second column
+        assert_eq!(&cross.texels[2 * 64..2 * 64 + 2], &[4, 0]);
+
+        let panorama = cube_from_image(&RgbaImage::from_pixel(16, 8, image::Rgba([9, 9, 9, 255]))).unwrap();
+        assert_eq!(panorama.size, [4, 4, 6]);
+        assert!(panorama.texels.chunks_exact(4).all(|t| t == [9, 9, 9, 255]));
+
+        assert!(cube_from_image(&coordinates(10, 7)).is_err());
+    }
+
+    #[test]
+    fn test_volume_layouts() {
+        let strip = volume_from_image(&coordinates(12, 4)).unwrap();
+        assert_eq!(strip.size, [4, 4, 3]);
+        assert_eq!(strip.texels[2 * 64], 8);
+
+        // 16^3 voxels as a 64x64 image of 4x4 tiles
+        let grid = volume_from_image(&coordinates(64, 64)).unwrap();
+        assert_eq!(grid.size, [16, 16, 16]);
+        // Slice 5 is row 1, column 1 of the grid
+        assert_eq!(&grid.texels[5 * 16 * 16 * 4..5 * 16 * 16 * 4 + 2], &[16, 16]);
+
+        let raw = volume_from_raw(&[7; 2 * 3 * 4], "smoke_2x3x4.raw").unwrap();
+        assert_eq!(raw.size, [2, 3, 4]);
+        assert_eq!(&raw.texels[..4], &[7, 7, 7, 255]);
+        assert_eq!(volume_from_raw(&[0; 27], "cube.raw").unwrap().size, [3, 3, 3]);
+        assert!(volume_from_raw(&[0; 10], "odd.raw").is_err());
+
+        let cube = volume_from_raw(&(0..27).collect::<Vec<u8>>(), "c.raw").unwrap();
+        assert_eq!(volume_from_image(&volume_to_strip(&cube).unwrap()).unwrap(), cube);
+    }
+
+    #[test]
+    fn test_formats() {
+        assert_eq!(ChannelFormat::Auto.resolve(ChannelKind::Texture2d, false), ChannelFormat::Srgb);
+        assert_eq!(ChannelFormat::Auto.resolve(ChannelKind::Volume, false), ChannelFormat::Linear);
+        assert_eq!(ChannelFormat::Auto.resolve(ChannelKind::Cube, true), ChannelFormat::Rgba16Float);
+        assert_eq!(ChannelFormat::Linear.resolve(ChannelKind::Cube, true), ChannelFormat::Linear);
+
+        assert_eq!(texel_bytes(&[1.5f32], ChannelFormat::Rgba16Float), half::f16::from_f32(1.5).to_le_bytes());
+        assert_eq!(texel_bytes(&[255u8], ChannelFormat::Rgba32Float), 1.0f32.to_le_bytes());
+        assert_eq!(texel_bytes(&[4.0f32, 0.5], ChannelFormat::Linear), [255, 128]);
+
+        // Float layouts keep values above 1
+        let hdr = RgbaBuffer::<f32>::from_pixel(24, 4, Rgba([4.0, 2.0, 1.0, 1.0]));
+        assert!(cube_from_image(&hdr).unwrap().texels.chunks_exact(4).all(|t| t == [4.0, 2.0, 1.0, 1.0]));
+
+        // HDR files embed as EXR with their values; 8-bit data as PNG
+        let dir = std::env::temp_dir().join(format!("webshard_channel_formats_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let exr = dir.join("sky.exr");
+        DynamicImage::ImageRgba32F(hdr).save(&exr).unwrap();
+        let embedded = embeddable_image(exr.to_str().unwrap(), &ChannelSettings::default()).unwrap();
+        assert_eq!(image::guess_format(&embedded).unwrap(), image::ImageFormat::OpenExr);
+        assert_eq!(image::load_from_memory(&embedded).unwrap().to_rgba32f().get_pixel(0, 0).0, [4.0, 2.0, 1.0, 1.0]);
+
+        let png = dir.join("deep.png");
+        let deep = image::ImageBuffer::<Rgba<u16>, _>::from_pixel(2, 2, Rgba([1000, 0, 0, 65535]));
+        DynamicImage::ImageRgba16(deep).save(&png).unwrap();
+        let settings = ChannelSettings {
+            format: ChannelFormat::Rgba16Float,
+            ..Default::default()
+        };
+        let embedded = image::load_from_memory(&embeddable_image(png.to_str().unwrap(), &settings).unwrap()).unwrap();
+        assert_eq!(embedded.to_rgba16().get_pixel(1, 1).0, [1000, 0, 0, 65535]);
+        let embedded = embeddable_image(png.to_str().unwrap(), &ChannelSettings::default()).unwrap();
+        assert_eq!(image::guess_format(&embedded).unwrap(), image::ImageFormat::Png);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_flip_and_sampler_settings() {
+        let settings = ChannelSettings {
+            flip_y: true,
+            ..Default::default()
+        };
+        let flipped = ChannelData::from_frame(&coordinates(2, 3), &settings);
+        assert_eq!(&flipped.texels[..4], [0, 2, 0, 255]);
+        assert_eq!(&flipped.texels[8..12], [0, 1, 0, 255]);
+        assert_eq!(&flipped.texels[16..20], [0, 0, 0, 255]);
+
+        // Each cube face flips on its own; only 2D channels flip on load
+        let mut cube = cube_from_image(&coordinates(12, 2)).unwrap();
+        cube.flip_y();
+        assert_eq!(&cube.texels[..4], [0, 1, 0, 255]);
+        assert_eq!(&cube.texels[16..20], [2, 1, 0, 255]);
+        let cube_settings = ChannelSettings {
+            kind: ChannelKind::Cube,
+            ..settings
+        };
+        assert!(!cube_settings.flips());
+
+        let sampler = ChannelSettings {
+            wrap: ChannelWrap::Mirror,
+            filter: ChannelFilter::Nearest,
+            ..Default::default()
+        }
+        .sampler_descriptor();
+        assert_eq!(sampler.address_mode_u, AddressMode::MirrorRepeat);
+        assert_eq!(sampler.mag_filter, FilterMode::Nearest);
+
+        let size = Extent3d {
+            width: 64,
+            height: 32,
+            depth_or_array_layers: 6,
+        };
+        assert_eq!(texture_bytes(size, TextureFormat::Rgba8UnormSrgb), 64 * 32 * 6 * 4);
+        assert_eq!(texture_bytes(size, TextureFormat::Rgba16Float), 64 * 32 * 6 * 8);
+    }
+}
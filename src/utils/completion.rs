@@ -0,0 +1,449 @@
+//! WGSL code completion
+//!
+//! Builds completion candidates for the shader editor from four sources:
+//! - WGSL builtin functions and attributes (`data/wgsl_builtins.json`)
+//! - WGSL keywords, types and builtin values (shared with `wgsl_syntax`)
+//! - Symbols from the auto-injected boilerplate (`uniforms.*`, `iChannel0-3`, buffer textures)
+//! - Functions, structs and globals declared in the buffer being edited
+
+use regex::Regex;
+use serde::Deserialize;
+use std::sync::OnceLock;
+
+use crate::utils::shader_constants::{SHADER_BOILERPLATE, TEXTURE_BINDINGS};
+use crate::utils::text::{apply_completion, current_prefix};
+use crate::utils::wgsl_syntax;
+
+/// Maximum number of entries shown in the completion popup
+pub const MAX_COMPLETIONS: usize = 12;
+
+/// Category of a completion candidate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompletionKind {
+    Function,
+    Attribute,
+    Keyword,
+    Type,
+    Struct,
+    Variable,
+    Field,
+}
+
+impl CompletionKind {
+    /// Short tag displayed next to the label in the popup
+    pub fn tag(&self) -> &'static str {
+        match self {
+            CompletionKind::Function => "fn",
+            CompletionKind::Attribute => "@",
+            CompletionKind::Keyword => "kw",
+            CompletionKind::Type => "ty",
+            CompletionKind::Struct => "struct",
+            CompletionKind::Variable => "var",
+            CompletionKind::Field => "field",
+        }
+    }
+}
+
+/// A single completion candidate
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompletionItem {
+    pub label: String,
+    /// Signature or type shown in the preview
+    #[serde(default)]
+    pub detail: String,
+    /// Documentation shown below the signature
+    #[serde(default)]
+    pub doc: String,
+    /// Text appended after the label on accept (e.g. "()" for functions)
+    #[serde(default)]
+    pub insert_suffix: Option<String>,
+    pub kind: CompletionKind,
+}
+
+impl CompletionItem {
+    fn new(label: &str, detail: impl Into<String>, kind: CompletionKind) -> Self {
+        Self {
+            label: label.to_string(),
+            detail: detail.into(),
+            doc: String::new(),
+            insert_suffix: None,
+            kind,
+        }
+    }
+}
+
+/// Functions, structs and globals declared in a piece of WGSL source
+#[derive(Debug, Clone, Default)]
+pub struct DeclaredSymbols {
+    pub items: Vec<CompletionItem>,
+    /// Struct name -> fields as (name, type)
+    pub structs: Vec<(String, Vec<(String, String)>)>,
+    /// Variable name -> declared type
+    pub variables: Vec<(String, String)>,
+}
+
+impl DeclaredSymbols {
+    fn struct_fields(&self, name: &str) -> Option<&[(String, String)]> {
+        self.structs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, fields)| fields.as_slice())
+    }
+
+    fn variable_type(&self, name: &str) -> Option<&str> {
+        self.variables
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, ty)| ty.as_str())
+    }
+}
+
+/// Builtin functions and attributes loaded from `data/wgsl_builtins.json`
+pub fn builtins() -> &'static [CompletionItem] {
+    static BUILTINS: OnceLock<Vec<CompletionItem>> = OnceLock::new();
+    BUILTINS.get_or_init(|| {
+        match serde_json::from_str(include_str!("../../data/wgsl_builtins.json")) {
+            Ok(items) => items,
+            Err(e) => {
+                log::error!("Failed to parse wgsl_builtins.json: {}", e);
+                Vec::new()
+            }
+        }
+    })
+}
+
+/// Symbols provided by the auto-injected boilerplate and multi-pass bindings
+pub fn boilerplate_symbols() -> &'static DeclaredSymbols {
+    static BOILERPLATE: OnceLock<DeclaredSymbols> = OnceLock::new();
+    BOILERPLATE.get_or_init(|| {
+        let mut symbols = scan_declarations(&format!("{}\n{}", SHADER_BOILERPLATE, TEXTURE_BINDINGS));
+        for item in &mut symbols.items {
+            item.doc = "Auto-injected by the editor".to_string();
+        }
+        symbols
+    })
+}
+
+fn keyword_items() -> &'static [CompletionItem] {
+    static KEYWORDS: OnceLock<Vec<CompletionItem>> = OnceLock::new();
+    KEYWORDS.get_or_init(|| {
+        let keywords = wgsl_syntax::KEYWORDS
+            .iter()
+            .map(|k| CompletionItem::new(k, "keyword", CompletionKind::Keyword));
+        let types = wgsl_syntax::TYPES
+            .iter()
+            .map(|t| CompletionItem::new(t, "type", CompletionKind::Type));
+        // Attributes, address spaces and builtin values not covered by the JSON data
+        let special = wgsl_syntax::SPECIAL
+            .iter()
+            .map(|s| CompletionItem::new(s, "builtin", CompletionKind::Keyword));
+        keywords.chain(types).chain(special).collect()
+    })
+}
+
+/// Scan WGSL source for top-level functions, structs and variables
+///
+/// This is a lightweight regex scan rather than a full parse so it keeps
+/// working while the user is in the middle of typing invalid code.
+pub fn scan_declarations(source: &str) -> DeclaredSymbols {
+    static FN_RE: OnceLock<Regex> = OnceLock::new();
+    static STRUCT_RE: OnceLock<Regex> = OnceLock::new();
+    static VAR_RE: OnceLock<Regex> = OnceLock::new();
+    static FIELD_RE: OnceLock<Regex> = OnceLock::new();
+
+    let fn_re = FN_RE.get_or_init(|| {
+        Regex::new(r"\bfn\s+([A-Za-z_][A-Za-z0-9_]*)\s*\(([^)]*)\)\s*(->\s*[^{]+)?\{").unwrap()
+    });
+    let struct_re = STRUCT_RE.get_or_init(|| {
+        Regex::new(r"\bstruct\s+([A-Za-z_][A-Za-z0-9_]*)\s*\{([^}]*)\}").unwrap()
+    });
+    let var_re = VAR_RE.get_or_init(|| {
+        Regex::new(r"\b(var|let|const|override)(<[^>]*>)?\s+([A-Za-z_][A-Za-z0-9_]*)\s*(:\s*([^=;]+))?")
+            .unwrap()
+    });
+    let field_re = FIELD_RE.get_or_init(|| {
+        Regex::new(r"(?:@\w+(?:\([^)]*\))?\s*)*([A-Za-z_][A-Za-z0-9_]*)\s*:\s*([^,]+)").unwrap()
+    });
+
+    let source = strip_comments(source);
+    let mut symbols = DeclaredSymbols::default();
+
+    for caps in fn_re.captures_iter(&source) {
+        let name = &caps[1];
+        let params = collapse_whitespace(&caps[2]);
+        let ret = caps
+            .get(3)
+            .map(|m| format!(" {}", collapse_whitespace(m.as_str())))
+            .unwrap_or_default();
+        let mut item = CompletionItem::new(
+            name,
+            format!("fn {}({}){}", name, params, ret),
+            CompletionKind::Function,
+        );
+        item.insert_suffix = Some("()".to_string());
+        symbols.items.push(item);
+    }
+
+    for caps in struct_re.captures_iter(&source) {
+        let name = caps[1].to_string();
+        let fields: Vec<(String, String)> = field_re
+            .captures_iter(&caps[2])
+            .map(|f| (f[1].to_string(), collapse_whitespace(&f[2])))
+            .collect();
+        symbols
+            .items
+            .push(CompletionItem::new(&name, format!("struct {}", name), CompletionKind::Struct));
+        symbols.structs.push((name, fields));
+    }
+
+    for caps in var_re.captures_iter(&source) {
+        let name = &caps[3];
+        if symbols.variables.iter().any(|(n, _)| n == name) {
+            continue;
+        }
+        let ty = caps
+            .get(5)
+            .map(|m| collapse_whitespace(m.as_str()))
+            .unwrap_or_default();
+        let detail = if ty.is_empty() {
+            format!("{} {}", &caps[1], name)
+        } else {
+            format!("{} {}: {}", &caps[1], name, ty)
+        };
+        symbols
+            .items
+            .push(CompletionItem::new(name, detail, CompletionKind::Variable));
+        symbols.variables.push((name.to_string(), ty));
+    }
+
+    symbols
+}
+
+/// Compute completion candidates for the caret position (in chars) within `source`
+pub fn completions(source: &str, caret_char: usize) -> Vec<CompletionItem> {
+    let prefix = current_prefix(source, caret_char);
+    let prefix_len = prefix.chars().count();
+    let declared = scan_declarations(source);
+
+    // Member access: `uniforms.ti|` completes fields of the variable's struct type
+    if let Some(receiver) = member_receiver(source, caret_char - prefix_len) {
+        let boilerplate = boilerplate_symbols();
+        let ty = declared
+            .variable_type(&receiver)
+            .or_else(|| boilerplate.variable_type(&receiver));
+        let fields = ty.and_then(|ty| {
+            declared
+                .struct_fields(ty)
+                .or_else(|| boilerplate.struct_fields(ty))
+        });
+        return fields
+            .unwrap_or_default()
+            .iter()
+            .filter(|(name, _)| name.starts_with(&prefix) && !name.starts_with('_'))
+            .take(MAX_COMPLETIONS)
+            .map(|(name, ty)| {
+                CompletionItem::new(name, format!("{}.{}: {}", receiver, name, ty), CompletionKind::Field)
+            })
+            .collect();
+    }
+
+    if prefix_len < 2 {
+        return Vec::new();
+    }
+
+    let candidates = declared
+        .items
+        .iter()
+        .chain(boilerplate_symbols().items.iter())
+        .chain(builtins().iter())
+        .chain(keyword_items().iter());
+
+    let mut seen = std::collections::HashSet::new();
+    let mut matches: Vec<(u8, CompletionItem)> = Vec::new();
+    let prefix_lower = prefix.to_lowercase();
+
+    for item in candidates {
+        if item.label == prefix || !seen.insert(item.label.as_str()) {
+            continue;
+        }
+        let rank = if item.label.starts_with(&prefix) {
+            0
+        } else if item.label.to_lowercase().starts_with(&prefix_lower) {
+            1
+        } else {
+            continue;
+        };
+        matches.push((rank, item.clone()));
+    }
+
+    // Stable sort keeps source order (user, boilerplate, builtins, keywords) within a rank
+    matches.sort_by_key(|(rank, _)| *rank);
+    matches
+        .into_iter()
+        .take(MAX_COMPLETIONS)
+        .map(|(_, item)| item)
+        .collect()
+}
+
+/// Replace the prefix at the caret with the completion and return the new caret position
+///
+/// Function completions get their `()` suffix inserted with the caret placed
+/// between the parentheses.
+pub fn accept_completion(target: &mut String, caret_char: usize, item: &CompletionItem) -> usize {
+    match item.insert_suffix.as_deref() {
+        Some(suffix) if !suffix.is_empty() => {
+            let next_char = target.chars().nth(caret_char);
+            // Don't double up parentheses when completing an existing call
+            if suffix.starts_with('(') && next_char == Some('(') {
+                return apply_completion(target, caret_char, &item.label);
+            }
+            let word = format!("{}{}", item.label, suffix);
+            let caret = apply_completion(target, caret_char, &word);
+            if suffix == "()" {
+                caret - 1
+            } else {
+                caret
+            }
+        }
+        _ => apply_completion(target, caret_char, &item.label),
+    }
+}
+
+/// If the text before `start_char` is `ident.`, return `ident`
+fn member_receiver(source: &str, start_char: usize) -> Option<String> {
+    let chars: Vec<char> = source.chars().take(start_char).collect();
+    if chars.last() != Some(&'.') {
+        return None;
+    }
+    let receiver: String = chars[..chars.len() - 1]
+        .iter()
+        .rev()
+        .take_while(|c| c.is_alphanumeric() || **c == '_')
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect();
+    if receiver.is_empty() {
+        None
+    } else {
+        Some(receiver)
+    }
+}
+
+/// Remove `//` and `/* */` comments, keeping line structure intact
+fn strip_comments(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '/' && chars.peek() == Some(&'/') {
+            for c in chars.by_ref() {
+                if c == '\n' {
+                    out.push('\n');
+                    break;
+                }
+            }
+        } else if c == '/' && chars.peek() == Some(&'*') {
+            chars.next();
+            let mut prev = ' ';
+            for c in chars.by_ref() {
+                if c == '\n' {
+                    out.push('\n');
+                }
+                if prev == '*' && c == '/' {
+                    break;
+                }
+                prev = c;
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(items: &[CompletionItem]) -> Vec<&str> {
+        items.iter().map(|i| i.label.as_str()).collect()
+    }
+
+    #[test]
+    fn test_builtins_load() {
+        let builtins = builtins();
+        assert!(builtins.len() > 100);
+        assert!(builtins.iter().any(|b| b.label == "textureSampleLevel"));
+        assert!(builtins.iter().any(|b| b.label == "@location" && b.kind == CompletionKind::Attribute));
+    }
+
+    #[test]
+    fn test_builtin_and_keyword_completion() {
+        let src = "fn f() { let a = smo";
+        let items = completions(src, src.chars().count());
+        assert_eq!(labels(&items), vec!["smoothstep"]);
+
+        let src = "fn f() { retu";
+        let items = completions(src, src.chars().count());
+        assert_eq!(items[0].label, "return");
+        assert_eq!(items[0].kind, CompletionKind::Keyword);
+    }
+
+    #[test]
+    fn test_user_symbols_ranked_first() {
+        let src = "fn hash21(p: vec2<f32>) -> f32 { return 0.0; }\nstruct Hit { d: f32 }\nfn f() { let x = ha";
+        let items = completions(src, src.chars().count());
+        assert_eq!(items[0].label, "hash21");
+        assert_eq!(items[0].detail, "fn hash21(p: vec2<f32>) -> f32");
+
+        let src = "struct HitInfo { d: f32 }\nfn f() { var h: Hi";
+        let items = completions(src, src.chars().count());
+        assert_eq!(items[0].label, "HitInfo");
+    }
+
+    #[test]
+    fn test_boilerplate_symbols() {
+        let src = "fn f() { let c = iCh";
+        let items = completions(src, src.chars().count());
+        assert!(labels(&items).contains(&"iChannel0"));
+        assert!(labels(&items).contains(&"iChannel3Sampler"));
+
+        let src = "fn f() { let c = buffer_a";
+        let items = completions(src, src.chars().count());
+        assert!(labels(&items).contains(&"buffer_a_texture"));
+    }
+
+    #[test]
+    fn test_uniform_member_completion() {
+        let src = "fn f() { let t = uniforms.";
+        let items = completions(src, src.chars().count());
+        assert!(labels(&items).contains(&"time"));
+        assert!(labels(&items).contains(&"audio_bass"));
+        assert!(!labels(&items).contains(&"_pad0"));
+
+        let src = "fn f() { let t = uniforms.au";
+        let items = completions(src, src.chars().count());
+        assert_eq!(labels(&items), vec!["audio_bass", "audio_mid", "audio_high"]);
+    }
+
+    #[test]
+    fn test_accept_function_places_caret_in_parens() {
+        let mut src = String::from("let a = smo");
+        let item = builtins().iter().find(|b| b.label == "smoothstep").unwrap();
+        let caret = accept_completion(&mut src, 11, item);
+        assert_eq!(src, "let a = smoothstep()");
+        assert_eq!(caret, 19);
+    }
+
+    #[test]
+    fn test_comments_ignored() {
+        let src = "// fn commented() {}\n/* struct Hidden { a: f32 } */\nfn f() { co";
+        let items = completions(src, src.chars().count());
+        assert!(!labels(&items).contains(&"commented"));
+    }
+}
@@ -0,0 +1,509 @@
+//! Structured shader diagnostics
+//!
+//! A [`Diagnostic`] is built directly from naga parse/validation errors, wgpu
+//! pipeline errors or the editor's own checks, and rendered as needed: a
+//! codespan-style report for the error window and logs, a one-line summary for
+//! toasts, JSON for tooling, or inline underlines in the editor.
+
+use serde::{Serialize, Serializer};
+use std::ops::Range;
+
+use crate::utils::BufferKind;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(dead_code)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+}
+
+/// A location in shader source, 1-based like editor line numbers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Span {
+    /// Section the span is in (`MainImage`, `Common`, a library file, ...).
+    /// `None` while the span still refers to the assembled shader.
+    pub section: Option<String>,
+    pub line: usize,
+    /// Column in bytes
+    pub column: usize,
+    /// Length in bytes
+    pub len: usize,
+    /// Text of the line, kept for rendering snippets
+    #[serde(skip)]
+    pub line_text: String,
+}
+
+impl Span {
+    /// Span of a byte range within `source`
+    pub fn from_range(source: &str, range: Range<usize>) -> Self {
+        let start = range.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        Self {
+            section: None,
+            line: source[..start].matches('\n').count() + 1,
+            column: start - line_start + 1,
+            len: range.end.min(line_end).saturating_sub(start),
+            line_text: source[line_start..line_end].to_string(),
+        }
+    }
+
+    /// `Section:line:column`, or `line:column` if the section is unknown
+    pub fn location(&self) -> String {
+        match &self.section {
+            Some(section) => format!("{}:{}:{}", section, self.line, self.column),
+            None => format!("{}:{}", self.line, self.column),
+        }
+    }
+
+    /// Byte range of this span in `code`, the section's own text
+    pub fn range_in(&self, code: &str) -> Option<Range<usize>> {
+        let line_start = if self.line == 1 {
+            0
+        } else {
+            code.match_indices('\n').nth(self.line - 2)?.0 + 1
+        };
+        let line_end = code[line_start..]
+            .find('\n')
+            .map_or(code.len(), |i| line_start + i);
+        let start = (line_start + self.column - 1).min(line_end);
+        let end = (start + self.len).min(line_end);
+        (code.is_char_boundary(start) && code.is_char_boundary(end)).then_some(start..end)
+    }
+}
+
+/// A span with a message; the first label of a diagnostic is the primary one
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// A suggested edit that resolves the diagnostic
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Fix {
+    pub message: String,
+    /// Text to replace; `None` if the fix only describes what to do
+    pub span: Option<Span>,
+    pub replacement: String,
+}
+
+impl Fix {
+    /// Apply the fix to `code`, the text of the span's section
+    ///
+    /// Returns `None` if the fix only describes what to do, or if the code
+    /// has changed since the diagnostic was produced.
+    pub fn apply(&self, code: &str) -> Option<String> {
+        let span = self.span.as_ref()?;
+        let range = span.range_in(code)?;
+        let start = span.column.checked_sub(1)?;
+        if span.line_text.get(start..start + span.len)? != &code[range.clone()] {
+            return None;
+        }
+        let mut fixed = code.to_string();
+        fixed.replace_range(range, &self.replacement);
+        Some(fixed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    #[serde(serialize_with = "serialize_buffer")]
+    pub buffer: Option<BufferKind>,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub fix: Option<Fix>,
+}
+
+fn serialize_buffer<S: Serializer>(
+    buffer: &Option<BufferKind>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match buffer {
+        Some(kind) => serializer.serialize_some(kind.as_str()),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl Diagnostic {
+    /// Error from a plain message. A blank line separates the message from
+    /// explanatory text, which becomes a note.
+    pub fn error(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let (message, rest) = match text.split_once("\n\n") {
+            Some((message, rest)) => (message.trim().to_string(), rest.trim()),
+            None => (text.trim().to_string(), ""),
+        };
+        Self {
+            severity: Severity::Error,
+            buffer: None,
+            message,
+            labels: Vec::new(),
+            notes: if rest.is_empty() {
+                Vec::new()
+            } else {
+                vec![rest.to_string()]
+            },
+            fix: None,
+        }
+    }
+
+    /// Diagnostic for a WGSL parse error in `source`
+    pub fn from_parse_error(error: &naga::front::wgsl::ParseError, source: &str) -> Self {
+        let labels = error
+            .labels()
+            .filter_map(|(span, message)| {
+                Some(Label {
+                    span: Span::from_range(source, span.to_range()?),
+                    message: message.to_string(),
+                })
+            })
+            .collect();
+
+        // naga keeps parse notes private; they only surface in the emitted report
+        let notes = error
+            .emit_to_string(source)
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("= note:"))
+            .map(|note| note.trim().to_string())
+            .collect();
+
+        Self {
+            severity: Severity::Error,
+            buffer: None,
+            message: error.message().to_string(),
+            labels,
+            notes,
+            fix: None,
+        }
+    }
+
+    /// Diagnostic for a naga validation error in `source`
+    pub fn from_validation_error(
+        error: &naga::WithSpan<naga::valid::ValidationError>,
+        source: &str,
+    ) -> Self {
+        let labels = error
+            .spans()
+            .filter_map(|(span, message)| {
+                Some(Label {
+                    span: Span::from_range(source, span.to_range()?),
+                    message: message.clone(),
+                })
+            })
+            .collect();
+
+        let mut notes = Vec::new();
+        let mut cause: &dyn std::error::Error = error.as_inner();
+        while let Some(next) = cause.source() {
+            notes.push(next.to_string());
+            cause = next;
+        }
+
+        Self {
+            severity: Severity::Error,
+            buffer: None,
+            message: error.as_inner().to_string(),
+            labels,
+            notes,
+            fix: None,
+        }
+    }
+
+    /// Diagnostic for GLSL parse errors in `source`, one label per error
+    pub fn from_glsl_errors(errors: &naga::front::glsl::ParseErrors, source: &str) -> Self {
+        let labels = errors
+            .errors
+            .iter()
+            .filter_map(|error| {
+                Some(Label {
+                    span: Span::from_range(source, error.meta.to_range()?),
+                    message: error.kind.to_string(),
+                })
+            })
+            .collect();
+
+        Self {
+            severity: Severity::Error,
+            buffer: None,
+            message: errors
+                .errors
+                .first()
+                .map_or_else(|| "GLSL parse error".to_string(), |e| e.kind.to_string()),
+            labels,
+            notes: Vec::new(),
+            fix: None,
+        }
+    }
+
+    /// Diagnostic for an error captured from a wgpu error scope
+    pub fn from_wgpu_error(error: &eframe::wgpu::Error) -> Self {
+        match error {
+            eframe::wgpu::Error::OutOfMemory { .. } => {
+                Self::error("GPU out of memory\n\nTry a smaller buffer resolution or fewer passes")
+            }
+            _ => Self::from_wgpu_message(&error.to_string()),
+        }
+    }
+
+    /// Diagnostic for a wgpu error report (`Validation Error` + `Caused by:` chain)
+    pub fn from_wgpu_message(report: &str) -> Self {
+        let mut lines = report
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && *line != "Caused by:")
+            .map(|line| line.trim_start_matches("wgpu error:").trim());
+        let message = lines.next().unwrap_or("GPU error").to_string();
+        Self {
+            severity: Severity::Error,
+            buffer: None,
+            message,
+            labels: Vec::new(),
+            notes: lines
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+            fix: None,
+        }
+    }
+
+    pub fn in_buffer(mut self, buffer: BufferKind) -> Self {
+        self.buffer = Some(buffer);
+        self
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+
+    pub fn primary_span(&self) -> Option<&Span> {
+        self.labels.first().map(|label| &label.span)
+    }
+
+    /// Every span of the diagnostic, e.g. to relocate them into sections
+    pub fn spans_mut(&mut self) -> impl Iterator<Item = &mut Span> {
+        self.labels
+            .iter_mut()
+            .map(|label| &mut label.span)
+            .chain(self.fix.iter_mut().filter_map(|fix| fix.span.as_mut()))
+    }
+
+    /// One line for toasts and status bars: `MainImage:12:5: message`
+    pub fn summary(&self) -> String {
+        match (self.primary_span(), self.buffer) {
+            (Some(span), _) if span.section.is_some() => {
+                format!("{}: {}", span.location(), self.message)
+            }
+            (_, Some(buffer)) => format!("[{}] {}", buffer.as_str(), self.message),
+            _ => self.message.clone(),
+        }
+    }
+
+    /// Multi-line report with source snippets, for the error window and logs
+    pub fn render_text(&self) -> String {
+        let mut out = match self.buffer {
+            Some(buffer) => format!(
+                "{}[{}]: {}\n",
+                self.severity.as_str(),
+                buffer.as_str(),
+                self.message
+            ),
+            None => format!("{}: {}\n", self.severity.as_str(), self.message),
+        };
+
+        let width = self
+            .labels
+            .iter()
+            .map(|l| l.span.line.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let gutter = " ".repeat(width);
+        for label in &self.labels {
+            let span = &label.span;
+            out.push_str(&format!("{} ┌─ {}\n", gutter, span.location()));
+            if !span.line_text.is_empty() {
+                let column = span.column.saturating_sub(1).min(span.line_text.len());
+                let indent = span
+                    .line_text
+                    .get(..column)
+                    .map_or(column, |s| s.chars().count());
+                let marker_len = span
+                    .line_text
+                    .get(column..(column + span.len).min(span.line_text.len()))
+                    .map_or(1, |s| s.chars().count().max(1));
+                out.push_str(&format!("{} │\n", gutter));
+                out.push_str(&format!(
+                    "{:>width$} │ {}\n",
+                    span.line,
+                    span.line_text,
+                    width = width
+                ));
+                out.push_str(&format!(
+                    "{} │ {}{} {}\n",
+                    gutter,
+                    " ".repeat(indent),
+                    "^".repeat(marker_len),
+                    label.message
+                ));
+            } else if !label.message.is_empty() {
+                out.push_str(&format!("{} │ {}\n", gutter, label.message));
+            }
+        }
+
+        if !self.notes.is_empty() || self.fix.is_some() {
+            out.push_str(&format!("{} │\n", gutter));
+        }
+        for note in &self.notes {
+            let mut lines = note.lines();
+            if let Some(first) = lines.next() {
+                out.push_str(&format!("{} = note: {}\n", gutter, first));
+            }
+            for line in lines {
+                out.push_str(&format!("{}         {}\n", gutter, line));
+            }
+        }
+        if let Some(fix) = &self.fix {
+            out.push_str(&format!("{} = help: {}\n", gutter, fix.message));
+        }
+        out.trim_end().to_string()
+    }
+
+    /// Machine-readable form for tooling
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Underlines for the editor showing `section`, whose text is `code`
+    pub fn inline_markers(&self, section: &str, code: &str) -> Vec<InlineMarker> {
+        self.labels
+            .iter()
+            .enumerate()
+            .filter(|(_, label)| label.span.section.as_deref() == Some(section))
+            .filter_map(|(i, label)| {
+                let mut range = label.span.range_in(code)?;
+                if range.is_empty() {
+                    // Point at something visible, e.g. a missing `;` at the line end
+                    let end = code[range.start..]
+                        .chars()
+                        .next()
+                        .map_or(range.start, |c| range.start + c.len_utf8());
+                    range.end = end;
+                }
+                let message = if i == 0 {
+                    match &self.fix {
+                        Some(fix) => format!("{}: {}\nhelp: {}", self.severity.as_str(), self.message, fix.message),
+                        None => format!("{}: {}", self.severity.as_str(), self.message),
+                    }
+                } else if label.message.is_empty() {
+                    format!("{}: {}", self.severity.as_str(), self.message)
+                } else {
+                    label.message.clone()
+                };
+                Some(InlineMarker {
+                    range,
+                    severity: self.severity,
+                    message,
+                })
+            })
+            .collect()
+    }
+}
+
+/// An underlined byte range in an editor with the message shown on hover
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineMarker {
+    pub range: Range<usize>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "fn main_fn() -> f32 {\n    return 1.0 + oops;\n}\n";
+
+    fn parse_error() -> Diagnostic {
+        let error = naga::front::wgsl::parse_str(SOURCE).unwrap_err();
+        Diagnostic::from_parse_error(&error, SOURCE)
+    }
+
+    #[test]
+    fn test_from_parse_error() {
+        let diagnostic = parse_error();
+        let span = diagnostic.primary_span().unwrap();
+        assert_eq!((span.line, span.column, span.len), (2, 18, 4));
+        assert_eq!(span.line_text, "    return 1.0 + oops;");
+        assert!(
+            diagnostic.message.contains("oops"),
+            "{}",
+            diagnostic.message
+        );
+    }
+
+    #[test]
+    fn test_renderers() {
+        let mut diagnostic = parse_error().in_buffer(BufferKind::MainImage);
+        diagnostic
+            .spans_mut()
+            .for_each(|span| span.section = Some("MainImage".into()));
+
+        assert!(diagnostic.summary().starts_with("MainImage:2:18: "));
+        let text = diagnostic.render_text();
+        assert!(text.starts_with("error[MainImage]: "), "{}", text);
+        assert!(text.contains("2 │     return 1.0 + oops;"), "{}", text);
+        assert!(text.contains("│                  ^^^^"), "{}", text);
+
+        let json: serde_json::Value = serde_json::from_str(&diagnostic.to_json()).unwrap();
+        assert_eq!(json["buffer"], "MainImage");
+        assert_eq!(json["labels"][0]["span"]["line"], 2);
+
+        let markers = diagnostic.inline_markers("MainImage", SOURCE);
+        assert_eq!(&SOURCE[markers[0].range.clone()], "oops");
+        assert!(diagnostic.inline_markers("Common", SOURCE).is_empty());
+    }
+
+    #[test]
+    fn test_error_from_text_and_wgpu() {
+        let diagnostic = Diagnostic::error(
+            "Missing uniform binding.\n\nRequired:\nvar<uniform> uniforms: Uniforms;",
+        );
+        assert_eq!(diagnostic.message, "Missing uniform binding.");
+        assert_eq!(
+            diagnostic.notes,
+            vec!["Required:\nvar<uniform> uniforms: Uniforms;".to_string()]
+        );
+
+        let diagnostic = Diagnostic::from_wgpu_message(
+            "wgpu error: Validation Error\n\nCaused by:\n  In Device::create_render_pipeline\n    Entry point fs_main not found\n",
+        );
+        assert_eq!(diagnostic.message, "Validation Error");
+        assert_eq!(diagnostic.notes.len(), 2);
+    }
+}
@@ -1,23 +1,66 @@
 use std::any::Any;
 use std::fmt;
 
+use crate::utils::diagnostics::Diagnostic;
+use crate::utils::BufferKind;
+
 /// Error type for shader compilation and pipeline errors
+///
+/// The variant records the stage that failed; the [`Diagnostic`] carries
+/// the message, source locations and notes.
 #[derive(Debug, Clone)]
 #[allow(clippy::enum_variant_names)]
 pub enum ShaderError {
-    CompilationError(String),
-    ValidationError(String),
-    DeviceError(String),
-    UnknownError(String),
+    CompilationError(Box<Diagnostic>),
+    ValidationError(Box<Diagnostic>),
+    DeviceError(Box<Diagnostic>),
+    UnknownError(Box<Diagnostic>),
+}
+
+impl ShaderError {
+    pub fn diagnostic(&self) -> &Diagnostic {
+        match self {
+            ShaderError::CompilationError(d)
+            | ShaderError::ValidationError(d)
+            | ShaderError::DeviceError(d)
+            | ShaderError::UnknownError(d) => d,
+        }
+    }
+
+    pub fn diagnostic_mut(&mut self) -> &mut Diagnostic {
+        match self {
+            ShaderError::CompilationError(d)
+            | ShaderError::ValidationError(d)
+            | ShaderError::DeviceError(d)
+            | ShaderError::UnknownError(d) => d,
+        }
+    }
+
+    /// Validation error from a plain message (see [`Diagnostic::error`])
+    pub fn validation(message: impl Into<String>) -> Self {
+        ShaderError::ValidationError(Box::new(Diagnostic::error(message)))
+    }
+
+    /// Compilation error from a plain message (see [`Diagnostic::error`])
+    pub fn compilation(message: impl Into<String>) -> Self {
+        ShaderError::CompilationError(Box::new(Diagnostic::error(message)))
+    }
+
+    /// Attribute the error to a buffer
+    pub fn in_buffer(mut self, buffer: BufferKind) -> Self {
+        self.diagnostic_mut().buffer = Some(buffer);
+        self
+    }
 }
 
 impl fmt::Display for ShaderError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let summary = self.diagnostic().summary();
         match self {
-            ShaderError::CompilationError(msg) => write!(f, "Shader compilation error: {}", msg),
-            ShaderError::ValidationError(msg) => write!(f, "Shader validation error: {}", msg),
-            ShaderError::DeviceError(msg) => write!(f, "Device error: {}", msg),
-            ShaderError::UnknownError(msg) => write!(f, "Unknown error: {}", msg),
+            ShaderError::CompilationError(_) => write!(f, "Shader compilation error: {}", summary),
+            ShaderError::ValidationError(_) => write!(f, "Shader validation error: {}", summary),
+            ShaderError::DeviceError(_) => write!(f, "Device error: {}", summary),
+            ShaderError::UnknownError(_) => write!(f, "Unknown error: {}", summary),
         }
     }
 }
@@ -26,13 +69,13 @@ impl std::error::Error for ShaderError {}
 
 impl From<String> for ShaderError {
     fn from(s: String) -> Self {
-        ShaderError::UnknownError(s)
+        ShaderError::UnknownError(Box::new(Diagnostic::error(s)))
     }
 }
 
 impl From<&str> for ShaderError {
     fn from(s: &str) -> Self {
-        ShaderError::UnknownError(s.to_string())
+        ShaderError::UnknownError(Box::new(Diagnostic::error(s)))
     }
 }
 
@@ -49,60 +92,18 @@ pub fn panic_to_string(e: Box<dyn Any + Send>) -> String {
     }
 }
 
-/// Parse WGSL error message to extract useful information
-#[allow(dead_code)]
-pub fn parse_wgsl_error(error_msg: &str) -> ShaderError {
-    // Check for common WGSL error patterns
-    if error_msg.contains("expected") || error_msg.contains("unexpected") {
-        ShaderError::CompilationError(error_msg.to_string())
-    } else if error_msg.contains("validation") {
-        ShaderError::ValidationError(error_msg.to_string())
-    } else if error_msg.contains("device") {
-        ShaderError::DeviceError(error_msg.to_string())
-    } else {
-        ShaderError::UnknownError(error_msg.to_string())
-    }
-}
-
-/// Format shader error for display with line numbers if available
+/// Format shader error for the error window and logs
+///
+/// One of several renderers of the underlying [`Diagnostic`]; see also
+/// [`Diagnostic::summary`], [`Diagnostic::to_json`] and
+/// [`Diagnostic::inline_markers`].
 pub fn format_shader_error(error: &ShaderError) -> String {
-    let msg = match error {
-        ShaderError::CompilationError(msg) => msg,
-        ShaderError::ValidationError(msg) => msg,
-        ShaderError::DeviceError(msg) => msg,
-        ShaderError::UnknownError(msg) => msg,
-    };
-    
-    // Clean up the error message by removing excessive whitespace and formatting nicely
-    let cleaned = msg
-        .lines()
-        .map(|line| line.trim())
-        .filter(|line| !line.is_empty())
-        .collect::<Vec<_>>()
-        .join("\n");
-    
-    // Add type prefix
     let prefix = match error {
         ShaderError::CompilationError(_) => "Compilation Error",
         ShaderError::ValidationError(_) => "Validation Error",
         ShaderError::DeviceError(_) => "Device Error",
         ShaderError::UnknownError(_) => "Shader Error",
     };
-    
-    format!("{}\n\n{}", prefix, cleaned)
-}
 
-/// Extract line information from error message (e.g., "line 42")
-#[allow(dead_code)]
-fn extract_line_info(msg: &str) -> Option<String> {
-    // Look for patterns like "line 42" or "42:5"
-    for word in msg.split_whitespace() {
-        if word.starts_with("line") {
-            return Some(word.to_string());
-        }
-        if word.contains(':') && word.chars().next()?.is_ascii_digit() {
-            return Some(format!("line {}", word));
-        }
-    }
-    None
+    format!("{}\n\n{}", prefix, error.diagnostic().render_text())
 }
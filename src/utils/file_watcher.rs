@@ -0,0 +1,197 @@
+//! Change detection for files the editor has loaded
+//!
+//! Loaded images, audio, included library files and a linked shard are polled
+//! by modification time a couple of times a second. A change is reported once
+//! the new time has been seen on two polls in a row, so a file is not reloaded
+//! while an external editor is still writing it.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Time between checks of the watched files
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What a watched file is loaded as, which decides how it is reloaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WatchTarget {
+    /// iChannel image, animation or image sequence
+    Channel(usize),
+    Audio,
+    /// Files pulled in by `//#include`
+    Include,
+    /// Shard JSON reloaded whenever it is saved
+    LinkedShard,
+}
+
+#[derive(Debug)]
+struct WatchedFile {
+    path: PathBuf,
+    /// Time of the last reported version; `None` while the file is missing
+    modified: Option<SystemTime>,
+    /// Time seen on the last poll that differed from `modified`
+    pending: Option<Option<SystemTime>>,
+}
+
+impl WatchedFile {
+    fn new(path: PathBuf) -> Self {
+        Self {
+            modified: modified(&path),
+            path,
+            pending: None,
+        }
+    }
+
+    /// Whether the file settled into a new version since it was last reported
+    fn changed(&mut self) -> bool {
+        let now = modified(&self.path);
+        if now == self.modified {
+            self.pending = None;
+            return false;
+        }
+        if self.pending == Some(now) {
+            self.modified = now;
+            self.pending = None;
+            return true;
+        }
+        self.pending = Some(now);
+        false
+    }
+}
+
+/// Files watched for each [`WatchTarget`]
+#[derive(Debug, Default)]
+pub struct FileWatcher {
+    targets: BTreeMap<WatchTarget, Vec<WatchedFile>>,
+    last_poll: Option<Instant>,
+}
+
+impl FileWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Watch `paths` for `target`, replacing what it watched before
+    ///
+    /// Paths watched already keep their state, so this is cheap to call every frame.
+    pub fn watch(&mut self, target: WatchTarget, paths: impl IntoIterator<Item = PathBuf>) {
+        let paths: Vec<PathBuf> = paths.into_iter().collect();
+        if paths.is_empty() {
+            self.targets.remove(&target);
+            return;
+        }
+        let files = self.targets.entry(target).or_default();
+        if files.iter().map(|file| &file.path).eq(paths.iter()) {
+            return;
+        }
+        let mut old = std::mem::take(files);
+        for path in paths {
+            match old.iter().position(|file| file.path == path) {
+                Some(index) => files.push(old.swap_remove(index)),
+                None => files.push(WatchedFile::new(path)),
+            }
+        }
+    }
+
+    /// Treat every watched file as up to date, e.g. after the editor rewrote them itself
+    pub fn mark_current(&mut self) {
+        for file in self.targets.values_mut().flatten() {
+            file.modified = modified(&file.path);
+            file.pending = None;
+        }
+    }
+
+    /// Targets with changed files, checking at most every [`POLL_INTERVAL`]
+    pub fn poll(&mut self) -> Vec<WatchTarget> {
+        if self.last_poll.is_some_and(|last| last.elapsed() < POLL_INTERVAL) {
+            return Vec::new();
+        }
+        self.last_poll = Some(Instant::now());
+        self.poll_now()
+    }
+
+    /// Targets with changed files
+    pub fn poll_now(&mut self) -> Vec<WatchTarget> {
+        let mut changed = Vec::new();
+        for (target, files) in &mut self.targets {
+            // Check every file so each one settles, even if an earlier one changed
+            let mut any = false;
+            for file in files.iter_mut() {
+                any |= file.changed();
+            }
+            if any {
+                changed.push(*target);
+            }
+        }
+        changed
+    }
+}
+
+/// Modification time of a file, or the newest among a directory and its entries
+fn modified(path: &Path) -> Option<SystemTime> {
+    let metadata = std::fs::metadata(path).ok()?;
+    let own = metadata.modified().ok();
+    if !metadata.is_dir() {
+        return own;
+    }
+    std::fs::read_dir(path)
+        .ok()?
+        .filter_map(|entry| entry.ok()?.metadata().ok()?.modified().ok())
+        .chain(own)
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_reported_once_settled() {
+        let dir = std::env::temp_dir().join(format!("webshard_watch_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let image = dir.join("image.png");
+        let include = dir.join("noise.wgsl");
+        std::fs::write(&image, "a").unwrap();
+        std::fs::write(&include, "a").unwrap();
+
+        let mut watcher = FileWatcher::new();
+        watcher.watch(WatchTarget::Channel(1), [image.clone()]);
+        watcher.watch(WatchTarget::Include, [include.clone(), dir.join("missing.wgsl")]);
+        assert!(watcher.poll_now().is_empty());
+
+        let touch = |path: &Path, seconds| {
+            let file = std::fs::File::options().write(true).open(path).unwrap();
+            file.set_modified(SystemTime::now() + Duration::from_secs(seconds)).unwrap();
+        };
+        touch(&image, 10);
+        assert!(watcher.poll_now().is_empty(), "reported before the write settled");
+        assert_eq!(watcher.poll_now(), vec![WatchTarget::Channel(1)]);
+        assert!(watcher.poll_now().is_empty());
+
+        // A file appearing counts as a change
+        std::fs::write(dir.join("missing.wgsl"), "b").unwrap();
+        touch(&include, 20);
+        watcher.poll_now();
+        assert_eq!(watcher.poll_now(), vec![WatchTarget::Include]);
+
+        // Re-watching the same paths keeps pending state; new paths start current
+        touch(&image, 30);
+        watcher.poll_now();
+        watcher.watch(WatchTarget::Channel(1), [image.clone()]);
+        assert_eq!(watcher.poll_now(), vec![WatchTarget::Channel(1)]);
+        touch(&image, 40);
+        watcher.watch(WatchTarget::Channel(1), [include.clone(), image.clone()]);
+        watcher.poll_now();
+        assert_eq!(watcher.poll_now(), vec![WatchTarget::Channel(1)]);
+
+        touch(&include, 50);
+        watcher.mark_current();
+        watcher.watch(WatchTarget::Channel(1), []);
+        assert!(!watcher.targets.contains_key(&WatchTarget::Channel(1)));
+        watcher.poll_now();
+        assert!(watcher.poll_now().is_empty());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
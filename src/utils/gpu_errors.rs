@@ -0,0 +1,115 @@
+//! wgpu error capture and device-loss tracking
+//!
+//! wgpu reports invalid pipelines, binding mismatches or exceeded limits to
+//! the device's uncaptured error handler, which panics by default. Pipeline
+//! creation instead runs inside error scopes so each failure becomes a
+//! [`ShaderError`] naming the pass, and [`DeviceMonitor`] replaces the
+//! panicking handler and records when the device is lost.
+//!
+//! eframe creates the wgpu device once and never replaces it, so a lost device
+//! can't be swapped for a new one in-process. The editor recovers by saving
+//! the session and relaunching with [`RECOVERY_ENV`] pointing at it: the new
+//! process gets a fresh device and rebuilds every pipeline and texture.
+//!
+//! Only what a shard stores survives the relaunch. Live cameras, the audio
+//! file, playback time and the contents of feedback buffers are not kept.
+
+use eframe::wgpu::{Device, Error, ErrorFilter};
+use std::sync::{Arc, Mutex};
+
+use crate::utils::diagnostics::Diagnostic;
+use crate::utils::{BufferKind, ShaderError};
+
+/// Run `f` inside validation, out-of-memory and internal error scopes
+///
+/// Returns the value of `f`, or the first error wgpu raised while it ran,
+/// attributed to `pass` when given.
+pub fn capture_errors<T>(device: &Device, pass: Option<BufferKind>, f: impl FnOnce() -> T) -> Result<T, ShaderError> {
+    device.push_error_scope(ErrorFilter::Internal);
+    device.push_error_scope(ErrorFilter::OutOfMemory);
+    device.push_error_scope(ErrorFilter::Validation);
+
+    let value = f();
+
+    // Pop every scope even after finding an error, or later scopes would leak
+    let errors = [
+        pollster::block_on(device.pop_error_scope()),
+        pollster::block_on(device.pop_error_scope()),
+        pollster::block_on(device.pop_error_scope()),
+    ];
+
+    match errors.into_iter().flatten().next() {
+        None => Ok(value),
+        Some(error) => {
+            let mut diagnostic = Diagnostic::from_wgpu_error(&error);
+            diagnostic.buffer = pass;
+            log::error!("wgpu error while creating {}: {}", pass.map_or("shared resources", |p| p.as_str()), error);
+            Err(match error {
+                Error::OutOfMemory { .. } | Error::Internal { .. } => ShaderError::DeviceError(Box::new(diagnostic)),
+                Error::Validation { .. } => ShaderError::CompilationError(Box::new(diagnostic)),
+            })
+        }
+    }
+}
+
+/// Environment variable naming a shard to restore on startup, set when the
+/// editor relaunches itself after losing its GPU device
+pub const RECOVERY_ENV: &str = "WEBSHARD_RECOVER_SHARD";
+
+/// Start a new editor process that restores the shard at `session`
+///
+/// The caller closes its own window afterwards; the new process creates its
+/// own device, so everything built on the lost one is recreated.
+pub fn relaunch_with_session(session: &std::path::Path) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Cannot find the editor executable: {}", e))?;
+    std::process::Command::new(exe)
+        .args(std::env::args_os().skip(1))
+        .env(RECOVERY_ENV, session)
+        .spawn()
+        .map_err(|e| format!("Failed to restart the editor: {}", e))?;
+    Ok(())
+}
+
+/// Uncaptured errors and device loss reported by one wgpu device
+pub struct DeviceMonitor {
+    lost: Arc<Mutex<Option<String>>>,
+    uncaptured: Arc<Mutex<Vec<String>>>,
+}
+
+impl DeviceMonitor {
+    /// Install handlers on `device`
+    ///
+    /// Errors outside an error scope (e.g. while drawing) are logged and kept
+    /// for the UI instead of panicking.
+    pub fn install(device: &Device) -> Self {
+        let lost = Arc::new(Mutex::new(None));
+        let uncaptured = Arc::new(Mutex::new(Vec::new()));
+
+        let errors = uncaptured.clone();
+        device.on_uncaptured_error(Arc::new(move |error: Error| {
+            log::error!("Uncaptured wgpu error: {}", error);
+            errors.lock().unwrap().push(error.to_string());
+        }));
+
+        let lost_reason = lost.clone();
+        device.set_device_lost_callback(move |reason, message| {
+            log::error!("GPU device lost ({:?}): {}", reason, message);
+            *lost_reason.lock().unwrap() = Some(format!("{:?}: {}", reason, message));
+        });
+
+        Self {
+            lost,
+            uncaptured,
+        }
+    }
+
+    /// Reason the device was lost, if it was
+    pub fn lost_reason(&self) -> Option<String> {
+        self.lost.lock().unwrap().clone()
+    }
+
+    /// Errors raised outside error scopes since the last call
+    pub fn take_uncaptured(&self) -> Vec<String> {
+        std::mem::take(&mut *self.uncaptured.lock().unwrap())
+    }
+}
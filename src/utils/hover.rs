@@ -0,0 +1,593 @@
+//! Hover documentation and signature help for the shader editor
+//!
+//! Maps a character position in a buffer to the symbol under it and resolves
+//! a description for it:
+//! - WGSL builtins use the signature and docs from `data/wgsl_builtins.json`
+//! - User symbols use the types naga resolves for the buffer (with the
+//!   boilerplate prepended), falling back to the declaration text when the
+//!   buffer does not currently parse
+
+use naga::common::wgsl::TypeContext;
+use std::ops::Range;
+
+use crate::utils::completion::{self, CompletionItem};
+use crate::utils::shader_constants::{SHADER_BOILERPLATE, TEXTURE_BINDINGS};
+use crate::utils::wgsl_syntax;
+
+/// Tooltip content for a hovered identifier
+#[derive(Debug, Clone, PartialEq)]
+pub struct HoverInfo {
+    /// Signature or declared type, rendered in monospace
+    pub signature: String,
+    /// Optional documentation line
+    pub doc: String,
+}
+
+/// Signature help for the call surrounding the caret
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureHelp {
+    pub name: String,
+    pub params: Vec<String>,
+    /// Return type including the arrow, or empty
+    pub ret: String,
+    /// Index of the parameter the caret is in
+    pub active: usize,
+    pub doc: String,
+}
+
+/// A buffer parsed and validated by naga, with the boilerplate prepended
+///
+/// Parsing is too slow to repeat every frame, so the editor keeps the last
+/// analysis around and only rebuilds it when the code changes.
+pub struct AnalyzedSource {
+    /// Byte length of the prepended boilerplate
+    offset: usize,
+    module: Option<naga::Module>,
+    info: Option<naga::valid::ModuleInfo>,
+}
+
+impl AnalyzedSource {
+    /// Parse and validate `code` as it would be compiled by the editor
+    ///
+    /// `prelude` is code injected between the boilerplate and `code` (the
+    /// Common tab), empty when analyzing Common itself.
+    pub fn new(prelude: &str, code: &str) -> Self {
+        let prefix = format!("{}{}\n{}\n", SHADER_BOILERPLATE, TEXTURE_BINDINGS, prelude);
+        let full = format!("{}{}", prefix, code);
+
+        let module = naga::front::wgsl::parse_str(&full).ok();
+        let info = module.as_ref().and_then(|module| {
+            naga::valid::Validator::new(
+                naga::valid::ValidationFlags::all(),
+                naga::valid::Capabilities::all(),
+            )
+            .validate(module)
+            .ok()
+        });
+
+        Self {
+            offset: prefix.len(),
+            module,
+            info,
+        }
+    }
+
+    /// Describe `name` as seen from byte offset `at` of the user code
+    fn describe(&self, code: &str, name: &str, at: usize) -> Option<HoverInfo> {
+        let module = self.module.as_ref()?;
+        let ctx = module.to_ctx();
+
+        // Locals, arguments and `let` bindings of the enclosing function
+        if let Some(fn_name) = enclosing_function(code, at) {
+            let function = module
+                .functions
+                .iter()
+                .find(|(_, f)| f.name.as_deref() == Some(fn_name.as_str()))
+                .map(|(handle, f)| (f, self.info.as_ref().map(|info| &info[handle])))
+                .or_else(|| {
+                    module
+                        .entry_points
+                        .iter()
+                        .enumerate()
+                        .find(|(_, ep)| ep.name == fn_name)
+                        .map(|(i, ep)| {
+                            (
+                                &ep.function,
+                                self.info.as_ref().map(|info| info.get_entry_point(i)),
+                            )
+                        })
+                });
+
+            if let Some((function, fn_info)) = function {
+                if let Some(info) = self.describe_local(&ctx, function, fn_info, name, at) {
+                    return Some(info);
+                }
+            }
+        }
+
+        // Module-scope declarations
+        if let Some((_, var)) = module
+            .global_variables
+            .iter()
+            .find(|(_, v)| v.name.as_deref() == Some(name))
+        {
+            let space = match var.space {
+                naga::AddressSpace::Uniform => "<uniform>",
+                naga::AddressSpace::Storage { .. } => "<storage>",
+                naga::AddressSpace::Private => "<private>",
+                naga::AddressSpace::WorkGroup => "<workgroup>",
+                _ => "",
+            };
+            return Some(HoverInfo {
+                signature: format!("var{} {}: {}", space, name, ctx.type_to_string(var.ty)),
+                doc: String::new(),
+            });
+        }
+
+        if let Some((_, constant)) = module
+            .constants
+            .iter()
+            .find(|(_, c)| c.name.as_deref() == Some(name))
+        {
+            return Some(HoverInfo {
+                signature: format!("const {}: {}", name, ctx.type_to_string(constant.ty)),
+                doc: String::new(),
+            });
+        }
+
+        if let Some((_, function)) = module
+            .functions
+            .iter()
+            .find(|(_, f)| f.name.as_deref() == Some(name))
+        {
+            let args = function
+                .arguments
+                .iter()
+                .map(|a| {
+                    format!(
+                        "{}: {}",
+                        a.name.as_deref().unwrap_or("_"),
+                        ctx.type_to_string(a.ty)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            let ret = function
+                .result
+                .as_ref()
+                .map(|r| format!(" -> {}", ctx.type_to_string(r.ty)))
+                .unwrap_or_default();
+            return Some(HoverInfo {
+                signature: format!("fn {}({}){}", name, args, ret),
+                doc: String::new(),
+            });
+        }
+
+        if let Some((_, ty)) = module
+            .types
+            .iter()
+            .find(|(_, t)| t.name.as_deref() == Some(name))
+        {
+            if let naga::TypeInner::Struct { members, .. } = &ty.inner {
+                let fields = members
+                    .iter()
+                    .map(|m| {
+                        format!(
+                            "    {}: {},",
+                            m.name.as_deref().unwrap_or("_"),
+                            ctx.type_to_string(m.ty)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                return Some(HoverInfo {
+                    signature: format!("struct {} {{\n{}\n}}", name, fields),
+                    doc: String::new(),
+                });
+            }
+        }
+
+        None
+    }
+
+    fn describe_local(
+        &self,
+        ctx: &naga::proc::GlobalCtx,
+        function: &naga::Function,
+        fn_info: Option<&naga::valid::FunctionInfo>,
+        name: &str,
+        at: usize,
+    ) -> Option<HoverInfo> {
+        let at = at + self.offset;
+
+        // Pick the closest declaration before the hover position so shadowing works
+        let named = function
+            .named_expressions
+            .iter()
+            .filter(|(_, n)| n.as_str() == name)
+            .map(|(handle, _)| {
+                (
+                    *handle,
+                    declaration_start(function.expressions.get_span(*handle)),
+                )
+            })
+            .filter(|(_, start)| *start <= at)
+            .max_by_key(|(_, start)| *start);
+
+        let local = function
+            .local_variables
+            .iter()
+            .filter(|(_, v)| v.name.as_deref() == Some(name))
+            .map(|(handle, v)| {
+                (
+                    v,
+                    declaration_start(function.local_variables.get_span(handle)),
+                )
+            })
+            .filter(|(_, start)| *start <= at)
+            .max_by_key(|(_, start)| *start);
+
+        match (named, local) {
+            (Some((expr, expr_start)), local)
+                if local.is_none_or(|(_, s)| expr_start >= s)
+                    && !matches!(
+                        function.expressions[expr],
+                        naga::Expression::FunctionArgument(_)
+                    ) =>
+            {
+                let ty = fn_info.map(|info| ctx.type_resolution_to_string(&info[expr].ty))?;
+                return Some(HoverInfo {
+                    signature: format!("let {}: {}", name, ty),
+                    doc: String::new(),
+                });
+            }
+            (_, Some((var, _))) => {
+                return Some(HoverInfo {
+                    signature: format!("var {}: {}", name, ctx.type_to_string(var.ty)),
+                    doc: String::new(),
+                });
+            }
+            _ => {}
+        }
+
+        function
+            .arguments
+            .iter()
+            .find(|a| a.name.as_deref() == Some(name))
+            .map(|a| HoverInfo {
+                signature: format!("{}: {}", name, ctx.type_to_string(a.ty)),
+                doc: "Function parameter".to_string(),
+            })
+    }
+}
+
+fn declaration_start(span: naga::Span) -> usize {
+    span.to_range().map(|r| r.start).unwrap_or(0)
+}
+
+/// Find the identifier (or `@attribute`) under the char position
+///
+/// Returns its char range and text.
+pub fn word_at(source: &str, char_index: usize) -> Option<(Range<usize>, String)> {
+    let chars: Vec<char> = source.chars().collect();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+    let mut start = char_index.min(chars.len());
+    while start > 0 && is_word(chars[start - 1]) {
+        start -= 1;
+    }
+    let mut end = char_index.min(chars.len());
+    while end < chars.len() && is_word(chars[end]) {
+        end += 1;
+    }
+    if start > 0 && chars[start - 1] == '@' {
+        start -= 1;
+    }
+    if start == end || chars[start].is_ascii_digit() {
+        return None;
+    }
+    Some((start..end, chars[start..end].iter().collect()))
+}
+
+/// Resolve hover information for the identifier at `char_index`
+pub fn hover_info(code: &str, char_index: usize, analysis: &AnalyzedSource) -> Option<HoverInfo> {
+    let (range, word) = word_at(code, char_index)?;
+    let byte_at = code
+        .char_indices()
+        .nth(range.start)
+        .map(|(b, _)| b)
+        .unwrap_or(code.len());
+
+    // Struct members: `uniforms.time` shows the field with its type
+    if range.start > 0 && code.chars().nth(range.start - 1) == Some('.') {
+        return member_info(code, range.start - 1, &word);
+    }
+
+    if let Some(info) = analysis.describe(code, &word, byte_at) {
+        return Some(info);
+    }
+
+    // The buffer doesn't parse right now: fall back to the declaration text
+    let declared = completion::scan_declarations(code);
+    let boilerplate = completion::boilerplate_symbols();
+    if let Some(item) = declared
+        .items
+        .iter()
+        .chain(boilerplate.items.iter())
+        .find(|i| i.label == word)
+    {
+        return Some(item_info(item));
+    }
+
+    if let Some(item) = completion::builtins().iter().find(|b| b.label == word) {
+        return Some(item_info(item));
+    }
+
+    if wgsl_syntax::KEYWORDS.contains(&word.as_str()) {
+        return Some(HoverInfo {
+            signature: word,
+            doc: "WGSL keyword".to_string(),
+        });
+    }
+    if wgsl_syntax::TYPES.contains(&word.as_str()) {
+        return Some(HoverInfo {
+            signature: word,
+            doc: "WGSL built-in type".to_string(),
+        });
+    }
+
+    None
+}
+
+fn item_info(item: &CompletionItem) -> HoverInfo {
+    HoverInfo {
+        signature: item.detail.clone(),
+        doc: item.doc.clone(),
+    }
+}
+
+fn member_info(code: &str, dot_char: usize, field: &str) -> Option<HoverInfo> {
+    // Reuse member completion with the full field name as prefix
+    let before: String = code.chars().take(dot_char + 1).collect();
+    let probe = format!("{}{}", before, field);
+    completion::completions(&probe, probe.chars().count())
+        .into_iter()
+        .find(|item| item.label == field)
+        .map(|item| item_info(&item))
+}
+
+/// Compute signature help for the innermost call containing the caret
+pub fn signature_help(code: &str, caret_char: usize) -> Option<SignatureHelp> {
+    let chars: Vec<char> = code.chars().take(caret_char).collect();
+
+    // Walk back to the unmatched '(' counting top-level commas
+    let mut depth = 0usize;
+    let mut commas = 0usize;
+    let mut open = None;
+    for i in (0..chars.len()).rev() {
+        match chars[i] {
+            ')' => depth += 1,
+            '(' if depth == 0 => {
+                open = Some(i);
+                break;
+            }
+            '(' => depth -= 1,
+            ',' if depth == 0 => commas += 1,
+            ';' | '{' | '}' => return None,
+            _ => {}
+        }
+    }
+    let open = open?;
+
+    // Callee identifier before '(' (skipping an explicit template like bitcast<u32>)
+    let mut end = open;
+    while end > 0 && chars[end - 1].is_whitespace() {
+        end -= 1;
+    }
+    if end > 0 && chars[end - 1] == '>' {
+        while end > 0 && chars[end - 1] != '<' {
+            end -= 1;
+        }
+        end = end.saturating_sub(1);
+    }
+    let mut start = end;
+    while start > 0 && (chars[start - 1].is_alphanumeric() || chars[start - 1] == '_') {
+        start -= 1;
+    }
+    if start == end {
+        return None;
+    }
+    let callee: String = chars[start..end].iter().collect();
+
+    let declared = completion::scan_declarations(code);
+    let item = declared
+        .items
+        .iter()
+        .chain(completion::boilerplate_symbols().items.iter())
+        .chain(completion::builtins().iter())
+        .find(|i| i.label == callee && i.kind == completion::CompletionKind::Function)?;
+
+    let (params, ret) = parse_signature(&item.detail)?;
+    Some(SignatureHelp {
+        name: callee,
+        active: commas.min(params.len().saturating_sub(1)),
+        params,
+        ret,
+        doc: item.doc.clone(),
+    })
+}
+
+/// Split `fn name(a: T, b: vec2<f32>) -> R` into parameters and return type
+pub fn parse_signature(detail: &str) -> Option<(Vec<String>, String)> {
+    let open = detail.find('(')?;
+    let mut depth = 0usize;
+    let mut close = None;
+    for (i, c) in detail[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(open + i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let close = close?;
+
+    let mut params = Vec::new();
+    let mut current = String::new();
+    let mut nesting = 0i32;
+    for c in detail[open + 1..close].chars() {
+        match c {
+            '<' | '(' => nesting += 1,
+            '>' | ')' => nesting -= 1,
+            ',' if nesting == 0 => {
+                params.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    if !current.trim().is_empty() {
+        params.push(current.trim().to_string());
+    }
+
+    Some((params, detail[close + 1..].trim().to_string()))
+}
+
+/// Name of the function whose body contains byte offset `at`
+fn enclosing_function(code: &str, at: usize) -> Option<String> {
+    let bytes = code.as_bytes();
+    let mut search_end = at.min(code.len());
+
+    // Walk back through `fn` declarations and check their brace-matched body
+    while let Some(fn_pos) = code[..search_end].rfind("fn ") {
+        let is_word_start = fn_pos == 0
+            || !(bytes[fn_pos - 1].is_ascii_alphanumeric() || bytes[fn_pos - 1] == b'_');
+        if is_word_start {
+            let rest = &code[fn_pos + 3..];
+            let name: String = rest
+                .trim_start()
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .collect();
+            if let Some(body_start) = code[fn_pos..].find('{').map(|i| fn_pos + i) {
+                let mut depth = 0usize;
+                let mut body_end = code.len();
+                for (i, b) in bytes.iter().enumerate().skip(body_start) {
+                    match b {
+                        b'{' => depth += 1,
+                        b'}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                body_end = i;
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                if at >= fn_pos && at <= body_end && !name.is_empty() {
+                    return Some(name);
+                }
+            }
+        }
+        search_end = fn_pos;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hover(code: &str, needle: &str) -> Option<HoverInfo> {
+        let byte = code.find(needle).unwrap();
+        let char_index = code[..byte].chars().count();
+        hover_info(code, char_index, &AnalyzedSource::new("", code))
+    }
+
+    #[test]
+    fn test_word_at() {
+        assert_eq!(word_at("let abc = 1;", 5), Some((4..7, "abc".to_string())));
+        assert_eq!(
+            word_at("@location(0)", 3),
+            Some((0..9, "@location".to_string()))
+        );
+        assert_eq!(word_at("a + 1.0", 5), None);
+    }
+
+    #[test]
+    fn test_hover_builtin() {
+        let code = "fn f() -> f32 { return smoothstep(0.0, 1.0, 0.5); }";
+        let info = hover(code, "smoothstep").unwrap();
+        assert!(info.signature.starts_with("fn smoothstep("));
+        assert!(!info.doc.is_empty());
+    }
+
+    #[test]
+    fn test_hover_let_type_from_naga() {
+        let code =
+            "fn f() -> f32 {\n    let col = vec3<f32>(1.0) * uniforms.time;\n    return col.x;\n}";
+        let info = hover(code, "col.x").unwrap();
+        assert_eq!(info.signature, "let col: vec3<f32>");
+    }
+
+    #[test]
+    fn test_hover_user_function_and_global() {
+        let code = "fn hash(p: vec2<f32>) -> f32 { return p.x; }\nfn g() -> f32 { return hash(vec2<f32>(0.0)); }";
+        let info = hover(code, "hash(vec2").unwrap();
+        assert_eq!(info.signature, "fn hash(p: vec2<f32>) -> f32");
+
+        let info = hover(code, "p.x").unwrap();
+        assert_eq!(info.signature, "p: vec2<f32>");
+
+        let info = hover("fn g() { let t = iChannel0; }", "iChannel0").unwrap();
+        assert_eq!(info.signature, "var iChannel0: texture_2d<f32>");
+    }
+
+    #[test]
+    fn test_hover_uniform_member() {
+        let info = hover(
+            "fn g() -> f32 { return uniforms.audio_bass; }",
+            "audio_bass",
+        )
+        .unwrap();
+        assert_eq!(info.signature, "uniforms.audio_bass: f32");
+    }
+
+    #[test]
+    fn test_hover_falls_back_when_invalid() {
+        let code = "fn hash(p: vec2<f32>) -> f32 { return p.x }\nfn g() { hash(";
+        let info = hover(code, "hash(p").unwrap();
+        assert_eq!(info.signature, "fn hash(p: vec2<f32>) -> f32");
+    }
+
+    #[test]
+    fn test_signature_help_active_param() {
+        let code = "let a = clamp(x, 0.0, ";
+        let help = signature_help(code, code.chars().count()).unwrap();
+        assert_eq!(help.name, "clamp");
+        assert_eq!(help.params, vec!["e: T", "low: T", "high: T"]);
+        assert_eq!(help.active, 2);
+
+        let code = "let a = mix(foo(1, 2), ";
+        let help = signature_help(code, code.chars().count()).unwrap();
+        assert_eq!(help.name, "mix");
+        assert_eq!(help.active, 1);
+
+        assert!(signature_help("let a = 1; b", 12).is_none());
+    }
+
+    #[test]
+    fn test_parse_signature_nested_types() {
+        let (params, ret) = parse_signature("fn f(a: vec2<f32>, b: array<f32, 4>) -> f32").unwrap();
+        assert_eq!(params, vec!["a: vec2<f32>", "b: array<f32, 4>"]);
+        assert_eq!(ret, "-> f32");
+    }
+}
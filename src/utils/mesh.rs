@@ -0,0 +1,607 @@
+//! Triangle meshes for mesh passes, loaded from OBJ, glTF or GLB
+//!
+//! Every format becomes the same indexed [`MeshVertex`] list, uploaded as one
+//! interleaved vertex buffer:
+//!
+//! | `@location` | Attribute  | Type          |
+//! |-------------|------------|---------------|
+//! | 0           | `position` | `vec3<f32>`   |
+//! | 1           | `normal`   | `vec3<f32>`   |
+//! | 2           | `uv`       | `vec2<f32>`   |
+//!
+//! UVs have (0, 0) at the top-left like texture coordinates (OBJ's are
+//! flipped on load). Missing normals are computed from the faces. glTF node
+//! transforms of the default scene are applied; materials, skins, morph
+//! targets and sparse accessors are not supported.
+
+use bytemuck::{Pod, Zeroable};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::utils::camera::{self, Mat4};
+
+/// One vertex of a mesh, as laid out in the vertex buffer
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Pod, Zeroable)]
+pub struct MeshVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+impl MeshVertex {
+    const ATTRIBUTES: [eframe::wgpu::VertexAttribute; 3] =
+        eframe::wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2];
+
+    pub fn layout() -> eframe::wgpu::VertexBufferLayout<'static> {
+        eframe::wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<MeshVertex>() as u64,
+            step_mode: eframe::wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// An indexed triangle list
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mesh {
+    /// File the mesh was loaded from
+    pub source: String,
+    pub vertices: Vec<MeshVertex>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Axis-aligned bounding box (min, max)
+    pub fn bounds(&self) -> ([f32; 3], [f32; 3]) {
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for vertex in &self.vertices {
+            for i in 0..3 {
+                min[i] = min[i].min(vertex.position[i]);
+                max[i] = max[i].max(vertex.position[i]);
+            }
+        }
+        if self.vertices.is_empty() {
+            ([0.0; 3], [0.0; 3])
+        } else {
+            (min, max)
+        }
+    }
+}
+
+/// Load a mesh, choosing the format by extension (`.obj`, `.gltf`, `.glb`)
+pub fn load_mesh(path: &str) -> Result<Mesh, String> {
+    log::info!("Loading mesh from: {}", path);
+    let bytes = std::fs::read(path).map_err(|e| format!("Failed to read mesh {}: {}", path, e))?;
+    let base_dir = Path::new(path).parent().unwrap_or(Path::new("."));
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    let (vertices, indices) = match extension.as_str() {
+        "obj" => parse_obj(&String::from_utf8_lossy(&bytes))?,
+        "gltf" => parse_gltf(&bytes, None, base_dir)?,
+        "glb" => parse_glb(&bytes, base_dir)?,
+        other => return Err(format!("Unsupported mesh format `.{}` (use .obj, .gltf or .glb)", other)),
+    };
+    if indices.is_empty() {
+        return Err(format!("{} has no triangles", path));
+    }
+
+    let mesh = Mesh {
+        source: path.to_string(),
+        vertices,
+        indices,
+    };
+    log::info!("Mesh loaded: {} vertices, {} triangles", mesh.vertices.len(), mesh.triangle_count());
+    Ok(mesh)
+}
+
+type Geometry = (Vec<MeshVertex>, Vec<u32>);
+
+/// Parse Wavefront OBJ: `v`, `vt`, `vn` and polygonal `f` (fan-triangulated)
+pub fn parse_obj(text: &str) -> Result<Geometry, String> {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut missing_normals = Vec::new();
+    let mut seen: HashMap<(usize, Option<usize>, Option<usize>), u32> = HashMap::new();
+
+    for (line_number, line) in text.lines().enumerate() {
+        let error = |message: String| format!("OBJ line {}: {}", line_number + 1, message);
+        let mut parts = line.split_whitespace();
+        let Some(keyword) = parts.next() else {
+            continue;
+        };
+        let mut floats = || -> Result<Vec<f32>, String> {
+            parts
+                .by_ref()
+                .map(|part| part.parse::<f32>().map_err(|_| error(format!("invalid number `{}`", part))))
+                .collect()
+        };
+
+        match keyword {
+            "v" => {
+                let v = floats()?;
+                positions.push(read_components(&v).map_err(error)?);
+            }
+            "vt" => {
+                let v = floats()?;
+                let [u, v] = read_components(&v).map_err(error)?;
+                uvs.push([u, 1.0 - v]);
+            }
+            "vn" => {
+                let v = floats()?;
+                normals.push(read_components(&v).map_err(error)?);
+            }
+            "f" => {
+                let mut face = Vec::new();
+                for corner in parts {
+                    let mut fields = corner.split('/');
+                    let mut index = |len: usize| -> Result<Option<usize>, String> {
+                        match fields.next().filter(|f| !f.is_empty()) {
+                            None => Ok(None),
+                            Some(field) => obj_index(field, len).map(Some).map_err(error),
+                        }
+                    };
+                    let position = index(positions.len())?.ok_or_else(|| error("face corner without a vertex".into()))?;
+                    let uv = index(uvs.len())?;
+                    let normal = index(normals.len())?;
+
+                    let key = (position, uv, normal);
+                    let vertex = *seen.entry(key).or_insert_with(|| {
+                        if normal.is_none() {
+                            missing_normals.push(vertices.len());
+                        }
+                        vertices.push(MeshVertex {
+                            position: positions[position],
+                            normal: normal.map(|n| normals[n]).unwrap_or_default(),
+                            uv: uv.map(|t| uvs[t]).unwrap_or_default(),
+                        });
+                        vertices.len() as u32 - 1
+                    });
+                    face.push(vertex);
+                }
+                if face.len() < 3 {
+                    return Err(error("face with fewer than 3 vertices".into()));
+                }
+                for i in 1..face.len() - 1 {
+                    indices.extend_from_slice(&[face[0], face[i], face[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !missing_normals.is_empty() {
+        compute_normals(&mut vertices, &indices, &missing_normals);
+    }
+    Ok((vertices, indices))
+}
+
+fn read_components<const N: usize>(values: &[f32]) -> Result<[f32; N], String> {
+    values
+        .get(..N)
+        .and_then(|values| values.try_into().ok())
+        .ok_or_else(|| format!("expected {} numbers", N))
+}
+
+/// OBJ indices are 1-based, or relative to the end when negative
+fn obj_index(field: &str, len: usize) -> Result<usize, String> {
+    let index: i64 = field.parse().map_err(|_| format!("invalid index `{}`", field))?;
+    let resolved = if index < 0 { len as i64 + index } else { index - 1 };
+    if (0..len as i64).contains(&resolved) {
+        Ok(resolved as usize)
+    } else {
+        Err(format!("index {} out of range", index))
+    }
+}
+
+/// Area-weighted smooth normals for the vertices listed in `targets`
+fn compute_normals(vertices: &mut [MeshVertex], indices: &[u32], targets: &[usize]) {
+    let mut accumulated = vec![[0.0f32; 3]; vertices.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize].position);
+        let edge = |p: [f32; 3], q: [f32; 3]| [q[0] - p[0], q[1] - p[1], q[2] - p[2]];
+        let face = camera::cross(edge(a, b), edge(a, c));
+        for &index in triangle {
+            for (sum, component) in accumulated[index as usize].iter_mut().zip(face) {
+                *sum += component;
+            }
+        }
+    }
+    for &target in targets {
+        vertices[target].normal = camera::normalize(accumulated[target]);
+    }
+}
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_JSON: u32 = 0x4E4F_534A;
+const GLB_BIN: u32 = 0x004E_4942;
+
+/// Parse a binary glTF: a JSON chunk and an optional BIN chunk
+pub fn parse_glb(bytes: &[u8], base_dir: &Path) -> Result<Geometry, String> {
+    if bytes.len() < 12 || &bytes[..4] != GLB_MAGIC {
+        return Err("Not a GLB file".to_string());
+    }
+    let u32_at = |at: usize| -> Result<u32, String> {
+        bytes
+            .get(at..at + 4)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+            .ok_or_else(|| "Truncated GLB file".to_string())
+    };
+    let version = u32_at(4)?;
+    if version != 2 {
+        return Err(format!("Unsupported GLB version {}", version));
+    }
+
+    let mut json = None;
+    let mut bin = None;
+    let mut at = 12;
+    while at + 8 <= bytes.len() {
+        let length = u32_at(at)? as usize;
+        let kind = u32_at(at + 4)?;
+        let end = (at + 8).checked_add(length).ok_or("Truncated GLB chunk")?;
+        let chunk = bytes.get(at + 8..end).ok_or_else(|| "Truncated GLB chunk".to_string())?;
+        match kind {
+            GLB_JSON => json = Some(chunk),
+            GLB_BIN if bin.is_none() => bin = Some(chunk),
+            _ => {}
+        }
+        at = end;
+    }
+    parse_gltf(json.ok_or("GLB file has no JSON chunk")?, bin, base_dir)
+}
+
+/// Parse glTF JSON; buffers are data URIs, files next to it, or the GLB `bin` chunk
+pub fn parse_gltf(json: &[u8], bin: Option<&[u8]>, base_dir: &Path) -> Result<Geometry, String> {
+    let gltf: serde_json::Value = serde_json::from_slice(json).map_err(|e| format!("Invalid glTF JSON: {}", e))?;
+
+    let buffers = gltf["buffers"]
+        .as_array()
+        .map(|buffers| buffers.iter().map(|buffer| load_buffer(buffer, bin, base_dir)).collect())
+        .unwrap_or_else(|| Ok(Vec::new()))?;
+    let reader = AccessorReader { gltf: &gltf, buffers };
+
+    // Meshes as placed by the default scene's nodes, or every mesh untransformed
+    let mut instances = Vec::new();
+    let scene = gltf["scene"].as_u64().unwrap_or(0) as usize;
+    match gltf["scenes"][scene]["nodes"].as_array() {
+        Some(roots) => {
+            for root in roots {
+                collect_nodes(&gltf, index(root)?, camera::IDENTITY, &mut instances, 0)?;
+            }
+        }
+        None => {
+            let count = gltf["meshes"].as_array().map_or(0, |meshes| meshes.len());
+            instances.extend((0..count).map(|mesh| (mesh, camera::IDENTITY)));
+        }
+    }
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    for (mesh, transform) in instances {
+        let primitives = gltf["meshes"][mesh]["primitives"]
+            .as_array()
+            .ok_or_else(|| format!("glTF mesh {} has no primitives", mesh))?;
+        for primitive in primitives {
+            let mode = primitive["mode"].as_u64().unwrap_or(4);
+            if mode != 4 {
+                log::warn!("Skipping glTF primitive with mode {} (only triangles are supported)", mode);
+                continue;
+            }
+            let attributes = &primitive["attributes"];
+            let positions = reader.read(index(&attributes["POSITION"])?, 3)?;
+            let normals = match attributes.get("NORMAL") {
+                Some(accessor) => Some(reader.read(index(accessor)?, 3)?),
+                None => None,
+            };
+            let uvs = match attributes.get("TEXCOORD_0") {
+                Some(accessor) => Some(reader.read(index(accessor)?, 2)?),
+                None => None,
+            };
+
+            let base = vertices.len();
+            for (i, position) in positions.chunks_exact(3).enumerate() {
+                let normal = normals
+                    .as_ref()
+                    .and_then(|normals| normals.get(i * 3..i * 3 + 3))
+                    .map(|n| camera::normalize(camera::transform_vector(&transform, [n[0], n[1], n[2]])))
+                    .unwrap_or_default();
+                let uv = uvs
+                    .as_ref()
+                    .and_then(|uvs| uvs.get(i * 2..i * 2 + 2))
+                    .map(|t| [t[0], t[1]])
+                    .unwrap_or_default();
+                vertices.push(MeshVertex {
+                    position: camera::transform_point(&transform, [position[0], position[1], position[2]]),
+                    normal,
+                    uv,
+                });
+            }
+
+            let count = vertices.len() - base;
+            let primitive_indices: Vec<u32> = match primitive.get("indices") {
+                Some(accessor) => reader.read(index(accessor)?, 1)?.into_iter().map(|i| i as u32).collect(),
+                None => (0..count as u32).collect(),
+            };
+            if let Some(bad) = primitive_indices.iter().find(|&&i| i as usize >= count) {
+                return Err(format!("glTF index {} out of range ({} vertices)", bad, count));
+            }
+            let start = indices.len();
+            indices.extend(primitive_indices.iter().map(|i| i + base as u32));
+            if normals.is_none() {
+                let targets: Vec<usize> = (base..vertices.len()).collect();
+                compute_normals(&mut vertices, &indices[start..], &targets);
+            }
+        }
+    }
+    Ok((vertices, indices))
+}
+
+fn index(value: &serde_json::Value) -> Result<usize, String> {
+    value
+        .as_u64()
+        .map(|i| i as usize)
+        .ok_or_else(|| format!("Expected a glTF index, found {}", value))
+}
+
+/// Walk a node and its children, collecting (mesh, world transform)
+fn collect_nodes(
+    gltf: &serde_json::Value,
+    node_index: usize,
+    parent: Mat4,
+    out: &mut Vec<(usize, Mat4)>,
+    depth: usize,
+) -> Result<(), String> {
+    if depth > 64 {
+        return Err("glTF node hierarchy is too deep (cyclic?)".to_string());
+    }
+    let node = &gltf["nodes"][node_index];
+    let floats = |key: &str, default: &[f32]| -> Vec<f32> {
+        node[key]
+            .as_array()
+            .map(|values| values.iter().filter_map(|v| v.as_f64()).map(|v| v as f32).collect())
+            .unwrap_or_else(|| default.to_vec())
+    };
+
+    let local = match node["matrix"].as_array() {
+        Some(_) => {
+            let m = floats("matrix", &[]);
+            if m.len() != 16 {
+                return Err(format!("glTF node {} has an invalid matrix", node_index));
+            }
+            std::array::from_fn(|column| std::array::from_fn(|row| m[column * 4 + row]))
+        }
+        None => {
+            let t = floats("translation", &[0.0, 0.0, 0.0]);
+            let r = floats("rotation", &[0.0, 0.0, 0.0, 1.0]);
+            let s = floats("scale", &[1.0, 1.0, 1.0]);
+            if t.len() != 3 || r.len() != 4 || s.len() != 3 {
+                return Err(format!("glTF node {} has an invalid transform", node_index));
+            }
+            camera::from_trs([t[0], t[1], t[2]], [r[0], r[1], r[2], r[3]], [s[0], s[1], s[2]])
+        }
+    };
+    let world = camera::mul(&parent, &local);
+
+    if let Some(mesh) = node.get("mesh") {
+        out.push((index(mesh)?, world));
+    }
+    for child in node["children"].as_array().into_iter().flatten() {
+        collect_nodes(gltf, index(child)?, world, out, depth + 1)?;
+    }
+    Ok(())
+}
+
+fn load_buffer(buffer: &serde_json::Value, bin: Option<&[u8]>, base_dir: &Path) -> Result<Vec<u8>, String> {
+    let data = match buffer["uri"].as_str() {
+        None => bin.ok_or("glTF buffer has no uri and there is no GLB binary chunk")?.to_vec(),
+        Some(uri) if uri.starts_with("data:") => {
+            let (_, encoded) = uri.split_once(";base64,").ok_or("glTF data URI is not base64")?;
+            base64::Engine::decode(&base64::engine::general_purpose::STANDARD, encoded)
+                .map_err(|e| format!("Invalid glTF data URI: {}", e))?
+        }
+        Some(uri) => {
+            let path = base_dir.join(uri.replace("%20", " "));
+            std::fs::read(&path).map_err(|e| format!("Failed to read glTF buffer {}: {}", path.display(), e))?
+        }
+    };
+    let length = buffer["byteLength"].as_u64().unwrap_or(data.len() as u64) as usize;
+    if data.len() < length {
+        return Err(format!("glTF buffer is {} bytes, expected {}", data.len(), length));
+    }
+    Ok(data)
+}
+
+struct AccessorReader<'a> {
+    gltf: &'a serde_json::Value,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl AccessorReader<'_> {
+    /// Read an accessor as floats, `components` per element
+    ///
+    /// Integer components are converted as-is, or normalized to 0..1 when the
+    /// accessor says so.
+    fn read(&self, accessor_index: usize, components: usize) -> Result<Vec<f32>, String> {
+        let accessor = &self.gltf["accessors"][accessor_index];
+        let expected_type = match components {
+            1 => "SCALAR",
+            2 => "VEC2",
+            _ => "VEC3",
+        };
+        if accessor["type"].as_str() != Some(expected_type) {
+            return Err(format!("glTF accessor {} is not {}", accessor_index, expected_type));
+        }
+        if accessor.get("sparse").is_some() {
+            return Err(format!("glTF accessor {} is sparse, which is not supported", accessor_index));
+        }
+        let count = accessor["count"].as_u64().unwrap_or(0) as usize;
+        let component_type = accessor["componentType"].as_u64().unwrap_or(0);
+        let normalized = accessor["normalized"].as_bool().unwrap_or(false);
+        let (size, read): (usize, fn(&[u8]) -> f32) = match component_type {
+            5121 => (1, |b| b[0] as f32),
+            5123 => (2, |b| u16::from_le_bytes([b[0], b[1]]) as f32),
+            5125 => (4, |b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32),
+            5126 => (4, |b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])),
+            other => return Err(format!("glTF component type {} is not supported", other)),
+        };
+        let scale = match (normalized, component_type) {
+            (true, 5121) => 1.0 / 255.0,
+            (true, 5123) => 1.0 / 65535.0,
+            _ => 1.0,
+        };
+
+        let view = &self.gltf["bufferViews"][index(&accessor["bufferView"])?];
+        let buffer = self
+            .buffers
+            .get(index(&view["buffer"])?)
+            .ok_or_else(|| format!("glTF accessor {} refers to a missing buffer", accessor_index))?;
+        let view_offset = view["byteOffset"].as_u64().unwrap_or(0) as usize;
+        let view_end = view["byteLength"]
+            .as_u64()
+            .and_then(|length| view_offset.checked_add(length as usize))
+            .map_or(buffer.len(), |end| end.min(buffer.len()));
+        let element_size = size * components;
+        let stride = view["byteStride"].as_u64().map_or(element_size, |s| s as usize);
+        if stride < element_size {
+            return Err(format!("glTF accessor {} has a stride smaller than its elements", accessor_index));
+        }
+
+        // `count` comes from the file, so check it against the view before allocating
+        let offset = view_offset.checked_add(accessor["byteOffset"].as_u64().unwrap_or(0) as usize);
+        let end = match count {
+            0 => offset,
+            _ => offset.and_then(|offset| (count - 1).checked_mul(stride)?.checked_add(offset)?.checked_add(element_size)),
+        };
+        let (Some(offset), Some(end)) = (offset, end) else {
+            return Err(format!("glTF accessor {} reads past its buffer", accessor_index));
+        };
+        if end > view_end {
+            return Err(format!("glTF accessor {} reads past its buffer", accessor_index));
+        }
+
+        let mut values = Vec::with_capacity(count * components);
+        for element in 0..count {
+            for component in 0..components {
+                let at = offset + element * stride + component * size;
+                let bytes = buffer
+                    .get(at..at + size)
+                    .ok_or_else(|| format!("glTF accessor {} reads past its buffer", accessor_index))?;
+                values.push(read(bytes) * scale);
+            }
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_obj() {
+        let obj = "\
+# quad with shared corners and one relative face
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 1
+vn 0 0 1
+f 1/1/1 2/1/1 3/2/1 4/2/1
+f -4 -2 -1
+";
+        let (vertices, indices) = parse_obj(obj).unwrap();
+        assert_eq!(indices.len(), 9);
+        assert_eq!(vertices.len(), 4 + 3);
+        assert_eq!(vertices[0].uv, [0.0, 1.0], "OBJ v is flipped");
+        assert_eq!(vertices[0].normal, [0.0, 0.0, 1.0]);
+        // The face without normals gets a computed one
+        assert!((vertices[6].normal[2] - 1.0).abs() < 1e-6, "{:?}", vertices[6]);
+
+        assert!(parse_obj("v 0 0 0\nf 1 2 3\n").unwrap_err().contains("line 2"));
+    }
+
+    /// A one-triangle glTF with the given node transform and buffer URI
+    fn triangle_gltf(uri: &str, node: &str) -> String {
+        format!(
+            r#"{{
+                "scenes": [{{"nodes": [0]}}],
+                "nodes": [{node}],
+                "meshes": [{{"primitives": [{{"attributes": {{"POSITION": 0}}, "indices": 1}}]}}],
+                "buffers": [{{"byteLength": 42{uri}}}],
+                "bufferViews": [
+                    {{"buffer": 0, "byteOffset": 0, "byteLength": 36}},
+                    {{"buffer": 0, "byteOffset": 36, "byteLength": 6}}
+                ],
+                "accessors": [
+                    {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}},
+                    {{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}}
+                ]
+            }}"#
+        )
+    }
+
+    fn triangle_bytes() -> Vec<u8> {
+        let positions = [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        let mut bytes: Vec<u8> = bytemuck::cast_slice(&positions).to_vec();
+        bytes.extend(bytemuck::cast_slice(&[0u16, 1, 2]));
+        bytes
+    }
+
+    #[test]
+    fn test_gltf_and_glb() {
+        let data = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, triangle_bytes());
+        let json = triangle_gltf(
+            &format!(r#", "uri": "data:application/octet-stream;base64,{}""#, data),
+            r#"{"mesh": 0, "translation": [0, 0, 5]}"#,
+        );
+        let (vertices, indices) = parse_gltf(json.as_bytes(), None, Path::new(".")).unwrap();
+        assert_eq!(indices, [0, 1, 2]);
+        assert_eq!(vertices[1].position, [1.0, 0.0, 5.0]);
+        assert!((vertices[0].normal[2] - 1.0).abs() < 1e-6);
+
+        // Same triangle as GLB with the data in the BIN chunk (chunks are 4-byte aligned)
+        let mut json = triangle_gltf("", r#"{"mesh": 0}"#).into_bytes();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        let mut bin = triangle_bytes();
+        bin.resize(44, 0);
+        let mut glb = Vec::new();
+        glb.extend_from_slice(GLB_MAGIC);
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&((12 + 8 + json.len() + 8 + bin.len()) as u32).to_le_bytes());
+        for (kind, chunk) in [(GLB_JSON, &json), (GLB_BIN, &bin)] {
+            glb.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+            glb.extend_from_slice(&kind.to_le_bytes());
+            glb.extend_from_slice(chunk);
+        }
+        let (vertices, indices) = parse_glb(&glb, Path::new(".")).unwrap();
+        assert_eq!(indices, [0, 1, 2]);
+        assert_eq!(vertices[2].position, [0.0, 1.0, 0.0]);
+
+        // Counts and strides from the file are checked before anything is allocated
+        let crafted = [
+            (r#""count": 3, "type": "VEC3""#, r#""count": 4611686018427387904, "type": "VEC3""#),
+            (r#""byteLength": 36}"#, r#""byteLength": 36, "byteStride": 0}"#),
+        ];
+        for (from, to) in crafted {
+            let crafted = String::from_utf8(json.clone()).unwrap().replace(from, to);
+            let err = parse_gltf(crafted.as_bytes(), Some(&bin), Path::new(".")).unwrap_err();
+            assert!(err.contains("accessor 0"), "{}", err);
+        }
+    }
+}
@@ -1,28 +1,44 @@
 pub mod audio;
 pub mod audio_analyzer;
 pub mod audio_file;
+pub mod camera;
+pub mod channel_animation;
+pub mod channel_texture;
+pub mod completion;
+pub mod diagnostics;
 pub mod errors;
+pub mod file_watcher;
 pub mod fonts;
-pub mod image_loader;
+pub mod gpu_errors;
+pub mod hover;
+pub mod mesh;
 pub mod monitors;
 pub mod multi_buffer_pipeline;
 pub mod notification;
 pub mod panic_handler;
+pub mod pass_geometry;
 pub mod pipeline;
+pub mod procedural_texture;
+pub mod quick_fix;
 pub mod shader_constants;
 pub mod shader_json;
 pub mod shader_validator;
+pub mod shard_bundle;
 pub mod text;
 pub mod theme;
+pub mod video_capture;
+pub mod wgsl_symbols;
 pub mod wgsl_syntax;
+pub mod zip_archive;
 
 pub use errors::{format_shader_error, ShaderError};
 pub use fonts::register_error_fonts;
 pub use monitors::detect_primary_monitor_xrandr;
-pub use multi_buffer_pipeline::{BufferKind, MultiPassCallback, MultiPassPipelines};
+pub use multi_buffer_pipeline::{BufferKind, MultiPassCallback, MultiPassPipelines, SourceKind};
 pub use notification::NotificationManager;
 pub use panic_handler::{catch_panic_mut, format_panic_message};
+pub use pass_geometry::PassGeometry;
 pub use shader_constants::*;
 pub use shader_json::ShaderJson;
-pub use shader_validator::validate_shader;
+pub use shader_validator::{has_shader_code, validate_shader};
 pub use theme::apply_editor_theme;
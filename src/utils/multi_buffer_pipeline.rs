@@ -1,8 +1,16 @@
 #![allow(dead_code)]
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
 
-use crate::utils::{validate_shader, ShaderError};
+use crate::utils::camera::CameraUniforms;
+use crate::utils::channel_animation::{self, AnimatedChannel};
+use crate::utils::channel_texture::{self, ChannelInput, ChannelKind, ChannelSettings};
+use crate::utils::gpu_errors::capture_errors;
+use crate::utils::mesh::{Mesh, MeshVertex};
+use crate::utils::pass_geometry::{Topology, DEPTH_FORMAT};
+use crate::utils::video_capture::LiveChannel;
+use crate::utils::{validate_shader, PassGeometry, ShaderError};
 use eframe::epaint;
 use eframe::wgpu::{
     AddressMode, BindGroup, BindGroupLayout, Buffer, CommandEncoder, Device, Extent3d,
@@ -12,6 +20,7 @@ use eframe::wgpu::{
 
 // Re-export ShaderUniforms from pipeline module
 pub use crate::utils::pipeline::ShaderUniforms;
+use crate::utils::pipeline::PreviewMouse;
 
 /// Buffer types for multi-pass rendering
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -24,6 +33,15 @@ pub enum BufferKind {
 }
 
 impl BufferKind {
+    /// All buffers in tab order
+    pub const ALL: [BufferKind; 5] = [
+        BufferKind::MainImage,
+        BufferKind::BufferA,
+        BufferKind::BufferB,
+        BufferKind::BufferC,
+        BufferKind::BufferD,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             BufferKind::MainImage => "MainImage",
@@ -33,6 +51,44 @@ impl BufferKind {
             BufferKind::BufferD => "BufferD",
         }
     }
+
+    /// File name stem of the pass (`main_image`, `buffer_a`, ...), also its key in shards
+    pub fn file_stem(&self) -> &'static str {
+        match self {
+            BufferKind::MainImage => "main_image",
+            BufferKind::BufferA => "buffer_a",
+            BufferKind::BufferB => "buffer_b",
+            BufferKind::BufferC => "buffer_c",
+            BufferKind::BufferD => "buffer_d",
+        }
+    }
+}
+
+/// An editable shader source: the shared Common code or one of the buffers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceKind {
+    /// Helper code injected into every pass after the boilerplate
+    Common,
+    Buffer(BufferKind),
+}
+
+impl SourceKind {
+    /// All sources in tab order (Common comes last)
+    pub const ALL: [SourceKind; 6] = [
+        SourceKind::Buffer(BufferKind::MainImage),
+        SourceKind::Buffer(BufferKind::BufferA),
+        SourceKind::Buffer(BufferKind::BufferB),
+        SourceKind::Buffer(BufferKind::BufferC),
+        SourceKind::Buffer(BufferKind::BufferD),
+        SourceKind::Common,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SourceKind::Common => "Common",
+            SourceKind::Buffer(kind) => kind.as_str(),
+        }
+    }
 }
 
 /// Single render pass that renders into an offscreen texture
@@ -41,6 +97,10 @@ pub struct BufferPass {
     pub pipeline: RenderPipeline,
     pub target_texture: Texture,
     pub target_view: TextureView,
+    pub geometry: PassGeometry,
+    /// Only for passes with depth testing
+    pub depth_view: Option<TextureView>,
+    pub mesh: Option<GpuMesh>,
 }
 
 impl BufferPass {
@@ -56,14 +116,64 @@ impl BufferPass {
                 },
                 depth_slice: None,
             })],
-            depth_stencil_attachment: None,
+            depth_stencil_attachment: self.depth_view.as_ref().map(|view| {
+                eframe::wgpu::RenderPassDepthStencilAttachment {
+                    view,
+                    depth_ops: Some(eframe::wgpu::Operations {
+                        load: eframe::wgpu::LoadOp::Clear(1.0),
+                        store: eframe::wgpu::StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }
+            }),
             timestamp_writes: None,
             occlusion_query_set: None,
         });
 
         rpass.set_pipeline(&self.pipeline);
         rpass.set_bind_group(0, uniform_bind_group, &[]);
-        rpass.draw(0..6, 0..1);
+        draw(&mut rpass, &self.geometry, self.mesh.as_ref());
+    }
+}
+
+/// A mesh uploaded for a pass: vertex buffer in [`MeshVertex`] layout plus `u32` indices
+pub struct GpuMesh {
+    pub vertex_buffer: Buffer,
+    pub index_buffer: Buffer,
+    pub index_count: u32,
+}
+
+impl GpuMesh {
+    fn new(device: &Device, mesh: &Mesh, label: &str) -> Self {
+        use eframe::wgpu::util::DeviceExt;
+        let vertex_buffer = device.create_buffer_init(&eframe::wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{}_vertices", label)),
+            contents: bytemuck::cast_slice(&mesh.vertices),
+            usage: eframe::wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&eframe::wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{}_indices", label)),
+            contents: bytemuck::cast_slice(&mesh.indices),
+            usage: eframe::wgpu::BufferUsages::INDEX,
+        });
+        Self {
+            vertex_buffer,
+            index_buffer,
+            index_count: mesh.indices.len() as u32,
+        }
+    }
+}
+
+/// Issue a pass's draw call: the mesh's indexed triangles if it has one,
+/// otherwise `vertex_count` vertices
+fn draw(rpass: &mut eframe::wgpu::RenderPass<'_>, geometry: &PassGeometry, mesh: Option<&GpuMesh>) {
+    match mesh {
+        Some(mesh) => {
+            rpass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+            rpass.set_index_buffer(mesh.index_buffer.slice(..), eframe::wgpu::IndexFormat::Uint32);
+            rpass.draw_indexed(0..mesh.index_count, 0, 0..geometry.instance_count);
+        }
+        None => rpass.draw(0..geometry.vertex_count, 0..geometry.instance_count),
     }
 }
 
@@ -93,9 +203,179 @@ fn create_color_target(
     (texture, view)
 }
 
+/// Helper: create a depth buffer for a pass that depth tests
+fn create_depth_target(device: &Device, size: [u32; 2], label: &str) -> TextureView {
+    let texture = device.create_texture(&TextureDescriptor {
+        label: Some(label),
+        size: Extent3d {
+            width: size[0],
+            height: size[1],
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: DEPTH_FORMAT,
+        usage: TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    });
+    texture.create_view(&TextureViewDescriptor::default())
+}
+
+/// Full-screen triangle copying MainImage's offscreen render into egui's pass
+const SCREEN_BLIT: &str = r#"
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var screen_sampler: sampler;
+
+struct BlitOut {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vi: u32) -> BlitOut {
+    let uv = vec2<f32>(f32((vi << 1u) & 2u), f32(vi & 2u));
+    var out: BlitOut;
+    out.pos = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@fragment
+fn fs_main(in: BlitOut) -> @location(0) vec4<f32> {
+    return textureSample(screen_texture, screen_sampler, in.uv);
+}
+"#;
+
+/// Offscreen target for a depth-tested MainImage
+///
+/// egui's render pass, where MainImage normally draws, has no depth buffer,
+/// so a MainImage that depth tests renders to a screen-sized texture with its
+/// own depth buffer in `prepare` and is copied into egui's pass in `paint`.
+/// The target is only created for such shaders and follows the screen size.
+struct ScreenBlit {
+    pipeline: RenderPipeline,
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    format: TextureFormat,
+    target: std::sync::Mutex<Option<ScreenTarget>>,
+}
+
+struct ScreenTarget {
+    size: [u32; 2],
+    view: TextureView,
+    depth_view: TextureView,
+    bind_group: BindGroup,
+}
+
+impl ScreenBlit {
+    fn new(device: &Device, format: TextureFormat) -> Self {
+        let module = device.create_shader_module(eframe::wgpu::ShaderModuleDescriptor {
+            label: Some("screen_blit_shader"),
+            source: eframe::wgpu::ShaderSource::Wgsl(SCREEN_BLIT.into()),
+        });
+        let layout = device.create_bind_group_layout(&eframe::wgpu::BindGroupLayoutDescriptor {
+            label: Some("screen_blit_bgl"),
+            entries: &[
+                eframe::wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: eframe::wgpu::ShaderStages::FRAGMENT,
+                    ty: eframe::wgpu::BindingType::Texture {
+                        sample_type: eframe::wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: eframe::wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                eframe::wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: eframe::wgpu::ShaderStages::FRAGMENT,
+                    ty: eframe::wgpu::BindingType::Sampler(eframe::wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&eframe::wgpu::PipelineLayoutDescriptor {
+            label: Some("screen_blit_layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&eframe::wgpu::RenderPipelineDescriptor {
+            label: Some("screen_blit_pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: eframe::wgpu::VertexState {
+                module: &module,
+                entry_point: Some("vs_main"),
+                compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
+                buffers: &[],
+            },
+            fragment: Some(eframe::wgpu::FragmentState {
+                module: &module,
+                entry_point: Some("fs_main"),
+                compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
+                // MainImage already blended into the cleared target, leaving premultiplied colour
+                targets: &[Some(eframe::wgpu::ColorTargetState {
+                    format,
+                    blend: Some(eframe::wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: eframe::wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: eframe::wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: eframe::wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let sampler = device.create_sampler(&SamplerDescriptor {
+            label: Some("screen_blit_sampler"),
+            ..Default::default()
+        });
+        Self {
+            pipeline,
+            layout,
+            sampler,
+            format,
+            target: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// The target for a screen of `size`, recreated when the size changes
+    fn target<'a>(&'a self, device: &Device, size: [u32; 2]) -> std::sync::MutexGuard<'a, Option<ScreenTarget>> {
+        let size = size.map(|side| side.max(1));
+        let mut target = self.target.lock().unwrap();
+        if target.as_ref().is_none_or(|target| target.size != size) {
+            let (_, view) = create_color_target(device, size, self.format, "main_image_target");
+            let depth_view = create_depth_target(device, size, "main_image_depth");
+            let bind_group = device.create_bind_group(&eframe::wgpu::BindGroupDescriptor {
+                label: Some("screen_blit_bg"),
+                layout: &self.layout,
+                entries: &[
+                    eframe::wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: eframe::wgpu::BindingResource::TextureView(&view),
+                    },
+                    eframe::wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: eframe::wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                ],
+            });
+            *target = Some(ScreenTarget {
+                size,
+                view,
+                depth_view,
+                bind_group,
+            });
+        }
+        target
+    }
+}
+
 /// Multi-pass shader pipeline manager
 pub struct MultiPassPipelines {
     pub uniform_buffer: Buffer,
+    /// `camera` uniform of mesh passes, `@group(0) @binding(1)`
+    pub camera_buffer: Buffer,
     pub uniform_bind_group_layout: BindGroupLayout,
     pub texture_bind_group_layout: BindGroupLayout,
     pub uniform_bind_group: BindGroup,
@@ -108,24 +388,38 @@ pub struct MultiPassPipelines {
 
     // Main image pipeline (always present)
     pub main_image_pipeline: RenderPipeline,
+    pub main_image_geometry: PassGeometry,
+    pub main_image_mesh: Option<GpuMesh>,
     pub main_texture_bind_group: BindGroup,
+    /// Only when MainImage depth tests
+    main_image_blit: Option<ScreenBlit>,
 
     pub sampler: Sampler,
     pub start_time: Instant,
+    /// Frames prepared so far and the time of the last one, for `frame` and `time_delta`
+    frame_count: AtomicU32,
+    last_frame_time: std::sync::Mutex<Option<f32>>,
     
     // User-loaded image textures (iChannel0-3 in ShaderToy terms)
     pub user_image_textures: [Option<Texture>; 4],
     pub user_image_views: [Option<TextureView>; 4],
+    /// Animated channels, whose frame is uploaded before each render
+    pub channel_animations: [Option<AnimatedChannel>; 4],
+    /// Camera channels, whose newest frame is uploaded before each render
+    pub channel_lives: [Option<LiveChannel>; 4],
 }
 
 impl MultiPassPipelines {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         device: &Device,
         queue: &Queue,
         format: TextureFormat,
         screen_size: [u32; 2],
         sources: &std::collections::HashMap<BufferKind, String>,
-        image_paths: &[Option<String>; 4], // Array of 4 image paths for iChannel0-3
+        geometry: &std::collections::HashMap<BufferKind, PassGeometry>,
+        meshes: &std::collections::HashMap<BufferKind, Arc<Mesh>>,
+        channels: &[ChannelInput; 4], // Files and texture types of iChannel0-3
     ) -> Result<Self, ShaderError> {
         log::info!(
             "Creating multi-pass shader pipeline (resolution: {}x{})",
@@ -141,29 +435,44 @@ impl MultiPassPipelines {
             usage: eframe::wgpu::BufferUsages::COPY_DST | eframe::wgpu::BufferUsages::UNIFORM,
             mapped_at_creation: false,
         });
+        let camera_buffer = {
+            use eframe::wgpu::util::DeviceExt;
+            device.create_buffer_init(&eframe::wgpu::util::BufferInitDescriptor {
+                label: Some("camera_uniforms"),
+                contents: bytemuck::bytes_of(&CameraUniforms::default()),
+                usage: eframe::wgpu::BufferUsages::COPY_DST | eframe::wgpu::BufferUsages::UNIFORM,
+            })
+        };
 
         // ===== Bind group layout: uniforms @group(0) =====
+        let uniform_entry = |binding: u32| eframe::wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: eframe::wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: eframe::wgpu::BindingType::Buffer {
+                ty: eframe::wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
         let uniform_bgl = device.create_bind_group_layout(&eframe::wgpu::BindGroupLayoutDescriptor {
             label: Some("uniform_bgl"),
-            entries: &[eframe::wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: eframe::wgpu::ShaderStages::VERTEX_FRAGMENT,
-                ty: eframe::wgpu::BindingType::Buffer {
-                    ty: eframe::wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
+            entries: &[uniform_entry(0), uniform_entry(1)],
         });
 
         let uniform_bg = device.create_bind_group(&eframe::wgpu::BindGroupDescriptor {
             label: Some("uniform_bg"),
             layout: &uniform_bgl,
-            entries: &[eframe::wgpu::BindGroupEntry {
-                binding: 0,
-                resource: uniform_buffer.as_entire_binding(),
-            }],
+            entries: &[
+                eframe::wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+                eframe::wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: camera_buffer.as_entire_binding(),
+                },
+            ],
         });
 
         // ===== Bind group layout: textures @group(1) =====
@@ -257,7 +566,7 @@ impl MultiPassPipelines {
                     visibility: eframe::wgpu::ShaderStages::FRAGMENT,
                     ty: eframe::wgpu::BindingType::Texture {
                         sample_type: eframe::wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: eframe::wgpu::TextureViewDimension::D2,
+                        view_dimension: channels[0].settings.kind.view_dimension(),
                         multisampled: false,
                     },
                     count: None,
@@ -277,7 +586,7 @@ impl MultiPassPipelines {
                     visibility: eframe::wgpu::ShaderStages::FRAGMENT,
                     ty: eframe::wgpu::BindingType::Texture {
                         sample_type: eframe::wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: eframe::wgpu::TextureViewDimension::D2,
+                        view_dimension: channels[1].settings.kind.view_dimension(),
                         multisampled: false,
                     },
                     count: None,
@@ -297,7 +606,7 @@ impl MultiPassPipelines {
                     visibility: eframe::wgpu::ShaderStages::FRAGMENT,
                     ty: eframe::wgpu::BindingType::Texture {
                         sample_type: eframe::wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: eframe::wgpu::TextureViewDimension::D2,
+                        view_dimension: channels[2].settings.kind.view_dimension(),
                         multisampled: false,
                     },
                     count: None,
@@ -317,7 +626,7 @@ impl MultiPassPipelines {
                     visibility: eframe::wgpu::ShaderStages::FRAGMENT,
                     ty: eframe::wgpu::BindingType::Texture {
                         sample_type: eframe::wgpu::TextureSampleType::Float { filterable: true },
-                        view_dimension: eframe::wgpu::TextureViewDimension::D2,
+                        view_dimension: channels[3].settings.kind.view_dimension(),
                         multisampled: false,
                     },
                     count: None,
@@ -349,12 +658,57 @@ impl MultiPassPipelines {
         // ===== Load user image textures if provided (iChannel0-3) =====
         let mut user_image_textures: [Option<Texture>; 4] = [None, None, None, None];
         let mut user_image_views: [Option<TextureView>; 4] = [None, None, None, None];
+        let mut channel_animations: [Option<AnimatedChannel>; 4] = [None, None, None, None];
+        let mut channel_lives: [Option<LiveChannel>; 4] = [None, None, None, None];
         
-        for (i, path_opt) in image_paths.iter().enumerate() {
-            if let Some(path) = path_opt {
-                match crate::utils::image_loader::load_image_texture(device, queue, path) {
+        for (i, channel) in channels.iter().enumerate() {
+            if let Some(capture) = &channel.live {
+                if channel.settings.kind == ChannelKind::Texture2d {
+                    let (live, view) = LiveChannel::new(device, queue, capture.clone(), &channel.settings);
+                    user_image_views[i] = Some(view);
+                    channel_lives[i] = Some(live);
+                } else {
+                    log::warn!("iChannel{} is a camera; cameras can only be bound to 2D channels", i);
+                }
+                continue;
+            }
+            if let Some(procedural) = &channel.settings.procedural {
+                match channel_texture::load_procedural_texture(device, queue, procedural, &channel.settings) {
+                    Ok((tex, view, _)) => {
+                        user_image_textures[i] = Some(tex);
+                        user_image_views[i] = Some(view);
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to generate iChannel{} texture: {}", i, e);
+                    }
+                }
+                continue;
+            }
+            if let Some(path) = &channel.path {
+                if channel.settings.kind == ChannelKind::Texture2d || channel_animation::is_sequence(path) {
+                    match AnimatedChannel::load(device, queue, path, &channel.settings) {
+                        Ok(Some((animation, view))) => {
+                            user_image_views[i] = Some(view);
+                            channel_animations[i] = Some(animation);
+                            continue;
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            log::warn!("Failed to load iChannel{} animation: {}", i, e);
+                            continue;
+                        }
+                    }
+                }
+                match channel_texture::load_channel_texture(device, queue, path, &channel.settings) {
                     Ok((tex, view, dimensions)) => {
-                        log::info!("iChannel{} texture loaded: {}x{}", i, dimensions[0], dimensions[1]);
+                        log::info!(
+                            "iChannel{} {} texture loaded: {}x{}x{}",
+                            i,
+                            channel.settings.kind.as_str(),
+                            dimensions[0],
+                            dimensions[1],
+                            dimensions[2]
+                        );
                         user_image_textures[i] = Some(tex);
                         user_image_views[i] = Some(view);
                     }
@@ -365,309 +719,364 @@ impl MultiPassPipelines {
             }
         }
 
-        // ===== BUFFER A: offscreen pass (optional) =====
-        let buffer_a = if let Some(buffer_a_src) = sources.get(&BufferKind::BufferA) {
-            // Skip if empty or only whitespace
-            if buffer_a_src.trim().is_empty() {
-                log::debug!("BufferA is empty, skipping");
-                None
+        // Mesh passes always draw depth-tested triangles
+        let geometry = |kind: BufferKind| {
+            let geometry = geometry.get(&kind).copied().unwrap_or_default();
+            if meshes.contains_key(&kind) {
+                PassGeometry {
+                    topology: Topology::TriangleList,
+                    depth: true,
+                    ..geometry
+                }
             } else {
-                // Only create if it has actual shader code (not just comments)
-                let has_code = buffer_a_src.contains("fn fs_main") || buffer_a_src.contains("@fragment");
-                if !has_code {
-                    log::debug!("BufferA has no fragment shader code, skipping");
+                geometry
+            }
+        };
+        let vertex_buffers = |kind: BufferKind| {
+            if meshes.contains_key(&kind) {
+                vec![MeshVertex::layout()]
+            } else {
+                Vec::new()
+            }
+        };
+        let gpu_mesh = |kind: BufferKind, label: &str| meshes.get(&kind).map(|mesh| GpuMesh::new(device, mesh, label));
+
+        // ===== BUFFER A: offscreen pass (optional) =====
+        let buffer_a = capture_errors(device, Some(BufferKind::BufferA), || {
+            if let Some(buffer_a_src) = sources.get(&BufferKind::BufferA) {
+                // Skip if empty or only whitespace
+                if buffer_a_src.trim().is_empty() {
+                    log::debug!("BufferA is empty, skipping");
                     None
                 } else {
-                    log::debug!("Creating BufferA pass");
-                    
-                    // Try to validate, but skip if it fails (allow partial shaders during development)
-                    if let Err(e) = validate_shader(buffer_a_src) {
-                        log::warn!("[BufferA] Validation failed, skipping: {}", e);
+                    // Only create if it has actual shader code (not just comments)
+                    let has_code = buffer_a_src.contains("fn fs_main") || buffer_a_src.contains("@fragment");
+                    if !has_code {
+                        log::debug!("BufferA has no fragment shader code, skipping");
                         None
                     } else {
-                        let buffer_a_module = device.create_shader_module(eframe::wgpu::ShaderModuleDescriptor {
-                            label: Some("buffer_a_shader"),
-                            source: eframe::wgpu::ShaderSource::Wgsl(buffer_a_src.clone().into()),
-                        });
-
-                        let (buffer_a_tex, buffer_a_view) =
-                            create_color_target(device, screen_size, format, "buffer_a_target");
-
-                        let buffer_a_pipeline_layout =
-                            device.create_pipeline_layout(&eframe::wgpu::PipelineLayoutDescriptor {
-                                label: Some("buffer_a_pipeline_layout"),
-                                bind_group_layouts: &[&uniform_bgl],
-                                push_constant_ranges: &[],
+                        log::debug!("Creating BufferA pass");
+                        
+                        // Try to validate, but skip if it fails (allow partial shaders during development)
+                        if let Err(e) = validate_shader(buffer_a_src) {
+                            log::warn!("[BufferA] Validation failed, skipping: {}", e);
+                            None
+                        } else {
+                            let buffer_a_module = device.create_shader_module(eframe::wgpu::ShaderModuleDescriptor {
+                                label: Some("buffer_a_shader"),
+                                source: eframe::wgpu::ShaderSource::Wgsl(buffer_a_src.clone().into()),
                             });
 
-                        let buffer_a_pipeline =
-                            device.create_render_pipeline(&eframe::wgpu::RenderPipelineDescriptor {
-                                label: Some("buffer_a_pipeline"),
-                                layout: Some(&buffer_a_pipeline_layout),
-                                vertex: eframe::wgpu::VertexState {
-                                    module: &buffer_a_module,
-                                    entry_point: Some("vs_main"),
-                                    compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
-                                    buffers: &[],
-                                },
-                                fragment: Some(eframe::wgpu::FragmentState {
-                                    module: &buffer_a_module,
-                                    entry_point: Some("fs_main"),
-                                    compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
-                                    targets: &[Some(eframe::wgpu::ColorTargetState {
-                                        format,
-                                        blend: Some(eframe::wgpu::BlendState::ALPHA_BLENDING),
-                                        write_mask: eframe::wgpu::ColorWrites::ALL,
-                                    })],
-                                }),
-                                primitive: eframe::wgpu::PrimitiveState::default(),
-                                depth_stencil: None,
-                                multisample: eframe::wgpu::MultisampleState::default(),
-                                multiview: None,
-                                cache: None,
-                            });
+                            let (buffer_a_tex, buffer_a_view) =
+                                create_color_target(device, screen_size, format, "buffer_a_target");
+                            let buffer_a_geometry = geometry(BufferKind::BufferA);
+
+                            let buffer_a_pipeline_layout =
+                                device.create_pipeline_layout(&eframe::wgpu::PipelineLayoutDescriptor {
+                                    label: Some("buffer_a_pipeline_layout"),
+                                    bind_group_layouts: &[&uniform_bgl],
+                                    push_constant_ranges: &[],
+                                });
 
-                        Some(BufferPass {
-                            kind: BufferKind::BufferA,
-                            pipeline: buffer_a_pipeline,
-                            target_texture: buffer_a_tex,
-                            target_view: buffer_a_view,
-                        })
+                            let buffer_a_pipeline =
+                                device.create_render_pipeline(&eframe::wgpu::RenderPipelineDescriptor {
+                                    label: Some("buffer_a_pipeline"),
+                                    layout: Some(&buffer_a_pipeline_layout),
+                                    vertex: eframe::wgpu::VertexState {
+                                        module: &buffer_a_module,
+                                        entry_point: Some("vs_main"),
+                                        compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
+                                        buffers: &vertex_buffers(BufferKind::BufferA),
+                                    },
+                                    fragment: Some(eframe::wgpu::FragmentState {
+                                        module: &buffer_a_module,
+                                        entry_point: Some("fs_main"),
+                                        compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
+                                        targets: &[Some(eframe::wgpu::ColorTargetState {
+                                            format,
+                                            blend: Some(eframe::wgpu::BlendState::ALPHA_BLENDING),
+                                            write_mask: eframe::wgpu::ColorWrites::ALL,
+                                        })],
+                                    }),
+                                    primitive: buffer_a_geometry.primitive_state(),
+                                    depth_stencil: buffer_a_geometry.depth_stencil_state(),
+                                    multisample: eframe::wgpu::MultisampleState::default(),
+                                    multiview: None,
+                                    cache: None,
+                                });
+
+                            Some(BufferPass {
+                                kind: BufferKind::BufferA,
+                                pipeline: buffer_a_pipeline,
+                                target_texture: buffer_a_tex,
+                                target_view: buffer_a_view,
+                                geometry: buffer_a_geometry,
+                                depth_view: buffer_a_geometry
+                                    .depth
+                                    .then(|| create_depth_target(device, screen_size, "buffer_a_depth")),
+                                mesh: gpu_mesh(BufferKind::BufferA, "buffer_a_mesh"),
+                            })
+                        }
                     }
                 }
+            } else {
+                None
             }
-        } else {
-            None
-        };
+        })?;
 
         // ===== BUFFER B: offscreen pass (optional) =====
-        let buffer_b = if let Some(buffer_b_src) = sources.get(&BufferKind::BufferB) {
-            if buffer_b_src.trim().is_empty() {
-                log::debug!("BufferB is empty, skipping");
-                None
-            } else {
-                let has_code = buffer_b_src.contains("fn fs_main") || buffer_b_src.contains("@fragment");
-                if !has_code {
-                    log::debug!("BufferB has no fragment shader code, skipping");
+        let buffer_b = capture_errors(device, Some(BufferKind::BufferB), || {
+            if let Some(buffer_b_src) = sources.get(&BufferKind::BufferB) {
+                if buffer_b_src.trim().is_empty() {
+                    log::debug!("BufferB is empty, skipping");
                     None
                 } else {
-                    log::debug!("Creating BufferB pass");
-                    if let Err(e) = validate_shader(buffer_b_src) {
-                        log::warn!("[BufferB] Validation failed, skipping: {}", e);
+                    let has_code = buffer_b_src.contains("fn fs_main") || buffer_b_src.contains("@fragment");
+                    if !has_code {
+                        log::debug!("BufferB has no fragment shader code, skipping");
                         None
                     } else {
-                        let buffer_b_module = device.create_shader_module(eframe::wgpu::ShaderModuleDescriptor {
-                            label: Some("buffer_b_shader"),
-                            source: eframe::wgpu::ShaderSource::Wgsl(buffer_b_src.clone().into()),
-                        });
-                        let (buffer_b_tex, buffer_b_view) =
-                            create_color_target(device, screen_size, format, "buffer_b_target");
-                        let buffer_b_pipeline_layout =
-                            device.create_pipeline_layout(&eframe::wgpu::PipelineLayoutDescriptor {
-                                label: Some("buffer_b_pipeline_layout"),
-                                bind_group_layouts: &[&uniform_bgl],
-                                push_constant_ranges: &[],
+                        log::debug!("Creating BufferB pass");
+                        if let Err(e) = validate_shader(buffer_b_src) {
+                            log::warn!("[BufferB] Validation failed, skipping: {}", e);
+                            None
+                        } else {
+                            let buffer_b_module = device.create_shader_module(eframe::wgpu::ShaderModuleDescriptor {
+                                label: Some("buffer_b_shader"),
+                                source: eframe::wgpu::ShaderSource::Wgsl(buffer_b_src.clone().into()),
                             });
-                        let buffer_b_pipeline =
-                            device.create_render_pipeline(&eframe::wgpu::RenderPipelineDescriptor {
-                                label: Some("buffer_b_pipeline"),
-                                layout: Some(&buffer_b_pipeline_layout),
-                                vertex: eframe::wgpu::VertexState {
-                                    module: &buffer_b_module,
-                                    entry_point: Some("vs_main"),
-                                    compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
-                                    buffers: &[],
-                                },
-                                fragment: Some(eframe::wgpu::FragmentState {
-                                    module: &buffer_b_module,
-                                    entry_point: Some("fs_main"),
-                                    compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
-                                    targets: &[Some(eframe::wgpu::ColorTargetState {
-                                        format,
-                                        blend: Some(eframe::wgpu::BlendState::ALPHA_BLENDING),
-                                        write_mask: eframe::wgpu::ColorWrites::ALL,
-                                    })],
-                                }),
-                                primitive: eframe::wgpu::PrimitiveState::default(),
-                                depth_stencil: None,
-                                multisample: eframe::wgpu::MultisampleState::default(),
-                                multiview: None,
-                                cache: None,
-                            });
-                        Some(BufferPass {
-                            kind: BufferKind::BufferB,
-                            pipeline: buffer_b_pipeline,
-                            target_texture: buffer_b_tex,
-                            target_view: buffer_b_view,
-                        })
+                            let (buffer_b_tex, buffer_b_view) =
+                                create_color_target(device, screen_size, format, "buffer_b_target");
+                            let buffer_b_geometry = geometry(BufferKind::BufferB);
+                            let buffer_b_pipeline_layout =
+                                device.create_pipeline_layout(&eframe::wgpu::PipelineLayoutDescriptor {
+                                    label: Some("buffer_b_pipeline_layout"),
+                                    bind_group_layouts: &[&uniform_bgl],
+                                    push_constant_ranges: &[],
+                                });
+                            let buffer_b_pipeline =
+                                device.create_render_pipeline(&eframe::wgpu::RenderPipelineDescriptor {
+                                    label: Some("buffer_b_pipeline"),
+                                    layout: Some(&buffer_b_pipeline_layout),
+                                    vertex: eframe::wgpu::VertexState {
+                                        module: &buffer_b_module,
+                                        entry_point: Some("vs_main"),
+                                        compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
+                                        buffers: &vertex_buffers(BufferKind::BufferB),
+                                    },
+                                    fragment: Some(eframe::wgpu::FragmentState {
+                                        module: &buffer_b_module,
+                                        entry_point: Some("fs_main"),
+                                        compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
+                                        targets: &[Some(eframe::wgpu::ColorTargetState {
+                                            format,
+                                            blend: Some(eframe::wgpu::BlendState::ALPHA_BLENDING),
+                                            write_mask: eframe::wgpu::ColorWrites::ALL,
+                                        })],
+                                    }),
+                                    primitive: buffer_b_geometry.primitive_state(),
+                                    depth_stencil: buffer_b_geometry.depth_stencil_state(),
+                                    multisample: eframe::wgpu::MultisampleState::default(),
+                                    multiview: None,
+                                    cache: None,
+                                });
+                            Some(BufferPass {
+                                kind: BufferKind::BufferB,
+                                pipeline: buffer_b_pipeline,
+                                target_texture: buffer_b_tex,
+                                target_view: buffer_b_view,
+                                geometry: buffer_b_geometry,
+                                depth_view: buffer_b_geometry
+                                    .depth
+                                    .then(|| create_depth_target(device, screen_size, "buffer_b_depth")),
+                                mesh: gpu_mesh(BufferKind::BufferB, "buffer_b_mesh"),
+                            })
+                        }
                     }
                 }
+            } else {
+                None
             }
-        } else {
-            None
-        };
+        })?;
 
         // ===== BUFFER C: offscreen pass (optional) =====
-        let buffer_c = if let Some(buffer_c_src) = sources.get(&BufferKind::BufferC) {
-            if buffer_c_src.trim().is_empty() {
-                log::debug!("BufferC is empty, skipping");
-                None
-            } else {
-                let has_code = buffer_c_src.contains("fn fs_main") || buffer_c_src.contains("@fragment");
-                if !has_code {
-                    log::debug!("BufferC has no fragment shader code, skipping");
+        let buffer_c = capture_errors(device, Some(BufferKind::BufferC), || {
+            if let Some(buffer_c_src) = sources.get(&BufferKind::BufferC) {
+                if buffer_c_src.trim().is_empty() {
+                    log::debug!("BufferC is empty, skipping");
                     None
                 } else {
-                    log::debug!("Creating BufferC pass");
-                    if let Err(e) = validate_shader(buffer_c_src) {
-                        log::warn!("[BufferC] Validation failed, skipping: {}", e);
+                    let has_code = buffer_c_src.contains("fn fs_main") || buffer_c_src.contains("@fragment");
+                    if !has_code {
+                        log::debug!("BufferC has no fragment shader code, skipping");
                         None
                     } else {
-                        let buffer_c_module = device.create_shader_module(eframe::wgpu::ShaderModuleDescriptor {
-                            label: Some("buffer_c_shader"),
-                            source: eframe::wgpu::ShaderSource::Wgsl(buffer_c_src.clone().into()),
-                        });
-                        let (buffer_c_tex, buffer_c_view) =
-                            create_color_target(device, screen_size, format, "buffer_c_target");
-                        let buffer_c_pipeline_layout =
-                            device.create_pipeline_layout(&eframe::wgpu::PipelineLayoutDescriptor {
-                                label: Some("buffer_c_pipeline_layout"),
-                                bind_group_layouts: &[&uniform_bgl],
-                                push_constant_ranges: &[],
+                        log::debug!("Creating BufferC pass");
+                        if let Err(e) = validate_shader(buffer_c_src) {
+                            log::warn!("[BufferC] Validation failed, skipping: {}", e);
+                            None
+                        } else {
+                            let buffer_c_module = device.create_shader_module(eframe::wgpu::ShaderModuleDescriptor {
+                                label: Some("buffer_c_shader"),
+                                source: eframe::wgpu::ShaderSource::Wgsl(buffer_c_src.clone().into()),
                             });
-                        let buffer_c_pipeline =
-                            device.create_render_pipeline(&eframe::wgpu::RenderPipelineDescriptor {
-                                label: Some("buffer_c_pipeline"),
-                                layout: Some(&buffer_c_pipeline_layout),
-                                vertex: eframe::wgpu::VertexState {
-                                    module: &buffer_c_module,
-                                    entry_point: Some("vs_main"),
-                                    compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
-                                    buffers: &[],
-                                },
-                                fragment: Some(eframe::wgpu::FragmentState {
-                                    module: &buffer_c_module,
-                                    entry_point: Some("fs_main"),
-                                    compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
-                                    targets: &[Some(eframe::wgpu::ColorTargetState {
-                                        format,
-                                        blend: Some(eframe::wgpu::BlendState::ALPHA_BLENDING),
-                                        write_mask: eframe::wgpu::ColorWrites::ALL,
-                                    })],
-                                }),
-                                primitive: eframe::wgpu::PrimitiveState::default(),
-                                depth_stencil: None,
-                                multisample: eframe::wgpu::MultisampleState::default(),
-                                multiview: None,
-                                cache: None,
-                            });
-                        Some(BufferPass {
-                            kind: BufferKind::BufferC,
-                            pipeline: buffer_c_pipeline,
-                            target_texture: buffer_c_tex,
-                            target_view: buffer_c_view,
-                        })
+                            let (buffer_c_tex, buffer_c_view) =
+                                create_color_target(device, screen_size, format, "buffer_c_target");
+                            let buffer_c_geometry = geometry(BufferKind::BufferC);
+                            let buffer_c_pipeline_layout =
+                                device.create_pipeline_layout(&eframe::wgpu::PipelineLayoutDescriptor {
+                                    label: Some("buffer_c_pipeline_layout"),
+                                    bind_group_layouts: &[&uniform_bgl],
+                                    push_constant_ranges: &[],
+                                });
+                            let buffer_c_pipeline =
+                                device.create_render_pipeline(&eframe::wgpu::RenderPipelineDescriptor {
+                                    label: Some("buffer_c_pipeline"),
+                                    layout: Some(&buffer_c_pipeline_layout),
+                                    vertex: eframe::wgpu::VertexState {
+                                        module: &buffer_c_module,
+                                        entry_point: Some("vs_main"),
+                                        compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
+                                        buffers: &vertex_buffers(BufferKind::BufferC),
+                                    },
+                                    fragment: Some(eframe::wgpu::FragmentState {
+                                        module: &buffer_c_module,
+                                        entry_point: Some("fs_main"),
+                                        compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
+                                        targets: &[Some(eframe::wgpu::ColorTargetState {
+                                            format,
+                                            blend: Some(eframe::wgpu::BlendState::ALPHA_BLENDING),
+                                            write_mask: eframe::wgpu::ColorWrites::ALL,
+                                        })],
+                                    }),
+                                    primitive: buffer_c_geometry.primitive_state(),
+                                    depth_stencil: buffer_c_geometry.depth_stencil_state(),
+                                    multisample: eframe::wgpu::MultisampleState::default(),
+                                    multiview: None,
+                                    cache: None,
+                                });
+                            Some(BufferPass {
+                                kind: BufferKind::BufferC,
+                                pipeline: buffer_c_pipeline,
+                                target_texture: buffer_c_tex,
+                                target_view: buffer_c_view,
+                                geometry: buffer_c_geometry,
+                                depth_view: buffer_c_geometry
+                                    .depth
+                                    .then(|| create_depth_target(device, screen_size, "buffer_c_depth")),
+                                mesh: gpu_mesh(BufferKind::BufferC, "buffer_c_mesh"),
+                            })
+                        }
                     }
                 }
+            } else {
+                None
             }
-        } else {
-            None
-        };
+        })?;
 
         // ===== BUFFER D: offscreen pass (optional) =====
-        let buffer_d = if let Some(buffer_d_src) = sources.get(&BufferKind::BufferD) {
-            if buffer_d_src.trim().is_empty() {
-                log::debug!("BufferD is empty, skipping");
-                None
-            } else {
-                let has_code = buffer_d_src.contains("fn fs_main") || buffer_d_src.contains("@fragment");
-                if !has_code {
-                    log::debug!("BufferD has no fragment shader code, skipping");
+        let buffer_d = capture_errors(device, Some(BufferKind::BufferD), || {
+            if let Some(buffer_d_src) = sources.get(&BufferKind::BufferD) {
+                if buffer_d_src.trim().is_empty() {
+                    log::debug!("BufferD is empty, skipping");
                     None
                 } else {
-                    log::debug!("Creating BufferD pass");
-                    if let Err(e) = validate_shader(buffer_d_src) {
-                        log::warn!("[BufferD] Validation failed, skipping: {}", e);
+                    let has_code = buffer_d_src.contains("fn fs_main") || buffer_d_src.contains("@fragment");
+                    if !has_code {
+                        log::debug!("BufferD has no fragment shader code, skipping");
                         None
                     } else {
-                        let buffer_d_module = device.create_shader_module(eframe::wgpu::ShaderModuleDescriptor {
-                            label: Some("buffer_d_shader"),
-                            source: eframe::wgpu::ShaderSource::Wgsl(buffer_d_src.clone().into()),
-                        });
-                        let (buffer_d_tex, buffer_d_view) =
-                            create_color_target(device, screen_size, format, "buffer_d_target");
-                        let buffer_d_pipeline_layout =
-                            device.create_pipeline_layout(&eframe::wgpu::PipelineLayoutDescriptor {
-                                label: Some("buffer_d_pipeline_layout"),
-                                bind_group_layouts: &[&uniform_bgl],
-                                push_constant_ranges: &[],
+                        log::debug!("Creating BufferD pass");
+                        if let Err(e) = validate_shader(buffer_d_src) {
+                            log::warn!("[BufferD] Validation failed, skipping: {}", e);
+                            None
+                        } else {
+                            let buffer_d_module = device.create_shader_module(eframe::wgpu::ShaderModuleDescriptor {
+                                label: Some("buffer_d_shader"),
+                                source: eframe::wgpu::ShaderSource::Wgsl(buffer_d_src.clone().into()),
                             });
-                        let buffer_d_pipeline =
-                            device.create_render_pipeline(&eframe::wgpu::RenderPipelineDescriptor {
-                                label: Some("buffer_d_pipeline"),
-                                layout: Some(&buffer_d_pipeline_layout),
-                                vertex: eframe::wgpu::VertexState {
-                                    module: &buffer_d_module,
-                                    entry_point: Some("vs_main"),
-                                    compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
-                                    buffers: &[],
-                                },
-                                fragment: Some(eframe::wgpu::FragmentState {
-                                    module: &buffer_d_module,
-                                    entry_point: Some("fs_main"),
-                                    compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
-                                    targets: &[Some(eframe::wgpu::ColorTargetState {
-                                        format,
-                                        blend: Some(eframe::wgpu::BlendState::ALPHA_BLENDING),
-                                        write_mask: eframe::wgpu::ColorWrites::ALL,
-                                    })],
-                                }),
-                                primitive: eframe::wgpu::PrimitiveState::default(),
-                                depth_stencil: None,
-                                multisample: eframe::wgpu::MultisampleState::default(),
-                                multiview: None,
-                                cache: None,
-                            });
-                        Some(BufferPass {
-                            kind: BufferKind::BufferD,
-                            pipeline: buffer_d_pipeline,
-                            target_texture: buffer_d_tex,
-                            target_view: buffer_d_view,
-                        })
+                            let (buffer_d_tex, buffer_d_view) =
+                                create_color_target(device, screen_size, format, "buffer_d_target");
+                            let buffer_d_geometry = geometry(BufferKind::BufferD);
+                            let buffer_d_pipeline_layout =
+                                device.create_pipeline_layout(&eframe::wgpu::PipelineLayoutDescriptor {
+                                    label: Some("buffer_d_pipeline_layout"),
+                                    bind_group_layouts: &[&uniform_bgl],
+                                    push_constant_ranges: &[],
+                                });
+                            let buffer_d_pipeline =
+                                device.create_render_pipeline(&eframe::wgpu::RenderPipelineDescriptor {
+                                    label: Some("buffer_d_pipeline"),
+                                    layout: Some(&buffer_d_pipeline_layout),
+                                    vertex: eframe::wgpu::VertexState {
+                                        module: &buffer_d_module,
+                                        entry_point: Some("vs_main"),
+                                        compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
+                                        buffers: &vertex_buffers(BufferKind::BufferD),
+                                    },
+                                    fragment: Some(eframe::wgpu::FragmentState {
+                                        module: &buffer_d_module,
+                                        entry_point: Some("fs_main"),
+                                        compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
+                                        targets: &[Some(eframe::wgpu::ColorTargetState {
+                                            format,
+                                            blend: Some(eframe::wgpu::BlendState::ALPHA_BLENDING),
+                                            write_mask: eframe::wgpu::ColorWrites::ALL,
+                                        })],
+                                    }),
+                                    primitive: buffer_d_geometry.primitive_state(),
+                                    depth_stencil: buffer_d_geometry.depth_stencil_state(),
+                                    multisample: eframe::wgpu::MultisampleState::default(),
+                                    multiview: None,
+                                    cache: None,
+                                });
+                            Some(BufferPass {
+                                kind: BufferKind::BufferD,
+                                pipeline: buffer_d_pipeline,
+                                target_texture: buffer_d_tex,
+                                target_view: buffer_d_view,
+                                geometry: buffer_d_geometry,
+                                depth_view: buffer_d_geometry
+                                    .depth
+                                    .then(|| create_depth_target(device, screen_size, "buffer_d_depth")),
+                                mesh: gpu_mesh(BufferKind::BufferD, "buffer_d_mesh"),
+                            })
+                        }
                     }
                 }
+            } else {
+                None
             }
-        } else {
-            None
-        };
+        })?;
 
         // ===== MAIN IMAGE: reads BufferA texture =====
         let main_src = sources
             .get(&BufferKind::MainImage)
-            .ok_or_else(|| ShaderError::CompilationError("[MainImage] Missing shader source".into()))?;
+            .ok_or_else(|| ShaderError::compilation("Missing shader source").in_buffer(BufferKind::MainImage))?;
 
         // Skip if empty
         if main_src.trim().is_empty() {
-            return Err(ShaderError::CompilationError("[MainImage] Shader source is empty".into()));
+            return Err(ShaderError::compilation("Shader source is empty").in_buffer(BufferKind::MainImage));
         }
 
         log::debug!("Creating MainImage pipeline");
         validate_shader(main_src)
-            .map_err(|e| ShaderError::CompilationError(format!("[MainImage] {}", e)))?;
-
-        let main_module = device.create_shader_module(eframe::wgpu::ShaderModuleDescriptor {
-            label: Some("main_image_shader"),
-            source: eframe::wgpu::ShaderSource::Wgsl(main_src.clone().into()),
-        });
+            .map_err(|e| ShaderError::CompilationError(Box::new(e.diagnostic().clone().in_buffer(BufferKind::MainImage))))?;
 
-        let main_pipeline_layout =
-            device.create_pipeline_layout(&eframe::wgpu::PipelineLayoutDescriptor {
-                label: Some("main_pipeline_layout"),
-                bind_group_layouts: &[&uniform_bgl, &texture_bgl],
-                push_constant_ranges: &[],
+        let main_geometry = geometry(BufferKind::MainImage);
+        let main_pipeline = capture_errors(device, Some(BufferKind::MainImage), || {
+            let main_module = device.create_shader_module(eframe::wgpu::ShaderModuleDescriptor {
+                label: Some("main_image_shader"),
+                source: eframe::wgpu::ShaderSource::Wgsl(main_src.clone().into()),
             });
 
-        let main_pipeline =
+            let main_pipeline_layout =
+                device.create_pipeline_layout(&eframe::wgpu::PipelineLayoutDescriptor {
+                    label: Some("main_pipeline_layout"),
+                    bind_group_layouts: &[&uniform_bgl, &texture_bgl],
+                    push_constant_ranges: &[],
+                });
+
             device.create_render_pipeline(&eframe::wgpu::RenderPipelineDescriptor {
                 label: Some("main_image_pipeline"),
                 layout: Some(&main_pipeline_layout),
@@ -675,7 +1084,7 @@ impl MultiPassPipelines {
                     module: &main_module,
                     entry_point: Some("vs_main"),
                     compilation_options: eframe::wgpu::PipelineCompilationOptions::default(),
-                    buffers: &[],
+                    buffers: &vertex_buffers(BufferKind::MainImage),
                 },
                 fragment: Some(eframe::wgpu::FragmentState {
                     module: &main_module,
@@ -687,16 +1096,24 @@ impl MultiPassPipelines {
                         write_mask: eframe::wgpu::ColorWrites::ALL,
                     })],
                 }),
-                primitive: eframe::wgpu::PrimitiveState::default(),
-                depth_stencil: None,
+                primitive: main_geometry.primitive_state(),
+                // Depth-tested MainImages draw offscreen, see `ScreenBlit`
+                depth_stencil: main_geometry.depth_stencil_state(),
                 multisample: eframe::wgpu::MultisampleState::default(),
                 multiview: None,
                 cache: None,
-            });
+            })
+        })?;
+        let main_mesh = gpu_mesh(BufferKind::MainImage, "main_image_mesh");
 
         // ===== Bind group for MainImage to read all buffer textures =====
         // Create dummy texture for any missing buffers
         let (_dummy_tex, dummy_view) = create_color_target(device, [1, 1], format, "dummy_texture");
+        // Channels without a texture get a placeholder of their declared type
+        let channel_placeholders =
+            channels.each_ref().map(|channel| channel_texture::placeholder_view(device, queue, channel.settings.kind));
+        // Each channel samples with its own wrap and filter settings
+        let channel_samplers = channels.each_ref().map(|channel| device.create_sampler(&channel.settings.sampler_descriptor()));
         
         let main_tex_bg = device.create_bind_group(&eframe::wgpu::BindGroupDescriptor {
             label: Some("main_texture_bg"),
@@ -754,49 +1171,49 @@ impl MultiPassPipelines {
                 eframe::wgpu::BindGroupEntry {
                     binding: 8,
                     resource: eframe::wgpu::BindingResource::TextureView(
-                        user_image_views[0].as_ref().unwrap_or(&dummy_view)
+                        user_image_views[0].as_ref().unwrap_or(&channel_placeholders[0].1)
                     ),
                 },
                 // User image sampler iChannel0 @binding(9)
                 eframe::wgpu::BindGroupEntry {
                     binding: 9,
-                    resource: eframe::wgpu::BindingResource::Sampler(&sampler),
+                    resource: eframe::wgpu::BindingResource::Sampler(&channel_samplers[0]),
                 },
                 // User image texture iChannel1 @binding(10)
                 eframe::wgpu::BindGroupEntry {
                     binding: 10,
                     resource: eframe::wgpu::BindingResource::TextureView(
-                        user_image_views[1].as_ref().unwrap_or(&dummy_view)
+                        user_image_views[1].as_ref().unwrap_or(&channel_placeholders[1].1)
                     ),
                 },
                 // User image sampler iChannel1 @binding(11)
                 eframe::wgpu::BindGroupEntry {
                     binding: 11,
-                    resource: eframe::wgpu::BindingResource::Sampler(&sampler),
+                    resource: eframe::wgpu::BindingResource::Sampler(&channel_samplers[1]),
                 },
                 // User image texture iChannel2 @binding(12)
                 eframe::wgpu::BindGroupEntry {
                     binding: 12,
                     resource: eframe::wgpu::BindingResource::TextureView(
-                        user_image_views[2].as_ref().unwrap_or(&dummy_view)
+                        user_image_views[2].as_ref().unwrap_or(&channel_placeholders[2].1)
                     ),
                 },
                 // User image sampler iChannel2 @binding(13)
                 eframe::wgpu::BindGroupEntry {
                     binding: 13,
-                    resource: eframe::wgpu::BindingResource::Sampler(&sampler),
+                    resource: eframe::wgpu::BindingResource::Sampler(&channel_samplers[2]),
                 },
                 // User image texture iChannel3 @binding(14)
                 eframe::wgpu::BindGroupEntry {
                     binding: 14,
                     resource: eframe::wgpu::BindingResource::TextureView(
-                        user_image_views[3].as_ref().unwrap_or(&dummy_view)
+                        user_image_views[3].as_ref().unwrap_or(&channel_placeholders[3].1)
                     ),
                 },
                 // User image sampler iChannel3 @binding(15)
                 eframe::wgpu::BindGroupEntry {
                     binding: 15,
-                    resource: eframe::wgpu::BindingResource::Sampler(&sampler),
+                    resource: eframe::wgpu::BindingResource::Sampler(&channel_samplers[3]),
                 },
             ],
         });
@@ -805,6 +1222,7 @@ impl MultiPassPipelines {
 
         Ok(Self {
             uniform_buffer,
+            camera_buffer,
             uniform_bind_group_layout: uniform_bgl,
             texture_bind_group_layout: texture_bgl,
             uniform_bind_group: uniform_bg,
@@ -813,53 +1231,91 @@ impl MultiPassPipelines {
             buffer_c,
             buffer_d,
             main_image_pipeline: main_pipeline,
+            main_image_geometry: main_geometry,
+            main_image_mesh: main_mesh,
             main_texture_bind_group: main_tex_bg,
+            main_image_blit: main_geometry.depth.then(|| ScreenBlit::new(device, format)),
             sampler,
             start_time: Instant::now(),
+            frame_count: AtomicU32::new(0),
+            last_frame_time: std::sync::Mutex::new(None),
             user_image_textures,
+            channel_animations,
+            channel_lives,
             user_image_views,
         })
     }
 
-    /// Record all render passes: buffers first, then main image
-    pub fn record_passes(&self, encoder: &mut CommandEncoder, screen_view: &TextureView) {
-        // 1) Buffer A → its own texture
-        if let Some(ref buffer_a) = self.buffer_a {
-            buffer_a.render(encoder, &self.uniform_bind_group);
+    /// Reload channel `index`'s file into its existing texture
+    ///
+    /// Returns `Ok(false)` if the pipeline has to be rebuilt instead: the
+    /// channel is animated or live, or its file changed size or format.
+    pub fn reload_channel(&self, queue: &Queue, index: usize, channel: &ChannelInput) -> Result<bool, String> {
+        if self.channel_animations[index].is_some() || self.channel_lives[index].is_some() {
+            return Ok(false);
         }
+        match (&self.user_image_textures[index], &channel.path) {
+            (Some(texture), Some(path)) => channel_texture::reload_channel_texture(queue, texture, path, &channel.settings),
+            _ => Ok(false),
+        }
+    }
 
-        // 2) Buffer B, C, D (when implemented)
-        // ...
-
-        // 3) MainImage → screen, sampling from BufferA.target_view
-        {
-            let mut rpass = encoder.begin_render_pass(&eframe::wgpu::RenderPassDescriptor {
-                label: Some("main_image_pass"),
-                color_attachments: &[Some(eframe::wgpu::RenderPassColorAttachment {
-                    view: screen_view,
-                    resolve_target: None,
-                    ops: eframe::wgpu::Operations {
-                        load: eframe::wgpu::LoadOp::Clear(eframe::wgpu::Color::BLACK),
-                        store: eframe::wgpu::StoreOp::Store,
-                    },
-                    depth_slice: None,
-                })],
-                depth_stencil_attachment: None,
-                timestamp_writes: None,
-                occlusion_query_set: None,
-            });
+    /// Texture bound to channel `index`, whether a still image, animation or camera
+    pub fn channel_texture(&self, index: usize) -> Option<&Texture> {
+        self.user_image_textures[index]
+            .as_ref()
+            .or_else(|| self.channel_animations[index].as_ref().map(AnimatedChannel::texture))
+            .or_else(|| self.channel_lives[index].as_ref().map(LiveChannel::texture))
+    }
 
-            rpass.set_pipeline(&self.main_image_pipeline);
-            rpass.set_bind_group(0, &self.uniform_bind_group, &[]);
-            rpass.set_bind_group(1, &self.main_texture_bind_group, &[]);
-            rpass.draw(0..6, 0..1);
-        }
+    /// Draw MainImage into a pass targeting the screen
+    fn draw_main_image(&self, rpass: &mut eframe::wgpu::RenderPass<'_>) {
+        rpass.set_pipeline(&self.main_image_pipeline);
+        rpass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        rpass.set_bind_group(1, &self.main_texture_bind_group, &[]);
+        draw(rpass, &self.main_image_geometry, self.main_image_mesh.as_ref());
+    }
+
+    /// Render a depth-tested MainImage into its offscreen target
+    fn render_main_image_offscreen(&self, blit: &ScreenBlit, device: &Device, encoder: &mut CommandEncoder, size: [u32; 2]) {
+        let target = blit.target(device, size);
+        let Some(target) = target.as_ref() else {
+            return;
+        };
+        let mut rpass = encoder.begin_render_pass(&eframe::wgpu::RenderPassDescriptor {
+            label: Some("main_image_pass"),
+            color_attachments: &[Some(eframe::wgpu::RenderPassColorAttachment {
+                view: &target.view,
+                resolve_target: None,
+                ops: eframe::wgpu::Operations {
+                    load: eframe::wgpu::LoadOp::Clear(eframe::wgpu::Color::TRANSPARENT),
+                    store: eframe::wgpu::StoreOp::Store,
+                },
+                depth_slice: None,
+            })],
+            depth_stencil_attachment: Some(eframe::wgpu::RenderPassDepthStencilAttachment {
+                view: &target.depth_view,
+                depth_ops: Some(eframe::wgpu::Operations {
+                    load: eframe::wgpu::LoadOp::Clear(1.0),
+                    store: eframe::wgpu::StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        self.draw_main_image(&mut rpass);
     }
 
     /// Update uniforms before rendering
     pub fn update_uniforms(&self, queue: &Queue, uniforms: &ShaderUniforms) {
         queue.write_buffer(&self.uniform_buffer, 0, bytemuck::bytes_of(uniforms));
     }
+
+    /// Update the camera of mesh passes before rendering
+    pub fn update_camera(&self, queue: &Queue, camera: &CameraUniforms) {
+        queue.write_buffer(&self.camera_buffer, 0, bytemuck::bytes_of(camera));
+    }
 }
 
 /// Callback for rendering multi-pass shader
@@ -871,18 +1327,32 @@ pub struct MultiPassCallback {
     pub gamma: Arc<std::sync::Mutex<f32>>,
     pub contrast: Arc<std::sync::Mutex<f32>>,
     pub saturation: Arc<std::sync::Mutex<f32>>,
+    /// Orbit camera of the preview, for mesh passes
+    pub camera: CameraUniforms,
+    /// Primary button state over the preview
+    pub mouse: PreviewMouse,
+    /// Playback settings of animated channels
+    pub channels: [ChannelSettings; 4],
 }
 
 impl eframe::egui_wgpu::CallbackTrait for MultiPassCallback {
     fn prepare(
         &self,
-        _device: &eframe::wgpu::Device,
+        device: &eframe::wgpu::Device,
         queue: &eframe::wgpu::Queue,
         screen_descriptor: &eframe::egui_wgpu::ScreenDescriptor,
         encoder: &mut eframe::wgpu::CommandEncoder,
         _resources: &mut eframe::egui_wgpu::CallbackResources,
     ) -> Vec<eframe::wgpu::CommandBuffer> {
         let elapsed = self.shader.start_time.elapsed().as_secs_f32();
+        let frame = self.shader.frame_count.fetch_add(1, Ordering::Relaxed);
+        let time_delta = self
+            .shader
+            .last_frame_time
+            .lock()
+            .unwrap()
+            .replace(elapsed)
+            .map_or(0.0, |last| elapsed - last);
 
         let resolution = [
             screen_descriptor.size_in_pixels[0] as f32,
@@ -897,8 +1367,7 @@ impl eframe::egui_wgpu::CallbackTrait for MultiPassCallback {
         let saturation = *self.saturation.lock().unwrap();
 
         // Debug log every 60 frames (about once per second at 60fps)
-        static FRAME_COUNTER: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
-        if FRAME_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed) % 60 == 0 {
+        if frame.is_multiple_of(60) {
             log::debug!("Uniforms: gamma={:.2}, contrast={:.2}, saturation={:.2}", gamma, contrast, saturation);
         }
 
@@ -912,9 +1381,22 @@ impl eframe::egui_wgpu::CallbackTrait for MultiPassCallback {
             contrast,
             saturation,
             _pad0: 0.0,
+            _pad1: [0.0; 2],
+            channel_time: std::array::from_fn(|i| {
+                match (&self.shader.channel_animations[i], &self.shader.channel_lives[i]) {
+                    (Some(animation), _) => animation.update(queue, elapsed, &self.channels[i]),
+                    (None, Some(live)) => live.update(queue),
+                    (None, None) => 0.0,
+                }
+            }),
+            mouse: self.mouse.uniform(resolution),
+            time_delta,
+            frame,
+            _pad2: [0.0; 2],
         };
 
         self.shader.update_uniforms(queue, &uniforms);
+        self.shader.update_camera(queue, &self.camera);
 
         // Render buffer passes to offscreen textures
         if let Some(ref buffer_a) = self.shader.buffer_a {
@@ -929,6 +1411,9 @@ impl eframe::egui_wgpu::CallbackTrait for MultiPassCallback {
         if let Some(ref buffer_d) = self.shader.buffer_d {
             buffer_d.render(encoder, &self.shader.uniform_bind_group);
         }
+        if let Some(blit) = &self.shader.main_image_blit {
+            self.shader.render_main_image_offscreen(blit, device, encoder, screen_descriptor.size_in_pixels);
+        }
 
         Vec::new()
     }
@@ -939,11 +1424,17 @@ impl eframe::egui_wgpu::CallbackTrait for MultiPassCallback {
         render_pass: &mut eframe::wgpu::RenderPass<'static>,
         _resources: &eframe::egui_wgpu::CallbackResources,
     ) {
-        // Render main image (which samples from buffer textures)
-        render_pass.set_pipeline(&self.shader.main_image_pipeline);
-        render_pass.set_bind_group(0, &self.shader.uniform_bind_group, &[]);
-        render_pass.set_bind_group(1, &self.shader.main_texture_bind_group, &[]);
-        render_pass.draw(0..6, 0..1);
+        // Render main image (which samples from buffer textures), or copy its offscreen render
+        match &self.shader.main_image_blit {
+            Some(blit) => {
+                if let Some(target) = blit.target.lock().unwrap().as_ref() {
+                    render_pass.set_pipeline(&blit.pipeline);
+                    render_pass.set_bind_group(0, &target.bind_group, &[]);
+                    render_pass.draw(0..3, 0..1);
+                }
+            }
+            None => self.shader.draw_main_image(render_pass),
+        }
 
         static FIRST_RENDER: std::sync::Once = std::sync::Once::new();
         FIRST_RENDER.call_once(|| {
@@ -951,3 +1442,16 @@ impl eframe::egui_wgpu::CallbackTrait for MultiPassCallback {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_screen_blit_validates() {
+        let module = naga::front::wgsl::parse_str(SCREEN_BLIT).unwrap();
+        naga::valid::Validator::new(naga::valid::ValidationFlags::all(), naga::valid::Capabilities::empty())
+            .validate(&module)
+            .unwrap();
+    }
+}
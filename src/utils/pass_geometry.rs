@@ -0,0 +1,144 @@
+//! Per-pass draw settings
+//!
+//! By default every pass draws the standard full-screen triangle: six
+//! vertices, one instance, a triangle list and no depth test. Passes with a
+//! custom vertex shader can draw anything else, e.g. a point cloud driven by
+//! `@builtin(vertex_index)` or an instanced grid using `@builtin(instance_index)`.
+
+use eframe::wgpu;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Depth buffer format of passes that depth test
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+pub const MAX_VERTEX_COUNT: u32 = 1 << 22;
+pub const MAX_INSTANCE_COUNT: u32 = 1 << 16;
+
+/// Primitive topology of a pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Topology {
+    PointList,
+    LineList,
+    LineStrip,
+    #[default]
+    TriangleList,
+    TriangleStrip,
+}
+
+impl Topology {
+    pub const ALL: [Topology; 5] = [
+        Topology::PointList,
+        Topology::LineList,
+        Topology::LineStrip,
+        Topology::TriangleList,
+        Topology::TriangleStrip,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Topology::PointList => "Points",
+            Topology::LineList => "Lines",
+            Topology::LineStrip => "Line Strip",
+            Topology::TriangleList => "Triangles",
+            Topology::TriangleStrip => "Triangle Strip",
+        }
+    }
+
+    /// Name of the topology in the WebGPU JavaScript API
+    pub fn web_name(&self) -> &'static str {
+        match self {
+            Topology::PointList => "point-list",
+            Topology::LineList => "line-list",
+            Topology::LineStrip => "line-strip",
+            Topology::TriangleList => "triangle-list",
+            Topology::TriangleStrip => "triangle-strip",
+        }
+    }
+
+    fn to_wgpu(self) -> wgpu::PrimitiveTopology {
+        match self {
+            Topology::PointList => wgpu::PrimitiveTopology::PointList,
+            Topology::LineList => wgpu::PrimitiveTopology::LineList,
+            Topology::LineStrip => wgpu::PrimitiveTopology::LineStrip,
+            Topology::TriangleList => wgpu::PrimitiveTopology::TriangleList,
+            Topology::TriangleStrip => wgpu::PrimitiveTopology::TriangleStrip,
+        }
+    }
+}
+
+/// What a pass draws: `draw(0..vertex_count, 0..instance_count)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct PassGeometry {
+    #[schemars(range(min = 1, max = MAX_VERTEX_COUNT))]
+    pub vertex_count: u32,
+    #[schemars(range(min = 1, max = MAX_INSTANCE_COUNT))]
+    pub instance_count: u32,
+    pub topology: Topology,
+    /// Depth test with `less` and write depth, cleared to 1.0 every frame
+    pub depth: bool,
+}
+
+impl Default for PassGeometry {
+    fn default() -> Self {
+        Self {
+            vertex_count: 6,
+            instance_count: 1,
+            topology: Topology::TriangleList,
+            depth: false,
+        }
+    }
+}
+
+impl PassGeometry {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Counts limited to 1..=[`MAX_VERTEX_COUNT`] and 1..=[`MAX_INSTANCE_COUNT`]
+    pub fn clamped(self) -> Self {
+        Self {
+            vertex_count: self.vertex_count.clamp(1, MAX_VERTEX_COUNT),
+            instance_count: self.instance_count.clamp(1, MAX_INSTANCE_COUNT),
+            ..self
+        }
+    }
+
+    pub fn primitive_state(&self) -> wgpu::PrimitiveState {
+        wgpu::PrimitiveState {
+            topology: self.topology.to_wgpu(),
+            ..Default::default()
+        }
+    }
+
+    /// Depth state for a pass; only passes that depth test get a depth attachment
+    pub fn depth_stencil_state(&self) -> Option<wgpu::DepthStencilState> {
+        self.depth.then(|| wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serde_defaults_and_clamping() {
+        let geometry: PassGeometry = serde_json::from_str(r#"{"vertex_count": 0, "topology": "point_list"}"#).unwrap();
+        assert_eq!(geometry.topology, Topology::PointList);
+        assert_eq!(geometry.instance_count, 1);
+        assert_eq!(geometry.clamped().vertex_count, 1);
+        assert!(serde_json::from_str::<PassGeometry>(r#"{"speed": 2}"#).is_err());
+
+        assert!(PassGeometry::default().depth_stencil_state().is_none());
+        let depth = PassGeometry { depth: true, ..Default::default() }.depth_stencil_state().unwrap();
+        assert!(depth.depth_write_enabled);
+    }
+}
@@ -11,4 +11,96 @@ pub struct ShaderUniforms {
     pub contrast: f32,
     pub saturation: f32,
     pub _pad0: f32,  // Padding for alignment
+    /// WGSL aligns `vec4<f32>` to 16 bytes, so `channel_time` starts at 48
+    pub _pad1: [f32; 2],
+    /// Playback time of each iChannel's animation, 0 for still images
+    pub channel_time: [f32; 4],
+    /// ShaderToy's `iMouse`, see `PreviewMouse::uniform`
+    pub mouse: [f32; 4],
+    /// Seconds since the previous frame
+    pub time_delta: f32,
+    /// Frames rendered since the pipeline was built
+    pub frame: u32,
+    pub _pad2: [f32; 2],
+}
+
+/// Mouse state of the preview, in 0..1 preview coordinates with the origin
+/// at the bottom left like `fragCoord`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PreviewMouse {
+    /// Last position while the button was held
+    pub position: [f32; 2],
+    /// Where the last press started
+    pub click: [f32; 2],
+    pub down: bool,
+    /// Whether the press started this frame
+    pub clicked: bool,
+}
+
+impl PreviewMouse {
+    /// `iMouse` in pixels of `resolution`: xy is the last held position, zw
+    /// the last click, z is negative while the button is up and w is
+    /// negative except on the frame of the click
+    pub fn uniform(&self, resolution: [f32; 2]) -> [f32; 4] {
+        let [x, y] = [self.position[0] * resolution[0], self.position[1] * resolution[1]];
+        let [cx, cy] = [self.click[0] * resolution[0], self.click[1] * resolution[1]];
+        [
+            x,
+            y,
+            if self.down { cx } else { -cx },
+            if self.clicked { cy } else { -cy },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::shader_constants::SHADER_BOILERPLATE;
+    use std::mem::offset_of;
+
+    #[test]
+    fn test_layout_matches_wgsl() {
+        let module = naga::front::wgsl::parse_str(SHADER_BOILERPLATE).unwrap();
+        let (members, span) = module
+            .types
+            .iter()
+            .find_map(|(_, ty)| match &ty.inner {
+                naga::TypeInner::Struct { members, span } if ty.name.as_deref() == Some("Uniforms") => Some((members.clone(), *span)),
+                _ => None,
+            })
+            .unwrap();
+        let offset = |name: &str| members.iter().find(|m| m.name.as_deref() == Some(name)).unwrap().offset as usize;
+
+        assert_eq!(span as usize, std::mem::size_of::<ShaderUniforms>());
+        assert_eq!(offset("time"), offset_of!(ShaderUniforms, time));
+        assert_eq!(offset("audio_bass"), offset_of!(ShaderUniforms, audio_bass));
+        assert_eq!(offset("audio_mid"), offset_of!(ShaderUniforms, audio_mid));
+        assert_eq!(offset("audio_high"), offset_of!(ShaderUniforms, audio_high));
+        assert_eq!(offset("resolution"), offset_of!(ShaderUniforms, resolution));
+        assert_eq!(offset("gamma"), offset_of!(ShaderUniforms, gamma));
+        assert_eq!(offset("contrast"), offset_of!(ShaderUniforms, contrast));
+        assert_eq!(offset("saturation"), offset_of!(ShaderUniforms, saturation));
+        assert_eq!(offset("channel_time"), offset_of!(ShaderUniforms, channel_time));
+        assert_eq!(offset("mouse"), offset_of!(ShaderUniforms, mouse));
+        assert_eq!(offset("time_delta"), offset_of!(ShaderUniforms, time_delta));
+        assert_eq!(offset("frame"), offset_of!(ShaderUniforms, frame));
+    }
+
+    #[test]
+    fn test_preview_mouse_follows_shadertoy() {
+        let mut mouse = PreviewMouse {
+            position: [0.5, 0.25],
+            click: [0.5, 0.25],
+            down: true,
+            clicked: true,
+        };
+        assert_eq!(mouse.uniform([200.0, 100.0]), [100.0, 25.0, 100.0, 25.0]);
+        mouse.clicked = false;
+        mouse.position = [1.0, 1.0];
+        assert_eq!(mouse.uniform([200.0, 100.0]), [200.0, 100.0, 100.0, -25.0]);
+        mouse.down = false;
+        assert_eq!(mouse.uniform([200.0, 100.0]), [200.0, 100.0, -100.0, -25.0]);
+        assert_eq!(PreviewMouse::default().uniform([200.0, 100.0]), [0.0, 0.0, -0.0, -0.0]);
+    }
 }
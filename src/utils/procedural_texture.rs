@@ -0,0 +1,479 @@
+//! Built-in generated channel textures
+//!
+//! A channel can show a [`ProceduralTexture`] instead of a file. Shards store
+//! its parameters rather than the image, and the same parameters generate the
+//! same texels on every machine.
+//!
+//! - **White noise**: independent uniform values in each of R, G, B and A.
+//! - **Blue noise**: white noise with its low frequencies filtered out, ranked
+//!   back to a uniform distribution, for dithering without clumps.
+//! - **Value** and **Perlin noise**: fractal sums of `octaves` layers on a
+//!   lattice of `scale` cells across, wrapped so the texture tiles; each of
+//!   R, G, B and A is a separate noise.
+//! - **Checker**: `scale` squares across in the two colours, the second tinted
+//!   by UV (red across, green down) so orientation and flips show.
+//! - **Gradient**: a horizontal ramp between the two colours or a preset.
+
+use image::{DynamicImage, RgbaImage};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::channel_texture::{ChannelFormat, ChannelKind};
+
+pub const MAX_PROCEDURAL_SIZE: u32 = 4096;
+pub const MAX_SCALE: u32 = 1024;
+pub const MAX_OCTAVES: u32 = 8;
+
+/// What a procedural texture generates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Pattern {
+    #[default]
+    WhiteNoise,
+    BlueNoise,
+    ValueNoise,
+    PerlinNoise,
+    Checker,
+    Gradient,
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 6] = [
+        Pattern::WhiteNoise,
+        Pattern::BlueNoise,
+        Pattern::ValueNoise,
+        Pattern::PerlinNoise,
+        Pattern::Checker,
+        Pattern::Gradient,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Pattern::WhiteNoise => "White Noise",
+            Pattern::BlueNoise => "Blue Noise",
+            Pattern::ValueNoise => "Value Noise",
+            Pattern::PerlinNoise => "Perlin Noise",
+            Pattern::Checker => "Checker",
+            Pattern::Gradient => "Gradient",
+        }
+    }
+
+    /// Whether the texels are data rather than colours
+    pub fn is_noise(&self) -> bool {
+        !matches!(self, Pattern::Checker | Pattern::Gradient)
+    }
+
+    /// Whether `scale` and `octaves` apply
+    pub fn is_fractal(&self) -> bool {
+        matches!(self, Pattern::ValueNoise | Pattern::PerlinNoise)
+    }
+}
+
+/// Colours of a gradient
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Ramp {
+    /// From the first colour to the second
+    #[default]
+    Colors,
+    Rainbow,
+    Heat,
+    Viridis,
+}
+
+impl Ramp {
+    pub const ALL: [Ramp; 4] = [Ramp::Colors, Ramp::Rainbow, Ramp::Heat, Ramp::Viridis];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Ramp::Colors => "Two Colours",
+            Ramp::Rainbow => "Rainbow",
+            Ramp::Heat => "Heat",
+            Ramp::Viridis => "Viridis",
+        }
+    }
+
+    /// Colour at `t` in 0..1
+    fn sample(&self, t: f32, colors: &[[u8; 4]; 2]) -> [u8; 4] {
+        let stops: &[[u8; 4]] = match self {
+            Ramp::Colors => colors,
+            Ramp::Rainbow => &[
+                [255, 0, 0, 255],
+                [255, 255, 0, 255],
+                [0, 255, 0, 255],
+                [0, 255, 255, 255],
+                [0, 0, 255, 255],
+                [255, 0, 255, 255],
+            ],
+            Ramp::Heat => &[
+                [0, 0, 0, 255],
+                [180, 0, 0, 255],
+                [255, 120, 0, 255],
+                [255, 230, 0, 255],
+                [255, 255, 255, 255],
+            ],
+            Ramp::Viridis => &[
+                [68, 1, 84, 255],
+                [59, 82, 139, 255],
+                [33, 145, 140, 255],
+                [94, 201, 98, 255],
+                [253, 231, 37, 255],
+            ],
+        };
+        let at = t.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+        let i = (at as usize).min(stops.len() - 2);
+        let f = at - i as f32;
+        std::array::from_fn(|c| (stops[i][c] as f32 + (stops[i + 1][c] as f32 - stops[i][c] as f32) * f).round() as u8)
+    }
+}
+
+/// Parameters of a generated channel texture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProceduralTexture {
+    pub pattern: Pattern,
+    /// Width and height in texels
+    #[schemars(inner(range(min = 1, max = MAX_PROCEDURAL_SIZE)))]
+    pub size: [u32; 2],
+    pub seed: u32,
+    /// Lattice cells of value and Perlin noise, or checker squares, across the texture
+    #[schemars(range(min = 1, max = MAX_SCALE))]
+    pub scale: u32,
+    /// Layers of value and Perlin noise, each at twice the frequency and half the amplitude
+    #[schemars(range(min = 1, max = MAX_OCTAVES))]
+    pub octaves: u32,
+    /// Colours of a gradient
+    pub ramp: Ramp,
+    /// Checker squares and the ends of a two-colour gradient, as sRGB RGBA
+    pub colors: [[u8; 4]; 2],
+}
+
+impl Default for ProceduralTexture {
+    fn default() -> Self {
+        Self {
+            pattern: Pattern::WhiteNoise,
+            size: [256, 256],
+            seed: 0,
+            scale: 8,
+            octaves: 4,
+            ramp: Ramp::Colors,
+            colors: [[0, 0, 0, 255], [255, 255, 255, 255]],
+        }
+    }
+}
+
+impl ProceduralTexture {
+    /// The texture with sizes and counts in range
+    pub fn clamped(self) -> Self {
+        Self {
+            size: self.size.map(|edge| edge.clamp(1, MAX_PROCEDURAL_SIZE)),
+            scale: self.scale.clamp(1, MAX_SCALE),
+            octaves: self.octaves.clamp(1, MAX_OCTAVES),
+            ..self
+        }
+    }
+
+    /// The format `Auto` stands for: noise is data, so it isn't sRGB decoded
+    pub fn resolve_format(&self, format: ChannelFormat, kind: ChannelKind) -> ChannelFormat {
+        match format {
+            ChannelFormat::Auto if self.pattern.is_noise() => ChannelFormat::Linear,
+            format => format.resolve(kind, false),
+        }
+    }
+
+    /// Short description, e.g. `Blue Noise 256x256`
+    pub fn label(&self) -> String {
+        format!("{} {}x{}", self.pattern.as_str(), self.size[0], self.size[1])
+    }
+
+    /// Generate the texels
+    pub fn generate(&self) -> RgbaImage {
+        let this = self.clamped();
+        let [width, height] = this.size;
+        match this.pattern {
+            Pattern::WhiteNoise => RgbaImage::from_fn(width, height, |x, y| {
+                image::Rgba(std::array::from_fn(|c| (hash3(x, y, c as u32, this.seed) >> 24) as u8))
+            }),
+            Pattern::BlueNoise => {
+                let channels: [Vec<u8>; 4] = std::array::from_fn(|c| blue_noise(width, height, hash3(c as u32, 0, 0, this.seed)));
+                RgbaImage::from_fn(width, height, |x, y| {
+                    let i = (y * width + x) as usize;
+                    image::Rgba(channels.each_ref().map(|channel| channel[i]))
+                })
+            }
+            Pattern::ValueNoise | Pattern::PerlinNoise => RgbaImage::from_fn(width, height, |x, y| {
+                let uv = [(x as f32 + 0.5) / width as f32, (y as f32 + 0.5) / height as f32];
+                image::Rgba(std::array::from_fn(|c| (this.fractal(uv, c as u32) * 255.0).round() as u8))
+            }),
+            Pattern::Checker => RgbaImage::from_fn(width, height, |x, y| {
+                let square = (x as u64 * this.scale as u64 / width as u64) + (y as u64 * this.scale as u64 / height as u64);
+                if square.is_multiple_of(2) {
+                    return image::Rgba(this.colors[0]);
+                }
+                let [r, g, b, a] = this.colors[1];
+                let u = (x as f32 + 0.5) / width as f32;
+                let v = (y as f32 + 0.5) / height as f32;
+                image::Rgba([(r as f32 * u).round() as u8, (g as f32 * v).round() as u8, b, a])
+            }),
+            Pattern::Gradient => RgbaImage::from_fn(width, height, |x, _| {
+                let t = if width > 1 { x as f32 / (width - 1) as f32 } else { 0.0 };
+                image::Rgba(this.ramp.sample(t, &this.colors))
+            }),
+        }
+    }
+
+    /// The generated texels as PNG, for exports that need an image file
+    pub fn encode_png(&self) -> Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgba8(self.generate())
+            .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .map_err(|e| format!("Failed to encode {} as PNG: {}", self.label(), e))?;
+        Ok(bytes)
+    }
+
+    /// Fractal value or Perlin noise of channel `c` at `uv`, in 0..1
+    fn fractal(&self, uv: [f32; 2], c: u32) -> f32 {
+        let mut sum = 0.0;
+        let mut total = 0.0;
+        let mut amplitude = 1.0;
+        for octave in 0..self.octaves {
+            let period = self.scale << octave;
+            let seed = hash3(c, octave, 1, self.seed);
+            let point = [uv[0] * period as f32, uv[1] * period as f32];
+            let noise = match self.pattern {
+                Pattern::PerlinNoise => perlin(point, period, seed),
+                _ => value_noise(point, period, seed),
+            };
+            sum += noise * amplitude;
+            total += amplitude;
+            amplitude *= 0.5;
+        }
+        (sum / total).clamp(0.0, 1.0)
+    }
+}
+
+/// Integer hash with good avalanche (Chris Wellons' lowbias32)
+fn hash(mut x: u32) -> u32 {
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x7feb_352d);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x846c_a68b);
+    x ^= x >> 16;
+    x
+}
+
+fn hash3(x: u32, y: u32, z: u32, seed: u32) -> u32 {
+    hash(x ^ hash(y ^ hash(z ^ hash(seed))))
+}
+
+/// A hash as a value in 0..1
+fn unit(h: u32) -> f32 {
+    h as f32 / u32::MAX as f32
+}
+
+/// Integer lattice corner `offset` from `cell`, wrapped to `period`
+fn corner(cell: [f32; 2], offset: [u32; 2], period: u32) -> [u32; 2] {
+    [(cell[0] as u32 + offset[0]) % period, (cell[1] as u32 + offset[1]) % period]
+}
+
+/// Smoothly interpolated random values at lattice points, tiling every `period` cells
+fn value_noise(point: [f32; 2], period: u32, seed: u32) -> f32 {
+    let cell = [point[0].floor(), point[1].floor()];
+    let [fx, fy] = [point[0] - cell[0], point[1] - cell[1]];
+    let value = |offset: [u32; 2]| {
+        let [x, y] = corner(cell, offset, period);
+        unit(hash3(x, y, 0, seed))
+    };
+    let [sx, sy] = [fx * fx * (3.0 - 2.0 * fx), fy * fy * (3.0 - 2.0 * fy)];
+    let top = value([0, 0]) + (value([1, 0]) - value([0, 0])) * sx;
+    let bottom = value([0, 1]) + (value([1, 1]) - value([0, 1])) * sx;
+    top + (bottom - top) * sy
+}
+
+/// Gradient noise with random unit gradients at lattice points, tiling every `period` cells
+fn perlin(point: [f32; 2], period: u32, seed: u32) -> f32 {
+    let cell = [point[0].floor(), point[1].floor()];
+    let [fx, fy] = [point[0] - cell[0], point[1] - cell[1]];
+    let dot = |offset: [u32; 2]| {
+        let [x, y] = corner(cell, offset, period);
+        let angle = unit(hash3(x, y, 0, seed)) * std::f32::consts::TAU;
+        angle.cos() * (fx - offset[0] as f32) + angle.sin() * (fy - offset[1] as f32)
+    };
+    let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+    let [sx, sy] = [fade(fx), fade(fy)];
+    let top = dot([0, 0]) + (dot([1, 0]) - dot([0, 0])) * sx;
+    let bottom = dot([0, 1]) + (dot([1, 1]) - dot([0, 1])) * sx;
+    // Unit gradients keep 2D Perlin noise within ±√2/2
+    0.5 + (top + (bottom - top) * sy) * std::f32::consts::FRAC_1_SQRT_2
+}
+
+/// One channel of blue noise: high-passed white noise, ranked to 0..=255
+fn blue_noise(width: u32, height: u32, seed: u32) -> Vec<u8> {
+    let (w, h) = (width as usize, height as usize);
+    let mut values: Vec<f32> = (0..w * h)
+        .map(|i| unit(hash3((i % w) as u32, (i / w) as u32, 0, seed)))
+        .collect();
+    for _ in 0..2 {
+        let blurred = blur_wrapped(&values, w, h);
+        for (value, low) in values.iter_mut().zip(blurred) {
+            *value -= low;
+        }
+    }
+
+    // Equal numbers of each level, in the order of the filtered values
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+    let mut texels = vec![0u8; values.len()];
+    for (rank, i) in order.into_iter().enumerate() {
+        texels[i] = (rank * 256 / values.len()) as u8;
+    }
+    texels
+}
+
+/// Gaussian blur (σ = 1.5 texels) wrapping around the edges
+fn blur_wrapped(values: &[f32], w: usize, h: usize) -> Vec<f32> {
+    const RADIUS: isize = 4;
+    let weights: Vec<f32> = (-RADIUS..=RADIUS).map(|d| (-(d * d) as f32 / (2.0 * 1.5 * 1.5)).exp()).collect();
+    let total: f32 = weights.iter().sum();
+    let pass = |input: &[f32], step: [usize; 2]| -> Vec<f32> {
+        (0..w * h)
+            .map(|i| {
+                let (x, y) = ((i % w) as isize, (i / w) as isize);
+                (-RADIUS..=RADIUS)
+                    .zip(&weights)
+                    .map(|(d, weight)| {
+                        let sx = (x + d * step[0] as isize).rem_euclid(w as isize) as usize;
+                        let sy = (y + d * step[1] as isize).rem_euclid(h as isize) as usize;
+                        input[sy * w + sx] * weight
+                    })
+                    .sum::<f32>()
+                    / total
+            })
+            .collect()
+    };
+    pass(&pass(values, [1, 0]), [0, 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texture(pattern: Pattern) -> ProceduralTexture {
+        ProceduralTexture {
+            pattern,
+            size: [64, 64],
+            seed: 7,
+            ..Default::default()
+        }
+    }
+
+    /// Variance of the red channel's 4×4 block means: low for noise without low frequencies
+    fn block_variance(image: &RgbaImage) -> f32 {
+        let means: Vec<f32> = (0..16)
+            .flat_map(|by| (0..16).map(move |bx| (bx, by)))
+            .map(|(bx, by)| {
+                let sum: u32 = (0..16).map(|i| image.get_pixel(bx * 4 + i % 4, by * 4 + i / 4)[0] as u32).sum();
+                sum as f32 / 16.0
+            })
+            .collect();
+        let mean = means.iter().sum::<f32>() / means.len() as f32;
+        means.iter().map(|m| (m - mean).powi(2)).sum::<f32>() / means.len() as f32
+    }
+
+    #[test]
+    fn test_generation_is_reproducible() {
+        for pattern in Pattern::ALL {
+            let texture = texture(pattern);
+            let image = texture.generate();
+            assert_eq!(image.dimensions(), (64, 64));
+            assert_eq!(image, texture.generate(), "{:?}", pattern);
+        }
+        let white = texture(Pattern::WhiteNoise);
+        let reseeded = ProceduralTexture { seed: 8, ..white };
+        assert_ne!(white.generate(), reseeded.generate());
+
+        // Channels are independent
+        let image = white.generate();
+        assert!(image.pixels().any(|p| p[0] != p[1]));
+    }
+
+    #[test]
+    fn test_blue_noise_lacks_low_frequencies() {
+        let blue = texture(Pattern::BlueNoise).generate();
+        let white = texture(Pattern::WhiteNoise).generate();
+        assert!(block_variance(&blue) < block_variance(&white) * 0.5, "{} vs {}", block_variance(&blue), block_variance(&white));
+
+        // Every level is used equally often
+        let mut histogram = [0u32; 256];
+        for pixel in blue.pixels() {
+            histogram[pixel[0] as usize] += 1;
+        }
+        assert!(histogram.iter().all(|count| *count == 16));
+    }
+
+    #[test]
+    fn test_fractal_noise_tiles() {
+        for pattern in [Pattern::ValueNoise, Pattern::PerlinNoise] {
+            let texture = texture(pattern);
+            for c in 0..4 {
+                let inside = texture.fractal([0.0, 0.3], c);
+                let wrapped = texture.fractal([1.0, 0.3], c);
+                assert!((inside - wrapped).abs() < 1e-4, "{:?}", pattern);
+            }
+            // Smooth: neighbours differ far less than white noise does
+            let image = texture.generate();
+            let step: u32 = (0..63).map(|x| (image.get_pixel(x, 10)[0] as i32 - image.get_pixel(x + 1, 10)[0] as i32).unsigned_abs()).sum();
+            assert!(step < 63 * 20, "{:?}: {}", pattern, step);
+        }
+    }
+
+    #[test]
+    fn test_checker_and_gradient() {
+        let checker = ProceduralTexture {
+            pattern: Pattern::Checker,
+            size: [8, 8],
+            scale: 2,
+            ..Default::default()
+        }
+        .generate();
+        assert_eq!(checker.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        assert_eq!(checker.get_pixel(7, 7).0, [0, 0, 0, 255]);
+        // Light squares are tinted by UV
+        assert_eq!(checker.get_pixel(7, 0).0, [239, 16, 255, 255]);
+        assert_eq!(checker.get_pixel(0, 7).0, [16, 239, 255, 255]);
+
+        let gradient = ProceduralTexture {
+            pattern: Pattern::Gradient,
+            size: [5, 1],
+            colors: [[0, 0, 0, 255], [200, 100, 0, 255]],
+            ..Default::default()
+        };
+        let image = gradient.generate();
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        assert_eq!(image.get_pixel(2, 0).0, [100, 50, 0, 255]);
+        assert_eq!(image.get_pixel(4, 0).0, [200, 100, 0, 255]);
+        let heat = ProceduralTexture { ramp: Ramp::Heat, ..gradient }.generate();
+        assert_eq!(heat.get_pixel(4, 0).0, [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_formats_and_clamping() {
+        let noise = texture(Pattern::PerlinNoise);
+        assert_eq!(noise.resolve_format(ChannelFormat::Auto, ChannelKind::Texture2d), ChannelFormat::Linear);
+        assert_eq!(noise.resolve_format(ChannelFormat::Srgb, ChannelKind::Texture2d), ChannelFormat::Srgb);
+        let checker = texture(Pattern::Checker);
+        assert_eq!(checker.resolve_format(ChannelFormat::Auto, ChannelKind::Texture2d), ChannelFormat::Srgb);
+
+        let huge = ProceduralTexture {
+            size: [0, 100_000],
+            octaves: 40,
+            ..Default::default()
+        }
+        .clamped();
+        assert_eq!(huge.size, [1, MAX_PROCEDURAL_SIZE]);
+        assert_eq!(huge.octaves, MAX_OCTAVES);
+
+        let png = noise.encode_png().unwrap();
+        assert_eq!(image::load_from_memory(&png).unwrap().to_rgba8(), noise.generate());
+    }
+}
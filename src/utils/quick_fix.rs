@@ -0,0 +1,535 @@
+//! Quick fixes for common shader mistakes
+//!
+//! Recognises mechanical errors in naga diagnostics — GLSL type and function
+//! names, vector types without a component type, `mix` with the blend factor
+//! first, a final expression missing its `return` — and suggests a [`Fix`] the
+//! editor can apply in one click. Mistakes without a safe mechanical edit,
+//! such as buffer textures used outside MainImage, get a descriptive fix
+//! without a span.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use crate::utils::diagnostics::{Diagnostic, Fix, Span};
+
+/// GLSL type names and their WGSL equivalents
+const TYPE_RENAMES: &[(&str, &str)] = &[
+    ("float", "f32"),
+    ("int", "i32"),
+    ("uint", "u32"),
+    ("ivec2", "vec2<i32>"),
+    ("ivec3", "vec3<i32>"),
+    ("ivec4", "vec4<i32>"),
+    ("uvec2", "vec2<u32>"),
+    ("uvec3", "vec3<u32>"),
+    ("uvec4", "vec4<u32>"),
+    ("bvec2", "vec2<bool>"),
+    ("bvec3", "vec3<bool>"),
+    ("bvec4", "vec4<bool>"),
+    ("mat2", "mat2x2<f32>"),
+    ("mat3", "mat3x3<f32>"),
+    ("mat4", "mat4x4<f32>"),
+];
+
+/// GLSL/HLSL builtins that only differ from WGSL by name
+const FUNCTION_RENAMES: &[(&str, &str)] = &[
+    ("lerp", "mix"),
+    ("frac", "fract"),
+    ("dFdx", "dpdx"),
+    ("dFdy", "dpdy"),
+    ("inversesqrt", "inverseSqrt"),
+];
+
+/// Suggest a fix for a validation error, see [`suggest`]
+pub fn suggest_for_validation(
+    error: &naga::WithSpan<naga::valid::ValidationError>,
+    diagnostic: &Diagnostic,
+    source: &str,
+) -> Option<Fix> {
+    use naga::valid::{EntryPointError, FunctionError, ValidationError};
+
+    let function_error = match error.as_inner() {
+        ValidationError::Function { source, .. } => Some(source),
+        ValidationError::EntryPoint {
+            source: EntryPointError::Function(source),
+            ..
+        } => Some(source),
+        _ => None,
+    };
+    // A function that ends without returning anything
+    if let Some(FunctionError::InvalidReturnType {
+        expression: None,
+        expected_ty: Some(_),
+    }) = function_error
+    {
+        return Some(Fix {
+            message: "Add a `return` of the declared type at the end of the function".to_string(),
+            span: None,
+            replacement: String::new(),
+        });
+    }
+    suggest(diagnostic, source)
+}
+
+/// Suggest a fix for a diagnostic whose spans still refer to `source`
+pub fn suggest(diagnostic: &Diagnostic, source: &str) -> Option<Fix> {
+    let message = diagnostic.message.as_str();
+
+    let range = diagnostic.primary_span()?.range_in(source)?;
+    let token = &source[range.clone()];
+
+    if message.starts_with("unknown type") {
+        let (_, wgsl) = TYPE_RENAMES.iter().find(|(glsl, _)| *glsl == token)?;
+        replace(
+            source,
+            range,
+            wgsl,
+            format!("WGSL spells `{}` as `{}`", token, wgsl),
+        )
+    } else if message.starts_with("no definition in scope for identifier") {
+        unknown_identifier(source, range)
+    } else if message.starts_with("expected `<`") {
+        missing_component_type(source, range.start)
+    } else if message.starts_with("expected assignment or increment/decrement") {
+        missing_return(source, range.start)
+    } else if message == "too many arguments passed to `atan`" {
+        let name = diagnostic.labels.iter().find(|l| l.message.is_empty())?;
+        let range = name.span.range_in(source)?;
+        replace(
+            source,
+            range,
+            "atan2",
+            "Use `atan2(y, x)` for the two-argument arctangent",
+        )
+    } else if message == "inconsistent type passed as argument #2 to `mix`" {
+        mix_factor_first(diagnostic, source)
+    } else {
+        None
+    }
+}
+
+fn unknown_identifier(source: &str, range: Range<usize>) -> Option<Fix> {
+    let name = &source[range.clone()];
+
+    if let Some((_, wgsl)) = TYPE_RENAMES.iter().find(|(glsl, _)| *glsl == name) {
+        return replace(
+            source,
+            range,
+            wgsl,
+            format!("WGSL spells `{}` as `{}`", name, wgsl),
+        );
+    }
+    if let Some((_, wgsl)) = FUNCTION_RENAMES.iter().find(|(other, _)| *other == name) {
+        return replace(
+            source,
+            range,
+            wgsl,
+            format!("WGSL calls `{}` `{}`", name, wgsl),
+        );
+    }
+
+    match name {
+        "mod" => {
+            let (end, args) = call_args(source, range.end)?;
+            let [x, y] = args[..] else {
+                return None;
+            };
+            let (replacement, message) = if matches!(y, "1" | "1.0" | "1.") {
+                (
+                    format!("fract({})", x),
+                    "WGSL has no `mod`; `mod(x, 1.0)` is `fract(x)`",
+                )
+            } else {
+                let (x, y) = (operand(x), operand(y));
+                (
+                    format!("({x} - {y} * floor({x} / {y}))"),
+                    "WGSL has no `mod`; GLSL `mod(x, y)` is `x - y * floor(x / y)`",
+                )
+            };
+            replace(source, range.start..end, &replacement, message)
+        }
+        "texture" | "texture2D" => {
+            let sample = call_args(source, range.end).and_then(|(end, args)| {
+                let texture = args.first()?;
+                let sampler = format!("{}_sampler", texture.strip_suffix("_texture")?);
+                let rest = args[1..].join(", ");
+                Some((
+                    end,
+                    format!("textureSample({}, {}, {})", texture, sampler, rest),
+                ))
+            });
+            match sample {
+                Some((end, replacement)) => replace(
+                    source,
+                    range.start..end,
+                    &replacement,
+                    "WGSL samples with `textureSample(texture, sampler, uv)`",
+                ),
+                None => Some(Fix {
+                    message: "WGSL samples with `textureSample(texture, sampler, uv)`".to_string(),
+                    span: None,
+                    replacement: String::new(),
+                }),
+            }
+        }
+        _ if is_buffer_binding(name) => Some(Fix {
+            message: format!(
+                "`{}` is only bound in MainImage; sample other buffers from MainImage",
+                name
+            ),
+            span: None,
+            replacement: String::new(),
+        }),
+        _ => None,
+    }
+}
+
+/// `mix(t, a, b)` with a scalar blend factor first, as if it were `mix(a, b, t)`
+fn mix_factor_first(diagnostic: &Diagnostic, source: &str) -> Option<Fix> {
+    let scalar_first = diagnostic.notes.first().is_some_and(|note| {
+        ["f32", "f16", "{AbstractFloat}"].iter().any(|ty| {
+            *note
+                == format!(
+                    "Because argument #1 has type {}, only the following types",
+                    ty
+                )
+        })
+    });
+    if !scalar_first {
+        return None;
+    }
+    let name = diagnostic.labels.iter().find(|l| l.message.is_empty())?;
+    let range = name.span.range_in(source)?;
+    let (end, args) = call_args(source, range.end)?;
+    let [t, a, b] = args[..] else {
+        return None;
+    };
+    replace(
+        source,
+        range.start..end,
+        &format!("mix({}, {}, {})", a, b, t),
+        "`mix` takes the blend factor last: `mix(a, b, t)`",
+    )
+}
+
+/// `vec3` or `mat2x2` written without `<f32>` as a type
+fn missing_component_type(source: &str, at: usize) -> Option<Fix> {
+    let before = source[..at].trim_end();
+    let start = before
+        .rfind(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .map_or(0, |i| i + 1);
+    let word = &before[start..];
+
+    let is_vector = matches!(word, "vec2" | "vec3" | "vec4");
+    let is_matrix = word.len() == 6
+        && word.starts_with("mat")
+        && word.as_bytes()[4] == b'x'
+        && matches!(word.as_bytes()[3], b'2'..=b'4')
+        && matches!(word.as_bytes()[5], b'2'..=b'4');
+    if !is_vector && !is_matrix {
+        return None;
+    }
+
+    let replacement = format!("{}<f32>", word);
+    let message = format!("Add the component type: `{}`", replacement);
+    replace(source, start..before.len(), &replacement, message)
+}
+
+/// A block's last statement is an expression, as in GLSL-style `{ ...; col }`
+fn missing_return(source: &str, at: usize) -> Option<Fix> {
+    let statement_start = source[..at].rfind([';', '{', '}']).map_or(0, |i| i + 1);
+    let start = statement_start
+        + (source[statement_start..].len() - source[statement_start..].trim_start().len());
+
+    let mut depth = 0i32;
+    let mut end = None;
+    for (i, c) in source[at..].char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ';' if depth == 0 => return None,
+            '}' if depth == 0 => {
+                end = Some(at + i);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let expression = source[start..end?].trim_end();
+    if expression.is_empty() || start > at {
+        return None;
+    }
+    let replacement = format!("return {};", expression);
+    let message = format!("Return the final expression: `{}`", replacement);
+    replace(
+        source,
+        start..start + expression.len(),
+        &replacement,
+        message,
+    )
+}
+
+/// Arguments of a call whose name ends at `name_end`, and the end of the call
+fn call_args(source: &str, name_end: usize) -> Option<(usize, Vec<&str>)> {
+    let open = name_end + source[name_end..].find(|c: char| !c.is_whitespace())?;
+    if !source[open..].starts_with('(') {
+        return None;
+    }
+
+    let mut depth = 0;
+    let mut args = Vec::new();
+    let mut arg_start = open + 1;
+    for (i, c) in source[open..].char_indices() {
+        let i = open + i;
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    args.push(source[arg_start..i].trim());
+                    args.retain(|arg| !arg.is_empty());
+                    return Some((i + 1, args));
+                }
+            }
+            ',' if depth == 1 => {
+                args.push(source[arg_start..i].trim());
+                arg_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `expression` as an operand: bare if it is a single name, number or field
+/// access, otherwise in parentheses
+fn operand(expression: &str) -> Cow<'_, str> {
+    if expression
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+    {
+        Cow::Borrowed(expression)
+    } else {
+        Cow::Owned(format!("({})", expression))
+    }
+}
+
+fn is_buffer_binding(name: &str) -> bool {
+    let Some(rest) = name.strip_prefix("buffer_") else {
+        return false;
+    };
+    let mut chars = rest.chars();
+    matches!(chars.next(), Some('a'..='d')) && matches!(chars.as_str(), "_texture" | "_sampler")
+}
+
+/// Fix replacing `range`, which must stay on one line so the span can hold it
+fn replace(
+    source: &str,
+    range: Range<usize>,
+    replacement: &str,
+    message: impl Into<String>,
+) -> Option<Fix> {
+    if source[range.clone()].contains('\n') {
+        return None;
+    }
+    Some(Fix {
+        message: message.into(),
+        span: Some(Span::from_range(source, range)),
+        replacement: replacement.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fix_for(source: &str) -> Option<Fix> {
+        match naga::front::wgsl::parse_str(source) {
+            Err(e) => suggest(&Diagnostic::from_parse_error(&e, source), source),
+            Ok(module) => {
+                let mut validator = naga::valid::Validator::new(
+                    naga::valid::ValidationFlags::all(),
+                    naga::valid::Capabilities::all(),
+                );
+                let error = validator.validate(&module).unwrap_err();
+                let diagnostic = Diagnostic::from_validation_error(&error, source);
+                suggest_for_validation(&error, &diagnostic, source)
+            }
+        }
+    }
+
+    /// Apply the fix for `source` and check the result still parses
+    fn apply_fix(source: &str) -> String {
+        let fix = fix_for(source).unwrap_or_else(|| panic!("no fix for {}", source));
+        let fixed = fix.apply(source).unwrap();
+        naga::front::wgsl::parse_str(&fixed)
+            .unwrap_or_else(|e| panic!("{}", e.emit_to_string(&fixed)));
+        fixed
+    }
+
+    #[test]
+    fn test_fixes_apply() {
+        let cases = [
+            ("fn f() -> f32 {\n    let x: float = 1.0;\n    return x;\n}", "let x: f32 = 1.0;"),
+            ("fn f() -> vec3<f32> {\n    var c: vec3;\n    return c;\n}", "var c: vec3<f32>;"),
+            ("fn f() -> f32 {\n    return mod(3.0, 1.0);\n}", "return fract(3.0);"),
+            ("fn f() -> f32 {\n    return mod(3.0, 2.0);\n}", "return (3.0 - 2.0 * floor(3.0 / 2.0));"),
+            (
+                "fn f(a: f32, b: f32) -> f32 {\n    return mod(a + b, b - 1.0);\n}",
+                "return ((a + b) - (b - 1.0) * floor((a + b) / (b - 1.0)));",
+            ),
+            ("fn f() -> f32 {\n    return lerp(3.0, 2.0, 0.5);\n}", "return mix(3.0, 2.0, 0.5);"),
+            ("fn f() -> f32 {\n    return atan(1.0, 2.0);\n}", "return atan2(1.0, 2.0);"),
+            ("fn f() -> f32 {\n    let a = 1.0;\n    a * 2.0\n}", "    return a * 2.0;\n}"),
+            (
+                "@group(0) @binding(0) var buffer_a_texture: texture_2d<f32>;\n@group(0) @binding(1) var buffer_a_sampler: sampler;\n@fragment\nfn f() -> @location(0) vec4<f32> {\n    return texture(buffer_a_texture, vec2<f32>(0.5));\n}",
+                "return textureSample(buffer_a_texture, buffer_a_sampler, vec2<f32>(0.5));",
+            ),
+            ("fn f() -> vec3<f32> {\n    return mix(0.5, vec3<f32>(1.0), vec3<f32>(0.0));\n}", "return mix(vec3<f32>(1.0), vec3<f32>(0.0), 0.5);"),
+            ("fn f(t: f32, a: vec3<f32>) -> vec3<f32> {\n    return mix(t, a, vec3<f32>(0.0));\n}", "return mix(a, vec3<f32>(0.0), t);"),
+        ];
+
+        for (source, expected) in cases {
+            let fixed = apply_fix(source);
+            assert!(fixed.contains(expected), "{}", fixed);
+        }
+    }
+
+    #[test]
+    fn test_type_renames() {
+        let fixed = apply_fix("fn f() -> f32 {\n    let v = ivec2(1, 2);\n    return 1.0;\n}");
+        assert!(fixed.contains("let v = vec2<i32>(1, 2);"), "{}", fixed);
+
+        let fix = fix_for("fn f() -> f32 {\n    let m: mat3 = mat3x3<f32>();\n    return 1.0;\n}")
+            .unwrap();
+        assert_eq!(fix.replacement, "mat3x3<f32>");
+        assert!(fix.message.contains("`mat3`"), "{}", fix.message);
+    }
+
+    #[test]
+    fn test_function_renames() {
+        let cases = [
+            ("lerp(1.0, 2.0, 0.5)", "mix(1.0, 2.0, 0.5)"),
+            ("frac(1.5)", "fract(1.5)"),
+            ("inversesqrt(4.0)", "inverseSqrt(4.0)"),
+            ("dFdx(0.5)", "dpdx(0.5)"),
+            ("dFdy(0.5)", "dpdy(0.5)"),
+        ];
+        for (glsl, wgsl) in cases {
+            let fixed = apply_fix(&format!(
+                "@fragment\nfn f() -> @location(0) vec4<f32> {{\n    return vec4<f32>({});\n}}",
+                glsl
+            ));
+            assert!(fixed.contains(&format!("vec4<f32>({})", wgsl)), "{}", fixed);
+        }
+    }
+
+    #[test]
+    fn test_mod() {
+        assert_eq!(operand("x"), "x");
+        assert_eq!(operand("uv.x"), "uv.x");
+        assert_eq!(operand("2.0"), "2.0");
+        assert_eq!(operand("a * b"), "(a * b)");
+        assert_eq!(operand("-a"), "(-a)");
+        assert_eq!(operand("f(a)"), "(f(a))");
+
+        // Only `1.0` becomes `fract`, whatever the first argument is
+        let fixed = apply_fix("fn f(a: f32) -> f32 {\n    return mod(a * 2.0, 1.0);\n}");
+        assert!(fixed.contains("return fract(a * 2.0);"), "{}", fixed);
+
+        let fixed = apply_fix("fn f(uv: vec2<f32>) -> f32 {\n    return mod(uv.x, -2.0);\n}");
+        assert!(
+            fixed.contains("return (uv.x - (-2.0) * floor(uv.x / (-2.0)));"),
+            "{}",
+            fixed
+        );
+    }
+
+    #[test]
+    fn test_texture_sample() {
+        let fixed = apply_fix("@group(0) @binding(0) var image_texture: texture_2d<f32>;\n@group(0) @binding(1) var image_sampler: sampler;\n@fragment\nfn f() -> @location(0) vec4<f32> {\n    return texture2D(image_texture, vec2<f32>(0.5));\n}");
+        assert!(
+            fixed.contains("return textureSample(image_texture, image_sampler, vec2<f32>(0.5));"),
+            "{}",
+            fixed
+        );
+
+        // Without a `_texture` name the sampler can't be guessed
+        let fix = fix_for("@fragment\nfn f() -> @location(0) vec4<f32> {\n    return texture(image, vec2<f32>(0.5));\n}")
+            .unwrap();
+        assert!(fix.span.is_none());
+        assert!(fix.message.contains("textureSample"), "{}", fix.message);
+    }
+
+    #[test]
+    fn test_component_type() {
+        let fixed = apply_fix("fn f() -> f32 {\n    var m: mat2x3;\n    return 1.0;\n}");
+        assert!(fixed.contains("var m: mat2x3<f32>;"), "{}", fixed);
+
+        let fixed = apply_fix("fn f(c: vec4) -> f32 {\n    return c.x;\n}");
+        assert!(fixed.contains("fn f(c: vec4<f32>)"), "{}", fixed);
+    }
+
+    #[test]
+    fn test_missing_return() {
+        let fixed = apply_fix(
+            "fn f(uv: vec2<f32>) -> vec2<f32> {\n    let a = 2.0;\n    (uv + 1.0) * a\n}",
+        );
+        assert!(fixed.contains("    return (uv + 1.0) * a;\n}"), "{}", fixed);
+
+        // No expression to return: found from the validation error itself
+        let fix = fix_for("fn f() -> f32 {\n    let a = 1.0;\n}").unwrap();
+        assert!(fix.span.is_none());
+        assert!(fix.message.contains("return"), "{}", fix.message);
+
+        let fix =
+            fix_for("@fragment\nfn f() -> @location(0) vec4<f32> {\n    let a = 1.0;\n}").unwrap();
+        assert!(fix.span.is_none());
+        assert!(fix.message.contains("return"), "{}", fix.message);
+    }
+
+    #[test]
+    fn test_atan2() {
+        let fixed = apply_fix("fn f(p: vec2<f32>) -> f32 {\n    return atan(p.y, p.x) * 0.5;\n}");
+        assert!(fixed.contains("return atan2(p.y, p.x) * 0.5;"), "{}", fixed);
+    }
+
+    #[test]
+    fn test_mix_order() {
+        let fixed = apply_fix(
+            "fn f(a: vec4<f32>, b: vec4<f32>) -> vec4<f32> {\n    return mix(0.25, a, b);\n}",
+        );
+        assert!(fixed.contains("return mix(a, b, 0.25);"), "{}", fixed);
+
+        // Mismatched vectors aren't a swapped blend factor
+        assert!(fix_for(
+            "fn f(a: vec4<f32>, b: vec3<f32>) -> vec4<f32> {\n    return mix(a, b, 0.5);\n}"
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_buffer_binding() {
+        assert!(is_buffer_binding("buffer_b_texture"));
+        assert!(is_buffer_binding("buffer_d_sampler"));
+        assert!(!is_buffer_binding("buffer_e_texture"));
+        assert!(!is_buffer_binding("buffer_a"));
+
+        for source in [
+            "fn f() -> f32 {\n    let size = textureDimensions(buffer_b_texture);\n    return 1.0;\n}",
+            "fn f() -> vec4<f32> {\n    return textureLoad(buffer_b_texture, vec2<i32>(0), 0) * 2.0;\n}",
+        ] {
+            let fix = fix_for(source).unwrap();
+            assert!(fix.span.is_none());
+            assert!(fix.message.contains("only bound in MainImage"), "{}", fix.message);
+        }
+    }
+
+    #[test]
+    fn test_unrelated_errors() {
+        // Fixes are never offered for code that happens to look similar
+        assert!(fix_for("fn f() -> f32 {\n    return undefined_name;\n}").is_none());
+        assert!(fix_for("fn f() -> f32 {\n    return true;\n}").is_none());
+    }
+}
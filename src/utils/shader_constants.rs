@@ -16,6 +16,11 @@ fn vs_main(@builtin(vertex_index) vi: u32) -> VSOut {
 }
 "#;
 
+/// Placeholder for the Common tab (comment-only code is not injected)
+pub const DEFAULT_COMMON: &str = r#"// Common: functions, structs and constants shared by every pass
+// This code is injected after the boilerplate in MainImage and Buffer A-D
+"#;
+
 /// Default fragment shader code (simple gradient for fallback)
 pub const DEFAULT_FRAGMENT: &str = r#"@fragment
 fn fs_main(in: VSOut) -> @location(0) vec4<f32> {
@@ -45,7 +50,7 @@ fn fs_main(in: VSOut) -> @location(0) vec4<f32> {
 /// Standard boilerplate auto-injected into every shader
 ///
 /// Includes:
-/// - Uniforms struct with time, audio bands, and resolution
+/// - Uniforms struct with time, audio bands, resolution, iChannel playback times, mouse and frame timing
 /// - VSOut struct for vertex shader output
 pub const SHADER_BOILERPLATE: &str = r#"
 // Auto-injected uniforms (available in all shaders)
@@ -59,6 +64,12 @@ struct Uniforms {
     contrast: f32,
     saturation: f32,
     _pad0: f32,
+    _pad1: vec2<f32>,
+    channel_time: vec4<f32>,
+    mouse: vec4<f32>,
+    time_delta: f32,
+    frame: u32,
+    _pad2: vec2<f32>,
 }
 
 @group(0) @binding(0)
@@ -127,6 +138,51 @@ fn vs_main(@builtin(vertex_index) vi: u32) -> VSOut {
 }
 "#;
 
+/// Declarations injected into passes with a mesh attached
+///
+/// The vertex layout is fixed by `MeshVertex` in mesh.rs and the camera
+/// matches `CameraUniforms` in camera.rs.
+pub const MESH_BINDINGS: &str = r#"
+// Auto-injected orbit camera (mesh passes)
+struct Camera {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    view_projection: mat4x4<f32>,
+    eye: vec4<f32>,
+}
+
+@group(0) @binding(1)
+var<uniform> camera: Camera;
+
+// Auto-injected mesh vertex attributes
+struct MeshVertex {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+}
+
+// Auto-injected mesh vertex output (fragments taking VSOut still work)
+struct MeshOut {
+    @builtin(position) pos: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) world_pos: vec3<f32>,
+}
+"#;
+
+/// Standard vertex shader of mesh passes: project the mesh with the camera
+pub const STANDARD_MESH_VERTEX: &str = r#"
+@vertex
+fn vs_main(v: MeshVertex) -> MeshOut {
+    var out: MeshOut;
+    out.pos = camera.view_projection * vec4<f32>(v.position, 1.0);
+    out.uv = v.uv;
+    out.normal = v.normal;
+    out.world_pos = v.position;
+    return out;
+}
+"#;
+
 /// Multi-pass texture bindings (added for MainImage and buffers that need texture access)
 /// Layout matches multi_buffer_pipeline.rs bind group layout:
 /// Buffer A: texture @0, sampler @1
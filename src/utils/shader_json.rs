@@ -1,53 +1,117 @@
 #![allow(dead_code)]
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use crate::utils::BufferKind;
-use crate::utils::shader_constants::{SHADER_BOILERPLATE, STANDARD_VERTEX, TEXTURE_BINDINGS};
+use std::collections::{BTreeMap, HashMap};
+use crate::screens::shader_buffer::ShaderBuffer;
+use crate::utils::{has_shader_code, BufferKind, PassGeometry};
+use crate::utils::channel_texture::{shader_boilerplate, ChannelSettings};
+use crate::utils::procedural_texture::ProceduralTexture;
+use crate::utils::shader_constants::{STANDARD_VERTEX, TEXTURE_BINDINGS};
 
 /// JSON shader format for editor exports
 /// Supports both plain text and base64-encoded shaders
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Shader code is held decoded; `encoding` only decides how [`ShaderJson::to_json`]
+/// writes it. Field doc comments are the descriptions in [`ShaderJson::json_schema`],
+/// so each is a single line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct ShaderJson {
+    /// Schema version; older shards are migrated on load
     #[serde(default = "default_version")]
+    #[schemars(extend("const" = SCHEMA_VERSION))]
     pub version: String,
 
+    /// Display name (since 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Free-form description (since 2.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// RFC 3339 timestamp of the export
     #[serde(skip_serializing_if = "Option::is_none")]
     pub exported_at: Option<String>,
 
-    /// Encoding format: "plain" or "base64" (default: "plain")
+    /// How shader code fields are stored: `plain` or `base64`
     #[serde(default = "default_encoding")]
+    #[schemars(extend("enum" = ["plain", "base64"]))]
     pub encoding: String,
 
+    /// MainImage vertex shader, if not the standard one
     #[serde(skip_serializing_if = "Option::is_none")]
     pub vertex: Option<String>,
 
-    pub fragment: String,  // MainImage - required
+    /// MainImage fragment shader
+    pub fragment: String,
+
+    /// Code shared by every pass, injected after the boilerplate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub common: Option<String>,
 
+    /// Buffer A fragment shader
     #[serde(skip_serializing_if = "Option::is_none")]
     pub buffer_a: Option<String>,
 
+    /// Buffer B fragment shader
     #[serde(skip_serializing_if = "Option::is_none")]
     pub buffer_b: Option<String>,
 
+    /// Buffer C fragment shader
     #[serde(skip_serializing_if = "Option::is_none")]
     pub buffer_c: Option<String>,
 
+    /// Buffer D fragment shader
     #[serde(skip_serializing_if = "Option::is_none")]
     pub buffer_d: Option<String>,
 
-    /// Base64-encoded PNG/JPEG image data for iChannel0-3
+    /// Buffer A vertex shader, if not the standard full-screen triangle (since 2.1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_a_vertex: Option<String>,
+
+    /// Buffer B vertex shader, if not the standard full-screen triangle (since 2.1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_b_vertex: Option<String>,
+
+    /// Buffer C vertex shader, if not the standard full-screen triangle (since 2.1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_c_vertex: Option<String>,
+
+    /// Buffer D vertex shader, if not the standard full-screen triangle (since 2.1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_d_vertex: Option<String>,
+
+    /// Base64-encoded PNG/JPEG, animated GIF/APNG or OpenEXR (float channels) bound to iChannel0
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("contentEncoding" = "base64"))]
     pub ichannel0: Option<String>,
 
+    /// Image bound to iChannel1, encoded like `ichannel0`
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("contentEncoding" = "base64"))]
     pub ichannel1: Option<String>,
 
+    /// Image bound to iChannel2, encoded like `ichannel0`
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("contentEncoding" = "base64"))]
     pub ichannel2: Option<String>,
 
+    /// Image bound to iChannel3, encoded like `ichannel0`
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(extend("contentEncoding" = "base64"))]
     pub ichannel3: Option<String>,
 
+    /// Draw settings per pass; passes not listed draw the full-screen triangle (since 2.2)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(transform = pass_keys)]
+    pub geometry: Option<BTreeMap<String, PassGeometry>>,
+
+    /// Settings per iChannel; channels not listed are 2D with the automatic format (since 2.3)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(transform = channel_keys)]
+    pub channels: Option<BTreeMap<String, ChannelSettings>>,
+
     /// Gamma correction value (default: 1.0 = no correction)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub gamma: Option<f32>,
@@ -61,42 +125,399 @@ pub struct ShaderJson {
     pub saturation: Option<f32>,
 }
 
-fn default_version() -> String {
-    "1.0".to_string()
+/// Limit the keys of the `geometry` map to pass names
+fn pass_keys(schema: &mut schemars::Schema) {
+    schema.insert(
+        "propertyNames".to_string(),
+        serde_json::json!({ "enum": BufferKind::ALL.map(|kind| kind.file_stem()) }),
+    );
+}
+
+/// Limit the keys of the `channels` map to `ichannel0`..`ichannel3`
+fn channel_keys(schema: &mut schemars::Schema) {
+    schema.insert("propertyNames".to_string(), serde_json::json!({ "enum": CHANNEL_KEYS }));
+}
+
+/// Editor contents for [`ShaderJson::from_editor_state`], with includes
+/// already resolved
+pub struct EditorState<'a> {
+    pub buffers: &'a HashMap<BufferKind, ShaderBuffer>,
+    pub common: &'a str,
+    /// Encoded PNG/JPEG bytes for iChannel0-3
+    pub images: &'a [Option<Vec<u8>>; 4],
+    pub channels: &'a [ChannelSettings; 4],
+    pub gamma: f32,
+    pub contrast: f32,
+    pub saturation: f32,
+}
+
+/// Version written by this editor
+///
+/// - `1.0`: the original format. Unversioned shards are 1.0. Blank code fields
+///   (`"buffer_a": ""`) were allowed and meant "no code"; some shards carried
+///   informal `name`/`description` fields.
+/// - `2.0`: blank code fields are left out; `name` and `description` are part
+///   of the schema. Unknown fields are rejected in strict mode.
+/// - `2.1`: Buffer A-D carry their own vertex shader (`buffer_a_vertex`..).
+///   Before, `vertex` was used by every pass.
+/// - `2.2`: optional per-pass `geometry` (vertex/instance count, topology, depth).
+/// - `2.3`: optional per-channel `channels` settings (2D, cubemap or volume).
+/// - `2.4`: channel settings gain a texel `format`; float channels embed OpenEXR.
+/// - `2.5`: channel settings gain `playback` and `rate`; channels may embed animated GIF/APNG.
+/// - `2.6`: channel settings gain sampler `wrap` and `filter`, and `flip_y`.
+/// - `2.7`: channel settings gain `procedural`, a generated texture stored as its parameters.
+pub const SCHEMA_VERSION: &str = "2.7";
+
+/// Migration from each older version to the next, applied in order until
+/// the shard reaches [`SCHEMA_VERSION`]
+const MIGRATIONS: &[(&str, Migration)] = &[
+    ("1.0", migrate_1_0),
+    ("2.0", migrate_2_0),
+    ("2.1", migrate_2_1),
+    ("2.2", migrate_2_2),
+    ("2.3", migrate_2_3),
+    ("2.4", migrate_2_4),
+    ("2.5", migrate_2_5),
+    ("2.6", migrate_2_6),
+];
+
+/// Keys of the `channels` map, by channel index
+pub(crate) const CHANNEL_KEYS: [&str; 4] = ["ichannel0", "ichannel1", "ichannel2", "ichannel3"];
+
+/// Rewrites a shard in place and returns the version it now has
+type Migration = fn(&mut JsonObject) -> &'static str;
+
+type JsonObject = serde_json::Map<String, serde_json::Value>;
+
+/// Shader code fields, in the order [`ShaderJson::code_fields_mut`] yields them
+const CODE_FIELDS: [&str; 11] = [
+    "fragment",
+    "vertex",
+    "common",
+    "buffer_a",
+    "buffer_b",
+    "buffer_c",
+    "buffer_d",
+    "buffer_a_vertex",
+    "buffer_b_vertex",
+    "buffer_c_vertex",
+    "buffer_d_vertex",
+];
+
+/// Buffer A-D fragment fields and their vertex fields
+const BUFFER_VERTEX_FIELDS: [(&str, &str); 4] = [
+    ("buffer_a", "buffer_a_vertex"),
+    ("buffer_b", "buffer_b_vertex"),
+    ("buffer_c", "buffer_c_vertex"),
+    ("buffer_d", "buffer_d_vertex"),
+];
+
+/// Remove the fields of `value` that `schema` doesn't describe, collecting
+/// their paths in `unknown`
+fn drop_unknown_fields(
+    value: &mut serde_json::Value,
+    schema: &serde_json::Value,
+    path: &str,
+    unknown: &mut Vec<String>,
+) {
+    // `Option`s are an `anyOf` of the type and null
+    for branch in ["anyOf", "oneOf"].into_iter().filter_map(|key| schema.get(key)?.as_array()).flatten() {
+        drop_unknown_fields(value, branch, path, unknown);
+    }
+    let serde_json::Value::Object(fields) = value else {
+        return;
+    };
+    let field_path = |field: &str| match path {
+        "" => field.to_string(),
+        path => format!("{}.{}", path, field),
+    };
+
+    if let Some(properties) = schema.get("properties").and_then(|properties| properties.as_object()) {
+        fields.retain(|field, _| {
+            let known = properties.contains_key(field);
+            if !known {
+                unknown.push(field_path(field));
+            }
+            known
+        });
+        for (field, value) in fields.iter_mut() {
+            drop_unknown_fields(value, &properties[field], &field_path(field), unknown);
+        }
+    } else if let Some(entries) = schema.get("additionalProperties").filter(|entries| entries.is_object()) {
+        for (key, value) in fields.iter_mut() {
+            drop_unknown_fields(value, entries, &field_path(key), unknown);
+        }
+    }
+}
+
+pub(crate) fn default_version() -> String {
+    SCHEMA_VERSION.to_string()
 }
 
-fn default_encoding() -> String {
+pub(crate) fn default_encoding() -> String {
     "plain".to_string()
 }
 
 impl ShaderJson {
-    /// Parse JSON shader from string and decode if needed
+    /// Parse JSON shader from string, migrate it and decode if needed
+    ///
+    /// Unknown fields are ignored and base64 that does not decode is kept as
+    /// plain text, with a warning in the log; see [`ShaderJson::from_json_strict`].
     pub fn from_json(json_str: &str) -> Result<Self, serde_json::Error> {
-        let mut shader: Self = serde_json::from_str(json_str)?;
+        Self::parse(json_str, false)
+    }
 
-        // Decode base64 fields if encoding is "base64"
-        if shader.encoding == "base64" {
-            shader.fragment = decode_base64(&shader.fragment).unwrap_or(shader.fragment);
-            if let Some(ref vertex) = shader.vertex {
-                shader.vertex = Some(decode_base64(vertex).unwrap_or_else(|| vertex.clone()));
+    /// Like [`ShaderJson::from_json`], but unknown fields, unknown encodings
+    /// and undecodable base64 are errors
+    pub fn from_json_strict(json_str: &str) -> Result<Self, serde_json::Error> {
+        Self::parse(json_str, true)
+    }
+
+    fn parse(json_str: &str, strict: bool) -> Result<Self, serde_json::Error> {
+        use serde::de::Error;
+
+        let mut value: serde_json::Value = serde_json::from_str(json_str)?;
+        let shard = value
+            .as_object_mut()
+            .ok_or_else(|| serde_json::Error::custom("a shard must be a JSON object"))?;
+        migrate(shard).map_err(serde_json::Error::custom)?;
+
+        // Passes and channels are map keys, which `deny_unknown_fields` doesn't check
+        let mut unknown_keys = Vec::new();
+        if let Some(serde_json::Value::Object(passes)) = shard.get("geometry") {
+            unknown_keys.extend(
+                passes
+                    .keys()
+                    .filter(|pass| !BufferKind::ALL.iter().any(|kind| kind.file_stem() == pass.as_str()))
+                    .map(|pass| format!("geometry pass `{}`", pass)),
+            );
+        }
+        if let Some(serde_json::Value::Object(channels)) = shard.get("channels") {
+            unknown_keys.extend(
+                channels
+                    .keys()
+                    .filter(|channel| !CHANNEL_KEYS.contains(&channel.as_str()))
+                    .map(|channel| format!("channel `{}`", channel)),
+            );
+        }
+        for key in unknown_keys {
+            if strict {
+                return Err(serde_json::Error::custom(format!("unknown {}", key)));
             }
-            if let Some(ref buffer_a) = shader.buffer_a {
-                shader.buffer_a = Some(decode_base64(buffer_a).unwrap_or_else(|| buffer_a.clone()));
+            log::warn!("Ignoring unknown {}", key);
+        }
+
+        // Strict parsing leaves unknown fields to serde, which rejects them
+        if !strict {
+            let mut unknown = Vec::new();
+            drop_unknown_fields(&mut value, &Self::json_schema(), "", &mut unknown);
+            for field in unknown {
+                log::warn!("Ignoring unknown shard field `{}`", field);
             }
-            if let Some(ref buffer_b) = shader.buffer_b {
-                shader.buffer_b = Some(decode_base64(buffer_b).unwrap_or_else(|| buffer_b.clone()));
+        }
+
+        let mut shader: Self = serde_json::from_value(value)?;
+        if let Some(channels) = &mut shader.channels {
+            channels.retain(|channel, _| CHANNEL_KEYS.contains(&channel.as_str()));
+            for settings in channels.values_mut() {
+                settings.procedural = settings.procedural.map(ProceduralTexture::clamped);
             }
-            if let Some(ref buffer_c) = shader.buffer_c {
-                shader.buffer_c = Some(decode_base64(buffer_c).unwrap_or_else(|| buffer_c.clone()));
+        }
+        if let Some(geometry) = &mut shader.geometry {
+            geometry.retain(|pass, _| BufferKind::ALL.iter().any(|kind| kind.file_stem() == pass));
+            for pass in geometry.values_mut() {
+                *pass = pass.clamped();
             }
-            if let Some(ref buffer_d) = shader.buffer_d {
-                shader.buffer_d = Some(decode_base64(buffer_d).unwrap_or_else(|| buffer_d.clone()));
+        }
+        match shader.encoding.as_str() {
+            "plain" => {}
+            "base64" => {
+                for (field, code) in shader.code_fields_mut() {
+                    match decode_base64(code) {
+                        Some(decoded) => *code = decoded,
+                        None if strict => {
+                            return Err(serde_json::Error::custom(format!(
+                                "`{}` is not valid base64-encoded UTF-8",
+                                field
+                            )))
+                        }
+                        None => log::warn!("`{}` is not valid base64, keeping it as plain text", field),
+                    }
+                }
+            }
+            other if strict => {
+                return Err(serde_json::Error::custom(format!(
+                    "unknown encoding `{}`, expected `plain` or `base64`",
+                    other
+                )))
+            }
+            other => log::warn!("Unknown encoding `{}`, reading shader code as plain text", other),
+        }
+
+        if strict {
+            let channels = [&shader.ichannel0, &shader.ichannel1, &shader.ichannel2, &shader.ichannel3];
+            for (i, channel) in channels.into_iter().enumerate() {
+                if let Some(data) = channel {
+                    base64::Engine::decode(&base64::engine::general_purpose::STANDARD, data).map_err(|e| {
+                        serde_json::Error::custom(format!("`ichannel{}` is not valid base64: {}", i, e))
+                    })?;
+                }
             }
         }
 
         Ok(shader)
     }
 
+    /// JSON Schema (draft 2020-12) for shards of the current [`SCHEMA_VERSION`]
+    ///
+    /// Derived from the serde types with schemars, subschemas inlined.
+    /// Published as `data/shard.schema.json`; regenerate it with
+    /// `UPDATE_SCHEMA=1 cargo test shard_schema`.
+    pub fn json_schema() -> serde_json::Value {
+        let mut schema = schemars::generate::SchemaSettings::draft2020_12()
+            .with(|settings| settings.inline_subschemas = true)
+            .into_generator()
+            .into_root_schema_for::<Self>();
+        schema.insert("title".to_string(), "WebShard shard".into());
+        schema.insert(
+            "description".to_string(),
+            format!("Shader exported by WebShard Editor, schema version {}", SCHEMA_VERSION).into(),
+        );
+        schema.to_value()
+    }
+
+    /// Capture the editor's buffers, Common code, images and adjustments
+    ///
+    /// Buffers and Common without code are left out, as are vertex shaders
+    /// identical to the standard one. Shader code is written base64-encoded.
+    pub fn from_editor_state(state: &EditorState) -> Self {
+        let fragment = |kind: BufferKind| {
+            state
+                .buffers
+                .get(&kind)
+                .map(|buffer| buffer.fragment_code.clone())
+                .filter(|code| has_shader_code(code))
+        };
+        // Only buffers that are exported keep their vertex shader
+        let vertex = |kind: BufferKind| {
+            state
+                .buffers
+                .get(&kind)
+                .filter(|buffer| kind == BufferKind::MainImage || has_shader_code(&buffer.fragment_code))
+                .map(|buffer| buffer.vertex_code.clone())
+                .filter(|vertex| is_custom_vertex(vertex))
+        };
+        let main_image = state.buffers.get(&BufferKind::MainImage);
+        let geometry: BTreeMap<String, PassGeometry> = BufferKind::ALL
+            .into_iter()
+            .filter(|kind| *kind == BufferKind::MainImage || fragment(*kind).is_some())
+            .filter_map(|kind| {
+                let geometry = state.buffers.get(&kind)?.geometry;
+                (!geometry.is_default()).then(|| (kind.file_stem().to_string(), geometry))
+            })
+            .collect();
+        let channels: BTreeMap<String, ChannelSettings> = CHANNEL_KEYS
+            .into_iter()
+            .zip(state.channels)
+            .filter(|(_, settings)| !settings.is_default())
+            .map(|(key, settings)| (key.to_string(), *settings))
+            .collect();
+        let [ichannel0, ichannel1, ichannel2, ichannel3] = state.images.clone().map(|image| {
+            image.map(|bytes| base64::Engine::encode(&base64::engine::general_purpose::STANDARD, bytes))
+        });
+
+        Self {
+            version: default_version(),
+            name: None,
+            description: None,
+            exported_at: None,
+            encoding: "base64".to_string(),
+            vertex: vertex(BufferKind::MainImage),
+            fragment: main_image.map(|buffer| buffer.fragment_code.clone()).unwrap_or_default(),
+            common: Some(state.common.to_string()).filter(|code| has_shader_code(code)),
+            buffer_a: fragment(BufferKind::BufferA),
+            buffer_b: fragment(BufferKind::BufferB),
+            buffer_c: fragment(BufferKind::BufferC),
+            buffer_d: fragment(BufferKind::BufferD),
+            buffer_a_vertex: vertex(BufferKind::BufferA),
+            buffer_b_vertex: vertex(BufferKind::BufferB),
+            buffer_c_vertex: vertex(BufferKind::BufferC),
+            buffer_d_vertex: vertex(BufferKind::BufferD),
+            geometry: Some(geometry).filter(|geometry| !geometry.is_empty()),
+            ichannel0,
+            ichannel1,
+            ichannel2,
+            ichannel3,
+            channels: Some(channels).filter(|channels| !channels.is_empty()),
+            gamma: Some(state.gamma),
+            contrast: Some(state.contrast),
+            saturation: Some(state.saturation),
+        }
+    }
+
+    /// Serialize to pretty JSON, encoding shader code as `encoding` says
+    /// (the inverse of [`ShaderJson::from_json`])
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        let mut shader = self.clone();
+        if shader.encoding == "base64" {
+            for (_, code) in shader.code_fields_mut() {
+                *code = Self::encode_to_base64(code);
+            }
+        }
+        serde_json::to_string_pretty(&shader)
+    }
+
+    /// Every shader source field that `encoding` applies to, with its name
+    fn code_fields_mut(&mut self) -> impl Iterator<Item = (&'static str, &mut String)> {
+        let fields = [
+            Some(&mut self.fragment),
+            self.vertex.as_mut(),
+            self.common.as_mut(),
+            self.buffer_a.as_mut(),
+            self.buffer_b.as_mut(),
+            self.buffer_c.as_mut(),
+            self.buffer_d.as_mut(),
+            self.buffer_a_vertex.as_mut(),
+            self.buffer_b_vertex.as_mut(),
+            self.buffer_c_vertex.as_mut(),
+            self.buffer_d_vertex.as_mut(),
+        ];
+        CODE_FIELDS
+            .into_iter()
+            .zip(fields)
+            .filter_map(|(name, code)| Some((name, code?)))
+    }
+
+    /// Custom vertex shader of a pass, if it has one
+    pub fn vertex_for(&self, kind: BufferKind) -> Option<&str> {
+        match kind {
+            BufferKind::MainImage => self.vertex.as_deref(),
+            BufferKind::BufferA => self.buffer_a_vertex.as_deref(),
+            BufferKind::BufferB => self.buffer_b_vertex.as_deref(),
+            BufferKind::BufferC => self.buffer_c_vertex.as_deref(),
+            BufferKind::BufferD => self.buffer_d_vertex.as_deref(),
+        }
+    }
+
+    /// Settings of iChannel`index`
+    pub fn channel_settings(&self, index: usize) -> ChannelSettings {
+        self.channels
+            .as_ref()
+            .zip(CHANNEL_KEYS.get(index))
+            .and_then(|(channels, key)| channels.get(*key))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Draw settings of a pass
+    pub fn geometry_for(&self, kind: BufferKind) -> PassGeometry {
+        self.geometry
+            .as_ref()
+            .and_then(|geometry| geometry.get(kind.file_stem()))
+            .copied()
+            .unwrap_or_default()
+    }
+
     /// Encode shader code to base64 for safe JSON storage
     pub fn encode_to_base64(code: &str) -> String {
         base64::Engine::encode(&base64::engine::general_purpose::STANDARD, code.as_bytes())
@@ -137,43 +558,34 @@ impl ShaderJson {
             || self.buffer_c.is_some()
             || self.buffer_d.is_some();
 
-        // Use centralized boilerplate from shader_constants (includes iChannel0-3)
-        let boilerplate = SHADER_BOILERPLATE;
+        // Centralized boilerplate, with iChannel0-3 declared as their channel types
+        let boilerplate = shader_boilerplate(std::array::from_fn(|i| self.channel_settings(i).kind));
 
-        // Vertex shader (use provided or standard default)
-        let vertex_shader = self.vertex.as_deref()
-            .unwrap_or(STANDARD_VERTEX);
+        // Common code shared by every pass, injected after the boilerplate
+        let common = self.common.as_deref().unwrap_or("");
 
-        // Process BufferA
-        if let Some(buffer_a_code) = &self.buffer_a {
-            let full_shader = format!("{}\n{}\n{}", boilerplate, vertex_shader, buffer_a_code);
-            map.insert(BufferKind::BufferA, full_shader);
-        }
+        // Each pass uses its own vertex shader, or the standard one
+        let vertex_shader = |kind: BufferKind| self.vertex_for(kind).unwrap_or(STANDARD_VERTEX);
 
-        // Process BufferB
-        if let Some(buffer_b_code) = &self.buffer_b {
-            let full_shader = format!("{}\n{}\n{}", boilerplate, vertex_shader, buffer_b_code);
-            map.insert(BufferKind::BufferB, full_shader);
-        }
-
-        // Process BufferC
-        if let Some(buffer_c_code) = &self.buffer_c {
-            let full_shader = format!("{}\n{}\n{}", boilerplate, vertex_shader, buffer_c_code);
-            map.insert(BufferKind::BufferC, full_shader);
-        }
-
-        // Process BufferD
-        if let Some(buffer_d_code) = &self.buffer_d {
-            let full_shader = format!("{}\n{}\n{}", boilerplate, vertex_shader, buffer_d_code);
-            map.insert(BufferKind::BufferD, full_shader);
+        for (kind, code) in [
+            (BufferKind::BufferA, &self.buffer_a),
+            (BufferKind::BufferB, &self.buffer_b),
+            (BufferKind::BufferC, &self.buffer_c),
+            (BufferKind::BufferD, &self.buffer_d),
+        ] {
+            if let Some(code) = code {
+                let full_shader = format!("{}\n{}\n{}\n{}", boilerplate, common, vertex_shader(kind), code);
+                map.insert(kind, full_shader);
+            }
         }
 
         // Process MainImage (fragment)
         // If we have buffers, inject texture bindings for BufferA-D access
+        let vertex_shader = vertex_shader(BufferKind::MainImage);
         let main_image_code = if has_buffers {
-            format!("{}\n{}\n{}\n{}", boilerplate, TEXTURE_BINDINGS, vertex_shader, &self.fragment)
+            format!("{}\n{}\n{}\n{}\n{}", boilerplate, TEXTURE_BINDINGS, common, vertex_shader, &self.fragment)
         } else {
-            format!("{}\n{}\n{}", boilerplate, vertex_shader, &self.fragment)
+            format!("{}\n{}\n{}\n{}", boilerplate, common, vertex_shader, &self.fragment)
         };
         map.insert(BufferKind::MainImage, main_image_code);
 
@@ -181,6 +593,99 @@ impl ShaderJson {
     }
 }
 
+/// Bring a shard up to [`SCHEMA_VERSION`], one migration at a time
+fn migrate(shard: &mut JsonObject) -> Result<(), String> {
+    let mut version = match shard.get("version") {
+        None => "1.0".to_string(),
+        Some(serde_json::Value::String(version)) => version.clone(),
+        Some(other) => return Err(format!("`version` must be a string, found {}", other)),
+    };
+
+    while version != SCHEMA_VERSION {
+        let Some((_, migration)) = MIGRATIONS.iter().find(|(from, _)| *from == version) else {
+            return Err(format!(
+                "unsupported shard version {} (this editor reads {} and older)",
+                version, SCHEMA_VERSION
+            ));
+        };
+        let next = migration(shard);
+        log::info!("Migrated shard from version {} to {}", version, next);
+        version = next.to_string();
+    }
+    Ok(())
+}
+
+/// 1.0 → 2.0: drop blank code fields, which 1.0 used to mean "no code"
+fn migrate_1_0(shard: &mut JsonObject) -> &'static str {
+    for field in &CODE_FIELDS[1..] {
+        let blank = match shard.get(*field) {
+            Some(serde_json::Value::Null) => true,
+            Some(serde_json::Value::String(code)) => code.trim().is_empty(),
+            _ => false,
+        };
+        if blank {
+            shard.remove(*field);
+        }
+    }
+    shard.insert("version".to_string(), "2.0".into());
+    "2.0"
+}
+
+/// 2.0 → 2.1: `vertex` applied to every pass, so give each buffer a copy
+fn migrate_2_0(shard: &mut JsonObject) -> &'static str {
+    if let Some(vertex) = shard.get("vertex").cloned() {
+        for (fragment, vertex_field) in BUFFER_VERTEX_FIELDS {
+            if shard.contains_key(fragment) && !shard.contains_key(vertex_field) {
+                shard.insert(vertex_field.to_string(), vertex.clone());
+            }
+        }
+    }
+    shard.insert("version".to_string(), "2.1".into());
+    "2.1"
+}
+
+/// 2.1 → 2.2: `geometry` is new and optional, so nothing changes
+fn migrate_2_1(shard: &mut JsonObject) -> &'static str {
+    shard.insert("version".to_string(), "2.2".into());
+    "2.2"
+}
+
+/// 2.2 → 2.3: `channels` is new and optional, so nothing changes
+fn migrate_2_2(shard: &mut JsonObject) -> &'static str {
+    shard.insert("version".to_string(), "2.3".into());
+    "2.3"
+}
+
+/// 2.3 → 2.4: a missing `format` is `auto`, which loads 2.3 channels as before
+fn migrate_2_3(shard: &mut JsonObject) -> &'static str {
+    shard.insert("version".to_string(), "2.4".into());
+    "2.4"
+}
+
+/// 2.4 → 2.5: missing `playback` and `rate` loop at normal speed
+fn migrate_2_4(shard: &mut JsonObject) -> &'static str {
+    shard.insert("version".to_string(), "2.5".into());
+    "2.5"
+}
+
+/// 2.5 → 2.6: missing `wrap`, `filter` and `flip_y` sample as before (clamped, linear, unflipped)
+fn migrate_2_5(shard: &mut JsonObject) -> &'static str {
+    shard.insert("version".to_string(), "2.6".into());
+    "2.6"
+}
+
+/// 2.6 → 2.7: channels without `procedural` load their embedded image as before
+fn migrate_2_6(shard: &mut JsonObject) -> &'static str {
+    shard.insert("version".to_string(), "2.7".into());
+    "2.7"
+}
+
+/// Whether a pass's vertex code differs from the standard full-screen triangle
+fn is_custom_vertex(vertex: &str) -> bool {
+    let vertex = vertex.trim();
+    !vertex.is_empty() && vertex != STANDARD_VERTEX.trim()
+}
+
 /// Decode base64 string to UTF-8 text
 fn decode_base64(encoded: &str) -> Option<String> {
     use base64::Engine;
@@ -193,6 +698,333 @@ fn decode_base64(encoded: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::channel_animation::PlaybackMode;
+    use crate::utils::channel_texture::{ChannelFilter, ChannelFormat, ChannelKind, ChannelWrap};
+    use crate::utils::procedural_texture::{Pattern, MAX_PROCEDURAL_SIZE};
+    use crate::utils::{DEFAULT_COMMON, DEFAULT_VERTEX};
+
+    const PRESETS: &[(&str, &str)] = &[
+        ("default", include_str!("../assets/shards/default.json")),
+        ("fractal", include_str!("../assets/shards/fractal.json")),
+        ("image_demo", include_str!("../assets/shards/image_demo.json")),
+        ("image_only", include_str!("../assets/shards/image_only.json")),
+        ("image_test", include_str!("../assets/shards/image_test.json")),
+        ("psychedelic", include_str!("../assets/shards/psychedelic.json")),
+        ("raymarch", include_str!("../assets/shards/raymarch.json")),
+        ("tunnel", include_str!("../assets/shards/tunnel.json")),
+    ];
+
+    /// Buffers as `TopApp::load_shader_from_json` fills them
+    fn editor_buffers(shader: &ShaderJson) -> HashMap<BufferKind, ShaderBuffer> {
+        let vertex = |kind| shader.vertex_for(kind).unwrap_or(DEFAULT_VERTEX).to_string();
+        let mut buffers = HashMap::from([(
+            BufferKind::MainImage,
+            ShaderBuffer::new(BufferKind::MainImage, vertex(BufferKind::MainImage), shader.fragment.clone()),
+        )]);
+        for (kind, code) in [
+            (BufferKind::BufferA, &shader.buffer_a),
+            (BufferKind::BufferB, &shader.buffer_b),
+            (BufferKind::BufferC, &shader.buffer_c),
+            (BufferKind::BufferD, &shader.buffer_d),
+        ] {
+            if let Some(code) = code {
+                buffers.insert(kind, ShaderBuffer::new(kind, vertex(kind), code.clone()));
+            }
+        }
+        buffers
+    }
+
+    #[test]
+    fn test_migrates_1_0() {
+        let shader = ShaderJson::from_json(
+            r#"{"fragment": "@fragment fn fs_main() {}", "vertex": "", "buffer_a": "  ", "buffer_b": null, "name": "Old"}"#,
+        )
+        .unwrap();
+        assert_eq!(shader.version, SCHEMA_VERSION);
+        assert_eq!((shader.vertex, shader.buffer_a, shader.buffer_b), (None, None, None));
+        assert_eq!(shader.name.as_deref(), Some("Old"));
+
+        let err = ShaderJson::from_json(r#"{"version": "99.0", "fragment": ""}"#).unwrap_err();
+        assert!(err.to_string().contains("unsupported shard version 99.0"), "{}", err);
+    }
+
+    #[test]
+    fn test_migrates_shared_vertex_to_buffers() {
+        let shader = ShaderJson::from_json(
+            r#"{"version": "2.0", "vertex": "// custom", "fragment": "x", "buffer_b": "y", "buffer_d": "z"}"#,
+        )
+        .unwrap();
+        assert_eq!(shader.version, SCHEMA_VERSION);
+        assert_eq!(shader.vertex_for(BufferKind::MainImage), Some("// custom"));
+        assert_eq!(shader.vertex_for(BufferKind::BufferA), None);
+        assert_eq!(shader.vertex_for(BufferKind::BufferB), Some("// custom"));
+        assert_eq!(shader.vertex_for(BufferKind::BufferD), Some("// custom"));
+
+        // 2.1 shards keep the standard vertex for buffers without their own
+        let shader = ShaderJson::from_json(r#"{"version": "2.1", "vertex": "// custom", "fragment": "x", "buffer_a": "y"}"#)
+            .unwrap();
+        assert_eq!(shader.vertex_for(BufferKind::BufferA), None);
+        assert!(shader.to_shader_map()[&BufferKind::BufferA].contains(STANDARD_VERTEX));
+    }
+
+    #[test]
+    fn test_buffer_vertex_round_trip() {
+        let custom = "@vertex\nfn vs_main(@builtin(vertex_index) vi: u32) -> VSOut {\n    var out: VSOut;\n    return out;\n}\n";
+        let buffers = HashMap::from([
+            (BufferKind::MainImage, ShaderBuffer::new(BufferKind::MainImage, DEFAULT_VERTEX.to_string(), "main".to_string())),
+            (BufferKind::BufferA, ShaderBuffer::new(BufferKind::BufferA, custom.to_string(), "a".to_string())),
+            (BufferKind::BufferB, ShaderBuffer::new(BufferKind::BufferB, DEFAULT_VERTEX.to_string(), "b".to_string())),
+            // Not exported, so neither is its vertex shader
+            (BufferKind::BufferC, ShaderBuffer::new(BufferKind::BufferC, custom.to_string(), "// Buffer C\n".to_string())),
+        ]);
+        let exported = ShaderJson::from_editor_state(&EditorState {
+            buffers: &buffers,
+            common: "",
+            images: &[None, None, None, None],
+            channels: &Default::default(),
+            gamma: 1.0,
+            contrast: 1.0,
+            saturation: 1.0,
+        });
+        assert_eq!(exported.vertex, None);
+        assert_eq!(exported.buffer_a_vertex.as_deref(), Some(custom));
+        assert_eq!((exported.buffer_b_vertex.as_ref(), exported.buffer_c_vertex.as_ref()), (None, None));
+
+        let reimported = ShaderJson::from_json(&exported.to_json().unwrap()).unwrap();
+        assert_eq!(reimported, exported);
+        assert_eq!(editor_buffers(&reimported)[&BufferKind::BufferA].vertex_code, custom);
+        assert!(reimported.to_shader_map()[&BufferKind::BufferA].contains(custom));
+    }
+
+    #[test]
+    fn test_geometry() {
+        let json = r#"{"fragment": "x", "buffer_a": "y", "geometry": {
+            "buffer_a": {"vertex_count": 30000, "topology": "point_list", "depth": true},
+            "main_image": {"instance_count": 0, "speed": 2},
+            "buffer_q": {}
+        }}"#;
+        assert!(ShaderJson::from_json_strict(json).unwrap_err().to_string().contains("unknown geometry"));
+        let shader = ShaderJson::from_json(json).unwrap();
+        let buffer_a = shader.geometry_for(BufferKind::BufferA);
+        assert_eq!((buffer_a.vertex_count, buffer_a.instance_count, buffer_a.depth), (30000, 1, true));
+        assert_eq!(shader.geometry_for(BufferKind::MainImage).instance_count, 1);
+        assert!(shader.geometry_for(BufferKind::BufferB).is_default());
+        assert_eq!(shader.geometry.as_ref().unwrap().len(), 2);
+
+        let mut buffers = editor_buffers(&shader);
+        for (kind, buffer) in buffers.iter_mut() {
+            buffer.geometry = shader.geometry_for(*kind);
+        }
+        let exported = ShaderJson::from_editor_state(&EditorState {
+            buffers: &buffers,
+            common: "",
+            images: &[None, None, None, None],
+            channels: &Default::default(),
+            gamma: 1.0,
+            contrast: 1.0,
+            saturation: 1.0,
+        });
+        // Default geometry is left out
+        assert_eq!(exported.geometry.as_ref().unwrap().keys().collect::<Vec<_>>(), ["buffer_a"]);
+        let reimported = ShaderJson::from_json_strict(&exported.to_json().unwrap()).unwrap();
+        assert_eq!(reimported.geometry_for(BufferKind::BufferA), buffer_a);
+    }
+
+    #[test]
+    fn test_channel_settings() {
+        let json = r#"{"version": "2.3", "fragment": "x", "channels": {"ichannel1": {"kind": "cube"}, "ichannel7": {}}}"#;
+        assert!(ShaderJson::from_json_strict(json).unwrap_err().to_string().contains("unknown channel `ichannel7`"));
+        let shader = ShaderJson::from_json(json).unwrap();
+        assert_eq!(shader.channel_settings(1).kind, ChannelKind::Cube);
+        assert!(shader.channel_settings(0).is_default());
+        assert!(shader.to_shader_map()[&BufferKind::MainImage].contains("var iChannel1: texture_cube<f32>;"));
+
+        let volume = ChannelSettings {
+            kind: ChannelKind::Volume,
+            format: ChannelFormat::Rgba16Float,
+            ..Default::default()
+        };
+        let exported = ShaderJson::from_editor_state(&EditorState {
+            buffers: &editor_buffers(&shader),
+            common: "",
+            images: &[None, None, None, None],
+            channels: &[ChannelSettings::default(), ChannelSettings::default(), volume, ChannelSettings::default()],
+            gamma: 1.0,
+            contrast: 1.0,
+            saturation: 1.0,
+        });
+        // 2D channels are left out
+        let reimported = ShaderJson::from_json_strict(&exported.to_json().unwrap()).unwrap();
+        assert_eq!(reimported.channels.as_ref().unwrap().len(), 1);
+        assert_eq!(reimported.channel_settings(2), volume);
+        assert!(exported.to_json().unwrap().contains(r#""format": "rgba16f""#));
+        assert!(ShaderJson::from_json_strict(&exported.to_json().unwrap().replace("rgba16f", "rgba8")).is_err());
+
+        let gif = r#"{"version": "2.5", "fragment": "x", "channels": {"ichannel0": {"playback": "ping_pong", "rate": 0.5}}}"#;
+        let settings = ShaderJson::from_json_strict(gif).unwrap().channel_settings(0);
+        assert_eq!((settings.playback, settings.rate), (PlaybackMode::PingPong, 0.5));
+        assert_eq!(settings.kind, ChannelKind::Texture2d);
+
+        let pixel_art = r#"{"version": "2.6", "fragment": "x", "channels": {"ichannel3": {"wrap": "repeat", "filter": "nearest", "flip_y": true}}}"#;
+        let settings = ShaderJson::from_json_strict(pixel_art).unwrap().channel_settings(3);
+        assert_eq!((settings.wrap, settings.filter, settings.flip_y), (ChannelWrap::Repeat, ChannelFilter::Nearest, true));
+        assert_eq!(ShaderJson::from_json(gif).unwrap().channel_settings(0).wrap, ChannelWrap::Clamp);
+
+        let noise = r#"{"version": "2.7", "fragment": "x", "channels": {"ichannel2": {"procedural": {"pattern": "blue_noise", "size": [64, 9999], "seed": 3}}}}"#;
+        let shader = ShaderJson::from_json_strict(noise).unwrap();
+        let procedural = shader.channel_settings(2).procedural.unwrap();
+        assert_eq!((procedural.pattern, procedural.seed), (Pattern::BlueNoise, 3));
+        assert_eq!(procedural.size, [64, MAX_PROCEDURAL_SIZE]);
+        assert_eq!(procedural.scale, ProceduralTexture::default().scale);
+        assert!(shader.ichannel2.is_none());
+        // Only the parameters are stored
+        let json = shader.to_json().unwrap();
+        assert!(json.contains(r#""pattern": "blue_noise""#));
+        let reimported = ShaderJson::from_json_strict(&json).unwrap();
+        assert_eq!(reimported.channel_settings(2), shader.channel_settings(2));
+        assert!(reimported.ichannel2.is_none());
+        let typo = noise.replace("seed", "sed");
+        assert!(ShaderJson::from_json_strict(&typo).unwrap_err().to_string().contains("unknown field `sed`"));
+        assert_eq!(ShaderJson::from_json(&typo).unwrap().channel_settings(2).procedural.unwrap().seed, 0);
+    }
+
+    #[test]
+    fn test_strict_mode() {
+        let unknown = r#"{"version": "2.0", "fragment": "x", "fragmnet": "y"}"#;
+        assert!(ShaderJson::from_json(unknown).is_ok());
+        let err = ShaderJson::from_json_strict(unknown).unwrap_err();
+        assert!(err.to_string().contains("unknown field `fragmnet`"), "{}", err);
+
+        // Outside strict mode unknown fields are dropped wherever they are
+        let mut nested = serde_json::json!({
+            "fragment": "x",
+            "fragmnet": "y",
+            "geometry": {"buffer_a": {"depth": true, "speed": 2}},
+            "channels": {"ichannel2": {"procedural": {"sed": 3}}},
+        });
+        let mut unknown = Vec::new();
+        drop_unknown_fields(&mut nested, &ShaderJson::json_schema(), "", &mut unknown);
+        assert_eq!(unknown, ["fragmnet", "channels.ichannel2.procedural.sed", "geometry.buffer_a.speed"]);
+        assert_eq!(nested["geometry"]["buffer_a"], serde_json::json!({"depth": true}));
+
+        let bad_code = r#"{"version": "2.0", "encoding": "base64", "fragment": "not base64!"}"#;
+        assert_eq!(ShaderJson::from_json(bad_code).unwrap().fragment, "not base64!");
+        let err = ShaderJson::from_json_strict(bad_code).unwrap_err();
+        assert!(err.to_string().contains("`fragment` is not valid base64"), "{}", err);
+
+        let bad_image = r#"{"version": "2.0", "fragment": "x", "ichannel2": "%%%"}"#;
+        let err = ShaderJson::from_json_strict(bad_image).unwrap_err();
+        assert!(err.to_string().contains("`ichannel2`"), "{}", err);
+
+        let bad_encoding = r#"{"version": "2.0", "encoding": "rot13", "fragment": "x"}"#;
+        assert!(ShaderJson::from_json_strict(bad_encoding).is_err());
+
+        for (name, json) in PRESETS {
+            ShaderJson::from_json_strict(json).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        }
+    }
+
+    #[test]
+    fn test_shard_schema_matches_serde() {
+        let shader = ShaderJson {
+            version: default_version(),
+            name: Some(String::new()),
+            description: Some(String::new()),
+            exported_at: Some(String::new()),
+            encoding: default_encoding(),
+            vertex: Some(String::new()),
+            fragment: String::new(),
+            common: Some(String::new()),
+            buffer_a: Some(String::new()),
+            buffer_b: Some(String::new()),
+            buffer_c: Some(String::new()),
+            buffer_d: Some(String::new()),
+            buffer_a_vertex: Some(String::new()),
+            buffer_b_vertex: Some(String::new()),
+            buffer_c_vertex: Some(String::new()),
+            buffer_d_vertex: Some(String::new()),
+            geometry: Some(BTreeMap::new()),
+            ichannel0: Some(String::new()),
+            ichannel1: Some(String::new()),
+            ichannel2: Some(String::new()),
+            ichannel3: Some(String::new()),
+            channels: Some(BTreeMap::new()),
+            gamma: Some(1.0),
+            contrast: Some(1.0),
+            saturation: Some(1.0),
+        };
+        let value = serde_json::to_value(&shader).unwrap();
+        let serialized: Vec<&String> = value.as_object().unwrap().keys().collect();
+        let schema = ShaderJson::json_schema();
+        let documented: Vec<&String> = schema["properties"].as_object().unwrap().keys().collect();
+        assert_eq!(serialized, documented);
+    }
+
+    #[test]
+    fn test_published_shard_schema_is_current() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/data/shard.schema.json");
+        let schema = serde_json::to_string_pretty(&ShaderJson::json_schema()).unwrap() + "\n";
+        if std::env::var_os("UPDATE_SCHEMA").is_some() {
+            std::fs::write(path, &schema).unwrap();
+        }
+        let published = std::fs::read_to_string(path).unwrap_or_default();
+        assert!(published == schema, "{} is out of date; rerun with UPDATE_SCHEMA=1", path);
+    }
+
+    #[test]
+    fn test_presets_round_trip() {
+        let code = |code: &Option<String>| code.clone().filter(|c| has_shader_code(c));
+
+        for (name, json) in PRESETS {
+            let preset = ShaderJson::from_json(json).unwrap();
+            let buffers = editor_buffers(&preset);
+            let images = preset.decode_embedded_images();
+            let exported = ShaderJson::from_editor_state(&EditorState {
+                buffers: &buffers,
+                common: preset.common.as_deref().unwrap_or(DEFAULT_COMMON),
+                images: &images,
+                channels: &std::array::from_fn(|i| preset.channel_settings(i)),
+                gamma: preset.gamma.unwrap_or(1.0),
+                contrast: preset.contrast.unwrap_or(1.0),
+                saturation: preset.saturation.unwrap_or(1.0),
+            });
+
+            let reimported = ShaderJson::from_json(&exported.to_json().unwrap()).unwrap();
+            assert_eq!(reimported, exported, "{}", name);
+
+            let mut plain = exported.clone();
+            plain.encoding = "plain".to_string();
+            assert_eq!(ShaderJson::from_json(&plain.to_json().unwrap()).unwrap(), plain, "{}", name);
+
+            // Everything the editor loaded from the preset survives export
+            assert_eq!(reimported.fragment, preset.fragment, "{}", name);
+            for kind in [
+                BufferKind::MainImage,
+                BufferKind::BufferA,
+                BufferKind::BufferB,
+                BufferKind::BufferC,
+                BufferKind::BufferD,
+            ] {
+                assert_eq!(
+                    reimported.vertex_for(kind),
+                    preset.vertex_for(kind).filter(|v| is_custom_vertex(v)),
+                    "{} {}",
+                    name,
+                    kind.as_str()
+                );
+            }
+            assert_eq!(code(&reimported.common), code(&preset.common), "{}", name);
+            for (exported, original) in [
+                (&reimported.buffer_a, &preset.buffer_a),
+                (&reimported.buffer_b, &preset.buffer_b),
+                (&reimported.buffer_c, &preset.buffer_c),
+                (&reimported.buffer_d, &preset.buffer_d),
+            ] {
+                assert_eq!(code(exported), code(original), "{}", name);
+            }
+            assert_eq!(reimported.decode_embedded_images(), images, "{}", name);
+        }
+    }
 
     #[test]
     fn test_simple_shader() {
@@ -225,4 +1057,33 @@ mod tests {
         assert!(map.contains_key(&BufferKind::BufferA));
         assert!(map.get(&BufferKind::MainImage).unwrap().contains("buffer_a_texture"));
     }
+
+    #[test]
+    fn test_common_injected_into_every_pass() {
+        let json = r#"{
+            "version": "1.0",
+            "encoding": "base64",
+            "fragment": "TWFpbkltYWdlIGNvZGU=",
+            "common": "Zm4gaGVscGVyKCkge30=",
+            "buffer_a": "QnVmZmVyQSBjb2Rl"
+        }"#;
+
+        let shader = ShaderJson::from_json(json).unwrap();
+        assert_eq!(shader.common.as_deref(), Some("fn helper() {}"));
+
+        let map = shader.to_shader_map();
+        for code in map.values() {
+            let common_at = code.find("fn helper() {}").unwrap();
+            assert!(common_at > code.find("struct Uniforms").unwrap());
+            assert!(common_at < code.find("fn vs_main").unwrap());
+        }
+    }
+
+    #[test]
+    fn test_common_is_optional() {
+        let shader = ShaderJson::from_json(r#"{ "fragment": "code" }"#).unwrap();
+        assert!(shader.common.is_none());
+        let json = serde_json::to_string(&shader).unwrap();
+        assert!(!json.contains("common"));
+    }
 }
@@ -6,12 +6,25 @@
 //! 
 //! Uses WGSL definitions from wgsl_syntax module for consistency.
 
+use crate::utils::diagnostics::Diagnostic;
+use crate::utils::quick_fix;
 use crate::utils::ShaderError;
 
 // WGSL Language Constants (aligned with wgsl_syntax.rs)
 const REQUIRED_KEYWORDS: &[&str] = &["fn", "struct", "var"];
 const REQUIRED_TYPES: &[&str] = &["f32", "vec2", "vec4"];
 
+/// Whether a source has anything besides blank lines and `//` comments
+///
+/// Buffers (and Common) with no code are skipped instead of compiled.
+/// Preprocessor directives such as `//#include` count as code.
+pub fn has_shader_code(source: &str) -> bool {
+    source.lines().any(|line| {
+        let trimmed = line.trim();
+        !trimmed.is_empty() && (!trimmed.starts_with("//") || trimmed.starts_with("//#"))
+    })
+}
+
 /// Validates a WGSL shader source code
 /// 
 /// Performs multiple validation checks:
@@ -30,7 +43,7 @@ pub fn validate_shader(wgsl_src: &str) -> Result<(), ShaderError> {
 pub fn validate_shader_with_entry_point(wgsl_src: &str, entry_point: &str) -> Result<(), ShaderError> {
     // 1. Check shader is not empty
     if wgsl_src.trim().is_empty() {
-        return Err(ShaderError::ValidationError(
+        return Err(ShaderError::validation(
             "Shader source is empty".to_string(),
         ));
     }
@@ -55,7 +68,7 @@ fn validate_wgsl_constructs(wgsl_src: &str) -> Result<(), ShaderError> {
     // Check for essential keywords
     for keyword in REQUIRED_KEYWORDS {
         if !wgsl_src.contains(keyword) {
-            return Err(ShaderError::ValidationError(
+            return Err(ShaderError::validation(
                 format!("Shader missing required WGSL keyword: '{}'", keyword)
             ));
         }
@@ -64,7 +77,7 @@ fn validate_wgsl_constructs(wgsl_src: &str) -> Result<(), ShaderError> {
     // Check for essential types
     for type_name in REQUIRED_TYPES {
         if !wgsl_src.contains(type_name) {
-            return Err(ShaderError::ValidationError(
+            return Err(ShaderError::validation(
                 format!("Shader missing required WGSL type: '{}'", type_name)
             ));
         }
@@ -86,8 +99,8 @@ fn validate_uniforms_struct(wgsl_src: &str) -> Result<(), ShaderError> {
     
     // Check if shader defines a Uniforms struct
     if !wgsl_src.contains("struct Uniforms") {
-        return Err(ShaderError::ValidationError(
-            "Shader must define a 'struct Uniforms' matching the pipeline structure.\n\nExpected:\nstruct Uniforms {\n    time: f32,\n    audio_bass: f32,\n    audio_mid: f32,\n    audio_high: f32,\n    resolution: vec2<f32>,\n    gamma: f32,\n    contrast: f32,\n    saturation: f32,\n    _pad0: f32,\n}".to_string()
+        return Err(ShaderError::validation(
+            "Shader must define a 'struct Uniforms' matching the pipeline structure.\n\nExpected:\nstruct Uniforms {\n    time: f32,\n    audio_bass: f32,\n    audio_mid: f32,\n    audio_high: f32,\n    resolution: vec2<f32>,\n    gamma: f32,\n    contrast: f32,\n    saturation: f32,\n    _pad0: f32,\n    _pad1: vec2<f32>,\n    channel_time: vec4<f32>,\n    mouse: vec4<f32>,\n    time_delta: f32,\n    frame: u32,\n    _pad2: vec2<f32>,\n}".to_string()
         ));
     }
     
@@ -101,7 +114,7 @@ fn validate_uniforms_struct(wgsl_src: &str) -> Result<(), ShaderError> {
                 // Check required fields only (gamma, contrast, saturation are optional for backward compatibility)
                 for field in &required_fields {
                     if !struct_body.contains(field) {
-                        return Err(ShaderError::ValidationError(format!(
+                        return Err(ShaderError::validation(format!(
                             "Uniforms struct mismatch!\n\nMissing field: {}\n\nRequired fields:\n{:?}",
                             field, required_fields
                         )));
@@ -125,7 +138,7 @@ fn validate_uniforms_struct(wgsl_src: &str) -> Result<(), ShaderError> {
     
     // Check binding declaration
     if !wgsl_src.contains("@group(0) @binding(0)") || !wgsl_src.contains("var<uniform> uniforms: Uniforms") {
-        return Err(ShaderError::ValidationError(
+        return Err(ShaderError::validation(
             "Missing uniform binding declaration.\n\nRequired:\n@group(0) @binding(0) var<uniform> uniforms: Uniforms;".to_string()
         ));
     }
@@ -142,20 +155,20 @@ fn validate_entry_points(wgsl_src: &str) -> Result<(), ShaderError> {
 fn validate_entry_points_with_fragment(wgsl_src: &str, fragment_entry: &str) -> Result<(), ShaderError> {
     // Check for @vertex and @fragment attributes (from REQUIRED_ATTRIBUTES)
     if !wgsl_src.contains("@vertex") {
-        return Err(ShaderError::ValidationError(
+        return Err(ShaderError::validation(
             "Shader missing @vertex attribute".to_string(),
         ));
     }
 
     if !wgsl_src.contains("@fragment") {
-        return Err(ShaderError::ValidationError(
+        return Err(ShaderError::validation(
             "Shader missing @fragment attribute".to_string(),
         ));
     }
 
     // Validate vertex entry point exists (flexible name check)
     if !wgsl_src.contains("fn vs_main") {
-        return Err(ShaderError::ValidationError(
+        return Err(ShaderError::validation(
             "Shader missing vertex entry point 'fn vs_main'.\n\nRequired:\n@vertex\nfn vs_main(@builtin(vertex_index) vertex_index: u32) -> YourVertexOutput".to_string(),
         ));
     }
@@ -163,7 +176,7 @@ fn validate_entry_points_with_fragment(wgsl_src: &str, fragment_entry: &str) ->
     // Validate fragment entry point exists (flexible name check)
     let fragment_fn = format!("fn {}", fragment_entry);
     if !wgsl_src.contains(&fragment_fn) {
-        return Err(ShaderError::ValidationError(
+        return Err(ShaderError::validation(
             format!("Shader missing fragment entry point '{}'.\n\nRequired:\n@fragment\nfn {}(@location(0) coords: vec2<f32>) -> @location(0) vec4<f32>", fragment_fn, fragment_entry)
         ));
     }
@@ -172,7 +185,7 @@ fn validate_entry_points_with_fragment(wgsl_src: &str, fragment_entry: &str) ->
     let required_attrs = ["@builtin(position)", "@location(0)"];
     for attr in required_attrs {
         if !wgsl_src.contains(attr) {
-            return Err(ShaderError::ValidationError(
+            return Err(ShaderError::validation(
                 format!("Shader missing required attribute: {}", attr)
             ));
         }
@@ -180,7 +193,7 @@ fn validate_entry_points_with_fragment(wgsl_src: &str, fragment_entry: &str) ->
     
     // Check for vertex output struct pattern with required types
     if !wgsl_src.contains("vec4<f32>") || !wgsl_src.contains("vec2<f32>") {
-        return Err(ShaderError::ValidationError(
+        return Err(ShaderError::validation(
             "Shader missing vertex output struct with required types.\n\nExample:\nstruct VSOut {\n    @builtin(position) pos: vec4<f32>,\n    @location(0) uv: vec2<f32>,\n}".to_string(),
         ));
     }
@@ -199,9 +212,10 @@ fn validate_wgsl_syntax(wgsl_src: &str) -> Result<(), ShaderError> {
             module
         }
         Err(parse_error) => {
-            let error_msg = format!("WGSL Parse Error:\n{}", parse_error.emit_to_string(wgsl_src));
-            log::error!("Shader parse failed: {}", error_msg);
-            return Err(ShaderError::ValidationError(error_msg));
+            let mut diagnostic = Diagnostic::from_parse_error(&parse_error, wgsl_src);
+            diagnostic.fix = quick_fix::suggest(&diagnostic, wgsl_src);
+            log::error!("Shader parse failed: {}", diagnostic.summary());
+            return Err(ShaderError::ValidationError(Box::new(diagnostic)));
         }
     };
 
@@ -212,9 +226,11 @@ fn validate_wgsl_syntax(wgsl_src: &str) -> Result<(), ShaderError> {
     );
 
     if let Err(validation_error) = validator.validate(&module) {
-        let error_msg = format!("WGSL Validation Error:\n{}", validation_error.emit_to_string(wgsl_src));
-        log::error!("Shader validation failed: {}", error_msg);
-        return Err(ShaderError::ValidationError(error_msg));
+        let mut diagnostic = Diagnostic::from_validation_error(&validation_error, wgsl_src);
+        diagnostic.fix =
+            quick_fix::suggest_for_validation(&validation_error, &diagnostic, wgsl_src);
+        log::error!("Shader validation failed: {}", diagnostic.summary());
+        return Err(ShaderError::ValidationError(Box::new(diagnostic)));
     }
 
     log::debug!("Naga validation passed");
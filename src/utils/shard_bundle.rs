@@ -0,0 +1,402 @@
+//! Zip shard bundles
+//!
+//! A bundle keeps the shader code and assets of a shard as separate files
+//! instead of base64 inside one JSON document:
+//!
+//! ```text
+//! shard.json                 manifest: settings plus paths of everything below
+//! passes/main_image.wgsl     one file per pass (plus common.wgsl and custom *.vert.wgsl)
+//! assets/<crc32>.<ext>       iChannel images and audio, stored once per content
+//! thumbnail.png              optional preview
+//! ```
+//!
+//! Loading goes through [`ShaderJson::from_json`], so bundles get the same
+//! migrations as plain shards.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::utils::channel_texture::ChannelSettings;
+use crate::utils::shader_json::SCHEMA_VERSION;
+use crate::utils::zip_archive::{self, ZipWriter};
+use crate::utils::{PassGeometry, ShaderJson};
+
+const MANIFEST: &str = "shard.json";
+const THUMBNAIL: &str = "thumbnail.png";
+
+/// Pass files, keyed by the `ShaderJson` field they hold
+const PASS_FILES: [(&str, &str); 11] = [
+    ("fragment", "passes/main_image.wgsl"),
+    ("vertex", "passes/main_image.vert.wgsl"),
+    ("common", "passes/common.wgsl"),
+    ("buffer_a", "passes/buffer_a.wgsl"),
+    ("buffer_b", "passes/buffer_b.wgsl"),
+    ("buffer_c", "passes/buffer_c.wgsl"),
+    ("buffer_d", "passes/buffer_d.wgsl"),
+    ("buffer_a_vertex", "passes/buffer_a.vert.wgsl"),
+    ("buffer_b_vertex", "passes/buffer_b.vert.wgsl"),
+    ("buffer_c_vertex", "passes/buffer_c.vert.wgsl"),
+    ("buffer_d_vertex", "passes/buffer_d.vert.wgsl"),
+];
+
+/// A file carried next to the shader, such as the audio track
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleAsset {
+    /// Original file name, kept for its extension
+    pub file_name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// A shard together with the files only bundles can carry
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardBundle {
+    pub shader: ShaderJson,
+    pub audio: Option<BundleAsset>,
+    /// PNG preview
+    pub thumbnail: Option<Vec<u8>>,
+}
+
+/// `shard.json` inside a bundle
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BundleManifest {
+    version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exported_at: Option<String>,
+    /// `ShaderJson` code field -> file in the bundle
+    passes: BTreeMap<String, String>,
+    /// iChannel0-3 images
+    #[serde(default)]
+    channels: [Option<String>; 4],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    audio: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    thumbnail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    geometry: Option<BTreeMap<String, PassGeometry>>,
+    /// Texture types of the channels, the shard's `channels`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    channel_settings: Option<BTreeMap<String, ChannelSettings>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gamma: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    contrast: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    saturation: Option<f32>,
+}
+
+/// Whether `bytes` look like a bundle rather than JSON
+pub fn is_bundle(bytes: &[u8]) -> bool {
+    zip_archive::is_zip(bytes)
+}
+
+impl ShardBundle {
+    /// Bundle a shard with no audio or thumbnail
+    pub fn new(shader: ShaderJson) -> Self {
+        Self {
+            shader,
+            audio: None,
+            thumbnail: None,
+        }
+    }
+
+    /// Write the bundle as a zip archive
+    ///
+    /// Identical images and audio are stored once.
+    pub fn to_zip(&self) -> Result<Vec<u8>, String> {
+        let shader = &self.shader;
+        let mut zip = ZipWriter::new();
+        let mut assets = AssetStore::default();
+        let mut manifest = BundleManifest {
+            version: SCHEMA_VERSION.to_string(),
+            name: shader.name.clone(),
+            description: shader.description.clone(),
+            exported_at: shader.exported_at.clone(),
+            gamma: shader.gamma,
+            contrast: shader.contrast,
+            saturation: shader.saturation,
+            geometry: shader.geometry.clone(),
+            channel_settings: shader.channels.clone(),
+            ..Default::default()
+        };
+
+        let code = [
+            Some(&shader.fragment),
+            shader.vertex.as_ref(),
+            shader.common.as_ref(),
+            shader.buffer_a.as_ref(),
+            shader.buffer_b.as_ref(),
+            shader.buffer_c.as_ref(),
+            shader.buffer_d.as_ref(),
+            shader.buffer_a_vertex.as_ref(),
+            shader.buffer_b_vertex.as_ref(),
+            shader.buffer_c_vertex.as_ref(),
+            shader.buffer_d_vertex.as_ref(),
+        ];
+        for ((field, path), code) in PASS_FILES.into_iter().zip(code) {
+            if let Some(code) = code {
+                zip.add(path, code.as_bytes())?;
+                manifest.passes.insert(field.to_string(), path.to_string());
+            }
+        }
+
+        let channels = [&shader.ichannel0, &shader.ichannel1, &shader.ichannel2, &shader.ichannel3];
+        for (i, channel) in channels.into_iter().enumerate() {
+            let Some(encoded) = channel else {
+                continue;
+            };
+            let bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, encoded)
+                .map_err(|e| format!("iChannel{} image is not valid base64: {}", i, e))?;
+            let extension = image::guess_format(&bytes)
+                .ok()
+                .and_then(|format| format.extensions_str().first().copied())
+                .unwrap_or("bin");
+            manifest.channels[i] = Some(assets.add(&mut zip, bytes, extension)?);
+        }
+
+        if let Some(audio) = &self.audio {
+            let extension = std::path::Path::new(&audio.file_name)
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("bin");
+            manifest.audio = Some(assets.add(&mut zip, audio.bytes.clone(), extension)?);
+        }
+
+        if let Some(thumbnail) = &self.thumbnail {
+            zip.add(THUMBNAIL, thumbnail)?;
+            manifest.thumbnail = Some(THUMBNAIL.to_string());
+        }
+
+        let manifest = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+        zip.add(MANIFEST, manifest.as_bytes())?;
+        zip.finish()
+    }
+
+    /// Read a bundle written by [`ShardBundle::to_zip`]
+    pub fn from_zip(bytes: &[u8]) -> Result<Self, String> {
+        let files = zip_archive::read(bytes)?;
+        let manifest = files
+            .get(MANIFEST)
+            .ok_or_else(|| format!("Bundle has no {}", MANIFEST))?;
+        let manifest: BundleManifest =
+            serde_json::from_slice(manifest).map_err(|e| format!("Invalid {}: {}", MANIFEST, e))?;
+
+        let take = |path: &str| {
+            files
+                .get(path)
+                .cloned()
+                .ok_or_else(|| format!("{} refers to {}, which is not in the bundle", MANIFEST, path))
+        };
+
+        // Rebuild the equivalent plain shard and let ShaderJson parse it
+        let mut shard = serde_json::json!({
+            "version": manifest.version,
+            "encoding": "plain",
+            "name": manifest.name,
+            "description": manifest.description,
+            "exported_at": manifest.exported_at,
+            "gamma": manifest.gamma,
+            "contrast": manifest.contrast,
+            "saturation": manifest.saturation,
+            "geometry": manifest.geometry,
+            "channels": manifest.channel_settings,
+        });
+        for (field, path) in &manifest.passes {
+            if !PASS_FILES.iter().any(|(known, _)| known == field) {
+                return Err(format!("Unknown pass `{}` in {}", field, MANIFEST));
+            }
+            let code = String::from_utf8(take(path)?).map_err(|_| format!("{} is not UTF-8 text", path))?;
+            shard[field] = code.into();
+        }
+        for (i, path) in manifest.channels.iter().enumerate() {
+            if let Some(path) = path {
+                let bytes = take(path)?;
+                shard[format!("ichannel{}", i)] =
+                    base64::Engine::encode(&base64::engine::general_purpose::STANDARD, bytes).into();
+            }
+        }
+        // Unset optional fields would otherwise come through as nulls
+        if let Some(object) = shard.as_object_mut() {
+            object.retain(|_, value| !value.is_null());
+        }
+        let shader = ShaderJson::from_json(&shard.to_string()).map_err(|e| format!("Invalid shard: {}", e))?;
+
+        let audio = match &manifest.audio {
+            Some(path) => Some(BundleAsset {
+                file_name: asset_file_name(path)?,
+                bytes: take(path)?,
+            }),
+            None => None,
+        };
+        let thumbnail = match &manifest.thumbnail {
+            Some(path) => Some(take(path)?),
+            None => None,
+        };
+
+        Ok(Self {
+            shader,
+            audio,
+            thumbnail,
+        })
+    }
+}
+
+/// The file name of an asset path from a manifest, safe to write into a directory
+///
+/// The manifest is untrusted, so names that could leave that directory (`..`,
+/// or a `\` separator that `Path` only splits on Windows) are refused.
+fn asset_file_name(path: &str) -> Result<String, String> {
+    std::path::Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .filter(|name| !name.contains("..") && !name.contains(['/', '\\']))
+        .map(str::to_string)
+        .ok_or_else(|| format!("{} refers to the unsafe file name {:?}", MANIFEST, path))
+}
+
+/// Content-addressed asset files, so identical assets share one entry
+#[derive(Default)]
+struct AssetStore {
+    stored: Vec<(String, Vec<u8>)>,
+}
+
+impl AssetStore {
+    fn add(&mut self, zip: &mut ZipWriter, bytes: Vec<u8>, extension: &str) -> Result<String, String> {
+        if let Some((path, _)) = self.stored.iter().find(|(_, stored)| *stored == bytes) {
+            return Ok(path.clone());
+        }
+
+        let hash = crc32fast::hash(&bytes);
+        let mut path = format!("assets/{:08x}.{}", hash, extension);
+        let mut suffix = 1;
+        while self.stored.iter().any(|(taken, _)| *taken == path) {
+            path = format!("assets/{:08x}-{}.{}", hash, suffix, extension);
+            suffix += 1;
+        }
+
+        zip.add(&path, &bytes)?;
+        self.stored.push((path.clone(), bytes));
+        Ok(path)
+    }
+}
+
+/// Convert a JSON shard to a bundle
+pub fn json_to_bundle(json: &str) -> Result<Vec<u8>, String> {
+    let shader = ShaderJson::from_json(json).map_err(|e| format!("Invalid shard: {}", e))?;
+    ShardBundle::new(shader).to_zip()
+}
+
+/// Convert a bundle to a JSON shard with base64-encoded code
+///
+/// JSON has no place for audio or a thumbnail, so they are dropped; the
+/// names of dropped files are returned alongside the JSON.
+pub fn bundle_to_json(bytes: &[u8]) -> Result<(String, Vec<String>), String> {
+    let bundle = ShardBundle::from_zip(bytes)?;
+    let mut dropped = Vec::new();
+    if let Some(audio) = &bundle.audio {
+        dropped.push(audio.file_name.clone());
+    }
+    if bundle.thumbnail.is_some() {
+        dropped.push(THUMBNAIL.to_string());
+    }
+
+    let mut shader = bundle.shader;
+    shader.encoding = "base64".to_string();
+    let json = shader.to_json().map_err(|e| e.to_string())?;
+    Ok((json, dropped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::BufferKind;
+
+    fn png(color: [u8; 4]) -> Vec<u8> {
+        let mut png = Vec::new();
+        image::RgbaImage::from_pixel(4, 4, image::Rgba(color))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    #[test]
+    fn test_bundle_round_trip_with_dedup() {
+        let red = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, png([255, 0, 0, 255]));
+        let blue = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, png([0, 0, 255, 255]));
+        let mut shader = ShaderJson::from_json(include_str!("../assets/shards/default.json")).unwrap();
+        shader.name = Some("Default".to_string());
+        shader.ichannel0 = Some(red.clone());
+        shader.ichannel2 = Some(red);
+        shader.ichannel3 = Some(blue);
+        let points = PassGeometry {
+            vertex_count: 1000,
+            topology: crate::utils::pass_geometry::Topology::PointList,
+            ..Default::default()
+        };
+        shader.geometry = Some(BTreeMap::from([("buffer_a".to_string(), points)]));
+        let cube = ChannelSettings {
+            kind: crate::utils::channel_texture::ChannelKind::Cube,
+            ..Default::default()
+        };
+        shader.channels = Some(BTreeMap::from([("ichannel3".to_string(), cube)]));
+        let bundle = ShardBundle {
+            shader,
+            audio: Some(BundleAsset {
+                file_name: "track.mp3".to_string(),
+                bytes: vec![7; 64],
+            }),
+            thumbnail: Some(png([0, 255, 0, 255])),
+        };
+
+        let zip = bundle.to_zip().unwrap();
+        assert_eq!(zip, bundle.to_zip().unwrap());
+
+        let files = zip_archive::read(&zip).unwrap();
+        let assets = files.keys().filter(|path| path.starts_with("assets/")).count();
+        assert_eq!(assets, 3, "{:?}", files.keys());
+        assert!(files.contains_key("passes/buffer_d.wgsl"));
+
+        let loaded = ShardBundle::from_zip(&zip).unwrap();
+        assert_eq!(loaded.shader.fragment, bundle.shader.fragment);
+        assert_eq!(loaded.shader.buffer_a, bundle.shader.buffer_a);
+        assert_eq!(loaded.shader.name, bundle.shader.name);
+        assert_eq!(loaded.shader.geometry_for(BufferKind::BufferA), points);
+        assert_eq!(loaded.shader.channel_settings(3), cube);
+        assert_eq!(loaded.shader.decode_embedded_images(), bundle.shader.decode_embedded_images());
+        assert_eq!(loaded.audio.as_ref().map(|a| &a.bytes), bundle.audio.as_ref().map(|a| &a.bytes));
+        assert_eq!(loaded.thumbnail, bundle.thumbnail);
+    }
+
+    #[test]
+    fn test_converter() {
+        let json = include_str!("../assets/shards/default.json");
+        let zip = json_to_bundle(json).unwrap();
+        let (converted, dropped) = bundle_to_json(&zip).unwrap();
+        assert!(dropped.is_empty());
+
+        let original = ShaderJson::from_json(json).unwrap();
+        let converted = ShaderJson::from_json(&converted).unwrap();
+        assert_eq!(converted.fragment, original.fragment);
+        assert_eq!(converted.buffer_c, original.buffer_c);
+
+        assert!(ShardBundle::from_zip(b"PK\x03\x04 not really").is_err());
+    }
+
+    #[test]
+    fn test_unsafe_audio_name_is_refused() {
+        assert_eq!(asset_file_name("assets/1234abcd.mp3").unwrap(), "1234abcd.mp3");
+        for path in ["assets/..\\..\\evil.mp3", "assets/..", "../", "assets/a\\b.ogg"] {
+            let mut zip = ZipWriter::new();
+            zip.add(path, b"not audio").unwrap();
+            zip.add("passes/main_image.wgsl", b"x").unwrap();
+            let passes = serde_json::json!({ "fragment": "passes/main_image.wgsl" });
+            let manifest = serde_json::json!({ "version": SCHEMA_VERSION, "passes": passes, "audio": path });
+            zip.add(MANIFEST, manifest.to_string().as_bytes()).unwrap();
+            let err = ShardBundle::from_zip(&zip.finish().unwrap()).unwrap_err();
+            assert!(err.contains("unsafe file name"), "{}: {}", path, err);
+        }
+    }
+}
@@ -1,6 +1,5 @@
 // No direct egui text types needed here
 
-#[allow(dead_code)]
 pub fn current_prefix(text: &str, caret_char: usize) -> String {
     let mut idx = caret_char.min(text.chars().count());
     let chars: Vec<char> = text.chars().collect();
@@ -15,7 +14,6 @@ pub fn current_prefix(text: &str, caret_char: usize) -> String {
     chars[idx..caret_char.min(chars.len())].iter().collect()
 }
 
-#[allow(dead_code)]
 pub fn byte_index_from_char_index(s: &str, char_index: usize) -> usize {
     if char_index == 0 {
         return 0;
@@ -28,7 +26,6 @@ pub fn byte_index_from_char_index(s: &str, char_index: usize) -> usize {
     s.len()
 }
 
-#[allow(dead_code)]
 pub fn apply_completion(target: &mut String, caret_char: usize, word: &str) -> usize {
     let chars: Vec<char> = target.chars().collect();
     let mut start = caret_char.min(chars.len());
@@ -0,0 +1,880 @@
+//! Live camera iChannels
+//!
+//! A [`LiveCapture`] reads frames from a capture device on a background thread
+//! and keeps the newest one; the pipeline uploads it to the channel's texture
+//! before each render. Captures are owned by the editor, not the pipeline, so
+//! the device stays open while shaders are rebuilt.
+//!
+//! Devices are V4L2 nodes (`/dev/videoN`) on 64-bit Linux streaming YUYV or
+//! MJPEG, or a [`FakeDevice`]: a file of raw YUYV frames named like
+//! `clip_640x480.yuyv`, decoded by the same code as a real camera. Setting `WEBSHARD_FAKE_CAMERA`
+//! to such a file lists it next to the real cameras.
+
+use eframe::wgpu::{Device, Queue, Texture, TextureView};
+use image::RgbaImage;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::utils::channel_texture::{self, ChannelData, ChannelFormat, ChannelKind, ChannelSettings};
+
+/// Environment variable naming a fake camera file to list with the real ones
+pub const FAKE_CAMERA_ENV: &str = "WEBSHARD_FAKE_CAMERA";
+
+/// How long a device read waits for a frame before checking for shutdown
+const READ_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Frame rate of fake devices
+const FAKE_FPS: u64 = 30;
+
+/// Pixel format a device streams
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// Packed 4:2:2 YCbCr, `Y0 U Y1 V` for each pair of pixels
+    Yuyv,
+    /// One JPEG image per frame
+    Mjpeg,
+}
+
+impl PixelFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PixelFormat::Yuyv => "YUYV",
+            PixelFormat::Mjpeg => "MJPEG",
+        }
+    }
+
+    /// Decode one frame of `size` into RGBA
+    pub fn decode(&self, bytes: &[u8], size: [u32; 2]) -> Result<RgbaImage, String> {
+        match self {
+            PixelFormat::Yuyv => yuyv_to_rgba(bytes, size),
+            PixelFormat::Mjpeg => image::load_from_memory_with_format(bytes, image::ImageFormat::Jpeg)
+                .map(|image| image.to_rgba8())
+                .map_err(|e| format!("Bad MJPEG frame: {}", e)),
+        }
+    }
+}
+
+/// BT.601 limited-range YUYV to RGBA
+pub fn yuyv_to_rgba(bytes: &[u8], [width, height]: [u32; 2]) -> Result<RgbaImage, String> {
+    let expected = (width * height * 2) as usize;
+    if width % 2 != 0 || bytes.len() < expected {
+        return Err(format!(
+            "A {}x{} YUYV frame needs {} bytes and an even width, got {} bytes",
+            width,
+            height,
+            expected,
+            bytes.len()
+        ));
+    }
+    let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+    for pair in bytes[..expected].chunks_exact(4) {
+        let (u, v) = (pair[1] as f32 - 128.0, pair[3] as f32 - 128.0);
+        for y in [pair[0], pair[2]] {
+            let y = 1.164 * (y as f32 - 16.0);
+            let channel = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+            rgba.extend_from_slice(&[
+                channel(y + 1.596 * v),
+                channel(y - 0.392 * u - 0.813 * v),
+                channel(y + 2.017 * u),
+                255,
+            ]);
+        }
+    }
+    RgbaImage::from_raw(width, height, rgba).ok_or_else(|| "YUYV frame size mismatch".to_string())
+}
+
+/// A camera the editor can capture from
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureDeviceInfo {
+    pub path: String,
+    pub name: String,
+    /// Supported frame sizes, smallest first
+    pub sizes: Vec<[u32; 2]>,
+}
+
+/// A source of raw frames
+pub trait CaptureDevice: Send {
+    fn size(&self) -> [u32; 2];
+    fn pixel_format(&self) -> PixelFormat;
+    /// The next frame's bytes, or `None` if none arrived within [`READ_TIMEOUT`]
+    fn read_frame(&mut self) -> Result<Option<Vec<u8>>, String>;
+}
+
+fn is_fake_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("yuyv"))
+}
+
+/// Cameras found on this machine, plus the fake camera from [`FAKE_CAMERA_ENV`]
+pub fn list_devices() -> Vec<CaptureDeviceInfo> {
+    #[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+    let mut devices = v4l2::list_devices();
+    #[cfg(not(all(target_os = "linux", target_pointer_width = "64")))]
+    let mut devices = Vec::new();
+
+    if let Ok(path) = std::env::var(FAKE_CAMERA_ENV) {
+        match FakeDevice::open(&path) {
+            Ok(fake) => devices.push(CaptureDeviceInfo {
+                name: format!("Fake camera ({})", Path::new(&path).file_name().and_then(|n| n.to_str()).unwrap_or(&path)),
+                path,
+                sizes: vec![fake.size()],
+            }),
+            Err(e) => log::warn!("{} is set but unusable: {}", FAKE_CAMERA_ENV, e),
+        }
+    }
+    devices
+}
+
+/// Open a device, asking for `size` if given (the device may pick the nearest it supports)
+pub fn open_device(path: &str, size: Option<[u32; 2]>) -> Result<Box<dyn CaptureDevice>, String> {
+    if is_fake_path(path) {
+        return Ok(Box::new(FakeDevice::open(path)?));
+    }
+    #[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+    {
+        Ok(Box::new(v4l2::Device::open(path, size)?))
+    }
+    #[cfg(not(all(target_os = "linux", target_pointer_width = "64")))]
+    {
+        let _ = size;
+        Err(format!("Can't open {}: camera capture needs 64-bit Linux (V4L2)", path))
+    }
+}
+
+/// Raw YUYV frames read from a file and replayed in a loop at 30 fps
+pub struct FakeDevice {
+    size: [u32; 2],
+    frames: Vec<u8>,
+    next: usize,
+    interval: Duration,
+}
+
+impl FakeDevice {
+    /// Open `name_WxH.yuyv`, which holds one or more frames back to back
+    pub fn open(path: &str) -> Result<Self, String> {
+        let size = size_from_name(path)
+            .ok_or_else(|| format!("Can't tell the frame size of {}; name it like `clip_640x480.yuyv`", path))?;
+        let frames = std::fs::read(path).map_err(|e| format!("Failed to read fake camera {}: {}", path, e))?;
+        let frame_bytes = (size[0] * size[1] * 2) as usize;
+        if frame_bytes == 0 || frames.is_empty() || frames.len() % frame_bytes != 0 {
+            return Err(format!(
+                "{} is {} bytes, which is not a whole number of {}x{} YUYV frames",
+                path,
+                frames.len(),
+                size[0],
+                size[1]
+            ));
+        }
+        Ok(Self {
+            size,
+            frames,
+            next: 0,
+            interval: Duration::from_millis(1000 / FAKE_FPS),
+        })
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len() / (self.size[0] * self.size[1] * 2) as usize
+    }
+}
+
+impl CaptureDevice for FakeDevice {
+    fn size(&self) -> [u32; 2] {
+        self.size
+    }
+
+    fn pixel_format(&self) -> PixelFormat {
+        PixelFormat::Yuyv
+    }
+
+    fn read_frame(&mut self) -> Result<Option<Vec<u8>>, String> {
+        std::thread::sleep(self.interval);
+        let frame_bytes = (self.size[0] * self.size[1] * 2) as usize;
+        let frame = self.frames[self.next * frame_bytes..(self.next + 1) * frame_bytes].to_vec();
+        self.next = (self.next + 1) % self.frame_count();
+        Ok(Some(frame))
+    }
+}
+
+/// `WxH` at the end of a file stem, e.g. `clip_640x480.yuyv`
+fn size_from_name(path: &str) -> Option<[u32; 2]> {
+    let stem = Path::new(path).file_stem()?.to_str()?;
+    let suffix = stem.rsplit(['_', '-', '.', ' ']).next()?;
+    let (width, height) = suffix.split_once('x')?;
+    Some([width.parse().ok()?, height.parse().ok()?])
+}
+
+/// Newest frame, numbered from 1
+type LatestFrame = Option<(u64, Arc<RgbaImage>)>;
+
+struct Shared {
+    latest: Mutex<LatestFrame>,
+    stop: AtomicBool,
+    error: Mutex<Option<String>>,
+}
+
+/// A device being read on a background thread
+pub struct LiveCapture {
+    pub path: String,
+    pub size: [u32; 2],
+    pub pixel_format: PixelFormat,
+    started: Instant,
+    shared: Arc<Shared>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl std::fmt::Debug for LiveCapture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LiveCapture")
+            .field("path", &self.path)
+            .field("size", &self.size)
+            .field("pixel_format", &self.pixel_format)
+            .finish()
+    }
+}
+
+impl LiveCapture {
+    /// Open `path` and start reading frames
+    pub fn start(path: &str, size: Option<[u32; 2]>) -> Result<Self, String> {
+        let device = open_device(path, size)?;
+        Ok(Self::from_device(path, device))
+    }
+
+    pub fn from_device(path: &str, mut device: Box<dyn CaptureDevice>) -> Self {
+        let size = device.size();
+        let pixel_format = device.pixel_format();
+        log::info!("Capturing {} at {}x{} ({})", path, size[0], size[1], pixel_format.as_str());
+
+        let shared = Arc::new(Shared {
+            latest: Mutex::new(None),
+            stop: AtomicBool::new(false),
+            error: Mutex::new(None),
+        });
+        let thread_shared = shared.clone();
+        let thread_path = path.to_string();
+        let thread = std::thread::Builder::new()
+            .name(format!("capture {}", path))
+            .spawn(move || {
+                let mut sequence = 0;
+                let mut warned = false;
+                while !thread_shared.stop.load(Ordering::Relaxed) {
+                    match device.read_frame() {
+                        Ok(Some(bytes)) => match pixel_format.decode(&bytes, size) {
+                            Ok(frame) if frame.dimensions() == (size[0], size[1]) => {
+                                sequence += 1;
+                                *thread_shared.latest.lock().unwrap() = Some((sequence, Arc::new(frame)));
+                            }
+                            Ok(_) => {}
+                            Err(e) => {
+                                if !warned {
+                                    log::warn!("{}: {}", thread_path, e);
+                                    warned = true;
+                                }
+                            }
+                        },
+                        Ok(None) => {}
+                        Err(e) => {
+                            log::error!("Capture from {} stopped: {}", thread_path, e);
+                            *thread_shared.error.lock().unwrap() = Some(e);
+                            break;
+                        }
+                    }
+                }
+            })
+            .ok();
+
+        Self {
+            path: path.to_string(),
+            size,
+            pixel_format,
+            started: Instant::now(),
+            shared,
+            thread: Mutex::new(thread),
+        }
+    }
+
+    /// The newest frame and its number, if one has arrived
+    pub fn latest(&self) -> Option<(u64, Arc<RgbaImage>)> {
+        self.shared.latest.lock().unwrap().clone()
+    }
+
+    /// Seconds since capture started
+    pub fn elapsed(&self) -> f32 {
+        self.started.elapsed().as_secs_f32()
+    }
+
+    /// Why capture stopped, if the device failed
+    pub fn error(&self) -> Option<String> {
+        self.shared.error.lock().unwrap().clone()
+    }
+
+    /// Stop reading and release the device
+    pub fn stop(&self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.lock().unwrap().take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for LiveCapture {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// A capture's texture in a pipeline, updated with each new frame
+pub struct LiveChannel {
+    capture: Arc<LiveCapture>,
+    texture: Texture,
+    format: ChannelFormat,
+    settings: ChannelSettings,
+    /// Number of the frame in `texture`
+    shown: AtomicU64,
+}
+
+impl LiveChannel {
+    /// A black texture of the capture's size, filled as frames arrive
+    pub fn new(device: &Device, queue: &Queue, capture: Arc<LiveCapture>, settings: &ChannelSettings) -> (Self, TextureView) {
+        let [width, height] = capture.size;
+        let (texture, view, format) = channel_texture::upload(
+            device,
+            queue,
+            &ChannelData::from_frame(&RgbaImage::from_pixel(width, height, image::Rgba([0, 0, 0, 255])), settings),
+            settings.format.resolve(ChannelKind::Texture2d, false),
+            &format!("channel_capture_{}", capture.path),
+        );
+        let channel = Self {
+            capture,
+            texture,
+            format,
+            settings: *settings,
+            shown: AtomicU64::new(0),
+        };
+        (channel, view)
+    }
+
+    /// Upload the newest frame if it changed, returning seconds since capture started
+    pub fn update(&self, queue: &Queue) -> f32 {
+        if let Some((sequence, frame)) = self.capture.latest() {
+            if self.shown.swap(sequence, Ordering::Relaxed) != sequence {
+                channel_texture::write_texels(queue, &self.texture, &ChannelData::from_frame(&frame, &self.settings), self.format);
+            }
+        }
+        self.capture.elapsed()
+    }
+
+    /// Texture the frames are uploaded into
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+}
+
+#[cfg(all(target_os = "linux", target_pointer_width = "64"))]
+mod v4l2 {
+    //! Minimal V4L2 capture with memory-mapped streaming buffers
+    //!
+    //! Struct layouts and ioctl numbers follow `linux/videodev2.h` for 64-bit
+    //! little-endian targets; the module is only built for 64-bit Linux, where
+    //! pointers are 8 bytes.
+
+    use super::{CaptureDevice, CaptureDeviceInfo, PixelFormat, READ_TIMEOUT};
+    use std::fs::File;
+    use std::os::fd::AsRawFd;
+
+    const BUF_TYPE_VIDEO_CAPTURE: u32 = 1;
+    const MEMORY_MMAP: u32 = 1;
+    const FIELD_NONE: u32 = 1;
+    const CAP_VIDEO_CAPTURE: u32 = 0x0000_0001;
+    const CAP_STREAMING: u32 = 0x0400_0000;
+    const CAP_DEVICE_CAPS: u32 = 0x8000_0000;
+    const FRMSIZE_TYPE_DISCRETE: u32 = 1;
+    const BUFFER_COUNT: u32 = 4;
+
+    const fn fourcc(code: &[u8; 4]) -> u32 {
+        code[0] as u32 | (code[1] as u32) << 8 | (code[2] as u32) << 16 | (code[3] as u32) << 24
+    }
+    const FOURCC_YUYV: u32 = fourcc(b"YUYV");
+    const FOURCC_MJPEG: u32 = fourcc(b"MJPG");
+
+    /// Sizes offered for devices that only report a continuous range
+    const COMMON_SIZES: [[u32; 2]; 5] = [[320, 240], [640, 480], [1280, 720], [1920, 1080], [3840, 2160]];
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct Capability {
+        driver: [u8; 16],
+        card: [u8; 32],
+        bus_info: [u8; 32],
+        version: u32,
+        capabilities: u32,
+        device_caps: u32,
+        reserved: [u32; 3],
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct FmtDesc {
+        index: u32,
+        type_: u32,
+        flags: u32,
+        description: [u8; 32],
+        pixelformat: u32,
+        mbus_code: u32,
+        reserved: [u32; 3],
+    }
+
+    #[repr(C)]
+    #[derive(Default, Clone, Copy)]
+    struct PixFormat {
+        width: u32,
+        height: u32,
+        pixelformat: u32,
+        field: u32,
+        bytesperline: u32,
+        sizeimage: u32,
+        colorspace: u32,
+        priv_: u32,
+        flags: u32,
+        ycbcr_enc: u32,
+        quantization: u32,
+        xfer_func: u32,
+    }
+
+    /// `struct v4l2_format`: the union holds pointers, so it starts at offset 8
+    #[repr(C)]
+    struct Format {
+        type_: u32,
+        union_align: u32,
+        pix: PixFormat,
+        rest: [u8; 200 - std::mem::size_of::<PixFormat>()],
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct RequestBuffers {
+        count: u32,
+        type_: u32,
+        memory: u32,
+        capabilities: u32,
+        flags: u8,
+        reserved: [u8; 3],
+    }
+
+    #[repr(C)]
+    struct Buffer {
+        index: u32,
+        type_: u32,
+        bytesused: u32,
+        flags: u32,
+        field: u32,
+        timestamp: libc::timeval,
+        timecode: [u32; 4],
+        sequence: u32,
+        memory: u32,
+        /// `m.offset` for MMAP buffers, in the low bytes of the union
+        m: libc::c_ulong,
+        length: u32,
+        reserved2: u32,
+        request_fd: u32,
+    }
+
+    impl Buffer {
+        fn new(index: u32) -> Self {
+            Self {
+                index,
+                type_: BUF_TYPE_VIDEO_CAPTURE,
+                bytesused: 0,
+                flags: 0,
+                field: 0,
+                timestamp: libc::timeval { tv_sec: 0, tv_usec: 0 },
+                timecode: [0; 4],
+                sequence: 0,
+                memory: MEMORY_MMAP,
+                m: 0,
+                length: 0,
+                reserved2: 0,
+                request_fd: 0,
+            }
+        }
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct FrmSizeEnum {
+        index: u32,
+        pixel_format: u32,
+        type_: u32,
+        /// `discrete.width/height`, or `stepwise.min_width..step_height`
+        sizes: [u32; 6],
+        reserved: [u32; 2],
+    }
+
+    const fn ioc(dir: u32, nr: u32, size: usize) -> u32 {
+        (dir << 30) | ((size as u32) << 16) | ((b'V' as u32) << 8) | nr
+    }
+    const fn ior<T>(nr: u32) -> u32 {
+        ioc(2, nr, std::mem::size_of::<T>())
+    }
+    const fn iow<T>(nr: u32) -> u32 {
+        ioc(1, nr, std::mem::size_of::<T>())
+    }
+    const fn iowr<T>(nr: u32) -> u32 {
+        ioc(3, nr, std::mem::size_of::<T>())
+    }
+
+    const VIDIOC_QUERYCAP: u32 = ior::<Capability>(0);
+    const VIDIOC_ENUM_FMT: u32 = iowr::<FmtDesc>(2);
+    const VIDIOC_S_FMT: u32 = iowr::<Format>(5);
+    const VIDIOC_REQBUFS: u32 = iowr::<RequestBuffers>(8);
+    const VIDIOC_QUERYBUF: u32 = iowr::<Buffer>(9);
+    const VIDIOC_QBUF: u32 = iowr::<Buffer>(15);
+    const VIDIOC_DQBUF: u32 = iowr::<Buffer>(17);
+    const VIDIOC_STREAMON: u32 = iow::<libc::c_int>(18);
+    const VIDIOC_STREAMOFF: u32 = iow::<libc::c_int>(19);
+    const VIDIOC_ENUM_FRAMESIZES: u32 = iowr::<FrmSizeEnum>(74);
+
+    /// `ioctl` retried on `EINTR`
+    fn ioctl<T>(file: &File, request: u32, arg: &mut T) -> std::io::Result<()> {
+        loop {
+            // SAFETY: `arg` is a live, exclusively borrowed `#[repr(C)]` struct
+            // of the size encoded in `request`
+            let result = unsafe { libc::ioctl(file.as_raw_fd(), request as _, arg as *mut T) };
+            if result != -1 {
+                return Ok(());
+            }
+            let error = std::io::Error::last_os_error();
+            if error.kind() != std::io::ErrorKind::Interrupted {
+                return Err(error);
+            }
+        }
+    }
+
+    fn c_string(bytes: &[u8]) -> String {
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).into_owned()
+    }
+
+    fn open_file(path: &str) -> Result<File, String> {
+        std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open {}: {}", path, e))
+    }
+
+    /// Card name, if the node captures video with streaming I/O
+    fn query_capture(file: &File) -> Option<String> {
+        let mut capability = Capability::default();
+        ioctl(file, VIDIOC_QUERYCAP, &mut capability).ok()?;
+        let caps = if capability.capabilities & CAP_DEVICE_CAPS != 0 {
+            capability.device_caps
+        } else {
+            capability.capabilities
+        };
+        (caps & CAP_VIDEO_CAPTURE != 0 && caps & CAP_STREAMING != 0).then(|| c_string(&capability.card))
+    }
+
+    /// YUYV if offered, else MJPEG
+    fn pick_format(file: &File) -> Option<(PixelFormat, u32)> {
+        let mut offered = Vec::new();
+        for index in 0.. {
+            let mut desc = FmtDesc {
+                index,
+                type_: BUF_TYPE_VIDEO_CAPTURE,
+                ..Default::default()
+            };
+            if ioctl(file, VIDIOC_ENUM_FMT, &mut desc).is_err() {
+                break;
+            }
+            offered.push(desc.pixelformat);
+        }
+        [(PixelFormat::Yuyv, FOURCC_YUYV), (PixelFormat::Mjpeg, FOURCC_MJPEG)]
+            .into_iter()
+            .find(|(_, code)| offered.contains(code))
+    }
+
+    fn frame_sizes(file: &File, pixel_format: u32) -> Vec<[u32; 2]> {
+        let mut sizes = Vec::new();
+        for index in 0.. {
+            let mut size = FrmSizeEnum {
+                index,
+                pixel_format,
+                ..Default::default()
+            };
+            if ioctl(file, VIDIOC_ENUM_FRAMESIZES, &mut size).is_err() {
+                break;
+            }
+            if size.type_ == FRMSIZE_TYPE_DISCRETE {
+                sizes.push([size.sizes[0], size.sizes[1]]);
+            } else {
+                let [min_width, max_width, _, min_height, max_height, _] = size.sizes;
+                sizes.extend(COMMON_SIZES.iter().filter(|[w, h]| {
+                    (min_width..=max_width).contains(w) && (min_height..=max_height).contains(h)
+                }));
+                break;
+            }
+        }
+        sizes.sort_by_key(|[w, h]| (w * h, *w));
+        sizes.dedup();
+        sizes
+    }
+
+    pub fn list_devices() -> Vec<CaptureDeviceInfo> {
+        let Ok(entries) = std::fs::read_dir("/dev") else {
+            return Vec::new();
+        };
+        let mut paths: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path().to_string_lossy().into_owned())
+            .filter(|path| path.starts_with("/dev/video"))
+            .collect();
+        paths.sort_by_key(|path| path["/dev/video".len()..].parse::<u32>().unwrap_or(u32::MAX));
+
+        paths
+            .into_iter()
+            .filter_map(|path| {
+                let file = open_file(&path).ok()?;
+                let name = query_capture(&file)?;
+                let (_, code) = pick_format(&file)?;
+                Some(CaptureDeviceInfo {
+                    sizes: frame_sizes(&file, code),
+                    name: format!("{} ({})", name, path),
+                    path,
+                })
+            })
+            .collect()
+    }
+
+    struct Mapping {
+        ptr: *mut libc::c_void,
+        length: usize,
+    }
+
+    /// An open V4L2 device streaming into memory-mapped buffers
+    pub struct Device {
+        file: File,
+        size: [u32; 2],
+        pixel_format: PixelFormat,
+        buffers: Vec<Mapping>,
+        streaming: bool,
+    }
+
+    // SAFETY: the mappings are only touched through `&mut self`, and the
+    // kernel keeps them valid until they are unmapped in `drop`
+    unsafe impl Send for Device {}
+
+    impl Device {
+        pub fn open(path: &str, size: Option<[u32; 2]>) -> Result<Self, String> {
+            let file = open_file(path)?;
+            query_capture(&file).ok_or_else(|| format!("{} is not a video capture device", path))?;
+            let (pixel_format, code) =
+                pick_format(&file).ok_or_else(|| format!("{} offers neither YUYV nor MJPEG frames", path))?;
+            let [width, height] = match size {
+                Some(size) => size,
+                None => frame_sizes(&file, code).last().copied().unwrap_or([640, 480]),
+            };
+
+            let mut format = Format {
+                type_: BUF_TYPE_VIDEO_CAPTURE,
+                union_align: 0,
+                pix: PixFormat {
+                    width,
+                    height,
+                    pixelformat: code,
+                    field: FIELD_NONE,
+                    ..Default::default()
+                },
+                rest: [0; 200 - std::mem::size_of::<PixFormat>()],
+            };
+            ioctl(&file, VIDIOC_S_FMT, &mut format).map_err(|e| format!("{} rejected {}x{}: {}", path, width, height, e))?;
+            let size = [format.pix.width, format.pix.height];
+
+            let mut device = Self {
+                file,
+                size,
+                pixel_format,
+                buffers: Vec::new(),
+                streaming: false,
+            };
+            device.start_streaming().map_err(|e| format!("Failed to start streaming from {}: {}", path, e))?;
+            Ok(device)
+        }
+
+        fn start_streaming(&mut self) -> std::io::Result<()> {
+            let mut request = RequestBuffers {
+                count: BUFFER_COUNT,
+                type_: BUF_TYPE_VIDEO_CAPTURE,
+                memory: MEMORY_MMAP,
+                ..Default::default()
+            };
+            ioctl(&self.file, VIDIOC_REQBUFS, &mut request)?;
+            for index in 0..request.count {
+                let mut buffer = Buffer::new(index);
+                ioctl(&self.file, VIDIOC_QUERYBUF, &mut buffer)?;
+                // SAFETY: maps the driver buffer at the offset and length it reported
+                let ptr = unsafe {
+                    libc::mmap(
+                        std::ptr::null_mut(),
+                        buffer.length as usize,
+                        libc::PROT_READ | libc::PROT_WRITE,
+                        libc::MAP_SHARED,
+                        self.file.as_raw_fd(),
+                        buffer.m as u32 as libc::off_t,
+                    )
+                };
+                if ptr == libc::MAP_FAILED {
+                    return Err(std::io::Error::last_os_error());
+                }
+                self.buffers.push(Mapping {
+                    ptr,
+                    length: buffer.length as usize,
+                });
+                ioctl(&self.file, VIDIOC_QBUF, &mut buffer)?;
+            }
+            let mut buffer_type = BUF_TYPE_VIDEO_CAPTURE as libc::c_int;
+            ioctl(&self.file, VIDIOC_STREAMON, &mut buffer_type)?;
+            self.streaming = true;
+            Ok(())
+        }
+    }
+
+    impl CaptureDevice for Device {
+        fn size(&self) -> [u32; 2] {
+            self.size
+        }
+
+        fn pixel_format(&self) -> PixelFormat {
+            self.pixel_format
+        }
+
+        fn read_frame(&mut self) -> Result<Option<Vec<u8>>, String> {
+            let mut poll = libc::pollfd {
+                fd: self.file.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: one valid pollfd
+            let ready = unsafe { libc::poll(&mut poll, 1, READ_TIMEOUT.as_millis() as libc::c_int) };
+            if ready < 0 {
+                let error = std::io::Error::last_os_error();
+                return match error.kind() {
+                    std::io::ErrorKind::Interrupted => Ok(None),
+                    _ => Err(error.to_string()),
+                };
+            }
+            if ready == 0 {
+                return Ok(None);
+            }
+
+            let mut buffer = Buffer::new(0);
+            ioctl(&self.file, VIDIOC_DQBUF, &mut buffer).map_err(|e| format!("Failed to dequeue a frame: {}", e))?;
+            let mapping = self
+                .buffers
+                .get(buffer.index as usize)
+                .ok_or_else(|| format!("Driver returned unknown buffer {}", buffer.index))?;
+            let used = (buffer.bytesused as usize).min(mapping.length);
+            // SAFETY: the buffer is dequeued, so the driver won't write it
+            // until it is queued again below
+            let frame = unsafe { std::slice::from_raw_parts(mapping.ptr as *const u8, used) }.to_vec();
+            ioctl(&self.file, VIDIOC_QBUF, &mut buffer).map_err(|e| format!("Failed to requeue a frame: {}", e))?;
+            Ok(Some(frame))
+        }
+    }
+
+    impl Drop for Device {
+        fn drop(&mut self) {
+            if self.streaming {
+                let mut buffer_type = BUF_TYPE_VIDEO_CAPTURE as libc::c_int;
+                let _ = ioctl(&self.file, VIDIOC_STREAMOFF, &mut buffer_type);
+            }
+            for mapping in &self.buffers {
+                // SAFETY: unmaps a mapping made in `start_streaming`
+                unsafe {
+                    libc::munmap(mapping.ptr, mapping.length);
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_ioctl_numbers_match_videodev2() {
+            assert_eq!(VIDIOC_QUERYBUF, 0xc058_5609);
+            assert_eq!(VIDIOC_S_FMT, 0xc0d0_5605);
+            assert_eq!(VIDIOC_QUERYCAP, 0x8068_5600);
+            assert_eq!(VIDIOC_REQBUFS, 0xc014_5608);
+            assert_eq!(VIDIOC_STREAMON, 0x4004_5612);
+            assert_eq!(VIDIOC_ENUM_FRAMESIZES, 0xc02c_564a);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `frames` solid frames of `size`, each `[y, u, y, v]` per pixel pair
+    fn yuyv_file(name: &str, size: [u32; 2], frames: &[[u8; 4]]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("webshard_{}_{}_{}x{}.yuyv", name, std::process::id(), size[0], size[1]));
+        let pairs = (size[0] * size[1] / 2) as usize;
+        let bytes: Vec<u8> = frames.iter().flat_map(|pair| pair.repeat(pairs)).collect();
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_yuyv_decoding() {
+        // Black, white and pure red in BT.601 limited range
+        let frame = [16, 128, 16, 128, 235, 128, 235, 128, 81, 90, 81, 240];
+        let rgba = yuyv_to_rgba(&frame, [6, 1]).unwrap();
+        assert_eq!(rgba.get_pixel(0, 0).0, [0, 0, 0, 255]);
+        assert_eq!(rgba.get_pixel(2, 0).0, [255, 255, 255, 255]);
+        let red = rgba.get_pixel(5, 0).0;
+        assert!(red[0] > 250 && red[1] < 5 && red[2] < 5, "{:?}", red);
+
+        assert!(yuyv_to_rgba(&frame, [7, 1]).is_err());
+        assert!(yuyv_to_rgba(&frame[..8], [6, 1]).is_err());
+    }
+
+    #[test]
+    fn test_fake_device() {
+        assert!(is_fake_path("clip_4x2.yuyv"));
+        assert!(!is_fake_path("/dev/video0"));
+        assert_eq!(size_from_name("/tmp/clip_640x480.yuyv"), Some([640, 480]));
+
+        let path = yuyv_file("fake_device", [4, 2], &[[16, 128, 16, 128], [235, 128, 235, 128]]);
+        let mut device = FakeDevice::open(path.to_str().unwrap()).unwrap();
+        device.interval = Duration::ZERO;
+        assert_eq!((device.size(), device.frame_count()), ([4, 2], 2));
+        let frames: Vec<Vec<u8>> = (0..3).map(|_| device.read_frame().unwrap().unwrap()).collect();
+        assert_eq!(frames[0], frames[2]);
+        assert_ne!(frames[0], frames[1]);
+
+        // Frames arrive through the capture thread, decoded
+        let capture = LiveCapture::from_device("fake", Box::new(device));
+        let deadline = std::time::Instant::now() + Duration::from_secs(5);
+        let frame = loop {
+            if let Some((_, frame)) = capture.latest() {
+                break frame;
+            }
+            assert!(std::time::Instant::now() < deadline, "no frame captured");
+            std::thread::sleep(Duration::from_millis(5));
+        };
+        assert_eq!(frame.dimensions(), (4, 2));
+        capture.stop();
+        assert!(capture.error().is_none());
+
+        std::fs::write(&path, [0; 5]).unwrap();
+        assert!(FakeDevice::open(path.to_str().unwrap()).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+}
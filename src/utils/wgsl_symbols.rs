@@ -0,0 +1,865 @@
+//! Lightweight WGSL symbol indexer
+//!
+//! Finds declarations (functions, structs, constants, variables, parameters)
+//! and the identifiers referring to them with a small tokenizer and lexical
+//! scoping rules. Unlike naga it works on code that does not compile, which is
+//! the normal state of a buffer while it is being edited.
+//!
+//! Used by go-to-definition, find references and rename in the shader editor.
+
+use std::ops::Range;
+
+use crate::utils::completion;
+use crate::utils::multi_buffer_pipeline::SourceKind;
+use crate::utils::wgsl_syntax;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Struct,
+    Constant,
+    Variable,
+    Parameter,
+}
+
+impl SymbolKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SymbolKind::Function => "function",
+            SymbolKind::Struct => "struct",
+            SymbolKind::Constant => "constant",
+            SymbolKind::Variable => "variable",
+            SymbolKind::Parameter => "parameter",
+        }
+    }
+}
+
+/// A declared symbol
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Byte range of the declared name
+    pub decl: Range<usize>,
+    /// Byte range the symbol is visible in, `None` for module scope
+    pub scope: Option<Range<usize>>,
+}
+
+impl Symbol {
+    pub fn is_module_scope(&self) -> bool {
+        self.scope.is_none()
+    }
+}
+
+/// A reference to a symbol in one of the buffers or Common
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub source: SourceKind,
+    /// Byte range of the identifier
+    pub range: Range<usize>,
+    /// 1-based line number
+    pub line: usize,
+    /// Trimmed text of the line, for display
+    pub line_text: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenameError {
+    InvalidName(String),
+    Reserved(String),
+    NotRenameable(String),
+    Conflict {
+        name: String,
+        source: SourceKind,
+        line: usize,
+    },
+}
+
+impl std::fmt::Display for RenameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenameError::InvalidName(name) => {
+                write!(f, "'{}' is not a valid WGSL identifier", name)
+            }
+            RenameError::Reserved(name) => {
+                write!(f, "'{}' is a WGSL keyword, type or builtin", name)
+            }
+            RenameError::NotRenameable(name) => {
+                write!(
+                    f,
+                    "'{}' is not declared in this buffer and cannot be renamed",
+                    name
+                )
+            }
+            RenameError::Conflict { name, source, line } => write!(
+                f,
+                "'{}' would clash with an existing symbol in {} line {}",
+                name,
+                source.as_str(),
+                line
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    /// Identifier that may refer to a declared symbol
+    Ident,
+    /// Identifier that never refers to a symbol (member access, struct field, attribute)
+    Name,
+    Number,
+    Punct(char),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    range: Range<usize>,
+}
+
+/// Symbol table and identifier tokens of one source
+pub struct SymbolIndex {
+    tokens: Vec<Token>,
+    symbols: Vec<Symbol>,
+}
+
+impl SymbolIndex {
+    pub fn build(source: &str) -> Self {
+        let mut tokens = tokenize(source);
+        let mut symbols = Vec::new();
+
+        let text = |t: &Token| &source[t.range.clone()];
+        let punct = |tokens: &[Token], i: usize| match tokens.get(i).map(|t| t.kind) {
+            Some(TokenKind::Punct(c)) => Some(c),
+            _ => None,
+        };
+
+        // Block bounds: for each token index of '{' / '(' the byte end of its match
+        let closing = match_brackets(&tokens, source.len());
+        let mut blocks: Vec<usize> = Vec::new();
+
+        let mut i = 0;
+        while i < tokens.len() {
+            match tokens[i].kind {
+                TokenKind::Punct('{') => blocks.push(closing[i]),
+                TokenKind::Punct('}') => {
+                    blocks.pop();
+                }
+                TokenKind::Punct('.') => {
+                    if let Some(next) = tokens.get_mut(i + 1) {
+                        if next.kind == TokenKind::Ident {
+                            next.kind = TokenKind::Name;
+                        }
+                    }
+                }
+                TokenKind::Punct('@') => {
+                    // Attribute name and arguments (e.g. @builtin(position))
+                    if let Some(next) = tokens.get_mut(i + 1) {
+                        next.kind = TokenKind::Name;
+                    }
+                    if punct(&tokens, i + 2) == Some('(') {
+                        let end = closing[i + 2];
+                        let mut j = i + 3;
+                        while j < tokens.len() && tokens[j].range.start < end {
+                            if tokens[j].kind == TokenKind::Ident {
+                                tokens[j].kind = TokenKind::Name;
+                            }
+                            j += 1;
+                        }
+                    }
+                }
+                TokenKind::Ident => {
+                    let keyword = text(&tokens[i]).to_string();
+                    match keyword.as_str() {
+                        "fn" => {
+                            if let Some(name) = ident_after(&tokens, i + 1) {
+                                symbols.push(Symbol {
+                                    name: text(&tokens[name]).to_string(),
+                                    kind: SymbolKind::Function,
+                                    decl: tokens[name].range.clone(),
+                                    scope: None,
+                                });
+                                if punct(&tokens, name + 1) == Some('(') {
+                                    let params_end = closing[name + 1];
+                                    let body_end = (name + 1..tokens.len())
+                                        .find(|&j| {
+                                            tokens[j].range.start >= params_end
+                                                && tokens[j].kind == TokenKind::Punct('{')
+                                        })
+                                        .map(|j| closing[j])
+                                        .unwrap_or(source.len());
+                                    let scope = tokens[name + 1].range.start..body_end;
+
+                                    let mut depth = 0;
+                                    let mut j = name + 2;
+                                    while j < tokens.len() && tokens[j].range.start < params_end {
+                                        match tokens[j].kind {
+                                            TokenKind::Punct('(') | TokenKind::Punct('<') => {
+                                                depth += 1
+                                            }
+                                            TokenKind::Punct(')') | TokenKind::Punct('>') => {
+                                                depth -= 1
+                                            }
+                                            TokenKind::Ident
+                                                if depth == 0
+                                                    && punct(&tokens, j + 1) == Some(':') =>
+                                            {
+                                                symbols.push(Symbol {
+                                                    name: text(&tokens[j]).to_string(),
+                                                    kind: SymbolKind::Parameter,
+                                                    decl: tokens[j].range.clone(),
+                                                    scope: Some(scope.clone()),
+                                                });
+                                            }
+                                            _ => {}
+                                        }
+                                        j += 1;
+                                    }
+                                }
+                            }
+                        }
+                        "struct" => {
+                            if let Some(name) = ident_after(&tokens, i + 1) {
+                                symbols.push(Symbol {
+                                    name: text(&tokens[name]).to_string(),
+                                    kind: SymbolKind::Struct,
+                                    decl: tokens[name].range.clone(),
+                                    scope: None,
+                                });
+                                if punct(&tokens, name + 1) == Some('{') {
+                                    let end = closing[name + 1];
+                                    let mut j = name + 2;
+                                    while j < tokens.len() && tokens[j].range.start < end {
+                                        if tokens[j].kind == TokenKind::Ident
+                                            && punct(&tokens, j + 1) == Some(':')
+                                        {
+                                            tokens[j].kind = TokenKind::Name;
+                                        }
+                                        j += 1;
+                                    }
+                                }
+                            }
+                        }
+                        "const" | "override" | "let" | "var" => {
+                            let mut j = i + 1;
+                            // Skip the address space of `var<uniform>`
+                            if keyword == "var" && punct(&tokens, j) == Some('<') {
+                                while j < tokens.len() && punct(&tokens, j) != Some('>') {
+                                    j += 1;
+                                }
+                                j += 1;
+                            }
+                            if let Some(name) = ident_after(&tokens, j) {
+                                // A local is visible from the end of its declaration, so
+                                // `let h = h * 2.0;` still refers to the outer `h`
+                                let scope = blocks.last().map(|&end| {
+                                    let start = (name..tokens.len())
+                                        .find(|&k| punct(&tokens, k) == Some(';'))
+                                        .map(|k| tokens[k].range.end)
+                                        .unwrap_or(end);
+                                    start..end
+                                });
+                                let kind = if scope.is_none() && keyword != "var" {
+                                    SymbolKind::Constant
+                                } else {
+                                    SymbolKind::Variable
+                                };
+                                symbols.push(Symbol {
+                                    name: text(&tokens[name]).to_string(),
+                                    kind,
+                                    decl: tokens[name].range.clone(),
+                                    scope,
+                                });
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        Self { tokens, symbols }
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    /// Symbol named `name` visible at byte offset `at`
+    ///
+    /// The innermost local declared before `at` wins over module-scope symbols.
+    pub fn resolve(&self, name: &str, at: usize) -> Option<&Symbol> {
+        let local = self
+            .symbols
+            .iter()
+            .filter(|s| s.name == name)
+            .filter(|s| {
+                s.scope
+                    .as_ref()
+                    .is_some_and(|scope| scope.contains(&at) || s.decl.contains(&at))
+            })
+            .max_by_key(|s| s.decl.start);
+
+        local.or_else(|| {
+            self.symbols
+                .iter()
+                .find(|s| s.name == name && s.is_module_scope())
+        })
+    }
+
+    /// Byte range of the identifier touching byte offset `at`
+    fn identifier_at(&self, at: usize) -> Option<Range<usize>> {
+        self.tokens
+            .iter()
+            .find(|t| t.kind == TokenKind::Ident && t.range.start <= at && at <= t.range.end)
+            .map(|t| t.range.clone())
+    }
+
+    /// Resolve the identifier at byte offset `at` to its declaration
+    pub fn symbol_at(&self, source: &str, at: usize) -> Option<&Symbol> {
+        let range = self.identifier_at(at)?;
+        self.resolve(&source[range.clone()], range.start)
+    }
+
+    /// Name of the identifier at byte offset `at`, declared here or not
+    pub fn name_at<'a>(&self, source: &'a str, at: usize) -> Option<&'a str> {
+        self.identifier_at(at).map(|range| &source[range])
+    }
+
+    /// All identifiers (including the declaration) that resolve to `symbol`
+    pub fn references(&self, source: &str, symbol: &Symbol) -> Vec<Range<usize>> {
+        self.idents_named(source, &symbol.name)
+            .filter(|range| self.resolve(&symbol.name, range.start) == Some(symbol))
+            .collect()
+    }
+
+    fn idents_named<'a>(
+        &'a self,
+        source: &'a str,
+        name: &'a str,
+    ) -> impl Iterator<Item = Range<usize>> + 'a {
+        self.tokens
+            .iter()
+            .filter(move |t| t.kind == TokenKind::Ident && &source[t.range.clone()] == name)
+            .map(|t| t.range.clone())
+    }
+}
+
+/// Find all references to the symbol at byte offset `at` of `origin`
+///
+/// Locals only have references in their own source. Module-scope symbols are
+/// matched by name everywhere: helpers declared in Common are visible in every
+/// buffer, and helpers copied between buffers are the same code in practice.
+/// Returns the symbol name (which may also be an injected symbol such as
+/// `uniforms`) and the references.
+pub fn find_references(
+    sources: &[(SourceKind, &str)],
+    origin: SourceKind,
+    at: usize,
+) -> Option<(String, Vec<Reference>)> {
+    let origin_code = sources.iter().find(|(kind, _)| *kind == origin)?.1;
+    let origin_index = SymbolIndex::build(origin_code);
+    let name = origin_index.name_at(origin_code, at)?.to_string();
+    let symbol = origin_index.symbol_at(origin_code, at).cloned();
+
+    let mut references = Vec::new();
+    for (kind, code) in sources {
+        let ranges: Vec<Range<usize>> = match &symbol {
+            Some(symbol) if !symbol.is_module_scope() => {
+                if *kind != origin {
+                    continue;
+                }
+                origin_index.references(code, symbol)
+            }
+            _ => module_scope_uses(&SymbolIndex::build(code), code, &name),
+        };
+        references.extend(
+            ranges
+                .into_iter()
+                .map(|range| make_reference(*kind, code, range)),
+        );
+    }
+
+    Some((name, references))
+}
+
+/// Rename the symbol at byte offset `at` of `origin`
+///
+/// With `all_sources`, module-scope symbols are renamed in every source that
+/// uses the name. Returns the new code of each source that changed, or an
+/// error if the new name is invalid or would change what another identifier
+/// refers to.
+pub fn rename(
+    sources: &[(SourceKind, &str)],
+    origin: SourceKind,
+    at: usize,
+    new_name: &str,
+    all_sources: bool,
+) -> Result<Vec<(SourceKind, String)>, RenameError> {
+    validate_identifier(new_name)?;
+
+    let origin_code = sources
+        .iter()
+        .find(|(kind, _)| *kind == origin)
+        .map(|(_, code)| *code)
+        .unwrap_or_default();
+    let origin_index = SymbolIndex::build(origin_code);
+    let Some(symbol) = origin_index.symbol_at(origin_code, at).cloned() else {
+        let name = origin_index.name_at(origin_code, at).unwrap_or_default();
+        return Err(RenameError::NotRenameable(name.to_string()));
+    };
+
+    let mut changed = Vec::new();
+    for (kind, code) in sources {
+        let index = SymbolIndex::build(code);
+        let (target, ranges) = if *kind == origin {
+            let ranges = index.references(code, &symbol);
+            (symbol.clone(), ranges)
+        } else if all_sources && symbol.is_module_scope() {
+            // Either a copy declared here or uses of a helper declared elsewhere
+            let target = index
+                .symbols()
+                .iter()
+                .find(|s| s.name == symbol.name && s.is_module_scope())
+                .cloned()
+                .unwrap_or_else(|| Symbol {
+                    decl: 0..0,
+                    ..symbol.clone()
+                });
+            (target, module_scope_uses(&index, code, &symbol.name))
+        } else {
+            continue;
+        };
+
+        if ranges.is_empty() {
+            continue;
+        }
+        check_conflicts(&index, code, *kind, &target, &ranges, new_name)?;
+
+        let mut updated = code.to_string();
+        for range in ranges.iter().rev() {
+            updated.replace_range(range.clone(), new_name);
+        }
+        changed.push((*kind, updated));
+    }
+
+    Ok(changed)
+}
+
+/// Identifiers named `name` that don't refer to a local
+fn module_scope_uses(index: &SymbolIndex, code: &str, name: &str) -> Vec<Range<usize>> {
+    index
+        .idents_named(code, name)
+        .filter(|range| {
+            index
+                .resolve(name, range.start)
+                .is_none_or(|s| s.is_module_scope())
+        })
+        .collect()
+}
+
+fn validate_identifier(name: &str) -> Result<(), RenameError> {
+    let mut chars = name.chars();
+    let valid_start = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_');
+    if !valid_start
+        || !chars.all(|c| c.is_alphanumeric() || c == '_')
+        || name == "_"
+        || name.starts_with("__")
+    {
+        return Err(RenameError::InvalidName(name.to_string()));
+    }
+
+    let reserved = wgsl_syntax::KEYWORDS.contains(&name)
+        || wgsl_syntax::TYPES.contains(&name)
+        || wgsl_syntax::SPECIAL.contains(&name)
+        || completion::builtins().iter().any(|b| b.label == name)
+        || completion::boilerplate_symbols()
+            .items
+            .iter()
+            .any(|b| b.label == name);
+    if reserved {
+        return Err(RenameError::Reserved(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Reject renames that would capture or shadow another symbol
+fn check_conflicts(
+    index: &SymbolIndex,
+    source: &str,
+    kind: SourceKind,
+    target: &Symbol,
+    ranges: &[Range<usize>],
+    new_name: &str,
+) -> Result<(), RenameError> {
+    let conflict = |at: usize| RenameError::Conflict {
+        name: new_name.to_string(),
+        source: kind,
+        line: source[..at].matches('\n').count() + 1,
+    };
+
+    // A renamed reference must not start resolving to an existing symbol, and a
+    // local must not be redeclared in the same block
+    for range in ranges {
+        if let Some(existing) = index.resolve(new_name, range.start) {
+            let captures = match (&target.scope, &existing.scope) {
+                (None, _) => true,
+                (Some(_), None) => false,
+                (Some(ours), Some(theirs)) => {
+                    existing.decl.start > target.decl.start || ours.end == theirs.end
+                }
+            };
+            if captures {
+                return Err(conflict(existing.decl.start));
+            }
+        }
+    }
+
+    // Existing uses of the new name must not start resolving to the renamed symbol
+    for range in index.idents_named(source, new_name) {
+        let shadowed = match &target.scope {
+            Some(scope) => scope.contains(&range.start),
+            None => index
+                .resolve(new_name, range.start)
+                .is_none_or(|s| s.is_module_scope()),
+        };
+        if shadowed {
+            return Err(conflict(range.start));
+        }
+    }
+
+    Ok(())
+}
+
+fn make_reference(kind: SourceKind, source: &str, range: Range<usize>) -> Reference {
+    let line_start = source[..range.start]
+        .rfind('\n')
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let line_end = source[range.start..]
+        .find('\n')
+        .map(|i| range.start + i)
+        .unwrap_or(source.len());
+    Reference {
+        source: kind,
+        line: source[..range.start].matches('\n').count() + 1,
+        line_text: source[line_start..line_end].trim().to_string(),
+        range,
+    }
+}
+
+/// Index of the next token if it is an identifier
+fn ident_after(tokens: &[Token], i: usize) -> Option<usize> {
+    tokens
+        .get(i)
+        .filter(|t| t.kind == TokenKind::Ident)
+        .map(|_| i)
+}
+
+/// For every opening '{' or '(' token, the byte offset where its block ends
+fn match_brackets(tokens: &[Token], source_len: usize) -> Vec<usize> {
+    let mut closing = vec![source_len; tokens.len()];
+    let mut stack = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        match token.kind {
+            TokenKind::Punct('{') | TokenKind::Punct('(') => stack.push(i),
+            TokenKind::Punct('}') | TokenKind::Punct(')') => {
+                if let Some(open) = stack.pop() {
+                    closing[open] = token.range.end;
+                }
+            }
+            _ => {}
+        }
+    }
+    closing
+}
+
+/// Split source into identifiers, numbers and punctuation, skipping comments
+fn tokenize(source: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+
+        if c == '/' {
+            match chars.peek().map(|(_, next)| *next) {
+                Some('/') => {
+                    for (_, c) in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                    continue;
+                }
+                Some('*') => {
+                    chars.next();
+                    let mut depth = 1;
+                    let mut prev = '\0';
+                    for (_, c) in chars.by_ref() {
+                        match (prev, c) {
+                            ('/', '*') => {
+                                depth += 1;
+                                prev = '\0';
+                                continue;
+                            }
+                            ('*', '/') => {
+                                depth -= 1;
+                                if depth == 0 {
+                                    break;
+                                }
+                                prev = '\0';
+                                continue;
+                            }
+                            _ => {}
+                        }
+                        prev = c;
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        let word = c.is_alphanumeric() || c == '_';
+        if word {
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, next)) = chars.peek() {
+                // Numbers may contain '.' (1.5) but identifiers may not
+                let continues =
+                    next.is_alphanumeric() || next == '_' || (c.is_ascii_digit() && next == '.');
+                if !continues {
+                    break;
+                }
+                end = i + next.len_utf8();
+                chars.next();
+            }
+            let kind = if c.is_ascii_digit() {
+                TokenKind::Number
+            } else {
+                TokenKind::Ident
+            };
+            tokens.push(Token {
+                kind,
+                range: start..end,
+            });
+        } else {
+            tokens.push(Token {
+                kind: TokenKind::Punct(c),
+                range: start..start + c.len_utf8(),
+            });
+        }
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::multi_buffer_pipeline::BufferKind;
+
+    const MAIN: SourceKind = SourceKind::Buffer(BufferKind::MainImage);
+    const BUFFER_A: SourceKind = SourceKind::Buffer(BufferKind::BufferA);
+
+    const SOURCE: &str = "struct Ray { origin: vec3<f32>, dir: vec3<f32> }
+const STEPS: i32 = 64;
+
+fn hash(p: vec2<f32>) -> f32 {
+    let h = dot(p, vec2<f32>(12.9, 78.2)); // p is a parameter
+    return fract(sin(h));
+}
+
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    var ray: Ray;
+    let h = hash(position.xy);
+    for (var i = 0; i < STEPS; i++) {
+        let h = h * 2.0;
+        ray.origin = vec3<f32>(h);
+    }
+    return vec4<f32>(h);
+}
+";
+
+    fn at(needle: &str, nth: usize) -> usize {
+        SOURCE.match_indices(needle).nth(nth).unwrap().0
+    }
+
+    #[test]
+    fn test_declarations() {
+        let index = SymbolIndex::build(SOURCE);
+        let names: Vec<_> = index
+            .symbols()
+            .iter()
+            .map(|s| (s.name.as_str(), s.kind))
+            .collect();
+        assert!(names.contains(&("Ray", SymbolKind::Struct)));
+        assert!(names.contains(&("STEPS", SymbolKind::Constant)));
+        assert!(names.contains(&("hash", SymbolKind::Function)));
+        assert!(names.contains(&("p", SymbolKind::Parameter)));
+        assert!(names.contains(&("position", SymbolKind::Parameter)));
+        assert!(names.contains(&("i", SymbolKind::Variable)));
+        // Struct fields are not symbols
+        assert!(!names.iter().any(|(n, _)| *n == "origin"));
+    }
+
+    #[test]
+    fn test_definition_respects_shadowing() {
+        let index = SymbolIndex::build(SOURCE);
+
+        let call = index.symbol_at(SOURCE, at("hash(position", 0)).unwrap();
+        assert_eq!(call.decl.start, at("hash", 0));
+
+        // `h * 2.0` refers to the outer `h` of fs_main, `vec3<f32>(h)` to the inner one
+        let outer = index.symbol_at(SOURCE, at("h * 2.0", 0)).unwrap();
+        assert_eq!(outer.decl.start, at("h = hash", 0));
+        let inner = index.symbol_at(SOURCE, at("h);", 0)).unwrap();
+        assert_eq!(inner.decl.start, at("h = h * 2.0", 0));
+
+        // Member access and attribute arguments never resolve
+        assert!(index.symbol_at(SOURCE, at("origin =", 0)).is_none());
+    }
+
+    #[test]
+    fn test_references_within_buffer() {
+        let index = SymbolIndex::build(SOURCE);
+        let p = index.symbol_at(SOURCE, at("p: vec2", 0)).unwrap().clone();
+        // Declaration plus the use in dot(); the comment doesn't count
+        assert_eq!(index.references(SOURCE, &p).len(), 2);
+
+        let position = index
+            .symbol_at(SOURCE, at("position.xy", 0))
+            .unwrap()
+            .clone();
+        assert_eq!(index.references(SOURCE, &position).len(), 2);
+    }
+
+    #[test]
+    fn test_find_references_across_buffers() {
+        let buffer_a = "fn hash(p: vec2<f32>) -> f32 { return p.x; }\nfn f() -> f32 { return hash(vec2<f32>(0.0)); }";
+        let buffers = [
+            (MAIN, SOURCE),
+            (BUFFER_A, buffer_a),
+        ];
+
+        let (name, refs) = find_references(&buffers, MAIN, at("hash", 0)).unwrap();
+        assert_eq!(name, "hash");
+        assert_eq!(
+            refs.iter()
+                .filter(|r| r.source == MAIN)
+                .count(),
+            2
+        );
+        assert_eq!(
+            refs.iter()
+                .filter(|r| r.source == BUFFER_A)
+                .count(),
+            2
+        );
+        assert_eq!(refs[0].line, 4);
+
+        // Locals stay in their buffer
+        let (_, refs) = find_references(&buffers, MAIN, at("p: vec2", 0)).unwrap();
+        assert!(refs.iter().all(|r| r.source == MAIN));
+    }
+
+    #[test]
+    fn test_rename_across_buffers() {
+        let buffer_a = "fn hash(p: vec2<f32>) -> f32 { return p.x; }\nfn f() -> f32 { return hash(vec2<f32>(0.0)); }";
+        let buffers = [
+            (MAIN, SOURCE),
+            (BUFFER_A, buffer_a),
+        ];
+
+        let changed = rename(
+            &buffers,
+            MAIN,
+            at("hash", 0),
+            "hash12",
+            true,
+        )
+        .unwrap();
+        assert_eq!(changed.len(), 2);
+        assert!(changed[1].1.contains("fn hash12(p") && changed[1].1.contains("return hash12("));
+
+        let changed = rename(
+            &buffers,
+            MAIN,
+            at("hash", 0),
+            "hash12",
+            false,
+        )
+        .unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].1.matches("hash12").count(), 2);
+    }
+
+    #[test]
+    fn test_common_helpers_are_shared() {
+        let common = "fn palette(t: f32) -> vec3<f32> { return vec3<f32>(t); }";
+        let buffer_a = "fn f() -> vec3<f32> {\n    let palette = 1.0;\n    return vec3<f32>(palette);\n}\nfn g() -> vec3<f32> { return palette(0.5); }";
+        let sources = [(BUFFER_A, buffer_a), (SourceKind::Common, common)];
+
+        // The local `palette` in f() is not a use of the Common helper
+        let (_, refs) = find_references(&sources, SourceKind::Common, 3).unwrap();
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[0].source, BUFFER_A);
+        assert_eq!(refs[0].line, 5);
+
+        let changed = rename(&sources, SourceKind::Common, 3, "pal", true).unwrap();
+        assert_eq!(changed.len(), 2);
+        assert!(changed[0].1.contains("let palette = 1.0") && changed[0].1.contains("return pal(0.5)"));
+        assert!(changed[1].1.starts_with("fn pal("));
+    }
+
+    #[test]
+    fn test_rename_rejects_unsafe_names() {
+        let buffers = [(MAIN, SOURCE)];
+        let outer_h = at("h = hash", 0);
+
+        assert!(matches!(
+            rename(&buffers, MAIN, outer_h, "2h", false),
+            Err(RenameError::InvalidName(_))
+        ));
+        assert!(matches!(
+            rename(&buffers, MAIN, outer_h, "sin", false),
+            Err(RenameError::Reserved(_))
+        ));
+        // `ray` is already declared in the same scope
+        assert!(matches!(
+            rename(&buffers, MAIN, outer_h, "ray", false),
+            Err(RenameError::Conflict { .. })
+        ));
+        // Renaming a parameter to a global it uses would capture the call
+        assert!(matches!(
+            rename(
+                &buffers,
+                MAIN,
+                at("position: vec4", 0),
+                "hash",
+                false
+            ),
+            Err(RenameError::Conflict { .. })
+        ));
+        assert!(matches!(
+            rename(
+                &buffers,
+                MAIN,
+                at("vec2<f32>(12", 0),
+                "v",
+                false
+            ),
+            Err(RenameError::NotRenameable(_))
+        ));
+
+        let changed = rename(&buffers, MAIN, outer_h, "height", false).unwrap();
+        // Declaration, `h * 2.0` and the final `vec4<f32>(h)`
+        assert_eq!(changed[0].1.matches("height").count(), 3);
+    }
+}
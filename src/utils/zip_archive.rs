@@ -0,0 +1,244 @@
+//! Minimal zip reading and writing for shard bundles
+//!
+//! Supports what bundles need and nothing more: stored and deflated entries,
+//! no zip64, no encryption. Entries are written in the order given with a
+//! fixed timestamp, so the same contents always produce the same archive.
+//! Archives that would need zip64 (sizes or offsets past 4 GiB, more than
+//! 65535 entries) are refused rather than written with truncated fields.
+
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// 1980-01-01 00:00, the earliest DOS date
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// Largest entry we are willing to inflate
+const MAX_ENTRY_SIZE: u64 = 256 * 1024 * 1024;
+
+/// `value` as a 32-bit zip field, or an error instead of a truncated one
+///
+/// `u32::MAX` itself is refused too: readers take it as a zip64 marker.
+fn zip32(value: usize, what: &str) -> Result<u32, String> {
+    u32::try_from(value)
+        .ok()
+        .filter(|value| *value != u32::MAX)
+        .ok_or_else(|| format!("{} is too large for a zip archive without zip64", what))
+}
+
+/// Whether `bytes` start like a zip archive
+pub fn is_zip(bytes: &[u8]) -> bool {
+    bytes.starts_with(&LOCAL_HEADER.to_le_bytes())
+}
+
+struct CentralEntry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed_size: u32,
+    size: u32,
+    offset: u32,
+}
+
+/// Builds a zip archive in memory
+#[derive(Default)]
+pub struct ZipWriter {
+    data: Vec<u8>,
+    entries: Vec<CentralEntry>,
+}
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file, deflated unless that would not make it smaller
+    pub fn add(&mut self, name: &str, contents: &[u8]) -> Result<(), String> {
+        let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+        let deflated = encoder
+            .write_all(contents)
+            .and_then(|_| encoder.finish())
+            .ok()
+            .filter(|deflated| deflated.len() < contents.len());
+        let (method, stored) = match &deflated {
+            Some(deflated) => (DEFLATED, deflated.as_slice()),
+            None => (STORED, contents),
+        };
+
+        let entry = CentralEntry {
+            name: name.to_string(),
+            method,
+            crc: crc32fast::hash(contents),
+            compressed_size: zip32(stored.len(), name)?,
+            size: zip32(contents.len(), name)?,
+            offset: zip32(self.data.len(), name)?,
+        };
+        if self.entries.len() >= u16::MAX as usize {
+            return Err(format!("Too many files for a zip archive without zip64 at {}", name));
+        }
+        let name_len = u16::try_from(name.len()).map_err(|_| format!("File name {}... is too long", name.chars().take(32).collect::<String>()))?;
+
+        self.data.extend_from_slice(&LOCAL_HEADER.to_le_bytes());
+        self.data.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        self.data.extend_from_slice(&0x0800u16.to_le_bytes()); // UTF-8 names
+        self.data.extend_from_slice(&entry.method.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes()); // time
+        self.data.extend_from_slice(&DOS_DATE.to_le_bytes());
+        self.data.extend_from_slice(&entry.crc.to_le_bytes());
+        self.data.extend_from_slice(&entry.compressed_size.to_le_bytes());
+        self.data.extend_from_slice(&entry.size.to_le_bytes());
+        self.data.extend_from_slice(&name_len.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+        self.data.extend_from_slice(name.as_bytes());
+        self.data.extend_from_slice(stored);
+
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Write the central directory and return the archive
+    pub fn finish(mut self) -> Result<Vec<u8>, String> {
+        let directory_offset = zip32(self.data.len(), "The archive")?;
+        for entry in &self.entries {
+            self.data.extend_from_slice(&CENTRAL_HEADER.to_le_bytes());
+            self.data.extend_from_slice(&20u16.to_le_bytes()); // version made by
+            self.data.extend_from_slice(&20u16.to_le_bytes()); // version needed
+            self.data.extend_from_slice(&0x0800u16.to_le_bytes());
+            self.data.extend_from_slice(&entry.method.to_le_bytes());
+            self.data.extend_from_slice(&0u16.to_le_bytes());
+            self.data.extend_from_slice(&DOS_DATE.to_le_bytes());
+            self.data.extend_from_slice(&entry.crc.to_le_bytes());
+            self.data.extend_from_slice(&entry.compressed_size.to_le_bytes());
+            self.data.extend_from_slice(&entry.size.to_le_bytes());
+            self.data.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            self.data.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+            self.data.extend_from_slice(&entry.offset.to_le_bytes());
+            self.data.extend_from_slice(entry.name.as_bytes());
+        }
+        let directory_size = zip32(self.data.len(), "The archive")? - directory_offset;
+
+        self.data.extend_from_slice(&END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        self.data.extend_from_slice(&[0; 4]); // disk numbers
+        self.data.extend_from_slice(&(self.entries.len() as u16).to_le_bytes());
+        self.data.extend_from_slice(&(self.entries.len() as u16).to_le_bytes());
+        self.data.extend_from_slice(&directory_size.to_le_bytes());
+        self.data.extend_from_slice(&directory_offset.to_le_bytes());
+        self.data.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        Ok(self.data)
+    }
+}
+
+/// Read every file in a zip archive, keyed by path
+pub fn read(bytes: &[u8]) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let end = (0..=bytes.len().saturating_sub(22))
+        .rev()
+        .find(|&i| bytes[i..].starts_with(&END_OF_CENTRAL_DIRECTORY.to_le_bytes()))
+        .ok_or("Not a zip archive (no end of central directory)")?;
+    let count = u16_at(bytes, end + 10)? as usize;
+    let mut position = u32_at(bytes, end + 16)? as usize;
+
+    let mut files = BTreeMap::new();
+    for _ in 0..count {
+        if u32_at(bytes, position)? != CENTRAL_HEADER {
+            return Err("Corrupt zip central directory".to_string());
+        }
+        let flags = u16_at(bytes, position + 8)?;
+        let method = u16_at(bytes, position + 10)?;
+        let crc = u32_at(bytes, position + 16)?;
+        let compressed_size = u32_at(bytes, position + 20)? as usize;
+        let size = u32_at(bytes, position + 24)? as u64;
+        let name_len = u16_at(bytes, position + 28)? as usize;
+        let extra_len = u16_at(bytes, position + 30)? as usize;
+        let comment_len = u16_at(bytes, position + 32)? as usize;
+        let offset = u32_at(bytes, position + 42)? as usize;
+        let name = String::from_utf8_lossy(slice(bytes, position + 46, name_len)?).into_owned();
+        position += 46 + name_len + extra_len + comment_len;
+
+        if name.ends_with('/') {
+            continue;
+        }
+        // Zip64 archives mark sizes and offsets that need 64 bits with all ones
+        if [compressed_size, offset].contains(&(u32::MAX as usize)) || size == u32::MAX as u64 {
+            return Err(format!("{} needs zip64, which is not supported", name));
+        }
+        if flags & 1 != 0 {
+            return Err(format!("{} is encrypted", name));
+        }
+        if size > MAX_ENTRY_SIZE {
+            return Err(format!("{} is too large ({} bytes)", name, size));
+        }
+
+        if u32_at(bytes, offset)? != LOCAL_HEADER {
+            return Err(format!("Corrupt zip entry {}", name));
+        }
+        let data_start = offset + 30 + u16_at(bytes, offset + 26)? as usize + u16_at(bytes, offset + 28)? as usize;
+        let stored = slice(bytes, data_start, compressed_size)?;
+
+        let contents = match method {
+            STORED => stored.to_vec(),
+            DEFLATED => {
+                // The header's size is untrusted: grow as data inflates instead of reserving it,
+                // and stop one byte past it so longer streams fail the size check below
+                let mut contents = Vec::new();
+                flate2::read::DeflateDecoder::new(stored)
+                    .take(size + 1)
+                    .read_to_end(&mut contents)
+                    .map_err(|e| format!("Failed to inflate {}: {}", name, e))?;
+                contents
+            }
+            other => return Err(format!("{} uses unsupported compression method {}", name, other)),
+        };
+        if contents.len() as u64 != size || crc32fast::hash(&contents) != crc {
+            return Err(format!("{} is corrupt (size or checksum mismatch)", name));
+        }
+        files.insert(name, contents);
+    }
+    Ok(files)
+}
+
+fn slice(bytes: &[u8], start: usize, len: usize) -> Result<&[u8], String> {
+    bytes
+        .get(start..start.checked_add(len).ok_or("Corrupt zip archive")?)
+        .ok_or_else(|| "Truncated zip archive".to_string())
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> Result<u16, String> {
+    Ok(u16::from_le_bytes(slice(bytes, at, 2)?.try_into().unwrap()))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Result<u32, String> {
+    Ok(u32::from_le_bytes(slice(bytes, at, 4)?.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let text = "fn f() -> f32 { return 1.0; }\n".repeat(50);
+        let mut writer = ZipWriter::new();
+        writer.add("passes/main_image.wgsl", text.as_bytes()).unwrap();
+        writer.add("assets/tiny.bin", &[1, 2, 3]).unwrap();
+        let archive = writer.finish().unwrap();
+
+        assert!(is_zip(&archive));
+        let files = read(&archive).unwrap();
+        assert_eq!(files["passes/main_image.wgsl"], text.as_bytes());
+        assert_eq!(files["assets/tiny.bin"], [1, 2, 3]);
+
+        let mut truncated = archive.clone();
+        truncated.truncate(archive.len() - 30);
+        assert!(read(&truncated).is_err());
+
+        assert!(zip32(u32::MAX as usize - 1, "big.bin").is_ok());
+        assert!(zip32(u32::MAX as usize, "big.bin").unwrap_err().contains("big.bin"));
+        assert!(zip32(u32::MAX as usize + 1, "big.bin").is_err());
+    }
+}